@@ -703,6 +703,8 @@ Group=wg-quickrs-group
 AmbientCapabilities=CAP_NET_ADMIN CAP_NET_RAW CAP_NET_BIND_SERVICE
 
 ExecStart=sudo $BIN_DIR/wg-quickrs agent run
+# + runs it as root, like the sudo that started the agent (sudo forwards the signal)
+ExecReload=+/bin/kill -HUP \$MAINPID
 Restart=always
 RestartSec=5
 
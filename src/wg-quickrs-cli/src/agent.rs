@@ -1,4 +1,4 @@
-use std::net::Ipv4Addr;
+use std::net::{IpAddr, Ipv4Addr};
 use std::path::PathBuf;
 use clap::{Args, Subcommand};
 use ipnet::Ipv4Net;
@@ -9,8 +9,14 @@ pub enum AgentCommands {
         about = "Initialize the wg-quickrs agent.\nConfiguration options can be filled either by prompts on screen (when no argument is provided) or specified as arguments to this command"
     )]
     Init(Box<InitOptions>),
-    #[command(about = "Run the wg-quickrs agent")]
-    Run,
+    #[command(about = "Run the wg-quickrs agent.\nSending SIGHUP makes the agent reload conf.yml")]
+    Run(RunOptions),
+}
+
+#[derive(Debug, Args)]
+pub struct RunOptions {
+    #[arg(long, help = "Also reload conf.yml whenever it changes on disk")]
+    pub watch_config: bool,
 }
 
 #[derive(Debug, Args)]
@@ -63,7 +69,7 @@ pub struct InitOptions {
     )]
     pub agent_vpn_port: Option<u16>,
 
-    #[arg(long, default_value = None, long_help = "Set path for the wg tool")]
+    #[arg(long, default_value = None, long_help = "Set path for the wg tool (only required for AmneziaWG)")]
     pub agent_vpn_wg: Option<PathBuf>,
 
     #[arg(long, default_value = None, long_help = "Use userspace implementation of WireGuard")]
@@ -206,7 +212,7 @@ pub struct InitOptions {
 
     #[arg(long, default_value = None, num_args = 0.., long_help = "Set DNS address for agent", value_name = "1.1.1.1"
     )]
-    pub agent_peer_dns_addresses: Vec<Ipv4Addr>,
+    pub agent_peer_dns_addresses: Vec<IpAddr>,
 
     #[arg(long, default_value = None, long_help = "Enable MTU configuration for agent")]
     pub agent_peer_mtu_enabled: Option<bool>,
@@ -271,7 +277,7 @@ pub struct InitOptions {
 
     #[arg(long, default_value = None, num_args = 0.., long_help = "Set default DNS address for new peers", value_name = "1.1.1.1"
     )]
-    pub default_peer_dns_addresses: Vec<Ipv4Addr>,
+    pub default_peer_dns_addresses: Vec<IpAddr>,
 
     #[arg(long, default_value = None, long_help = "Enable MTU for new peers by default")]
     pub default_peer_mtu_enabled: Option<bool>,
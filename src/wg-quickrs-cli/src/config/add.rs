@@ -1,6 +1,6 @@
 use clap::{Args, Subcommand};
-use ipnet::Ipv4Net;
-use std::net::Ipv4Addr;
+use ipnet::IpNet;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use uuid::Uuid;
 
 #[derive(Subcommand, Debug)]
@@ -15,6 +15,28 @@ pub enum AddCommands {
         #[command(flatten)]
         options: AddConnectionOptions,
     },
+    #[command(about = "Add a named user of the web UI and API")]
+    User {
+        #[command(flatten)]
+        options: AddUserOptions,
+    },
+    #[command(about = "Issue a token limited to a single peer and print it once")]
+    PeerToken {
+        #[command(flatten)]
+        options: AddPeerTokenOptions,
+    },
+    #[command(about = "Issue a long-lived API key for automation and print it once")]
+    ApiKey {
+        #[command(flatten)]
+        options: AddApiKeyOptions,
+    },
+    #[command(about = "Map a value of the OpenID Connect role claim to a role")]
+    OidcRoleMapping {
+        #[arg(help = "Claim value (e.g., a group name)")]
+        value: String,
+        #[arg(long, long_help = "Set the role users with this claim value get (viewer, operator or admin)")]
+        role: String,
+    },
 }
 
 #[derive(Args, Debug)]
@@ -28,10 +50,13 @@ pub struct AddPeerOptions {
     #[arg(long, default_value = None, long_help = "Set peer IPv4 address")]
     pub address: Option<Ipv4Addr>,
 
+    #[arg(long, default_value = None, long_help = "Set peer IPv6 address (only used when the network has an IPv6 subnet)")]
+    pub address_v6: Option<Ipv6Addr>,
+
     #[arg(long, default_value = None, long_help = "Enable endpoint")]
     pub endpoint_enabled: Option<bool>,
     
-    #[arg(long, default_value = None, long_help = "Set peer endpoint (hostname:port or ipv4:port)")]
+    #[arg(long, default_value = None, long_help = "Set peer endpoint (hostname:port, ipv4:port or [ipv6]:port)")]
     pub endpoint_address: Option<String>,
     
     #[arg(long, default_value = None, long_help = "Set peer kind (e.g., laptop, server, phone)", value_name = "laptop")]
@@ -47,7 +72,7 @@ pub struct AddPeerOptions {
     pub dns_enabled: Option<bool>,
 
     #[arg(long, default_value = None, num_args = 0.., long_help = "Set DNS address", value_name = "1.1.1.1")]
-    pub dns_addresses: Vec<Ipv4Addr>,
+    pub dns_addresses: Vec<IpAddr>,
 
     #[arg(long, default_value = None, long_help = "Enable MTU")]
     pub mtu_enabled: Option<bool>,
@@ -84,6 +109,9 @@ pub struct AddPeerOptions {
     #[arg(long, default_value = None, num_args = 0.., long_help = "Set PostDown script line(s). Can be specified multiple times for multiple script lines."
     )]
     pub script_post_down_line: Vec<String>,
+
+    #[arg(long, default_value = None, long_help = "Set peer public key instead of generating a private key (the peer keeps its private key on its own device)")]
+    pub public_key: Option<String>,
 }
 
 #[derive(Args, Debug)]
@@ -104,8 +132,38 @@ pub struct AddConnectionOptions {
     pub persistent_keepalive_period: Option<u16>,
 
     #[arg(long, default_value = None, num_args = 0.., long_help = "Set allowed IPs from the first peer to the second peer", value_name = "10.0.34.0/24")]
-    pub allowed_ips_first_to_second: Vec<Ipv4Net>,
+    pub allowed_ips_first_to_second: Vec<IpNet>,
 
     #[arg(long, default_value = None, num_args = 0.., long_help = "Set allowed IPs from the second peer to the first peer", value_name = "10.0.34.0/24")]
-    pub allowed_ips_second_to_first: Vec<Ipv4Net>,
+    pub allowed_ips_second_to_first: Vec<IpNet>,
+}
+
+#[derive(Args, Debug)]
+pub struct AddUserOptions {
+    #[arg(help = "User name (letters, digits, '.', '_' and '-')")]
+    pub name: String,
+
+    #[arg(long, long_help = "Set user role (viewer, operator or admin)")]
+    pub role: String,
+
+    #[arg(long, default_value = None, long_help = "The use of this option is HIGHLY DISCOURAGED because the plaintext password might show up in the shell history! THIS IS HIGHLY INSECURE! Please set the password without the --password flag, and the script will prompt for the password.")]
+    pub password: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct AddPeerTokenOptions {
+    #[arg(help = "Peer UUID the token is limited to", long_help = "UUID of the peer whose config and telemetry the token can read. Use 'wg-quickrs config list peers' to see available peer IDs.")]
+    pub peer_id: Uuid,
+
+    #[arg(long, help = "Also allow the token to replace the peer's private key")]
+    pub key_rotation: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct AddApiKeyOptions {
+    #[arg(help = "API key name (letters, digits, '.', '_' and '-')")]
+    pub name: String,
+
+    #[arg(long, long_help = "Set the role the key acts with (viewer, operator or admin)")]
+    pub role: String,
 }
@@ -1,7 +1,20 @@
-use clap::Args;
+use clap::{Args, ValueEnum};
 use uuid::Uuid;
 use std::path::PathBuf;
 
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ConfFormat {
+    /// wg-quick configuration text
+    #[default]
+    Conf,
+    /// QR code for terminals (ANSI colors and UTF-8 half blocks)
+    Qr,
+    /// QR code as a PNG image
+    Png,
+    /// QR code as an SVG image
+    Svg,
+}
+
 #[derive(Args, Debug)]
 pub struct ConfOptions {
     #[arg(help = "Peer ID to generate WireGuard configuration for", long_help = "UUID of the peer to generate WireGuard configuration for. Use 'wg-quickrs config list peers' to see available peer IDs.")]
@@ -10,6 +23,9 @@ pub struct ConfOptions {
     #[arg(long, help = "Generate stripped config", long_help = "Use --stripped to use with wg/awg, otherwise the conf will only be valid for wg-quick/awg-quick.")]
     pub stripped: bool,
 
+    #[arg(long, value_enum, default_value_t = ConfFormat::Conf, help = "Output format", long_help = "Output format of the generated WireGuard configuration. 'conf' outputs the configuration text, 'qr' renders a QR code in the terminal, 'png' and 'svg' render a QR code image (e.g. to scan with the mobile WireGuard app).")]
+    pub format: ConfFormat,
+
     #[arg(short, long, value_name = "FILE", help = "Output file path", long_help = "Write the generated WireGuard configuration to the specified file path. If not specified, the configuration will be written to stdout.")]
     pub out: Option<PathBuf>,
 }
@@ -33,6 +33,8 @@ pub enum DisableAgentCommands {
 pub enum DisableAgentVpnCommands {
     #[command(about = "Disable WireGuard userspace mode")]
     WgUserspace,
+    #[command(about = "Disable persistent telemetry history")]
+    TelemetryHistory,
 }
 
 #[derive(Subcommand, Debug)]
@@ -43,6 +45,14 @@ pub enum DisableAgentWebCommands {
     Https,
     #[command(about = "Disable password authentication for web server")]
     Password,
+    #[command(about = "Disable the Prometheus metrics endpoint")]
+    Metrics,
+    #[command(about = "Disable bearer token authentication for the metrics endpoint")]
+    MetricsToken,
+    #[command(about = "Disable keeping the JWT signing key in the config folder, tokens are invalidated on every restart")]
+    PersistentJwtKey,
+    #[command(about = "Disable login with an OpenID Connect provider")]
+    Oidc,
 }
 
 #[derive(Subcommand, Debug)]
@@ -78,6 +88,8 @@ pub enum DisablePeerCommands {
     Dns,
     #[command(about = "Disable peer MTU")]
     Mtu,
+    #[command(about = "Disable peer host address (/32 and /128 instead of the subnet prefix length)")]
+    HostAddress,
 }
 
 #[derive(Subcommand, Debug)]
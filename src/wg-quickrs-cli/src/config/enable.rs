@@ -33,6 +33,8 @@ pub enum EnableAgentCommands {
 pub enum EnableAgentVpnCommands {
     #[command(about = "Enable WireGuard userspace mode")]
     WgUserspace,
+    #[command(about = "Enable persistent telemetry history")]
+    TelemetryHistory,
 }
 
 #[derive(Subcommand, Debug)]
@@ -43,6 +45,14 @@ pub enum EnableAgentWebCommands {
     Https,
     #[command(about = "Enable password authentication for web server")]
     Password,
+    #[command(about = "Enable the Prometheus metrics endpoint")]
+    Metrics,
+    #[command(about = "Enable bearer token authentication for the metrics endpoint")]
+    MetricsToken,
+    #[command(about = "Enable keeping the JWT signing key in the config folder, so tokens survive restarts")]
+    PersistentJwtKey,
+    #[command(about = "Enable login with an OpenID Connect provider")]
+    Oidc,
 }
 
 #[derive(Subcommand, Debug)]
@@ -78,6 +88,8 @@ pub enum EnablePeerCommands {
     Dns,
     #[command(about = "Enable peer MTU")]
     Mtu,
+    #[command(about = "Enable peer host address (/32 and /128 instead of the subnet prefix length)")]
+    HostAddress,
 }
 
 #[derive(Subcommand, Debug)]
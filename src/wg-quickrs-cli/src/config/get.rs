@@ -28,6 +28,17 @@ pub enum GetAgentCommands {
         #[command(subcommand)]
         target: Option<GetAgentVpnCommands>,
     },
+    #[command(about = "Get config history configuration")]
+    ConfigHistory {
+        #[command(subcommand)]
+        target: Option<GetAgentConfigHistoryCommands>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum GetAgentConfigHistoryCommands {
+    #[command(about = "Get how many revisions of conf.yml are kept")]
+    Retention,
 }
 
 #[derive(Subcommand, Debug)]
@@ -49,6 +60,11 @@ pub enum GetAgentWebCommands {
         #[command(subcommand)]
         target: Option<GetAgentWebPasswordCommands>,
     },
+    #[command(about = "Get metrics endpoint configuration")]
+    Metrics {
+        #[command(subcommand)]
+        target: Option<GetAgentWebMetricsCommands>,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -79,6 +95,25 @@ pub enum GetAgentWebPasswordCommands {
     Hash,
 }
 
+#[derive(Subcommand, Debug)]
+pub enum GetAgentWebMetricsCommands {
+    #[command(about = "Get whether the metrics endpoint is enabled")]
+    Enabled,
+    #[command(about = "Get metrics bearer token configuration")]
+    Token {
+        #[command(subcommand)]
+        target: Option<GetAgentWebMetricsTokenCommands>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum GetAgentWebMetricsTokenCommands {
+    #[command(about = "Get whether bearer token authentication is enabled for the metrics endpoint")]
+    Enabled,
+    #[command(about = "Get metrics bearer token hash")]
+    Hash,
+}
+
 #[derive(Subcommand, Debug)]
 pub enum GetAgentVpnCommands {
     #[command(about = "Get whether VPN server is enabled")]
@@ -92,6 +127,34 @@ pub enum GetAgentVpnCommands {
         #[command(subcommand)]
         target: Option<GetAgentVpnWgUserspaceCommands>,
     },
+    #[command(about = "Get the network backend used on Linux")]
+    NetworkBackend,
+    #[command(about = "Get telemetry configuration")]
+    Telemetry {
+        #[command(subcommand)]
+        target: Option<GetAgentVpnTelemetryCommands>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum GetAgentVpnTelemetryCommands {
+    #[command(about = "Get when telemetry is collected for the web UI")]
+    Mode,
+    #[command(about = "Get the time between two telemetry samples in milliseconds")]
+    Interval,
+    #[command(about = "Get how long telemetry samples are kept in memory in seconds")]
+    Retention,
+    #[command(about = "Get persistent telemetry history configuration")]
+    History {
+        #[command(subcommand)]
+        target: Option<GetAgentVpnTelemetryHistoryCommands>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum GetAgentVpnTelemetryHistoryCommands {
+    #[command(about = "Get whether persistent telemetry history is enabled")]
+    Enabled,
 }
 
 #[derive(Subcommand, Debug)]
@@ -108,6 +171,8 @@ pub enum GetNetworkCommands {
     Name,
     #[command(about = "Get network subnet")]
     Subnet,
+    #[command(about = "Get network IPv6 subnet")]
+    SubnetV6,
     #[command(about = "Get this peer's UUID")]
     ThisPeer,
     #[command(about = "Get network peers")]
@@ -151,6 +216,10 @@ pub enum GetNetworkPeersCommands {
     Name,
     #[command(about = "Get peer IP address")]
     Address,
+    #[command(about = "Get peer IPv6 address")]
+    AddressV6,
+    #[command(about = "Get whether peer uses a host address")]
+    HostAddress,
     #[command(about = "Get peer endpoint")]
     Endpoint {
         #[command(subcommand)]
@@ -177,6 +246,8 @@ pub enum GetNetworkPeersCommands {
     Scripts,
     #[command(about = "Get peer private key")]
     PrivateKey,
+    #[command(about = "Get peer public key")]
+    PublicKey,
     #[command(about = "Get peer AmneziaWG parameters")]
     AmneziaParameters {
         #[command(subcommand)]
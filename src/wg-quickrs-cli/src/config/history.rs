@@ -0,0 +1,30 @@
+use clap::Subcommand;
+
+#[derive(Subcommand, Debug)]
+pub enum HistoryCommands {
+    #[command(about = "List the kept revisions of conf.yml with their author and a summary of the change")]
+    List,
+    #[command(about = "Print a kept revision of conf.yml")]
+    Show {
+        #[arg(help = "Revision number")]
+        revision: u64,
+    },
+    #[command(
+        about = "Show what changed in a revision",
+        long_about = "Show what changed in a revision as a unified diff against the revision before it, or against the revision given with --against."
+    )]
+    Diff {
+        #[arg(help = "Revision number")]
+        revision: u64,
+        #[arg(long, value_name = "REVISION", help = "Revision to compare against instead of the one before")]
+        against: Option<u64>,
+    },
+    #[command(
+        about = "Restore a revision of conf.yml",
+        long_about = "Restore a kept revision of conf.yml. The restored config is validated like any other change and stored as a new revision, so a rollback can be rolled back as well."
+    )]
+    Rollback {
+        #[arg(help = "Revision number")]
+        revision: u64,
+    },
+}
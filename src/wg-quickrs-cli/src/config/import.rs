@@ -0,0 +1,15 @@
+use clap::Subcommand;
+use std::path::PathBuf;
+
+#[derive(Subcommand, Debug)]
+pub enum ImportCommands {
+    #[command(
+        about = "Import wg-quick/wg configuration files",
+        long_about = "Import wg-quick/wg configuration files (e.g. /etc/wireguard/wg0.conf) into the network. \
+The [Interface] of every file becomes a peer named after the file, and every [Peer] section whose public key belongs to an imported file or an existing peer becomes a connection."
+    )]
+    WgQuick {
+        #[arg(required = true, value_name = "FILE", help = "wg-quick/wg configuration files to import", long_help = "wg-quick/wg configuration files to import. Pass the files of all devices of a network together, so peers can be matched with each other by their public keys.")]
+        files: Vec<PathBuf>,
+    },
+}
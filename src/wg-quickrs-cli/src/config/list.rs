@@ -8,4 +8,10 @@ pub enum ListCommands {
     Connections,
     #[command(about = "List all reservations in human-readable format")]
     Reservations,
+    #[command(about = "List all users of the web UI and API with their roles")]
+    Users,
+    #[command(about = "List all peer tokens with their peers")]
+    PeerTokens,
+    #[command(about = "List all API keys with their roles and when they were last used")]
+    ApiKeys,
 }
@@ -7,9 +7,12 @@ pub mod list;
 pub mod remove;
 pub mod add;
 pub mod conf;
+pub mod import;
+pub mod history;
 
 use clap::Subcommand;
 
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand, Debug)]
 pub enum ConfigCommands {
     #[command(about = "Enable a configuration option")]
@@ -52,6 +55,16 @@ pub enum ConfigCommands {
         #[command(subcommand)]
         target: add::AddCommands,
     },
+    #[command(about = "Import network entities from other configuration formats")]
+    Import {
+        #[command(subcommand)]
+        target: import::ImportCommands,
+    },
+    #[command(about = "Inspect and roll back earlier revisions of conf.yml")]
+    History {
+        #[command(subcommand)]
+        target: history::HistoryCommands,
+    },
     #[command(
         about = "Generate wg/awg or wg-quick/awg-quick configuration file for a peer",
     )]
@@ -19,4 +19,24 @@ pub enum RemoveCommands {
         #[arg(help = "IPv4 address of the reservation to remove")]
         address: Ipv4Addr,
     },
+    #[command(about = "Remove a user of the web UI and API by name")]
+    User {
+        #[arg(help = "User name to remove")]
+        name: String,
+    },
+    #[command(about = "Revoke a peer token by its ID")]
+    PeerToken {
+        #[arg(help = "Token ID (the part of the token before the '.')")]
+        id: String,
+    },
+    #[command(about = "Revoke an API key by name")]
+    ApiKey {
+        #[arg(help = "API key name to remove")]
+        name: String,
+    },
+    #[command(about = "Remove the role mapping of an OpenID Connect role claim value")]
+    OidcRoleMapping {
+        #[arg(help = "Claim value to remove")]
+        value: String,
+    },
 }
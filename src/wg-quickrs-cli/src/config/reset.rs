@@ -31,6 +31,8 @@ pub enum ResetAgentWebCommands {
         #[arg(long, help = "The use of this option is HIGHLY DISCOURAGED because the plaintext password might show up in the shell history! THIS IS HIGHLY INSECURE! Please set the password without the --password flag, and the script will prompt for the password.")]
         password: Option<String>,
     },
+    #[command(about = "Generate a new bearer token for the metrics endpoint and print it once")]
+    MetricsToken,
 }
 
 #[derive(Subcommand, Debug)]
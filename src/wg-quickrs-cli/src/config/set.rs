@@ -1,4 +1,4 @@
-use std::net::Ipv4Addr;
+use std::net::{Ipv4Addr, Ipv6Addr};
 use std::path::PathBuf;
 use clap::Subcommand;
 use uuid::Uuid;
@@ -29,6 +29,20 @@ pub enum SetAgentCommands {
         #[command(subcommand)]
         target: SetAgentVpnCommands,
     },
+    #[command(about = "Set config history configuration")]
+    ConfigHistory {
+        #[command(subcommand)]
+        target: SetAgentConfigHistoryCommands,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SetAgentConfigHistoryCommands {
+    #[command(about = "Set how many revisions of conf.yml are kept")]
+    Retention {
+        #[arg(help = "Number of revisions (1-10000)")]
+        value: String,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -48,6 +62,45 @@ pub enum SetAgentWebCommands {
         #[command(subcommand)]
         target: SetAgentWebHttpsCommands,
     },
+    #[command(about = "Set OpenID Connect login configuration")]
+    Oidc {
+        #[command(subcommand)]
+        target: SetAgentWebOidcCommands,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SetAgentWebOidcCommands {
+    #[command(about = "Set the issuer URL of the OpenID Connect provider")]
+    Issuer {
+        #[arg(help = "Issuer URL (e.g., https://sso.example.com/realms/main)")]
+        value: String,
+    },
+    #[command(about = "Set the client ID registered with the OpenID Connect provider")]
+    ClientId {
+        #[arg(help = "Client ID")]
+        value: String,
+    },
+    #[command(about = "Set the client secret registered with the OpenID Connect provider")]
+    ClientSecret {
+        #[arg(long, default_value = None, long_help = "The use of this option is HIGHLY DISCOURAGED because the plaintext secret might show up in the shell history! Please set the secret without the --client-secret flag, and the script will prompt for it.")]
+        client_secret: Option<String>,
+    },
+    #[command(about = "Set the redirect URL registered with the OpenID Connect provider")]
+    RedirectUrl {
+        #[arg(help = "URL of /api/oidc/callback on this agent (e.g., https://vpn.example.com/api/oidc/callback)")]
+        value: String,
+    },
+    #[command(about = "Set the ID token claim naming the user")]
+    UsernameClaim {
+        #[arg(help = "Claim name (e.g., preferred_username or email)")]
+        value: String,
+    },
+    #[command(about = "Set the ID token claim whose values are mapped to roles")]
+    RoleClaim {
+        #[arg(help = "Claim name (e.g., groups)")]
+        value: String,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -95,6 +148,35 @@ pub enum SetAgentVpnCommands {
         #[command(subcommand)]
         target: SetAgentVpnWgUserspaceCommands,
     },
+    #[command(about = "Set how the tunnel's interface, addresses, routes and rules are managed on Linux")]
+    NetworkBackend {
+        #[arg(help = "Network backend (netlink or shell)")]
+        value: String,
+    },
+    #[command(about = "Set telemetry collection configuration")]
+    Telemetry {
+        #[command(subcommand)]
+        target: SetAgentVpnTelemetryCommands,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SetAgentVpnTelemetryCommands {
+    #[command(about = "Set when telemetry is collected for the web UI")]
+    Mode {
+        #[arg(help = "Telemetry mode (always, on-demand or off)")]
+        value: String,
+    },
+    #[command(about = "Set the time between two telemetry samples")]
+    Interval {
+        #[arg(help = "Interval in milliseconds (100-3600000)")]
+        value: String,
+    },
+    #[command(about = "Set how long telemetry samples are kept in memory")]
+    Retention {
+        #[arg(help = "Retention in seconds")]
+        value: String,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -118,6 +200,11 @@ pub enum SetNetworkCommands {
         #[arg(help = "New subnet (e.g., 10.0.0.0/24)")]
         subnet: String,
     },
+    #[command(about = "Set network IPv6 subnet (enables dual-stack)")]
+    SubnetV6 {
+        #[arg(help = "New IPv6 unique local subnet (e.g., fd00:a:b::/64)")]
+        subnet: String,
+    },
     #[command(about = "Set peer configuration")]
     Peer {
         #[arg(help = "Peer UUID")]
@@ -156,9 +243,14 @@ pub enum SetPeerCommands {
         #[arg(help = "New IPv4 address")]
         address: Ipv4Addr,
     },
+    #[command(about = "Set peer IPv6 address")]
+    AddressV6 {
+        #[arg(help = "New IPv6 address")]
+        address: Ipv6Addr,
+    },
     #[command(about = "Set peer endpoint address")]
     Endpoint {
-        #[arg(help = "Endpoint address (hostname:port or ipv4:port)")]
+        #[arg(help = "Endpoint address (hostname:port, ipv4:port or [ipv6]:port)")]
         endpoint: String,
     },
     #[command(about = "Set peer kind")]
@@ -173,7 +265,7 @@ pub enum SetPeerCommands {
     },
     #[command(about = "Set peer DNS addresses")]
     Dns {
-        #[arg(help = "Comma-separated list of IPv4 addresses (e.g., 8.8.8.8,8.8.4.4)")]
+        #[arg(help = "Comma-separated list of IPv4/IPv6 addresses (e.g., 8.8.8.8,2001:4860:4860::8888)")]
         addresses: String,
     },
     #[command(about = "Set peer MTU value")]
@@ -181,6 +273,11 @@ pub enum SetPeerCommands {
         #[arg(help = "MTU value")]
         value: u16,
     },
+    #[command(about = "Set peer public key, dropping its private key from the agent")]
+    PublicKey {
+        #[arg(help = "WireGuard public key generated on the peer's device")]
+        public_key: String,
+    },
     #[command(about = "Set peer AmneziaWG parameters")]
     AmneziaParameters {
         #[command(subcommand)]
@@ -254,7 +351,7 @@ pub enum SetDefaultsPeerCommands {
     },
     #[command(about = "Set default peer DNS addresses")]
     Dns {
-        #[arg(help = "Comma-separated list of IPv4 addresses (e.g., 8.8.8.8,8.8.4.4)")]
+        #[arg(help = "Comma-separated list of IPv4/IPv6 addresses (e.g., 8.8.8.8,2001:4860:4860::8888)")]
         addresses: String,
     },
     #[command(about = "Set default peer MTU value")]
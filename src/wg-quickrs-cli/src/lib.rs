@@ -31,6 +31,12 @@ pub enum Commands {
     },
     #[command(about = "Edit agent configuration options")]
     Config {
+        #[arg(
+            long,
+            global = true,
+            help = "Validate the change and print the resulting network, the changed peer configs and the changes to the WireGuard interface without writing conf.yml"
+        )]
+        dry_run: bool,
         #[command(subcommand)]
         target: config::ConfigCommands,
     },
@@ -1,11 +1,19 @@
-use chrono::Utc;
+use std::collections::BTreeMap;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use ipnet::{IpNet, Ipv4Net, Ipv6Net};
 use crate::types::network::*;
 use crate::types::misc::{WireGuardLibError};
+use crate::types::api::{TelemetryHistoryPoint, TelemetryResolution};
 use x25519_dalek::{PublicKey, StaticSecret};
 use rand::RngCore;
 use uuid::Uuid;
 use crate::macros::full_version;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD_NO_PAD;
+use sha2::{Digest, Sha256};
 
+/// Written in place of the private key in the configs of peers that only have a public key
+pub const PRIVATE_KEY_PLACEHOLDER: &str = "<PRIVATE_KEY>";
 
 pub fn get_peer_wg_config(
     network: &Network,
@@ -33,9 +41,18 @@ pub fn get_peer_wg_config(
     // Peer fields
     writeln!(wg_conf, "# Peer: {} ({})", this_peer.name, peer_id).unwrap();
     writeln!(wg_conf, "[Interface]").unwrap();
-    writeln!(wg_conf, "PrivateKey = {}", this_peer.private_key).unwrap();
+    match &this_peer.private_key {
+        Some(private_key) => writeln!(wg_conf, "PrivateKey = {}", private_key).unwrap(),
+        None => {
+            writeln!(wg_conf, "# The private key of this peer is not stored by wg-quickrs, replace the placeholder with it").unwrap();
+            writeln!(wg_conf, "PrivateKey = {}", PRIVATE_KEY_PLACEHOLDER).unwrap();
+        }
+    }
     if !stripped {
-        writeln!(wg_conf, "Address = {}/24", this_peer.address).unwrap();
+        writeln!(wg_conf, "Address = {}", get_peer_addresses(network, this_peer).iter()
+            .map(|net| net.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")).unwrap();
     }
 
     if this_peer.endpoint.enabled
@@ -45,6 +62,9 @@ pub fn get_peer_wg_config(
             EndpointAddress::Ipv4AndPort(ipv4_port) => {
                 writeln!(wg_conf, "ListenPort = {}", ipv4_port.port).unwrap();
             }
+            EndpointAddress::Ipv6AndPort(ipv6_port) => {
+                writeln!(wg_conf, "ListenPort = {}", ipv6_port.port).unwrap();
+            }
             EndpointAddress::HostnameAndPort(host_port) => {
                 writeln!(wg_conf, "ListenPort = {}", host_port.port).unwrap();
             }
@@ -118,7 +138,8 @@ pub fn get_peer_wg_config(
         };
         writeln!(wg_conf, "# Linked Peer: {} ({})", other_peer_details.name, other_peer_id).unwrap();
         writeln!(wg_conf, "[Peer]").unwrap();
-        writeln!(wg_conf, "PublicKey = {}", wg_public_key_from_private_key(&other_peer_details.private_key)).unwrap();
+        let other_public_key = get_peer_public_key(other_peer_details).ok_or(WireGuardLibError::PeerKeyMissing(other_peer_id))?;
+        writeln!(wg_conf, "PublicKey = {}", other_public_key).unwrap();
         writeln!(wg_conf, "PresharedKey = {}", connection_details.pre_shared_key).unwrap();
         writeln!(wg_conf, "AllowedIPs = {}", allowed_ips.iter()
             .map(|net| net.to_string())
@@ -136,6 +157,8 @@ pub fn get_peer_wg_config(
         if other_peer_details.endpoint.enabled {
             if let EndpointAddress::Ipv4AndPort(ipv4_port) = &other_peer_details.endpoint.address {
                 writeln!(wg_conf, "Endpoint = {}:{}", ipv4_port.ipv4, ipv4_port.port).unwrap();
+            } else if let EndpointAddress::Ipv6AndPort(ipv6_port) = &other_peer_details.endpoint.address {
+                writeln!(wg_conf, "Endpoint = [{}]:{}", ipv6_port.ipv6, ipv6_port.port).unwrap();
             } else if let EndpointAddress::HostnameAndPort(host_port) = &other_peer_details.endpoint.address {
                 writeln!(wg_conf, "Endpoint = {}:{}", host_port.hostname, host_port.port).unwrap();
             }
@@ -145,6 +168,27 @@ pub fn get_peer_wg_config(
     Ok(wg_conf)
 }
 
+/// Get the interface addresses of a peer in CIDR notation.
+/// Addresses take the network's prefix lengths, unless the peer uses a host address (/32 and /128),
+/// in which case no on-link route is added and routing goes only through AllowedIPs.
+pub fn get_peer_addresses(network: &Network, peer: &Peer) -> Vec<IpNet> {
+    let prefix_len = if peer.host_address { 32 } else { network.subnet.prefix_len() };
+    let mut addresses = vec![IpNet::V4(Ipv4Net::new(peer.address, prefix_len).unwrap())];
+    if let (Some(address_v6), Some(subnet_v6)) = (peer.address_v6, network.subnet_v6) {
+        let prefix_len_v6 = if peer.host_address { 128 } else { subnet_v6.prefix_len() };
+        addresses.push(IpNet::V6(Ipv6Net::new(address_v6, prefix_len_v6).unwrap()));
+    }
+    addresses
+}
+
+/// Get the public key of a peer, either derived from its private key or as stored for peers without one.
+pub fn get_peer_public_key(peer: &Peer) -> Option<WireGuardKey> {
+    match peer.private_key {
+        Some(private_key) => Some(wg_public_key_from_private_key(&private_key)),
+        None => peer.public_key,
+    }
+}
+
 /// Compute a WireGuard public key with a private key.
 pub fn wg_public_key_from_private_key(priv_bytes: &WireGuardKey) -> WireGuardKey {
     let secret = StaticSecret::from(*priv_bytes.as_bytes());
@@ -152,6 +196,11 @@ pub fn wg_public_key_from_private_key(priv_bytes: &WireGuardKey) -> WireGuardKey
     WireGuardKey(*public.as_bytes())
 }
 
+/// Get the fingerprint of a WireGuard key in the OpenSSH style (unpadded base64 of its SHA-256 digest).
+/// Fingerprints identify secrets (e.g. to tell whether a key was rotated) without disclosing them.
+pub fn wg_key_fingerprint(key: &WireGuardKey) -> String {
+    format!("SHA256:{}", STANDARD_NO_PAD.encode(Sha256::digest(key.as_bytes())))
+}
 
 /// Generate a new WireGuard private key
 pub fn wg_generate_key() -> WireGuardKey {
@@ -176,3 +225,32 @@ pub fn remove_expired_reservations(network: &mut Network) {
     let now = Utc::now();
     network.reservations.retain(|_, reservation| reservation.valid_until > now);
 }
+
+/// Start of the bucket a timestamp falls into, days are aligned to midnight UTC
+pub fn telemetry_bucket_start(timestamp: &NaiveDateTime, resolution: TelemetryResolution) -> NaiveDateTime {
+    match resolution.bucket_secs() {
+        Some(secs) => {
+            let ts = timestamp.and_utc().timestamp();
+            DateTime::from_timestamp(ts - ts.rem_euclid(secs), 0).unwrap_or_default().naive_utc()
+        }
+        None => *timestamp,
+    }
+}
+
+/// Merges telemetry points into buckets of the given resolution, summing transfers and keeping the latest handshake
+pub fn rollup_telemetry(points: &[TelemetryHistoryPoint], resolution: TelemetryResolution) -> Vec<TelemetryHistoryPoint> {
+    let mut buckets = BTreeMap::<NaiveDateTime, TelemetryHistoryPoint>::new();
+    for point in points {
+        let start = telemetry_bucket_start(&point.timestamp, resolution);
+        let bucket = buckets.entry(start).or_insert(TelemetryHistoryPoint {
+            timestamp: start,
+            latest_handshake_at: 0,
+            transfer_a_to_b: 0,
+            transfer_b_to_a: 0,
+        });
+        bucket.latest_handshake_at = bucket.latest_handshake_at.max(point.latest_handshake_at);
+        bucket.transfer_a_to_b += point.transfer_a_to_b;
+        bucket.transfer_b_to_a += point.transfer_b_to_a;
+    }
+    buckets.into_values().collect()
+}
@@ -73,6 +73,13 @@ mod wasm {
         serde_wasm_bindgen::to_value(&res).map_err(|e| JsValue::from_str(&e.to_string()))
     }
 
+    #[wasm_bindgen]
+    pub fn validate_peer_address_v6_wasm(address: &str, network_js: JsValue) -> Result<JsValue, JsValue> {
+        let network: Network = serde_wasm_bindgen::from_value(network_js)?;
+        let res = ValidationResultWasm::from(parse_and_validate_peer_address_v6(address, &network));
+        serde_wasm_bindgen::to_value(&res).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
     #[wasm_bindgen]
     pub fn validate_peer_endpoint_wasm(enabled: bool, endpoint: &str) -> Result<JsValue, JsValue> {
         let res = match parse_and_validate_peer_endpoint(endpoint) {
@@ -1,7 +1,7 @@
 use chrono::naive::serde::ts_milliseconds;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
-use std::net::Ipv4Addr;
+use std::net::{Ipv4Addr, Ipv6Addr};
 use chrono::{DateTime, NaiveDateTime, Utc};
 use uuid::Uuid;
 use crate::types::misc::*;
@@ -35,10 +35,51 @@ impl From<&Summary> for SummaryDigest {
     }
 }
 
+/// Secrets of a network, which are redacted from the summary and only disclosed through the reveal endpoint.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct NetworkSecrets {
+    pub peers: BTreeMap<Uuid, PeerSecrets>,
+    pub connections: BTreeMap<ConnectionId, ConnectionSecrets>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct PeerSecrets {
+    pub private_key: WireGuardKey,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct ConnectionSecrets {
+    pub pre_shared_key: WireGuardKey,
+}
+
+impl From<&Network> for NetworkSecrets {
+    fn from(network: &Network) -> Self {
+        NetworkSecrets {
+            peers: network.peers.iter()
+                .filter_map(|(id, peer)| peer.private_key.map(|private_key| (*id, PeerSecrets { private_key })))
+                .collect(),
+            connections: network.connections.iter()
+                .map(|(id, conn)| (id.clone(), ConnectionSecrets { pre_shared_key: conn.pre_shared_key }))
+                .collect(),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub struct Telemetry {
-    pub max_len: u8,
+    pub max_len: u32,
     pub data: Vec<TelemetryData>,
+    /// periods without samples, counters may have started over across a gap
+    #[serde(default)]
+    pub gaps: Vec<TelemetryGap>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct TelemetryGap {
+    #[serde(with = "ts_milliseconds")]
+    pub from: NaiveDateTime,
+    #[serde(with = "ts_milliseconds")]
+    pub to: NaiveDateTime,
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
@@ -55,6 +96,59 @@ pub struct TelemetryDatum {
     pub transfer_b_to_a: u64,
 }
 
+/// Granularity of the points returned from the telemetry history
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum TelemetryResolution {
+    Raw,
+    Hourly,
+    Daily,
+}
+
+impl TelemetryResolution {
+    /// Length of a bucket in seconds, raw samples are not bucketed
+    pub fn bucket_secs(&self) -> Option<i64> {
+        match self {
+            TelemetryResolution::Raw => None,
+            TelemetryResolution::Hourly => Some(3600),
+            TelemetryResolution::Daily => Some(86400),
+        }
+    }
+}
+
+impl std::fmt::Display for TelemetryResolution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TelemetryResolution::Raw => write!(f, "raw"),
+            TelemetryResolution::Hourly => write!(f, "hourly"),
+            TelemetryResolution::Daily => write!(f, "daily"),
+        }
+    }
+}
+
+/// Traffic of a connection over a time range, transfers are the bytes moved within each point
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct TelemetryHistory {
+    pub connection_id: ConnectionId,
+    pub resolution: TelemetryResolution,
+    #[serde(with = "ts_milliseconds")]
+    pub from: NaiveDateTime,
+    #[serde(with = "ts_milliseconds")]
+    pub to: NaiveDateTime,
+    pub transfer_a_to_b: u64,
+    pub transfer_b_to_a: u64,
+    pub points: Vec<TelemetryHistoryPoint>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct TelemetryHistoryPoint {
+    #[serde(with = "ts_milliseconds")]
+    pub timestamp: NaiveDateTime,
+    pub latest_handshake_at: u64,
+    pub transfer_a_to_b: u64,
+    pub transfer_b_to_a: u64,
+}
+
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub struct ChangeSum {
     pub changed_fields: Option<ChangedFields>,
@@ -62,6 +156,9 @@ pub struct ChangeSum {
     pub added_connections: Option<BTreeMap<ConnectionId, Connection>>,
     pub removed_peers: Option<Vec<Uuid>>,
     pub removed_connections: Option<Vec<ConnectionId>>,
+    /// digest of the network the change was based on, same as an `If-Match` header
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
@@ -76,6 +173,8 @@ pub struct ChangedFields {
 pub struct OptionalPeer {
     pub name: Option<String>,
     pub address: Option<Ipv4Addr>,
+    pub address_v6: Option<Ipv6Addr>,
+    pub host_address: Option<bool>,
     pub endpoint: Option<Endpoint>,
     pub kind: Option<String>,
     pub icon: Option<Icon>,
@@ -83,6 +182,7 @@ pub struct OptionalPeer {
     pub mtu: Option<Mtu>,
     pub scripts: Option<OptionalScripts>,
     pub private_key: Option<WireGuardKey>,
+    pub public_key: Option<WireGuardKey>,
     pub amnezia_parameters: Option<OptionalAmneziaPeerParameters>,
 }
 
@@ -146,13 +246,19 @@ pub struct OptionalAmneziaNetworkParameters {
 pub struct AddedPeer {
     pub name: String,
     pub address: Ipv4Addr,
+    pub address_v6: Option<Ipv6Addr>,
+    #[serde(default)]
+    pub host_address: bool,
     pub endpoint: Endpoint,
     pub kind: String,
     pub icon: Icon,
     pub dns: Dns,
     pub mtu: Mtu,
     pub scripts: Scripts,
-    pub private_key: WireGuardKey,
+    #[serde(default)]
+    pub private_key: Option<WireGuardKey>,
+    #[serde(default)]
+    pub public_key: Option<WireGuardKey>,
     pub amnezia_parameters: AmneziaPeerParameters,
 }
 
@@ -161,6 +267,8 @@ impl From<&AddedPeer> for Peer {
         Peer {
             name: added_peer.name.clone(),
             address: added_peer.address,
+            address_v6: added_peer.address_v6,
+            host_address: added_peer.host_address,
             endpoint: added_peer.endpoint.clone(),
             kind: added_peer.kind.clone(),
             icon: added_peer.icon.clone(),
@@ -168,6 +276,7 @@ impl From<&AddedPeer> for Peer {
             mtu: added_peer.mtu.clone(),
             scripts: added_peer.scripts.clone(),
             private_key: added_peer.private_key,
+            public_key: added_peer.public_key,
             amnezia_parameters: added_peer.amnezia_parameters.clone(),
             created_at: Utc::now(), // TODO: use time from arg
             updated_at: Utc::now(),
@@ -1,6 +1,10 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
 use std::net::Ipv4Addr;
 use std::path::PathBuf;
+use uuid::Uuid;
 use crate::macros::*;
 use crate::types::misc::WireGuardLibError;
 use crate::types::network::{Network, NetworkWDigest, Script, Scripts};
@@ -60,6 +64,8 @@ pub struct Agent {
     pub web: AgentWeb,
     pub vpn: AgentVpn,
     pub firewall: AgentFirewall,
+    #[serde(default)]
+    pub config_history: AgentConfigHistory,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -68,6 +74,23 @@ pub struct AgentWeb {
     pub http: AgentWebHttp,
     pub https: AgentWebHttps,
     pub password: Password,
+    #[serde(default)]
+    pub metrics: AgentWebMetrics,
+    /// named accounts signing in with their own password, keyed by user name
+    #[serde(default)]
+    pub users: BTreeMap<String, AgentWebUser>,
+    /// tokens limited to a single peer, keyed by the id that prefixes the token
+    #[serde(default)]
+    pub peer_tokens: BTreeMap<String, AgentWebPeerToken>,
+    /// long-lived bearer tokens for automation, keyed by name
+    #[serde(default)]
+    pub api_keys: BTreeMap<String, AgentWebApiKey>,
+    /// keeps the key signing the tokens of /api/token in the config folder, so they survive restarts
+    #[serde(default)]
+    pub persistent_jwt_key: PersistentJwtKey,
+    /// sign-in with an OpenID Connect provider instead of a password
+    #[serde(default)]
+    pub oidc: AgentWebOidc,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -84,18 +107,248 @@ pub struct AgentWebHttps {
     pub tls_key: PathBuf,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
 pub struct Password {
     pub enabled: bool,
     pub hash: String,
 }
 
+/// A named account of the web UI and API
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AgentWebUser {
+    pub role: WebRole,
+    /// argon2 hash of the user's password
+    pub hash: String,
+}
+
+/// Lets the user of a peer download its config and see its traffic without a web user
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AgentWebPeerToken {
+    pub peer_id: Uuid,
+    /// allows the peer's private key to be replaced with a new one
+    #[serde(default)]
+    pub key_rotation: bool,
+    /// argon2 hash of the token's secret
+    pub hash: String,
+}
+
+/// Lets scripts and CI pipelines call the API without signing in, until the key is removed
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AgentWebApiKey {
+    /// prefixes the key to find its hash, the secret follows it
+    pub id: String,
+    pub role: WebRole,
+    pub created_at: DateTime<Utc>,
+    /// argon2 hash of the key's secret
+    pub hash: String,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct PersistentJwtKey {
+    pub enabled: bool,
+}
+
+/// Authorization code flow with an OpenID Connect provider, its users get the role their claims map to
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AgentWebOidc {
+    pub enabled: bool,
+    /// the provider's endpoints are discovered from `<issuer>/.well-known/openid-configuration`
+    pub issuer: String,
+    pub client_id: String,
+    pub client_secret: String,
+    /// `/api/oidc/callback` of this agent, as registered with the provider
+    pub redirect_url: String,
+    #[serde(default = "default_oidc_scopes")]
+    pub scopes: Vec<String>,
+    /// ID token claim naming the user in tokens and the config history, `sub` when it is missing
+    #[serde(default = "default_oidc_username_claim")]
+    pub username_claim: String,
+    /// ID token claim, a string or a list of strings, whose values are looked up in `role_mapping`
+    #[serde(default = "default_oidc_role_claim")]
+    pub role_claim: String,
+    /// role of each value of `role_claim`, the highest one applies and logins matching none are refused
+    #[serde(default)]
+    pub role_mapping: BTreeMap<String, WebRole>,
+}
+
+fn default_oidc_scopes() -> Vec<String> {
+    vec!["openid".to_string(), "profile".to_string(), "email".to_string()]
+}
+
+fn default_oidc_username_claim() -> String {
+    "preferred_username".to_string()
+}
+
+fn default_oidc_role_claim() -> String {
+    "groups".to_string()
+}
+
+impl Default for AgentWebOidc {
+    fn default() -> Self {
+        AgentWebOidc {
+            enabled: false,
+            issuer: String::new(),
+            client_id: String::new(),
+            client_secret: String::new(),
+            redirect_url: String::new(),
+            scopes: default_oidc_scopes(),
+            username_claim: default_oidc_username_claim(),
+            role_claim: default_oidc_role_claim(),
+            role_mapping: BTreeMap::new(),
+        }
+    }
+}
+
+/// What a web user may do, each role may also do everything the roles before it may
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+pub enum WebRole {
+    /// read the network summary, its events and telemetry
+    Viewer,
+    /// change peers and connections, reserve addresses and read peer configs and the config history
+    Operator,
+    /// change network-wide settings, reveal secrets, roll back the config and turn the tunnel on/off
+    Admin,
+}
+
+impl Display for WebRole {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WebRole::Viewer => write!(f, "viewer"),
+            WebRole::Operator => write!(f, "operator"),
+            WebRole::Admin => write!(f, "admin"),
+        }
+    }
+}
+
+/// Prometheus exporter served at /metrics, authenticated separately from the web UI
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct AgentWebMetrics {
+    pub enabled: bool,
+    /// argon2 hash of the bearer token scrapers have to send
+    #[serde(default)]
+    pub token: Password,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct AgentVpn {
     pub enabled: bool,
     pub port: u16,
     pub wg: PathBuf,
     pub wg_userspace: WireGuardUserspace,
+    #[serde(default)]
+    pub network_backend: NetworkBackend,
+    #[serde(default)]
+    pub telemetry: AgentVpnTelemetry,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AgentVpnTelemetry {
+    #[serde(default)]
+    pub mode: TelemetryMode,
+    /// milliseconds between two samples kept in memory
+    #[serde(default = "default_telemetry_interval")]
+    pub interval: u64,
+    /// seconds of samples kept in memory
+    #[serde(default = "default_telemetry_retention")]
+    pub retention: u64,
+    #[serde(default)]
+    pub history: TelemetryHistory,
+}
+
+fn default_telemetry_interval() -> u64 {
+    1000
+}
+
+fn default_telemetry_retention() -> u64 {
+    20
+}
+
+impl Default for AgentVpnTelemetry {
+    fn default() -> Self {
+        AgentVpnTelemetry {
+            mode: TelemetryMode::default(),
+            interval: default_telemetry_interval(),
+            retention: default_telemetry_retention(),
+            history: TelemetryHistory::default(),
+        }
+    }
+}
+
+impl AgentVpnTelemetry {
+    /// Number of samples that fit in the retention window
+    pub fn capacity(&self) -> usize {
+        (self.retention * 1000 / self.interval.max(1)) as usize + 1
+    }
+}
+
+/// When the in-memory telemetry shown in the web UI is collected
+#[derive(Serialize, Deserialize, Default, PartialEq, Debug, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+pub enum TelemetryMode {
+    /// collect all the time, also on headless agents
+    Always,
+    /// collect while a client is polling the network summary
+    #[default]
+    OnDemand,
+    /// never collect
+    Off,
+}
+
+impl Display for TelemetryMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TelemetryMode::Always => write!(f, "always"),
+            TelemetryMode::OnDemand => write!(f, "on-demand"),
+            TelemetryMode::Off => write!(f, "off"),
+        }
+    }
+}
+
+/// Snapshots of conf.yml kept under the config folder on every write
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AgentConfigHistory {
+    /// number of revisions kept, the oldest ones are removed first
+    #[serde(default = "default_config_history_retention")]
+    pub retention: u32,
+}
+
+fn default_config_history_retention() -> u32 {
+    50
+}
+
+impl Default for AgentConfigHistory {
+    fn default() -> Self {
+        AgentConfigHistory {
+            retention: default_config_history_retention(),
+        }
+    }
+}
+
+/// Persistent per-connection telemetry kept under the config folder
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct TelemetryHistory {
+    pub enabled: bool,
+}
+
+/// How the tunnel's interface, addresses, routes and rules are managed on Linux
+#[derive(Serialize, Deserialize, Default, PartialEq, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum NetworkBackend {
+    /// talk to the kernel over rtnetlink, works without iproute2
+    #[default]
+    Netlink,
+    /// run the ip and sysctl commands
+    Shell,
+}
+
+impl Display for NetworkBackend {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NetworkBackend::Netlink => write!(f, "netlink"),
+            NetworkBackend::Shell => write!(f, "shell"),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -9,6 +9,8 @@ pub enum WireGuardLibError {
     PeerNotFound(Uuid),
     #[error("unable to serialize network")]
     SerializationFailed(),
+    #[error("peer {0} has no key")]
+    PeerKeyMissing(Uuid),
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
@@ -1,4 +1,5 @@
 pub mod api;
 pub mod config;
 pub mod misc;
-pub mod network;
\ No newline at end of file
+pub mod network;
+pub mod wg_quick;
@@ -1,9 +1,9 @@
 use serde::{Deserialize, Serialize, Deserializer, Serializer};
 use std::collections::BTreeMap;
 use std::fmt::Display;
-use std::net::Ipv4Addr;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use chrono::{DateTime, Utc};
-use ipnet::Ipv4Net;
+use ipnet::{IpNet, Ipv4Net, Ipv6Net};
 use uuid::Uuid;
 use base64::{Engine as _, engine::general_purpose::STANDARD};
 use sha2::{Digest, Sha256};
@@ -79,6 +79,8 @@ impl Display for ConnectionId {
 pub struct Network {
     pub name: String,
     pub subnet: Ipv4Net,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subnet_v6: Option<Ipv6Net>,
     pub this_peer: Uuid,
     pub peers: BTreeMap<Uuid, Peer>,
     pub connections: BTreeMap<ConnectionId, Connection>,
@@ -92,13 +94,22 @@ pub struct Network {
 pub struct Peer {
     pub name: String,
     pub address: Ipv4Addr,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address_v6: Option<Ipv6Addr>,
+    #[serde(default)]
+    pub host_address: bool,
     pub endpoint: Endpoint,
     pub kind: String,
     pub icon: Icon,
     pub dns: Dns,
     pub mtu: Mtu,
     pub scripts: Scripts,
-    pub private_key: WireGuardKey,
+    /// not set for peers that generate their keys on their own devices and only share the public key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub private_key: Option<WireGuardKey>,
+    /// only set for peers without a private key, otherwise it is derived from the private key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub public_key: Option<WireGuardKey>,
     pub amnezia_parameters: AmneziaPeerParameters,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
@@ -119,6 +130,8 @@ pub enum EndpointAddress {
     None,
     #[serde(rename = "ipv4_and_port")]
     Ipv4AndPort(Ipv4AndPort),
+    #[serde(rename = "ipv6_and_port")]
+    Ipv6AndPort(Ipv6AndPort),
     #[serde(rename = "hostname_and_port")]
     HostnameAndPort(HostnameAndPort),
 }
@@ -130,6 +143,12 @@ pub struct Ipv4AndPort {
     pub port: u16,
 }
 
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct Ipv6AndPort {
+    pub ipv6: Ipv6Addr,
+    pub port: u16,
+}
+
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub struct HostnameAndPort {
     pub hostname: String,
@@ -146,7 +165,7 @@ pub struct Icon {
 #[derive(Serialize, Deserialize, Default, PartialEq, Debug, Clone)]
 pub struct Dns {
     pub enabled: bool,
-    pub addresses: Vec<Ipv4Addr>,
+    pub addresses: Vec<IpAddr>,
 }
 
 #[derive(Serialize, Deserialize, Default, PartialEq, Debug, Clone)]
@@ -183,7 +202,7 @@ impl IntoIterator for Scripts {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub struct WireGuardKey(pub [u8; 32]);
 
 impl WireGuardKey {
@@ -245,7 +264,7 @@ pub struct Connection {
     pub allowed_ips_b_to_a: AllowedIPs,
 }
 
-pub type AllowedIPs = Vec<Ipv4Net>;
+pub type AllowedIPs = Vec<IpNet>;
 
 #[derive(Serialize, Deserialize, Default, PartialEq, Debug, Clone)]
 pub struct PersistentKeepalive {
@@ -277,6 +296,8 @@ pub struct DefaultConnection {
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub struct ReservationData {
     pub peer_id: Uuid,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address_v6: Option<Ipv6Addr>,
     pub valid_until: DateTime<Utc>,
 }
 
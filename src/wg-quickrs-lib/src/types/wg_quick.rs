@@ -0,0 +1,186 @@
+use std::net::IpAddr;
+use std::str::FromStr;
+use ipnet::IpNet;
+use thiserror::Error;
+use crate::types::network::*;
+use crate::validation::error::ValidationError;
+use crate::validation::network::*;
+
+#[derive(Error, PartialEq, Debug)]
+pub enum WgQuickParseError {
+    #[error("line {0}: expected a [section] header or a 'Key = Value' pair")]
+    MalformedLine(usize),
+    #[error("line {0}: '{1}' is outside of a section")]
+    OutsideSection(usize, String),
+    #[error("line {0}: unknown section [{1}]")]
+    UnknownSection(usize, String),
+    #[error("line {0}: {1}: {2}")]
+    InvalidValue(usize, String, ValidationError),
+    #[error("missing [Interface] section")]
+    MissingInterface(),
+    #[error("line {0}: [Peer] section is missing a PublicKey")]
+    MissingPublicKey(usize),
+}
+
+/// A parsed wg-quick (or wg) configuration file.
+#[derive(PartialEq, Debug, Clone)]
+pub struct WgQuickConfig {
+    pub interface: WgQuickInterface,
+    pub peers: Vec<WgQuickPeer>,
+    /// 'Key = Value' pairs that have no equivalent in wg-quickrs (e.g. Table, FwMark, DNS search domains)
+    pub ignored: Vec<String>,
+}
+
+#[derive(Default, PartialEq, Debug, Clone)]
+pub struct WgQuickInterface {
+    pub private_key: Option<WireGuardKey>,
+    pub addresses: Vec<IpNet>,
+    pub listen_port: Option<u16>,
+    pub dns: Vec<IpAddr>,
+    pub mtu: Option<u16>,
+    pub scripts: Scripts,
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct WgQuickPeer {
+    pub public_key: WireGuardKey,
+    pub pre_shared_key: Option<WireGuardKey>,
+    pub allowed_ips: AllowedIPs,
+    pub endpoint: Option<EndpointAddress>,
+    pub persistent_keepalive: Option<u16>,
+}
+
+/// Fields of a [Peer] section while it is being parsed, PublicKey is only known to be set at the end of the section
+#[derive(Default)]
+struct PartialPeer {
+    line: usize,
+    public_key: Option<WireGuardKey>,
+    pre_shared_key: Option<WireGuardKey>,
+    allowed_ips: AllowedIPs,
+    endpoint: Option<EndpointAddress>,
+    persistent_keepalive: Option<u16>,
+}
+
+impl PartialPeer {
+    fn finish(self) -> Result<WgQuickPeer, WgQuickParseError> {
+        Ok(WgQuickPeer {
+            public_key: self.public_key.ok_or(WgQuickParseError::MissingPublicKey(self.line))?,
+            pre_shared_key: self.pre_shared_key,
+            allowed_ips: self.allowed_ips,
+            endpoint: self.endpoint,
+            persistent_keepalive: self.persistent_keepalive,
+        })
+    }
+}
+
+enum Section {
+    None,
+    Interface,
+    Peer(PartialPeer),
+}
+
+impl FromStr for WgQuickConfig {
+    type Err = WgQuickParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut interface: Option<WgQuickInterface> = None;
+        let mut peers = Vec::new();
+        let mut ignored = Vec::new();
+        let mut section = Section::None;
+
+        for (i, raw_line) in s.lines().enumerate() {
+            let line_no = i + 1;
+            let line = raw_line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                if let Section::Peer(peer) = std::mem::replace(&mut section, Section::None) {
+                    peers.push(peer.finish()?);
+                }
+                section = match name.trim().to_lowercase().as_str() {
+                    "interface" => {
+                        interface.get_or_insert_with(WgQuickInterface::default);
+                        Section::Interface
+                    }
+                    "peer" => Section::Peer(PartialPeer { line: line_no, ..Default::default() }),
+                    _ => return Err(WgQuickParseError::UnknownSection(line_no, name.to_string())),
+                };
+                continue;
+            }
+
+            let (key, value) = line.split_once('=')
+                .map(|(k, v)| (k.trim(), v.trim()))
+                .ok_or(WgQuickParseError::MalformedLine(line_no))?;
+            let invalid = |e: ValidationError| WgQuickParseError::InvalidValue(line_no, key.to_string(), e);
+
+            match &mut section {
+                Section::None => return Err(WgQuickParseError::OutsideSection(line_no, key.to_string())),
+                Section::Interface => {
+                    let interface = interface.as_mut().unwrap();
+                    match key.to_lowercase().as_str() {
+                        "privatekey" => interface.private_key = Some(parse_and_validate_wg_key(value).map_err(invalid)?),
+                        "address" => {
+                            for address in value.split(',').map(str::trim) {
+                                // addresses without a prefix length are host addresses
+                                let address = address.parse::<IpNet>()
+                                    .or_else(|_| address.parse::<IpAddr>().map(IpNet::from))
+                                    .map_err(|_| invalid(ValidationError::NotIPAddress()))?;
+                                interface.addresses.push(address);
+                            }
+                        }
+                        "listenport" => {
+                            interface.listen_port = Some(value.parse().map_err(|_| invalid(ValidationError::NotPortNumber()))?);
+                        }
+                        "dns" => {
+                            for dns in value.split(',').map(str::trim) {
+                                match dns.parse::<IpAddr>() {
+                                    Ok(address) => interface.dns.push(address),
+                                    Err(_) => ignored.push(format!("DNS = {}", dns)),
+                                }
+                            }
+                        }
+                        "mtu" => interface.mtu = Some(parse_and_validate_peer_mtu_value(value).map_err(invalid)?),
+                        "preup" => interface.scripts.pre_up.push(to_script(value)),
+                        "postup" => interface.scripts.post_up.push(to_script(value)),
+                        "predown" => interface.scripts.pre_down.push(to_script(value)),
+                        "postdown" => interface.scripts.post_down.push(to_script(value)),
+                        _ => ignored.push(format!("{} = {}", key, value)),
+                    }
+                }
+                Section::Peer(peer) => {
+                    match key.to_lowercase().as_str() {
+                        "publickey" => peer.public_key = Some(parse_and_validate_wg_key(value).map_err(invalid)?),
+                        "presharedkey" => peer.pre_shared_key = Some(parse_and_validate_wg_key(value).map_err(invalid)?),
+                        "allowedips" => peer.allowed_ips.extend(parse_and_validate_conn_allowed_ips(value).map_err(invalid)?),
+                        "endpoint" => peer.endpoint = Some(parse_and_validate_peer_endpoint(value).map_err(invalid)?),
+                        "persistentkeepalive" => {
+                            peer.persistent_keepalive = if value.eq_ignore_ascii_case("off") {
+                                None
+                            } else {
+                                Some(parse_and_validate_conn_persistent_keepalive_period(value).map_err(invalid)?)
+                            };
+                        }
+                        _ => ignored.push(format!("{} = {}", key, value)),
+                    }
+                }
+            }
+        }
+        if let Section::Peer(peer) = section {
+            peers.push(peer.finish()?);
+        }
+
+        Ok(WgQuickConfig {
+            interface: interface.ok_or(WgQuickParseError::MissingInterface())?,
+            peers,
+            ignored,
+        })
+    }
+}
+
+/// wg-quick runs hooks as-is, while wg-quickrs requires every script to end with a semicolon
+fn to_script(value: &str) -> Script {
+    let script = if value.ends_with(';') { value.to_string() } else { format!("{};", value) };
+    Script { enabled: true, script }
+}
@@ -2,6 +2,7 @@
 use std::net::Ipv4Addr;
 use std::os::unix::fs::PermissionsExt;
 use std::path::{Path, PathBuf};
+use crate::types::config::{AgentWebOidc, NetworkBackend, TelemetryMode, WebRole};
 use crate::validation::error::{ValidationError, ValidationResult};
 use crate::validation::helpers;
 
@@ -75,6 +76,127 @@ pub fn validate_wg_userspace_binary(wg_userspace_binary: &Path) -> ValidationRes
     }
 }
 
+pub fn parse_and_validate_network_backend(network_backend: &str) -> ValidationResult<NetworkBackend> {
+    match network_backend {
+        "netlink" => Ok(NetworkBackend::Netlink),
+        "shell" => Ok(NetworkBackend::Shell),
+        _ => Err(ValidationError::NetworkBackendNotSupported(network_backend.to_string())),
+    }
+}
+
+pub fn parse_and_validate_telemetry_mode(telemetry_mode: &str) -> ValidationResult<TelemetryMode> {
+    match telemetry_mode {
+        "always" => Ok(TelemetryMode::Always),
+        "on-demand" => Ok(TelemetryMode::OnDemand),
+        "off" => Ok(TelemetryMode::Off),
+        _ => Err(ValidationError::TelemetryModeNotSupported(telemetry_mode.to_string())),
+    }
+}
+
+pub fn parse_and_validate_web_role(role: &str) -> ValidationResult<WebRole> {
+    match role {
+        "viewer" => Ok(WebRole::Viewer),
+        "operator" => Ok(WebRole::Operator),
+        "admin" => Ok(WebRole::Admin),
+        _ => Err(ValidationError::WebRoleNotSupported(role.to_string())),
+    }
+}
+
+/// Names end up in tokens and in the author of config history revisions, so they are kept plain
+fn is_plain_name(name: &str) -> bool {
+    (1..=64).contains(&name.len())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+}
+
+pub fn validate_web_user_name(name: &str) -> ValidationResult<String> {
+    if !is_plain_name(name) {
+        return Err(ValidationError::InvalidWebUserName());
+    }
+    Ok(name.to_string())
+}
+
+pub fn validate_api_key_name(name: &str) -> ValidationResult<String> {
+    if !is_plain_name(name) {
+        return Err(ValidationError::InvalidApiKeyName());
+    }
+    Ok(name.to_string())
+}
+
+/// The issuer and redirect URL of OpenID Connect, the provider's own URLs come from its discovery document
+pub fn validate_oidc_url(url: &str) -> ValidationResult<String> {
+    let rest = url.strip_prefix("https://").or_else(|| url.strip_prefix("http://"));
+    if !rest.is_some_and(|rest| !rest.is_empty() && !rest.contains(char::is_whitespace)) {
+        return Err(ValidationError::InvalidOidcUrl());
+    }
+    Ok(url.to_string())
+}
+
+pub fn validate_oidc_client_id(client_id: &str) -> ValidationResult<String> {
+    if client_id.is_empty() {
+        return Err(ValidationError::EmptyOidcClientId());
+    }
+    Ok(client_id.to_string())
+}
+
+pub fn validate_oidc_claim(claim: &str) -> ValidationResult<String> {
+    if claim.is_empty() {
+        return Err(ValidationError::EmptyOidcClaim());
+    }
+    Ok(claim.to_string())
+}
+
+/// Everything a login needs, only checked while OpenID Connect is enabled
+pub fn validate_oidc(oidc: &AgentWebOidc) -> Result<(), (&'static str, ValidationError)> {
+    validate_oidc_url(&oidc.issuer).map_err(|e| ("issuer", e))?;
+    validate_oidc_client_id(&oidc.client_id).map_err(|e| ("client_id", e))?;
+    validate_oidc_url(&oidc.redirect_url).map_err(|e| ("redirect_url", e))?;
+    validate_oidc_claim(&oidc.username_claim).map_err(|e| ("username_claim", e))?;
+    validate_oidc_claim(&oidc.role_claim).map_err(|e| ("role_claim", e))?;
+    if oidc.role_mapping.is_empty() {
+        return Err(("role_mapping", ValidationError::EmptyOidcRoleMapping()));
+    }
+    Ok(())
+}
+
+pub fn parse_and_validate_telemetry_interval(interval: &str) -> ValidationResult<u64> {
+    let interval = interval.parse::<u64>().map_err(|_| ValidationError::InvalidTelemetryInterval())?;
+    validate_telemetry_interval(interval)
+}
+
+pub fn validate_telemetry_interval(interval: u64) -> ValidationResult<u64> {
+    if !(100..=3_600_000).contains(&interval) {
+        return Err(ValidationError::InvalidTelemetryInterval());
+    }
+    Ok(interval)
+}
+
+pub fn parse_and_validate_telemetry_retention(retention: &str, interval: u64) -> ValidationResult<u64> {
+    let retention = retention.parse::<u64>().map_err(|_| ValidationError::InvalidTelemetryRetention())?;
+    validate_telemetry_retention(retention, interval)
+}
+
+/// The retention has to hold at least two samples to show a rate, and not so many that polling gets heavy
+pub fn validate_telemetry_retention(retention: u64, interval: u64) -> ValidationResult<u64> {
+    let samples = retention.saturating_mul(1000) / interval.max(1) + 1;
+    if !(2..=10_000).contains(&samples) {
+        return Err(ValidationError::InvalidTelemetryRetention());
+    }
+    Ok(retention)
+}
+
+pub fn parse_and_validate_config_history_retention(retention: &str) -> ValidationResult<u32> {
+    let retention = retention.parse::<u32>().map_err(|_| ValidationError::InvalidConfigHistoryRetention())?;
+    validate_config_history_retention(retention)
+}
+
+/// At least the current revision is kept, so there is always something to diff against
+pub fn validate_config_history_retention(retention: u32) -> ValidationResult<u32> {
+    if !(1..=10_000).contains(&retention) {
+        return Err(ValidationError::InvalidConfigHistoryRetention());
+    }
+    Ok(retention)
+}
+
 pub fn parse_and_validate_fw_gateway(fw_gateway: &str) -> ValidationResult<String> {
     let interfaces = helpers::get_interfaces();
 
@@ -24,11 +24,30 @@ pub fn validate_config_file(config_file: &mut ConfigFile, config_folder_path: &P
         })?;
     }
 
+    for name in config_file.agent.web.users.keys() {
+        validate_web_user_name(name).map_err(|e| {
+            ConfigFileValidationError::Validation(format!("agent.web.users.{name}"), e)
+        })?;
+    }
+    for name in config_file.agent.web.api_keys.keys() {
+        validate_api_key_name(name).map_err(|e| {
+            ConfigFileValidationError::Validation(format!("agent.web.api_keys.{name}"), e)
+        })?;
+    }
+    if config_file.agent.web.oidc.enabled {
+        validate_oidc(&config_file.agent.web.oidc).map_err(|(field, e)| {
+            ConfigFileValidationError::Validation(format!("agent.web.oidc.{field}"), e)
+        })?;
+    }
+
     // Validate VPN settings
     if config_file.agent.vpn.enabled {
-        validate_wg_tool(&config_file.agent.vpn.wg).map_err(|e| {
-            ConfigFileValidationError::Validation("agent.vpn.wg".to_string(), e)
-        })?;
+        // devices are configured over netlink/UAPI, the wg tool is only needed for AmneziaWG
+        if config_file.network.amnezia_parameters.enabled {
+            validate_wg_tool(&config_file.agent.vpn.wg).map_err(|e| {
+                ConfigFileValidationError::Validation("agent.vpn.wg".to_string(), e)
+            })?;
+        }
 
         if config_file.agent.vpn.wg_userspace.enabled {
             validate_wg_userspace_binary(&config_file.agent.vpn.wg_userspace.binary).map_err(|e| {
@@ -36,6 +55,16 @@ pub fn validate_config_file(config_file: &mut ConfigFile, config_folder_path: &P
             })?;
         }
     }
+    validate_telemetry_interval(config_file.agent.vpn.telemetry.interval).map_err(|e| {
+        ConfigFileValidationError::Validation("agent.vpn.telemetry.interval".to_string(), e)
+    })?;
+    validate_telemetry_retention(config_file.agent.vpn.telemetry.retention, config_file.agent.vpn.telemetry.interval).map_err(|e| {
+        ConfigFileValidationError::Validation("agent.vpn.telemetry.retention".to_string(), e)
+    })?;
+    validate_config_history_retention(config_file.agent.config_history.retention).map_err(|e| {
+        ConfigFileValidationError::Validation("agent.config_history.retention".to_string(), e)
+    })?;
+
     // Validate Firewall scripts
     for (protocol, scripts_map) in [
         ("http", &config_file.agent.firewall.http),
@@ -72,6 +101,11 @@ pub fn validate_config_file(config_file: &mut ConfigFile, config_folder_path: &P
         ConfigFileValidationError::Validation("network.name".to_string(), e)
     })?;
     // skip network.subnet because if it can be deserialized, it means it's valid
+    if let Some(subnet_v6) = &config_file.network.subnet_v6 {
+        validate_ipv6_subnet(subnet_v6).map_err(|e| {
+            ConfigFileValidationError::Validation("network.subnet_v6".to_string(), e)
+        })?;
+    }
     // skip network.this_peer because if it can be deserialized, it means it's valid
 
     // Validate AmneziaWG parameters
@@ -105,6 +139,11 @@ pub fn validate_config_file(config_file: &mut ConfigFile, config_folder_path: &P
         validate_peer_address(&peer.address, &temp_network).map_err(|e| {
             ConfigFileValidationError::Validation(format!("{}.address", peer_path), e)
         })?;
+        if let Some(address_v6) = &peer.address_v6 {
+            validate_peer_address_v6(address_v6, &temp_network).map_err(|e| {
+                ConfigFileValidationError::Validation(format!("{}.address_v6", peer_path), e)
+            })?;
+        }
         validate_peer_endpoint(&peer.endpoint).map_err(|e| {
             ConfigFileValidationError::Validation(format!("{}.endpoint", peer_path), e)
         })?;
@@ -120,7 +159,16 @@ pub fn validate_config_file(config_file: &mut ConfigFile, config_folder_path: &P
         validate_peer_mtu(&peer.mtu).map_err(|e| {
             ConfigFileValidationError::Validation(format!("{}.mtu", peer_path), e)
         })?;
-        // skip network.peers.{peer_id}.private_key because if it can be deserialized, it means it's valid
+        // skip network.peers.{peer_id}.private_key/public_key because if they can be deserialized, they are valid keys
+        validate_peer_keys(&peer.private_key, &peer.public_key).map_err(|e| {
+            ConfigFileValidationError::Validation(format!("{}.private_key", peer_path), e)
+        })?;
+        if *peer_id == config_file.network.this_peer && peer.private_key.is_none() {
+            return Err(ConfigFileValidationError::Validation(
+                format!("{}.private_key", peer_path),
+                ValidationError::ThisPeerWithoutPrivateKey(),
+            ));
+        }
 
         // Validate peer amnezia parameters
         if config_file.network.amnezia_parameters.enabled {
@@ -194,13 +242,18 @@ pub fn validate_config_file(config_file: &mut ConfigFile, config_folder_path: &P
     })?;
 
     // Validate reservations
-    for address in config_file.network.reservations.keys() {
+    for (address, reservation) in &config_file.network.reservations {
         let mut temp_network = config_file.network.clone();
         temp_network.reservations.remove(address);
 
         validate_peer_address(address, &temp_network).map_err(|e| {
             ConfigFileValidationError::Validation(format!("network.reservations.{{{address}}}"), e)
         })?;
+        if let Some(address_v6) = &reservation.address_v6 {
+            validate_peer_address_v6(address_v6, &temp_network).map_err(|e| {
+                ConfigFileValidationError::Validation(format!("network.reservations.{{{address}}}.address_v6"), e)
+            })?;
+        }
         // skip network.reservations.{address}.peer_id because if it can be deserialized, it means it's valid
         // skip network.reservations.{address}.valid_until because if it can be deserialized, it means it's valid
     }
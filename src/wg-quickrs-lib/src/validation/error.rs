@@ -5,6 +5,10 @@ use uuid::Uuid;
 pub enum ValidationError {
     #[error("address is not IPv4")]
     NotIPv4Address(),
+    #[error("address is not IPv6")]
+    NotIPv6Address(),
+    #[error("address is not a valid IPv4 or IPv6 address")]
+    NotIPAddress(),
     #[error("port is not a valid number (1-65535)")]
     NotPortNumber(),
     #[error("tls file is not found")]
@@ -15,12 +19,40 @@ pub enum ValidationError {
     WgToolNotFound(String, String),
     #[error("userspace WireGuard implementation {0} is not found (possible options: [{1}])")]
     WgUserspaceNotFound(String, String),
+    #[error("network backend {0} is not supported (possible options: [netlink, shell])")]
+    NetworkBackendNotSupported(String),
+    #[error("telemetry mode {0} is not supported (possible options: [always, on-demand, off])")]
+    TelemetryModeNotSupported(String),
+    #[error("telemetry interval is invalid (100-3600000 milliseconds)")]
+    InvalidTelemetryInterval(),
+    #[error("telemetry retention is invalid (it has to fit 2-10000 samples of the telemetry interval)")]
+    InvalidTelemetryRetention(),
+    #[error("config history retention is invalid (1-10000 revisions)")]
+    InvalidConfigHistoryRetention(),
+    #[error("web role {0} is not supported (possible options: [viewer, operator, admin])")]
+    WebRoleNotSupported(String),
+    #[error("web user name is invalid (1-64 characters of letters, digits, '.', '_' and '-')")]
+    InvalidWebUserName(),
+    #[error("API key name is invalid (1-64 characters of letters, digits, '.', '_' and '-')")]
+    InvalidApiKeyName(),
+    #[error("URL is invalid (it has to start with http:// or https://)")]
+    InvalidOidcUrl(),
+    #[error("OpenID Connect client ID cannot be empty")]
+    EmptyOidcClientId(),
+    #[error("OpenID Connect claim name cannot be empty")]
+    EmptyOidcClaim(),
+    #[error("OpenID Connect role mapping has to map at least one claim value to a role")]
+    EmptyOidcRoleMapping(),
     #[error("gateway {0} is not found (possible options: {1})")]
     InterfaceNotFound(String, String),
     #[error("firewall utility {0} is not found (possible options: [{1}])")]
     FirewallUtilityNotFound(String, String),
     #[error("subnet is not in CIDR format")]
     NotCIDR(),
+    #[error("IPv6 subnet is not a unique local address range (fc00::/7)")]
+    NotULASubnet(),
+    #[error("network does not have an IPv6 subnet")]
+    NoIPv6Subnet(),
     #[error("uuid is invalid (not in v4 format)")]
     InvalidUuid(),
     #[error("network name cannot be empty")]
@@ -55,6 +87,12 @@ pub enum ValidationError {
     ScriptMissingSemicolonAt(usize),
     #[error("key is not a valid WireGuard key (32 bytes, base64 encoded)")]
     NotWireGuardKey(),
+    #[error("peer needs either a private_key or a public_key")]
+    MissingPeerKey(),
+    #[error("peer can't have both a private_key and a public_key")]
+    AmbiguousPeerKey(),
+    #[error("this_peer needs a private_key")]
+    ThisPeerWithoutPrivateKey(),
     #[error("persistent_keepalive period is not a valid number (1-65535)")]
     InvalidPersistentKeepalivePeriod(),
     #[error("allowed_ips is not in CIDR format")]
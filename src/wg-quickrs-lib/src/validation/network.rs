@@ -1,5 +1,5 @@
-use ipnet::Ipv4Net;
-use std::net::Ipv4Addr;
+use ipnet::{IpNet, Ipv4Net, Ipv6Net};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use chrono::Utc;
 use serde::Deserialize;
 use serde::de::IntoDeserializer;
@@ -19,6 +19,19 @@ pub fn parse_and_validate_ipv4_subnet(subnet: &str) -> ValidationResult<Ipv4Net>
     subnet.parse().map_err(|_| ValidationError::NotCIDR())
 }
 
+pub fn parse_and_validate_ipv6_subnet(subnet: &str) -> ValidationResult<Ipv6Net> {
+    let subnet_ipv6: Ipv6Net = subnet.parse().map_err(|_| ValidationError::NotCIDR())?;
+    validate_ipv6_subnet(&subnet_ipv6)
+}
+
+pub fn validate_ipv6_subnet(subnet: &Ipv6Net) -> ValidationResult<Ipv6Net> {
+    // only unique local addresses (fc00::/7) are handed out to peers
+    if !subnet.network().is_unique_local() {
+        return Err(ValidationError::NotULASubnet());
+    }
+    Ok(*subnet)
+}
+
 pub fn parse_and_validate_peer_id(peer_id: &str) -> ValidationResult<Uuid> {
     peer_id.parse().map_err(|_| ValidationError::InvalidUuid())
 }
@@ -56,6 +69,29 @@ pub fn validate_peer_address(address_ipv4: &Ipv4Addr, network: &Network) -> Vali
     Ok(*address_ipv4)
 }
 
+pub fn parse_and_validate_peer_address_v6(address: &str, network: &Network) -> ValidationResult<Ipv6Addr> {
+    let address_ipv6 = address.parse().map_err(|_| ValidationError::NotIPv6Address())?;
+    validate_peer_address_v6(&address_ipv6, network)
+}
+
+pub fn validate_peer_address_v6(address_ipv6: &Ipv6Addr, network: &Network) -> ValidationResult<Ipv6Addr> {
+    let subnet_v6 = network.subnet_v6.ok_or(ValidationError::NoIPv6Subnet())?;
+    if !subnet_v6.contains(address_ipv6) {
+        return Err(ValidationError::AddressNotInSubnet());
+    }
+    // the subnet-router anycast address
+    if *address_ipv6 == subnet_v6.network() {
+        return Err(ValidationError::AddressIsSubnetNetwork());
+    }
+    if let Some((peer_id, peer)) = network.peers.iter().find(|(_, p)| p.address_v6 == Some(*address_ipv6)) {
+        return Err(ValidationError::AddressIsTaken(*peer_id, peer.name.clone()));
+    }
+    if network.reservations.values().any(|res| res.address_v6 == Some(*address_ipv6) && Utc::now() < res.valid_until) {
+        return Err(ValidationError::AddressIsReserved());
+    }
+    Ok(*address_ipv6)
+}
+
 pub fn parse_and_validate_peer_endpoint(endpoint_address: &str) -> ValidationResult<EndpointAddress> {
     if endpoint_address.is_empty() {
         return Ok(EndpointAddress::None);
@@ -68,6 +104,12 @@ pub fn parse_and_validate_peer_endpoint(endpoint_address: &str) -> ValidationRes
     let port = port_str.parse::<u16>()
         .map_err(|_| ValidationError::InvalidEndpointPort())?;
 
+    // IPv6 endpoints are written in brackets, e.g. [fd00::1]:51820
+    if let Some(ipv6_str) = address_str.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+        let ipv6 = ipv6_str.parse::<Ipv6Addr>().map_err(|_| ValidationError::InvalidEndpoint())?;
+        return Ok(EndpointAddress::Ipv6AndPort(Ipv6AndPort{ ipv6, port }));
+    }
+
     // Try parsing as IPv4 first, then fall back to the hostname
     if let Ok(ipv4) = address_str.parse::<Ipv4Addr>() {
         return Ok(EndpointAddress::Ipv4AndPort(Ipv4AndPort{ ipv4, port }));
@@ -109,9 +151,9 @@ pub fn validate_peer_icon(icon: &Icon) -> ValidationResult<Icon> {
     Ok(icon.clone())
 }
 
-pub fn parse_and_validate_peer_dns_addresses(dns: &str) -> ValidationResult<Vec<Ipv4Addr>> {
+pub fn parse_and_validate_peer_dns_addresses(dns: &str) -> ValidationResult<Vec<IpAddr>> {
     let addresses = dns.split(',')
-        .map(|address| address.trim().parse().map_err(|_| ValidationError::NotIPv4Address()))
+        .map(|address| address.trim().parse().map_err(|_| ValidationError::NotIPAddress()))
         .collect::<ValidationResult<Vec<_>>>()?;
 
     Ok(addresses)
@@ -175,6 +217,14 @@ pub fn parse_and_validate_wg_key(key: &str) -> ValidationResult<WireGuardKey> {
         .map_err(|_: serde::de::value::Error| ValidationError::NotWireGuardKey())
 }
 
+pub fn validate_peer_keys(private_key: &Option<WireGuardKey>, public_key: &Option<WireGuardKey>) -> ValidationResult<()> {
+    match (private_key, public_key) {
+        (None, None) => Err(ValidationError::MissingPeerKey()),
+        (Some(_), Some(_)) => Err(ValidationError::AmbiguousPeerKey()),
+        _ => Ok(()),
+    }
+}
+
 // Network.AmneziaNetworkParameters Fields
 
 pub fn parse_and_validate_amnezia_s1(s1: &str) -> ValidationResult<u16> {
@@ -317,7 +367,7 @@ pub fn validate_conn_persistent_keepalive(persistent_keepalive: &PersistentKeepa
 
 pub fn parse_and_validate_conn_allowed_ips(allowed_ips: &str) -> ValidationResult<AllowedIPs> {
     let ips = allowed_ips.split(',')
-        .map(|cidr| cidr.trim().parse::<Ipv4Net>())
+        .map(|cidr| cidr.trim().parse::<IpNet>())
         .collect::<Result<Vec<_>, _>>()
         .map_err(|_| ValidationError::InvalidAllowedIPs())?;
 
@@ -1,5 +1,9 @@
+use std::collections::BTreeMap;
+use uuid::Uuid;
+use chrono::NaiveDateTime;
 use wg_quickrs_lib::helpers::*;
-use wg_quickrs_lib::types::network::WireGuardKey;
+use wg_quickrs_lib::types::api::{TelemetryHistoryPoint, TelemetryResolution};
+use wg_quickrs_lib::types::network::{Connection, Network, Peer, WireGuardKey};
 
 struct TestVector<'a> {
     priv_b64: &'a str,
@@ -41,3 +45,151 @@ fn test_wireguard_vectors() {
         );
     }
 }
+
+#[test]
+fn test_get_peer_addresses() {
+    let mut peer = Peer {
+        name: "test-peer".to_string(),
+        address: "10.8.3.7".parse().unwrap(),
+        address_v6: Some("fd00:8::3:7".parse().unwrap()),
+        host_address: false,
+        endpoint: Default::default(),
+        kind: Default::default(),
+        icon: Default::default(),
+        dns: Default::default(),
+        mtu: Default::default(),
+        scripts: Default::default(),
+        private_key: Default::default(),
+        public_key: Default::default(),
+        amnezia_parameters: Default::default(),
+        created_at: Default::default(),
+        updated_at: Default::default(),
+    };
+    let mut network = Network {
+        name: Default::default(),
+        subnet: "10.8.0.0/16".parse().unwrap(),
+        subnet_v6: None,
+        this_peer: Default::default(),
+        peers: Default::default(),
+        connections: Default::default(),
+        defaults: Default::default(),
+        reservations: Default::default(),
+        amnezia_parameters: Default::default(),
+        updated_at: Default::default(),
+    };
+    let to_strings = |network: &Network, peer: &Peer| -> Vec<String> {
+        get_peer_addresses(network, peer).iter().map(|a| a.to_string()).collect()
+    };
+
+    // addresses take the subnet prefix length
+    assert_eq!(to_strings(&network, &peer), vec!["10.8.3.7/16"]);
+
+    // IPv6 address is only used on dual-stack networks
+    network.subnet_v6 = Some("fd00:8::/64".parse().unwrap());
+    assert_eq!(to_strings(&network, &peer), vec!["10.8.3.7/16", "fd00:8::3:7/64"]);
+
+    // host address override
+    peer.host_address = true;
+    assert_eq!(to_strings(&network, &peer), vec!["10.8.3.7/32", "fd00:8::3:7/128"]);
+}
+
+#[test]
+fn test_wg_key_fingerprint() {
+    let key = WireGuardKey::from_base64("wODITqX4oJtjT1N4Mx17K2dRaogd9i/ZBhgNVsVoDlg=").unwrap();
+    let fingerprint = wg_key_fingerprint(&key);
+
+    // SHA256: prefix followed by 43 characters of unpadded base64
+    assert!(fingerprint.starts_with("SHA256:"));
+    assert_eq!(fingerprint.len(), "SHA256:".len() + 43);
+    assert_eq!(fingerprint, wg_key_fingerprint(&key));
+    assert_ne!(fingerprint, wg_key_fingerprint(&wg_public_key_from_private_key(&key)));
+}
+
+#[test]
+fn test_get_peer_wg_config_public_key_peer() {
+    let private_key = WireGuardKey::from_base64("wODITqX4oJtjT1N4Mx17K2dRaogd9i/ZBhgNVsVoDlg=").unwrap();
+    let public_key = WireGuardKey::from_base64("uo2hR4Jw2lv/0+db23XgnE32jN5woDKWUHACft7W/Eo=").unwrap();
+    let agent_id = Uuid::parse_str("0ed989c6-6dba-4e3c-8034-08adf4262d9e").unwrap();
+    let phone_id = Uuid::parse_str("6e9a8440-f884-4b54-bfe7-b982f15e40fd").unwrap();
+    let peer = |name: &str, address: &str, private_key: Option<WireGuardKey>, public_key: Option<WireGuardKey>| Peer {
+        name: name.to_string(),
+        address: address.parse().unwrap(),
+        address_v6: None,
+        host_address: false,
+        endpoint: Default::default(),
+        kind: Default::default(),
+        icon: Default::default(),
+        dns: Default::default(),
+        mtu: Default::default(),
+        scripts: Default::default(),
+        private_key,
+        public_key,
+        amnezia_parameters: Default::default(),
+        created_at: Default::default(),
+        updated_at: Default::default(),
+    };
+    let network = Network {
+        name: Default::default(),
+        subnet: "10.8.0.0/24".parse().unwrap(),
+        subnet_v6: None,
+        this_peer: agent_id,
+        peers: BTreeMap::from([
+            (agent_id, peer("agent", "10.8.0.1", Some(private_key), None)),
+            (phone_id, peer("phone", "10.8.0.2", None, Some(public_key))),
+        ]),
+        connections: BTreeMap::from([(get_connection_id(agent_id, phone_id), Connection {
+            enabled: true,
+            pre_shared_key: Default::default(),
+            persistent_keepalive: Default::default(),
+            allowed_ips_a_to_b: vec!["10.8.0.0/24".parse().unwrap()],
+            allowed_ips_b_to_a: vec!["10.8.0.0/24".parse().unwrap()],
+        })]),
+        defaults: Default::default(),
+        reservations: Default::default(),
+        amnezia_parameters: Default::default(),
+        updated_at: Default::default(),
+    };
+
+    assert_eq!(get_peer_public_key(&network.peers[&agent_id]), Some(wg_public_key_from_private_key(&private_key)));
+    assert_eq!(get_peer_public_key(&network.peers[&phone_id]), Some(public_key));
+
+    // the private key of the peer is replaced by a placeholder
+    let phone_conf = get_peer_wg_config(&network, &phone_id, false).unwrap();
+    assert!(phone_conf.contains(&format!("PrivateKey = {}\n", PRIVATE_KEY_PLACEHOLDER)));
+
+    // other peers use its stored public key
+    let agent_conf = get_peer_wg_config(&network, &agent_id, false).unwrap();
+    assert!(agent_conf.contains(&format!("PrivateKey = {}\n", private_key)));
+    assert!(agent_conf.contains(&format!("PublicKey = {}\n", public_key)));
+}
+
+#[test]
+fn test_rollup_telemetry() {
+    let point = |timestamp: &str, handshake: u64, a_to_b: u64, b_to_a: u64| TelemetryHistoryPoint {
+        timestamp: NaiveDateTime::parse_from_str(timestamp, "%Y-%m-%d %H:%M:%S").unwrap(),
+        latest_handshake_at: handshake,
+        transfer_a_to_b: a_to_b,
+        transfer_b_to_a: b_to_a,
+    };
+    let points = vec![
+        point("2025-01-31 23:59:00", 100, 1, 10),
+        point("2025-02-01 00:00:00", 300, 2, 20),
+        point("2025-02-01 00:59:59", 200, 4, 40),
+        point("2025-02-01 01:00:00", 400, 8, 80),
+    ];
+
+    assert_eq!(rollup_telemetry(&points, TelemetryResolution::Raw), points);
+    assert_eq!(rollup_telemetry(&points, TelemetryResolution::Hourly), vec![
+        point("2025-01-31 23:00:00", 100, 1, 10),
+        point("2025-02-01 00:00:00", 300, 6, 60),
+        point("2025-02-01 01:00:00", 400, 8, 80),
+    ]);
+    assert_eq!(rollup_telemetry(&points, TelemetryResolution::Daily), vec![
+        point("2025-01-31 00:00:00", 100, 1, 10),
+        point("2025-02-01 00:00:00", 400, 14, 140),
+    ]);
+
+    // rolling up hourly points again gives the same days
+    let hourly = rollup_telemetry(&points, TelemetryResolution::Hourly);
+    assert_eq!(rollup_telemetry(&hourly, TelemetryResolution::Daily), rollup_telemetry(&points, TelemetryResolution::Daily));
+}
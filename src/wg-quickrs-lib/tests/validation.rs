@@ -6,6 +6,7 @@ use wg_quickrs_lib::validation::network::*;
 use wg_quickrs_lib::validation::agent::*;
 use wg_quickrs_lib::validation::error::*;
 use wg_quickrs_lib::types::network::*;
+use wg_quickrs_lib::types::config::{AgentWebOidc, NetworkBackend, TelemetryMode, WebRole};
 
 
 /// Helper macro for passing tests
@@ -63,6 +64,108 @@ fn test_validate_port() {
     );
 }
 
+#[test]
+fn test_validate_network_backend() {
+    assert_eq!(parse_and_validate_network_backend("netlink"), Ok(NetworkBackend::Netlink));
+    assert_eq!(parse_and_validate_network_backend("shell"), Ok(NetworkBackend::Shell));
+    is_err!(
+        parse_and_validate_network_backend("iproute2"),
+        ValidationError::NetworkBackendNotSupported("iproute2".to_string())
+    );
+}
+
+#[test]
+fn test_validate_telemetry_mode() {
+    assert_eq!(parse_and_validate_telemetry_mode("always"), Ok(TelemetryMode::Always));
+    assert_eq!(parse_and_validate_telemetry_mode("on-demand"), Ok(TelemetryMode::OnDemand));
+    assert_eq!(parse_and_validate_telemetry_mode("off"), Ok(TelemetryMode::Off));
+    is_err!(
+        parse_and_validate_telemetry_mode("sometimes"),
+        ValidationError::TelemetryModeNotSupported("sometimes".to_string())
+    );
+}
+
+#[test]
+fn test_validate_web_role() {
+    assert_eq!(parse_and_validate_web_role("viewer"), Ok(WebRole::Viewer));
+    assert_eq!(parse_and_validate_web_role("operator"), Ok(WebRole::Operator));
+    assert_eq!(parse_and_validate_web_role("admin"), Ok(WebRole::Admin));
+    is_err!(
+        parse_and_validate_web_role("root"),
+        ValidationError::WebRoleNotSupported("root".to_string())
+    );
+    assert!(WebRole::Viewer < WebRole::Operator && WebRole::Operator < WebRole::Admin);
+}
+
+#[test]
+fn test_validate_web_user_name() {
+    assert_eq!(validate_web_user_name("alice"), Ok("alice".to_string()));
+    assert_eq!(validate_web_user_name("ops.team_1-b"), Ok("ops.team_1-b".to_string()));
+    is_err!(validate_web_user_name(""), ValidationError::InvalidWebUserName());
+    is_err!(validate_web_user_name("bob smith"), ValidationError::InvalidWebUserName());
+    is_err!(validate_web_user_name("api:bob"), ValidationError::InvalidWebUserName());
+    is_err!(validate_web_user_name(&"a".repeat(65)), ValidationError::InvalidWebUserName());
+}
+
+#[test]
+fn test_validate_api_key_name() {
+    assert_eq!(validate_api_key_name("ci-deploy"), Ok("ci-deploy".to_string()));
+    is_err!(validate_api_key_name(""), ValidationError::InvalidApiKeyName());
+    is_err!(validate_api_key_name("terraform/prod"), ValidationError::InvalidApiKeyName());
+}
+
+#[test]
+fn test_validate_oidc() {
+    assert_eq!(validate_oidc_url("https://sso.example.com/realms/ops"), Ok("https://sso.example.com/realms/ops".to_string()));
+    assert_eq!(validate_oidc_url("http://127.0.0.1:9000"), Ok("http://127.0.0.1:9000".to_string()));
+    is_err!(validate_oidc_url("sso.example.com"), ValidationError::InvalidOidcUrl());
+    is_err!(validate_oidc_url("https://"), ValidationError::InvalidOidcUrl());
+
+    let mut oidc = AgentWebOidc {
+        enabled: true,
+        issuer: "https://sso.example.com".to_string(),
+        client_id: "wg-quickrs".to_string(),
+        redirect_url: "https://vpn.example.com/api/oidc/callback".to_string(),
+        ..Default::default()
+    };
+    assert_eq!(validate_oidc(&oidc), Err(("role_mapping", ValidationError::EmptyOidcRoleMapping())));
+    oidc.role_mapping.insert("vpn-admins".to_string(), WebRole::Admin);
+    assert_eq!(validate_oidc(&oidc), Ok(()));
+    oidc.client_id = String::new();
+    assert_eq!(validate_oidc(&oidc), Err(("client_id", ValidationError::EmptyOidcClientId())));
+}
+
+#[test]
+fn test_validate_telemetry_interval() {
+    assert_eq!(parse_and_validate_telemetry_interval("1000"), Ok(1000));
+    assert_eq!(parse_and_validate_telemetry_interval("100"), Ok(100));
+    is_err!(parse_and_validate_telemetry_interval("99"), ValidationError::InvalidTelemetryInterval());
+    is_err!(parse_and_validate_telemetry_interval("3600001"), ValidationError::InvalidTelemetryInterval());
+    is_err!(parse_and_validate_telemetry_interval("1s"), ValidationError::InvalidTelemetryInterval());
+}
+
+#[test]
+fn test_validate_telemetry_retention() {
+    assert_eq!(parse_and_validate_telemetry_retention("20", 1000), Ok(20));
+    assert_eq!(parse_and_validate_telemetry_retention("1", 1000), Ok(1));
+    assert_eq!(parse_and_validate_telemetry_retention("9999", 1000), Ok(9999));
+    // shorter than a single interval leaves one sample and no rate
+    is_err!(parse_and_validate_telemetry_retention("0", 1000), ValidationError::InvalidTelemetryRetention());
+    is_err!(parse_and_validate_telemetry_retention("10", 60000), ValidationError::InvalidTelemetryRetention());
+    is_err!(parse_and_validate_telemetry_retention("10000", 1000), ValidationError::InvalidTelemetryRetention());
+    is_err!(parse_and_validate_telemetry_retention("-1", 1000), ValidationError::InvalidTelemetryRetention());
+}
+
+#[test]
+fn test_validate_config_history_retention() {
+    assert_eq!(parse_and_validate_config_history_retention("50"), Ok(50));
+    assert_eq!(parse_and_validate_config_history_retention("1"), Ok(1));
+    assert_eq!(parse_and_validate_config_history_retention("10000"), Ok(10000));
+    is_err!(parse_and_validate_config_history_retention("0"), ValidationError::InvalidConfigHistoryRetention());
+    is_err!(parse_and_validate_config_history_retention("10001"), ValidationError::InvalidConfigHistoryRetention());
+    is_err!(parse_and_validate_config_history_retention("-1"), ValidationError::InvalidConfigHistoryRetention());
+}
+
 // Network Fields
 
 #[test]
@@ -96,6 +199,24 @@ fn test_validate_ipv4_subnet() {
     );
 }
 
+#[test]
+fn test_validate_ipv6_subnet() {
+    ok!(parse_and_validate_ipv6_subnet("fd00::/64"));
+    ok!(parse_and_validate_ipv6_subnet("fdab:cdef:1234::/48"));
+    is_err!(
+        parse_and_validate_ipv6_subnet("not-a-cidr"),
+        ValidationError::NotCIDR()
+    );
+    is_err!(
+        parse_and_validate_ipv6_subnet("10.0.0.0/24"),
+        ValidationError::NotCIDR()
+    );
+    is_err!(
+        parse_and_validate_ipv6_subnet("2001:db8::/64"),
+        ValidationError::NotULASubnet()
+    );
+}
+
 #[test]
 fn test_validate_peer_id() {
     ok!(parse_and_validate_peer_id(&Uuid::new_v4().to_string()));
@@ -108,6 +229,8 @@ fn generate_peer(name: &str, address: &str) -> Peer {
     Peer {
         name: name.to_string(),
         address: address.parse().unwrap(),
+        address_v6: None,
+        host_address: false,
         endpoint: Default::default(),
         kind: Default::default(),
         icon: Default::default(),
@@ -115,6 +238,7 @@ fn generate_peer(name: &str, address: &str) -> Peer {
         mtu: Default::default(),
         scripts: Default::default(),
         private_key: Default::default(),
+        public_key: Default::default(),
         amnezia_parameters: Default::default(),
         created_at: Default::default(),
         updated_at: Default::default(),
@@ -125,6 +249,7 @@ fn generate_network(peers: BTreeMap<Uuid, Peer>, subnet: &str, reservations: BTr
     Network {
         name: Default::default(),
         subnet: subnet.parse().unwrap(),
+        subnet_v6: None,
         this_peer: Default::default(),
         peers,
         connections: Default::default(),
@@ -188,6 +313,7 @@ fn test_validate_peer_address() {
         "10.0.0.0/24",
         BTreeMap::from([("10.0.0.10".into(), ReservationData {
             peer_id: Uuid::new_v4(),
+            address_v6: None,
             valid_until: Utc::now() + Duration::minutes(10),
         })]));
     is_err!(
@@ -201,6 +327,7 @@ fn test_validate_peer_address() {
         "10.0.0.0/24",
         BTreeMap::from([("10.0.0.10".into(), ReservationData {
             peer_id: Uuid::new_v4(),
+            address_v6: None,
             valid_until: Utc::now() - Duration::minutes(10),
         })]));
     ok!(parse_and_validate_peer_address("10.0.0.10", &network));
@@ -215,6 +342,7 @@ fn test_validate_peer_address() {
         "10.0.0.0/24",
         BTreeMap::from([("10.0.0.10".into(), ReservationData {
             peer_id: Uuid::new_v4(),
+            address_v6: None,
             valid_until: Utc::now() - Duration::minutes(10),
         })]));
     ok!(parse_and_validate_peer_address("10.0.0.30", &network));
@@ -230,16 +358,74 @@ fn test_validate_peer_address() {
     );
 }
 
+#[test]
+fn test_validate_peer_address_v6() {
+    // Network without an IPv6 subnet
+    let network = generate_network(BTreeMap::new(), "10.0.0.0/24", BTreeMap::new());
+    is_err!(
+        parse_and_validate_peer_address_v6("fd00::2", &network),
+        ValidationError::NoIPv6Subnet()
+    );
+
+    let mut network = generate_network(BTreeMap::new(), "10.0.0.0/24", BTreeMap::new());
+    network.subnet_v6 = Some("fd00::/64".parse().unwrap());
+    is_err!(
+        parse_and_validate_peer_address_v6("10.0.0.2", &network),
+        ValidationError::NotIPv6Address()
+    );
+    is_err!(
+        parse_and_validate_peer_address_v6("fd00:0:0:1::2", &network),
+        ValidationError::AddressNotInSubnet()
+    );
+    is_err!(
+        parse_and_validate_peer_address_v6("fd00::", &network),
+        ValidationError::AddressIsSubnetNetwork()
+    );
+    ok!(parse_and_validate_peer_address_v6("fd00::2", &network));
+
+    // Address taken by a peer
+    let alice_peer_id = Uuid::new_v4();
+    let mut alice = generate_peer("Alice", "10.0.0.5");
+    alice.address_v6 = Some("fd00::5".parse().unwrap());
+    network.peers.insert(alice_peer_id, alice);
+    is_err!(
+        parse_and_validate_peer_address_v6("fd00::5", &network),
+        ValidationError::AddressIsTaken(alice_peer_id, "Alice".to_string())
+    );
+
+    // Address reserved with a valid reservation (future timestamp)
+    network.reservations.insert("10.0.0.10".parse().unwrap(), ReservationData {
+        peer_id: Uuid::new_v4(),
+        address_v6: Some("fd00::a".parse().unwrap()),
+        valid_until: Utc::now() + Duration::minutes(10),
+    });
+    is_err!(
+        parse_and_validate_peer_address_v6("fd00::a", &network),
+        ValidationError::AddressIsReserved()
+    );
+}
+
 #[test]
 fn test_validate_peer_endpoint() {
     ok!(parse_and_validate_peer_endpoint(""));
     ok!(parse_and_validate_peer_endpoint("10.0.0.1:51820"));
     ok!(parse_and_validate_peer_endpoint("YOUR-SERVER:51820"));
     ok!(parse_and_validate_peer_endpoint("example.com:51820"));
+    assert_eq!(
+        parse_and_validate_peer_endpoint("[2001:db8::1]:51820"),
+        Ok(EndpointAddress::Ipv6AndPort(Ipv6AndPort {
+            ipv6: "2001:db8::1".parse().unwrap(),
+            port: 51820,
+        }))
+    );
     is_err!(
         parse_and_validate_peer_endpoint("notvalid"),
         ValidationError::InvalidEndpoint()
     );
+    is_err!(
+        parse_and_validate_peer_endpoint("[not-an-ip]:51820"),
+        ValidationError::InvalidEndpoint()
+    );
 }
 
 #[test]
@@ -340,17 +526,19 @@ fn test_validate_icon_struct() {
 fn test_validate_peer_dns() {
     ok!(parse_and_validate_peer_dns_addresses("8.8.8.8"));
     ok!(parse_and_validate_peer_dns_addresses("8.8.8.8, 1.1.1.1"));
+    ok!(parse_and_validate_peer_dns_addresses("2606:4700:4700::1111"));
+    ok!(parse_and_validate_peer_dns_addresses("1.1.1.1, 2606:4700:4700::1111"));
     is_err!(
         parse_and_validate_peer_dns_addresses(""),
-        ValidationError::NotIPv4Address()
+        ValidationError::NotIPAddress()
     );
     is_err!(
         parse_and_validate_peer_dns_addresses("8.8.8.8, not-an-ip"),
-        ValidationError::NotIPv4Address()
+        ValidationError::NotIPAddress()
     );
     is_err!(
         parse_and_validate_peer_dns_addresses("not-an-ip"),
-        ValidationError::NotIPv4Address()
+        ValidationError::NotIPAddress()
     );
 }
 
@@ -568,6 +756,15 @@ fn test_validate_wg_key() {
     );
 }
 
+#[test]
+fn test_validate_peer_keys() {
+    let key = parse_and_validate_wg_key("qBZArZg+2vEvD5tS8T7m0H0/xvd1PKdoBHXWIrQ1DEE=").unwrap();
+    ok!(validate_peer_keys(&Some(key), &None));
+    ok!(validate_peer_keys(&None, &Some(key)));
+    is_err!(validate_peer_keys(&None, &None), ValidationError::MissingPeerKey());
+    is_err!(validate_peer_keys(&Some(key), &Some(key)), ValidationError::AmbiguousPeerKey());
+}
+
 // Network.Connection Fields
 
 #[test]
@@ -629,6 +826,8 @@ fn test_validate_persistent_keepalive_struct() {
 fn test_validate_conn_allowed_ips() {
     ok!(parse_and_validate_conn_allowed_ips("10.0.0.0/24"));
     ok!(parse_and_validate_conn_allowed_ips("10.0.0.0/24, 192.168.1.0/24"));
+    ok!(parse_and_validate_conn_allowed_ips("0.0.0.0/0, ::/0"));
+    ok!(parse_and_validate_conn_allowed_ips("fd00::/64"));
     is_err!(
         parse_and_validate_conn_allowed_ips("invalid"),
         ValidationError::InvalidAllowedIPs()
@@ -0,0 +1,72 @@
+use wg_quickrs_lib::types::network::{EndpointAddress, WireGuardKey};
+use wg_quickrs_lib::types::wg_quick::*;
+use wg_quickrs_lib::validation::error::ValidationError;
+
+#[test]
+fn test_parse_wg_quick_config() {
+    let conf = "\
+# wg0 on the server
+[Interface]
+PrivateKey = wODITqX4oJtjT1N4Mx17K2dRaogd9i/ZBhgNVsVoDlg=
+Address = 10.8.0.1/24, fd00:8::1/64
+ListenPort = 51820
+DNS = 1.1.1.1, example.internal
+MTU = 1420
+Table = off
+PostUp = iptables -A FORWARD -i %i -j ACCEPT
+PostDown = iptables -D FORWARD -i %i -j ACCEPT;
+
+[Peer]
+PublicKey = uo2hR4Jw2lv/0+db23XgnE32jN5woDKWUHACft7W/Eo=
+PresharedKey = iF9xlxiI3W/p9LSZ5QhT/4Rk6IHi8v5NzA/UTUdPOVI=
+AllowedIPs = 10.8.0.2/32
+AllowedIPs = fd00:8::2/128
+Endpoint = [2001:db8::2]:51820
+PersistentKeepalive = 25
+
+[Peer]
+PublicKey = wzTKDk4Ws8z58okzyLrJTnN5tkAJmBBOwTIq52RzgWQ=
+AllowedIPs = 10.8.0.3/32  # laptop
+PersistentKeepalive = off
+";
+    let parsed: WgQuickConfig = conf.parse().unwrap();
+
+    let interface = &parsed.interface;
+    assert_eq!(interface.private_key, Some(WireGuardKey::from_base64("wODITqX4oJtjT1N4Mx17K2dRaogd9i/ZBhgNVsVoDlg=").unwrap()));
+    assert_eq!(interface.addresses, vec!["10.8.0.1/24".parse().unwrap(), "fd00:8::1/64".parse().unwrap()]);
+    assert_eq!(interface.listen_port, Some(51820));
+    assert_eq!(interface.dns, vec!["1.1.1.1".parse::<std::net::IpAddr>().unwrap()]);
+    assert_eq!(interface.mtu, Some(1420));
+    // scripts get the semicolon wg-quickrs requires
+    assert_eq!(interface.scripts.post_up[0].script, "iptables -A FORWARD -i %i -j ACCEPT;");
+    assert_eq!(interface.scripts.post_down[0].script, "iptables -D FORWARD -i %i -j ACCEPT;");
+    assert_eq!(parsed.ignored, vec!["DNS = example.internal", "Table = off"]);
+
+    assert_eq!(parsed.peers.len(), 2);
+    let peer = &parsed.peers[0];
+    assert!(peer.pre_shared_key.is_some());
+    assert_eq!(peer.allowed_ips, vec!["10.8.0.2/32".parse().unwrap(), "fd00:8::2/128".parse().unwrap()]);
+    assert!(matches!(peer.endpoint, Some(EndpointAddress::Ipv6AndPort(_))));
+    assert_eq!(peer.persistent_keepalive, Some(25));
+    let peer = &parsed.peers[1];
+    assert_eq!(peer.pre_shared_key, None);
+    assert_eq!(peer.allowed_ips, vec!["10.8.0.3/32".parse().unwrap()]);
+    assert_eq!(peer.endpoint, None);
+    assert_eq!(peer.persistent_keepalive, None);
+}
+
+#[test]
+fn test_parse_wg_quick_config_errors() {
+    assert_eq!("".parse::<WgQuickConfig>(), Err(WgQuickParseError::MissingInterface()));
+    assert_eq!("PrivateKey = x".parse::<WgQuickConfig>(), Err(WgQuickParseError::OutsideSection(1, "PrivateKey".into())));
+    assert_eq!("[Interface]\nPrivateKey".parse::<WgQuickConfig>(), Err(WgQuickParseError::MalformedLine(2)));
+    assert_eq!("[Interfaces]".parse::<WgQuickConfig>(), Err(WgQuickParseError::UnknownSection(1, "Interfaces".into())));
+    assert_eq!(
+        "[Interface]\nPrivateKey = not-a-key".parse::<WgQuickConfig>(),
+        Err(WgQuickParseError::InvalidValue(2, "PrivateKey".into(), ValidationError::NotWireGuardKey()))
+    );
+    assert_eq!(
+        "[Interface]\n\n[Peer]\nAllowedIPs = 10.0.0.2/32\n".parse::<WgQuickConfig>(),
+        Err(WgQuickParseError::MissingPublicKey(3))
+    );
+}
@@ -3,8 +3,11 @@
 export default class API {
     token;
     does_need_auth = false;
+    events_controller = null;
+    // digest of the network the UI shows, changes are only applied on top of it
+    digest = '';
 
-    async call({method, path, headers, body}) {
+    async call({method, path, headers, body, as_text}) {
         if (this.does_need_auth) {
             throw new Error(`A valid token required for ${method} ${path}!`);
         }
@@ -35,25 +38,44 @@ export default class API {
             throw new Error(`${method} ${path}: ${res.status} ${res.statusText}\n${await res.text()}`);
         }
 
+        if (as_text) {
+            return await res.text();
+        }
+
         const json = await res.json();
 
         return json;
     }
 
-    async update_api_token(password) {
+    // without a username, the shared password of the agent is used
+    async update_api_token(password, username) {
         const token_res = await fetch(`${import.meta.env.VITE_API_FETCH_URL_PREFIX}/api/token`, {
             method: "post",
-            body: JSON.stringify({client_id: 'web', password}),
+            body: JSON.stringify({client_id: 'web', password, username: username || undefined}),
         });
         const token = await token_res.text();
         if (token_res.status === 200) {
             this.does_need_auth = false;
             this.token = token;
+        } else if (token_res.status === 429) {
+            const error = new Error(token);
+            error.retry_after = Number(token_res.headers.get('Retry-After')) || 1;
+            throw error;
         } else {
             throw new Error("Unauthorized access");
         }
     }
 
+    // whether the sign in dialog offers logging in with the OpenID Connect provider
+    async get_oidc() {
+        const res = await fetch(`${import.meta.env.VITE_API_FETCH_URL_PREFIX}/api/oidc`);
+        return res.ok ? await res.json() : {enabled: false};
+    }
+
+    oidc_login_url() {
+        return `${import.meta.env.VITE_API_FETCH_URL_PREFIX}/api/oidc/login`;
+    }
+
     async get_network_summary(url_encoded_params) {
         return this.call({
             method: 'get',
@@ -62,10 +84,68 @@ export default class API {
         });
     }
 
+    // EventSource cannot send the Authorization header, so the stream is read through fetch
+    async stream_network_events(on_event) {
+        const headers = {};
+        if (this.token !== '') {
+            headers["Authorization"] = `Bearer ${this.token}`;
+        }
+        this.events_controller = new AbortController();
+        const res = await fetch(`${import.meta.env.VITE_API_FETCH_URL_PREFIX}/api/network/events`, {
+            headers,
+            signal: this.events_controller.signal,
+        });
+        if (res.status === 401) {
+            this.does_need_auth = true;
+        }
+        if (!res.ok) {
+            throw new Error(`get /api/network/events: ${res.status} ${res.statusText}`);
+        }
+
+        const reader = res.body.pipeThrough(new TextDecoderStream()).getReader();
+        let buffer = '';
+        while (true) {
+            const {value, done} = await reader.read();
+            if (done) {
+                return;
+            }
+            buffer += value;
+            let end;
+            while ((end = buffer.indexOf('\n\n')) !== -1) {
+                const frame = buffer.slice(0, end);
+                buffer = buffer.slice(end + 2);
+                let event = 'message', data = '';
+                for (const line of frame.split('\n')) {
+                    if (line.startsWith('event: ')) {
+                        event = line.slice('event: '.length);
+                    } else if (line.startsWith('data: ')) {
+                        data += line.slice('data: '.length);
+                    }
+                }
+                if (data) {
+                    on_event(event, JSON.parse(data));
+                }
+            }
+        }
+    }
+
+    close_network_events() {
+        this.events_controller?.abort();
+    }
+
+    async get_network_peer_conf(peer_id) {
+        return this.call({
+            method: 'get',
+            path: `/api/network/peers/${peer_id}/conf`,
+            as_text: true
+        });
+    }
+
     async patch_network_config(change_sum) {
         return this.call({
             method: 'patch',
             path: `/api/network/config`,
+            headers: this.digest ? {"If-Match": `"${this.digest}"`} : {},
             body: change_sum
         });
     }
@@ -45,10 +45,31 @@ export default class WireGuardHelper {
         if ('ipv4_and_port' in endpoint.address) {
             return `${endpoint.address.ipv4_and_port.ipv4}:${endpoint.address.ipv4_and_port.port}`;
         }
+        if ('ipv6_and_port' in endpoint.address) {
+            return `[${endpoint.address.ipv6_and_port.ipv6}]:${endpoint.address.ipv6_and_port.port}`;
+        }
         if ('hostname_and_port' in endpoint.address) {
             return `${endpoint.address.hostname_and_port.hostname}:${endpoint.address.hostname_and_port.port}`;
         }
         return "";
     }
 
+    // The summary api redacts private and pre-shared keys, but the wasm validators expect a complete network.
+    // Return a copy of the network with the redacted secrets filled with placeholder keys (all zeros).
+    static fill_redacted_secrets(network) {
+        const placeholder_key = 'AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=';
+        const network_copy = JSON.parse(JSON.stringify(network));
+        for (const peer of Object.values(network_copy.peers)) {
+            // peers without a fingerprint only have a public key
+            if (peer.private_key_fingerprint !== undefined) {
+                peer.private_key ??= placeholder_key;
+                delete peer.public_key;
+            }
+        }
+        for (const connection of Object.values(network_copy.connections)) {
+            connection.pre_shared_key ??= placeholder_key;
+        }
+        return network_copy;
+    }
+
 }
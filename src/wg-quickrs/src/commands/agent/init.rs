@@ -494,16 +494,21 @@ pub fn initialize_agent(init_opts: &InitOptions) -> Result<(), AgentInitError> {
             parse_and_validate_port,
         );
 
-        // --agent-vpn-wg
-        let agent_vpn_wg = get_value(
-            init_opts.no_prompt,
-            step_str(step_counter),
-            init_opts.agent_vpn_wg.clone().map(|o| o.display().to_string()),
-            INIT_AGENT_VPN_WG_FLAG,
-            format!("\t{}", INIT_AGENT_VPN_WG_HELP).as_str(),
-            wg_tool_options().into_iter().next().map(|o| o.display().to_string()),
-            parse_and_validate_wg_tool,
-        );
+        // --agent-vpn-wg (only needed for AmneziaWG, skipped when no wg/awg tool is installed)
+        let agent_vpn_wg = if init_opts.agent_vpn_wg.is_none() && wg_tool_options().is_empty() {
+            println!("{} No wg/awg tool found (only needed for AmneziaWG), skipping...", step_str(step_counter));
+            PathBuf::new()
+        } else {
+            get_value(
+                init_opts.no_prompt,
+                step_str(step_counter),
+                init_opts.agent_vpn_wg.clone().map(|o| o.display().to_string()),
+                INIT_AGENT_VPN_WG_FLAG,
+                format!("\t{}", INIT_AGENT_VPN_WG_HELP).as_str(),
+                wg_tool_options().into_iter().next().map(|o| o.display().to_string()),
+                parse_and_validate_wg_tool,
+            )
+        };
 
         // --agent-vpn-wg-userspace-enabled & --agent-vpn-wg-userspace-binary
         let agent_vpn_wg_userspace_enabled = get_bool(
@@ -918,6 +923,7 @@ pub fn initialize_agent(init_opts: &InitOptions) -> Result<(), AgentInitError> {
     let temp_network = Network {
         name: "".to_string(),
         subnet: network_subnet,
+        subnet_v6: None,
         this_peer: Default::default(),
         peers: Default::default(),
         connections: Default::default(),
@@ -1355,6 +1361,12 @@ pub fn initialize_agent(init_opts: &InitOptions) -> Result<(), AgentInitError> {
                     enabled: agent_web_password_enabled,
                     hash: agent_web_password_hash,
                 },
+                metrics: Default::default(),
+                users: BTreeMap::new(),
+                peer_tokens: BTreeMap::new(),
+                api_keys: BTreeMap::new(),
+                persistent_jwt_key: PersistentJwtKey::default(),
+                oidc: AgentWebOidc::default(),
             },
             vpn: AgentVpn {
                 enabled: agent_vpn_enabled,
@@ -1364,22 +1376,28 @@ pub fn initialize_agent(init_opts: &InitOptions) -> Result<(), AgentInitError> {
                     enabled: agent_vpn_wg_userspace_enabled,
                     binary: agent_vpn_wg_userspace_binary,
                 },
+                network_backend: Default::default(),
+                telemetry: Default::default(),
             },
             firewall: AgentFirewall {
                 http: http_firewall_scripts,
                 https: https_firewall_scripts,
                 vpn: vpn_firewall_scripts,
             },
+            config_history: Default::default(),
         },
         network: Network {
             name: network_name.to_string(),
             subnet: network_subnet,
+            subnet_v6: None,
             this_peer: peer_id,
             peers: {
                 let mut map = BTreeMap::new();
                 map.insert(peer_id, Peer {
                     name: agent_peer_name.to_string(),
                     address: agent_peer_vpn_internal_address,
+                    address_v6: None,
+                    host_address: false,
                     endpoint: Endpoint {
                         enabled: true,
                         address: agent_peer_vpn_endpoint,
@@ -1403,7 +1421,8 @@ pub fn initialize_agent(init_opts: &InitOptions) -> Result<(), AgentInitError> {
                         pre_down: agent_peer_script_pre_down,
                         post_down: agent_peer_script_post_down,
                     },
-                    private_key: wg_generate_key(),
+                    private_key: Some(wg_generate_key()),
+                    public_key: None,
                     amnezia_parameters: agent_peer_amnezia_parameters,
                     created_at: now,
                     updated_at: now,
@@ -1,8 +1,18 @@
-use crate::web::server;
-use crate::{conf, wireguard};
+use crate::conf::control::{self, ControlError};
+use crate::web::{events, server};
+use crate::{conf, wireguard, WG_QUICKRS_CONFIG_FILE};
+use futures_util::future::LocalBoxFuture;
+use serde_json::json;
+use std::time::{Duration, SystemTime};
 use thiserror::Error;
-use tokio::try_join;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::mpsc;
 use crate::conf::util::ConfUtilError;
+use wg_quickrs_cli::agent::RunOptions;
+use wg_quickrs_lib::types::config::Config;
+
+/// How often conf.yml is checked for changes with `--watch-config`
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
 
 #[derive(Error, Debug)]
 pub enum AgentRunError {
@@ -10,12 +20,156 @@ pub enum AgentRunError {
     Conf(#[from] ConfUtilError),
     #[error("io error: {0}")]
     IO(#[from] std::io::Error),
+    #[error("{0}")]
+    Control(#[from] ControlError),
+    #[error("another agent is already running with this config folder")]
+    AlreadyRunning(),
+}
+
+type ServiceFuture = LocalBoxFuture<'static, std::io::Result<()>>;
+
+fn start_web_server(config: Config) -> ServiceFuture {
+    Box::pin(async move { server::run_web_server(&config).await })
+}
+
+fn start_vpn_server(config: Config) -> ServiceFuture {
+    Box::pin(async move { wireguard::cmd::run_vpn_server(&config).await })
+}
+
+/// Settings only applied when the HTTP/HTTPS servers start
+fn web_server_settings(config: &Config) -> serde_json::Value {
+    json!([
+        config.agent.web.address,
+        config.agent.web.http,
+        config.agent.web.https,
+        config.agent.firewall.http,
+        config.agent.firewall.https,
+    ])
+}
+
+/// Settings only applied when the tunnel is brought up
+fn vpn_server_settings(config: &Config) -> serde_json::Value {
+    let mut vpn = json!(config.agent.vpn);
+    // the running tunnel reads the telemetry settings on every tick
+    if let Some(vpn) = vpn.as_object_mut() {
+        vpn.remove("telemetry");
+    }
+    json!([vpn, config.agent.firewall.vpn])
+}
+
+fn config_file_modified() -> Option<SystemTime> {
+    std::fs::metadata(WG_QUICKRS_CONFIG_FILE.get().unwrap())
+        .and_then(|metadata| metadata.modified())
+        .ok()
 }
 
-pub async fn run_agent() -> Result<(), AgentRunError> {
+struct Services {
+    web: ServiceFuture,
+    web_running: bool,
+    vpn: ServiceFuture,
+    vpn_running: bool,
+}
+
+impl Services {
+    fn start(config: &Config) -> Self {
+        Services {
+            web: start_web_server(config.clone()),
+            web_running: true,
+            vpn: start_vpn_server(config.clone()),
+            vpn_running: true,
+        }
+    }
+
+    /// Loads conf.yml again and restarts only what can't take the change while running
+    async fn reload(&mut self) -> Result<(), AgentRunError> {
+        let Some((previous, config)) = conf::util::reload_config()? else {
+            log::debug!("config file is unchanged, nothing to reload");
+            return Ok(());
+        };
+
+        if web_server_settings(&previous) != web_server_settings(&config) {
+            log::info!("web server settings changed, restarting the web server");
+            if self.web_running {
+                server::stop_web_server();
+                (&mut self.web).await?;
+            }
+            self.web = start_web_server(config.clone());
+            self.web_running = true;
+        }
+
+        if vpn_server_settings(&previous) != vpn_server_settings(&config) {
+            log::info!("VPN settings changed, restarting the WireGuard tunnel");
+            if previous.agent.vpn.enabled && let Err(e) = wireguard::cmd::disable_tunnel() {
+                log::error!("Failed to disable the wireguard tunnel: {e}");
+            }
+            self.vpn = start_vpn_server(config.clone());
+            self.vpn_running = true;
+        } else if config.agent.vpn.enabled
+            && json!(previous.network) != json!(config.network)
+            && let Err(e) = wireguard::cmd::sync_conf(&config)
+        {
+            log::error!("Failed to synchronize the reloaded config: {e}");
+        }
+
+        if let Some(config_w_digest) = conf::util::CONFIG_W_NETWORK_DIGEST.get()
+            && let Ok(config_w_digest) = config_w_digest.read()
+        {
+            events::publish_change(None, &config_w_digest.network_w_digest.digest);
+        }
+        Ok(())
+    }
+
+    async fn reload_or_log(&mut self) {
+        if let Err(e) = self.reload().await {
+            log::error!("Failed to reload the config file, keeping the running config: {e}");
+        }
+    }
+}
+
+pub async fn run_agent(run_opts: &RunOptions) -> Result<(), AgentRunError> {
+    // a second agent would take the control socket and the tunnel over from the first one
+    if control::agent_listening()? {
+        return Err(AgentRunError::AlreadyRunning());
+    }
     let config = conf::util::get_config()?;
-    let web_future = server::run_web_server(&config);
-    let vpn_future = wireguard::cmd::run_vpn_server(&config);
-    try_join!(web_future, vpn_future)?;
+    let mut services = Services::start(&config);
+
+    let mut signal_hangup = signal(SignalKind::hangup())?;
+    let mut watch_ticker = tokio::time::interval(WATCH_INTERVAL);
+    let mut modified = config_file_modified();
+    let (reload_sender, mut reload_requests) = mpsc::unbounded_channel();
+    actix_web::rt::spawn(control::run_control_socket(reload_sender));
+
+    // runs until both the web server and the tunnel stopped, e.g. on SIGTERM
+    while services.web_running || services.vpn_running {
+        tokio::select! {
+            result = &mut services.web, if services.web_running => {
+                services.web_running = false;
+                result?;
+            }
+            result = &mut services.vpn, if services.vpn_running => {
+                services.vpn_running = false;
+                result?;
+            }
+            _ = signal_hangup.recv() => {
+                log::info!("Received SIGHUP, reloading the config file");
+                services.reload_or_log().await;
+            }
+            Some(reply) = reload_requests.recv() => {
+                log::info!("Reloading the config file for the CLI");
+                let result = services.reload().await.map_err(|e| e.to_string());
+                let _ = reply.send(result);
+            }
+            _ = watch_ticker.tick(), if run_opts.watch_config => {
+                let now_modified = config_file_modified();
+                if now_modified != modified {
+                    modified = now_modified;
+                    // writes of the agent itself change the modification time too, those are not loaded again
+                    services.reload_or_log().await;
+                }
+            }
+        }
+    }
+    control::remove_control_socket();
     Ok(())
 }
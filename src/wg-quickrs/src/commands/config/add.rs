@@ -2,11 +2,16 @@ use crate::conf;
 use crate::commands::config::{ConfigCommandError};
 use crate::commands::helpers::*;
 use chrono::Utc;
+use rand::distr::Alphanumeric;
+use rand::{rng, Rng};
 use uuid::Uuid;
 use wg_quickrs_lib::helpers::{get_connection_id, wg_generate_key};
 use wg_quickrs_lib::types::network::*;
 use wg_quickrs_lib::validation::network::*;
-use wg_quickrs_cli::config::add::{AddPeerOptions, AddConnectionOptions};
+use wg_quickrs_cli::config::add::{AddPeerOptions, AddConnectionOptions, AddUserOptions, AddPeerTokenOptions, AddApiKeyOptions};
+use wg_quickrs_lib::types::config::{AgentWebApiKey, AgentWebPeerToken, AgentWebUser};
+use wg_quickrs_lib::validation::agent::{parse_and_validate_web_role, validate_api_key_name, validate_web_user_name};
+use crate::commands::config::reset::read_new_password;
 
 include!(concat!(env!("OUT_DIR"), "/add_peer_options_generated.rs"));
 include!(concat!(env!("OUT_DIR"), "/add_connection_options_generated.rs"));
@@ -17,8 +22,9 @@ pub fn add_peer(opts: &AddPeerOptions) -> Result<(), ConfigCommandError> {
 
     // Generate new peer ID
     let peer_id = Uuid::new_v4();
+
     let mut step_counter = 1;
-    let step_str = make_step_formatter(11);
+    let step_str = make_step_formatter(12);
 
     // Get peer name
     let peer_name = get_value(
@@ -33,6 +39,7 @@ pub fn add_peer(opts: &AddPeerOptions) -> Result<(), ConfigCommandError> {
     step_counter += 1;
 
     // Get peer address
+    let next_addresses = conf::network::get_next_available_address(&config.network);
     let network_copy = config.network.clone();
     let peer_address = get_value(
         opts.no_prompt,
@@ -40,10 +47,26 @@ pub fn add_peer(opts: &AddPeerOptions) -> Result<(), ConfigCommandError> {
         opts.address.map(|o| o.to_string()),
         ADD_PEER_ADDRESS_FLAG,
         ADD_PEER_ADDRESS_HELP,
-        conf::network::get_next_available_address(&config.network).map(|o| o.to_string()),
+        next_addresses.map(|(o, _)| o.to_string()),
         move |s: &str| parse_and_validate_peer_address(s, &network_copy),
     );
 
+    // Get peer IPv6 address (dual-stack networks only)
+    let peer_address_v6 = if config.network.subnet_v6.is_some() {
+        let network_copy = config.network.clone();
+        Some(get_value(
+            opts.no_prompt,
+            step_str(step_counter),
+            opts.address_v6.map(|o| o.to_string()),
+            ADD_PEER_ADDRESS_V6_FLAG,
+            ADD_PEER_ADDRESS_V6_HELP,
+            next_addresses.and_then(|(_, o)| o).map(|o| o.to_string()),
+            move |s: &str| parse_and_validate_peer_address_v6(s, &network_copy),
+        ))
+    } else {
+        None
+    };
+
     // Get endpoint
     let endpoint_enabled = get_bool(
         opts.no_prompt,
@@ -194,11 +217,31 @@ pub fn add_peer(opts: &AddPeerOptions) -> Result<(), ConfigCommandError> {
         ADD_PEER_SCRIPT_POST_DOWN_ENABLED_HELP,
         "",
     );
+    step_counter += 1;
+
+    // Peers that generate their keys on their own devices only share the public key
+    let (private_key, public_key) = match &opts.public_key {
+        Some(public_key) => (None, Some(get_value(
+            opts.no_prompt,
+            step_str(step_counter),
+            Some(public_key.clone()),
+            ADD_PEER_PUBLIC_KEY_FLAG,
+            ADD_PEER_PUBLIC_KEY_HELP,
+            None,
+            parse_and_validate_wg_key,
+        ))),
+        None => {
+            println!("{} Generating a new private key (CLI option '{}' is not set)", step_str(step_counter), ADD_PEER_PUBLIC_KEY_FLAG);
+            (Some(wg_generate_key()), None)
+        }
+    };
 
     // Create the peer
     let peer = Peer {
         name: peer_name.clone(),
         address: peer_address,
+        address_v6: peer_address_v6,
+        host_address: false,
         endpoint: Endpoint {
             enabled: endpoint_enabled,
             address: endpoint_address,
@@ -222,7 +265,8 @@ pub fn add_peer(opts: &AddPeerOptions) -> Result<(), ConfigCommandError> {
             pre_down: script_pre_down,
             post_down: script_post_down,
         },
-        private_key: wg_generate_key(),
+        private_key,
+        public_key,
         amnezia_parameters: config.network.defaults.peer.amnezia_parameters.clone(),  // TODO: make this configurable
         created_at: Utc::now(),
         updated_at: Utc::now(),
@@ -468,3 +512,107 @@ pub fn add_connection(opts: &AddConnectionOptions) -> Result<(), ConfigCommandEr
     log::info!("Successfully added connection {}", conn_id);
     Ok(())
 }
+
+/// Add a named user of the web UI and API, it signs in with its own password
+pub fn add_user(opts: &AddUserOptions) -> Result<(), ConfigCommandError> {
+    let mut config = conf::util::get_config()?;
+
+    let name = validate_web_user_name(&opts.name)?;
+    if config.agent.web.users.contains_key(&name) {
+        return Err(ConfigCommandError::UserExists(name));
+    }
+    let role = parse_and_validate_web_role(&opts.role)?;
+    let password = read_new_password(&opts.password)?;
+    let hash = calculate_password_hash(password.trim())?;
+
+    config.agent.web.users.insert(name.clone(), AgentWebUser { role, hash });
+    if !config.agent.web.password.enabled {
+        log::warn!("Password authentication is disabled, users only have to sign in once it is enabled");
+    }
+
+    log::info!("Added user {} ({})", name, role);
+    conf::util::set_config(&mut config)?;
+    Ok(())
+}
+
+/// Issue a token limited to a single peer, only its hash is kept so it is printed this one time
+pub fn add_peer_token(opts: &AddPeerTokenOptions) -> Result<(), ConfigCommandError> {
+    let mut config = conf::util::get_config()?;
+
+    if opts.peer_id == config.network.this_peer {
+        return Err(ConfigCommandError::PeerTokenForThisPeer(opts.peer_id));
+    }
+    if !config.network.peers.contains_key(&opts.peer_id) {
+        return Err(ConfigCommandError::PeerNotFound(opts.peer_id));
+    }
+
+    // the id finds the token's hash, so only the secret has to be checked against it
+    let id = loop {
+        let id: String = rng().sample_iter(&Alphanumeric).take(8).map(|c| char::from(c).to_ascii_lowercase()).collect();
+        if !config.agent.web.peer_tokens.contains_key(&id) {
+            break id;
+        }
+    };
+    let secret: String = rng().sample_iter(&Alphanumeric).take(40).map(char::from).collect();
+    config.agent.web.peer_tokens.insert(id.clone(), AgentWebPeerToken {
+        peer_id: opts.peer_id,
+        key_rotation: opts.key_rotation,
+        hash: calculate_password_hash(&secret)?,
+    });
+    if !config.agent.web.password.enabled {
+        log::warn!("Password authentication is disabled, the API is open to everyone without the token");
+    }
+
+    log::info!("Added peer token {} for peer {}", id, opts.peer_id);
+    conf::util::set_config(&mut config)?;
+    println!("{}.{}", id, secret);
+    Ok(())
+}
+
+/// Issue a long-lived API key acting with a role, only its hash is kept so it is printed this one time
+pub fn add_api_key(opts: &AddApiKeyOptions) -> Result<(), ConfigCommandError> {
+    let mut config = conf::util::get_config()?;
+
+    let name = validate_api_key_name(&opts.name)?;
+    if config.agent.web.api_keys.contains_key(&name) {
+        return Err(ConfigCommandError::ApiKeyExists(name));
+    }
+    let role = parse_and_validate_web_role(&opts.role)?;
+
+    // the id finds the key's hash, so only the secret has to be checked against it
+    let id = loop {
+        let id: String = rng().sample_iter(&Alphanumeric).take(8).map(|c| char::from(c).to_ascii_lowercase()).collect();
+        if !config.agent.web.api_keys.values().any(|key| key.id == id) {
+            break id;
+        }
+    };
+    let secret: String = rng().sample_iter(&Alphanumeric).take(40).map(char::from).collect();
+    config.agent.web.api_keys.insert(name.clone(), AgentWebApiKey {
+        id: id.clone(),
+        role,
+        created_at: Utc::now(),
+        hash: conf::api_keys::hash_secret(&secret),
+    });
+    if !config.agent.web.password.enabled {
+        log::warn!("Password authentication is disabled, the API is open to everyone without the key");
+    }
+
+    log::info!("Added API key {} ({})", name, role);
+    conf::util::set_config(&mut config)?;
+    println!("wgq_{}.{}", id, secret);
+    Ok(())
+}
+
+/// Give users whose role claim contains `value` a role when they sign in with OpenID Connect
+pub fn add_oidc_role_mapping(value: &str, role: &str) -> Result<(), ConfigCommandError> {
+    let mut config = conf::util::get_config()?;
+
+    let role = parse_and_validate_web_role(role)?;
+    if let Some(previous) = config.agent.web.oidc.role_mapping.insert(value.to_string(), role) {
+        log::info!("Changing OpenID Connect role mapping {} from {} to {}", value, previous, role);
+    } else {
+        log::info!("Added OpenID Connect role mapping {} ({})", value, role);
+    }
+    conf::util::set_config(&mut config)?;
+    Ok(())
+}
@@ -99,6 +99,8 @@ macro_rules! impl_reservation_getter {
 impl_peer_getter!(get_network_peer);
 impl_peer_getter!(get_network_peer_name, name);
 impl_peer_getter!(get_network_peer_address, address);
+impl_peer_getter!(get_network_peer_address_v6, address_v6, yaml);
+impl_peer_getter!(get_network_peer_host_address, host_address);
 impl_peer_getter!(get_network_peer_endpoint, endpoint, yaml);
 impl_peer_getter!(get_network_peer_endpoint_enabled, endpoint.enabled);
 impl_peer_getter!(get_network_peer_endpoint_address, endpoint.address, yaml);
@@ -113,7 +115,7 @@ impl_peer_getter!(get_network_peer_mtu, mtu, yaml);
 impl_peer_getter!(get_network_peer_mtu_enabled, mtu.enabled);
 impl_peer_getter!(get_network_peer_mtu_value, mtu.value);
 impl_peer_getter!(get_network_peer_scripts, scripts, yaml);
-impl_peer_getter!(get_network_peer_private_key, private_key);
+impl_peer_getter!(get_network_peer_private_key, private_key, yaml);
 impl_peer_getter!(get_network_peer_amnezia_parameters, amnezia_parameters, yaml);
 impl_peer_getter!(get_network_peer_amnezia_parameters_jc, amnezia_parameters.jc);
 impl_peer_getter!(get_network_peer_amnezia_parameters_jmin, amnezia_parameters.jmin);
@@ -121,6 +123,14 @@ impl_peer_getter!(get_network_peer_amnezia_parameters_jmax, amnezia_parameters.j
 impl_peer_getter!(get_network_peer_created_at, created_at);
 impl_peer_getter!(get_network_peer_updated_at, updated_at);
 
+/// Get peer public key, derived from the private key unless the peer only has a public key
+pub fn get_network_peer_public_key(id: &Uuid) -> Result<(), ConfigCommandError> {
+    let config = conf::util::get_config()?;
+    let peer = config.network.peers.get(id)
+        .ok_or_else(|| ConfigCommandError::PeerNotFound(*id))?;
+    print_as_yaml(&wg_quickrs_lib::helpers::get_peer_public_key(peer))
+}
+
 // Network indexed getters for connections (using macros)
 
 impl_connection_getter!(get_network_connection);
@@ -176,6 +186,8 @@ impl_config_getter!(get_agent_web, agent.web, yaml);
 impl_config_getter!(get_agent_web_http, agent.web.http, yaml);
 impl_config_getter!(get_agent_web_https, agent.web.https, yaml);
 impl_config_getter!(get_agent_web_password, agent.web.password, yaml);
+impl_config_getter!(get_agent_web_metrics, agent.web.metrics, yaml);
+impl_config_getter!(get_agent_web_metrics_token, agent.web.metrics.token, yaml);
 impl_config_getter!(get_agent_vpn, agent.vpn, yaml);
 
 // Agent individual field getters
@@ -188,12 +200,24 @@ impl_config_getter!(get_agent_web_https_tls_cert, agent.web.https.tls_cert, disp
 impl_config_getter!(get_agent_web_https_tls_key, agent.web.https.tls_key, display);
 impl_config_getter!(get_agent_web_password_enabled, agent.web.password.enabled);
 impl_config_getter!(get_agent_web_password_hash, agent.web.password.hash);
+impl_config_getter!(get_agent_web_metrics_enabled, agent.web.metrics.enabled);
+impl_config_getter!(get_agent_web_metrics_token_enabled, agent.web.metrics.token.enabled);
+impl_config_getter!(get_agent_web_metrics_token_hash, agent.web.metrics.token.hash);
 impl_config_getter!(get_agent_vpn_enabled, agent.vpn.enabled);
 impl_config_getter!(get_agent_vpn_port, agent.vpn.port);
 impl_config_getter!(get_agent_vpn_wg, agent.vpn.wg, display);
 impl_config_getter!(get_agent_vpn_wg_userspace, agent.vpn.wg_userspace, yaml);
 impl_config_getter!(get_agent_vpn_wg_userspace_enabled, agent.vpn.wg_userspace.enabled);
 impl_config_getter!(get_agent_vpn_wg_userspace_binary, agent.vpn.wg_userspace.binary, display);
+impl_config_getter!(get_agent_vpn_network_backend, agent.vpn.network_backend);
+impl_config_getter!(get_agent_vpn_telemetry, agent.vpn.telemetry, yaml);
+impl_config_getter!(get_agent_vpn_telemetry_mode, agent.vpn.telemetry.mode);
+impl_config_getter!(get_agent_vpn_telemetry_interval, agent.vpn.telemetry.interval);
+impl_config_getter!(get_agent_vpn_telemetry_retention, agent.vpn.telemetry.retention);
+impl_config_getter!(get_agent_vpn_telemetry_history, agent.vpn.telemetry.history, yaml);
+impl_config_getter!(get_agent_vpn_telemetry_history_enabled, agent.vpn.telemetry.history.enabled);
+impl_config_getter!(get_agent_config_history, agent.config_history, yaml);
+impl_config_getter!(get_agent_config_history_retention, agent.config_history.retention);
 
 // Network struct getter
 impl_config_getter!(get_network, network, yaml);
@@ -201,6 +225,7 @@ impl_config_getter!(get_network, network, yaml);
 // Network individual field getters
 impl_config_getter!(get_network_name, network.name);
 impl_config_getter!(get_network_subnet, network.subnet);
+impl_config_getter!(get_network_subnet_v6, network.subnet_v6, yaml);
 impl_config_getter!(get_network_this_peer, network.this_peer);
 impl_config_getter!(get_network_peers, network.peers, yaml);
 impl_config_getter!(get_network_connections, network.connections, yaml);
@@ -0,0 +1,51 @@
+use crate::commands::config::ConfigCommandError;
+use crate::conf;
+use crate::conf::history;
+
+/// List all kept revisions in human-readable format
+/// Format: "revision timestamp author: summary{ (rollback of revision) if rolled back}"
+pub fn list_config_history() -> Result<(), ConfigCommandError> {
+    let revisions = history::list()?;
+
+    if revisions.is_empty() {
+        println!("No revisions found.");
+        return Ok(());
+    }
+
+    for entry in &revisions {
+        let rollback_str = match entry.rollback_of {
+            Some(revision) => format!(" (rollback of {})", revision),
+            None => String::new(),
+        };
+        println!("{} {} {}: {}{}", entry.revision, entry.timestamp.format("%Y-%m-%d %H:%M:%S UTC"), entry.author, entry.summary, rollback_str);
+    }
+
+    Ok(())
+}
+
+pub fn show_config_history(revision: u64) -> Result<(), ConfigCommandError> {
+    let (_, config_file_str) = history::read(revision)?;
+    print!("{}", config_file_str);
+    Ok(())
+}
+
+pub fn diff_config_history(revision: u64, against: Option<u64>) -> Result<(), ConfigCommandError> {
+    let (_, to_str) = history::read(revision)?;
+    let from = match against {
+        Some(against) => Some(against),
+        None => history::previous(revision)?,
+    };
+    let (from_str, from_label) = match from {
+        Some(from) => (history::read(from)?.1, format!("a/{from}.yml")),
+        // the oldest kept revision is diffed against nothing
+        None => (String::new(), "/dev/null".to_string()),
+    };
+    print!("{}", history::diff(&from_str, &to_str, &from_label, &format!("b/{revision}.yml")));
+    Ok(())
+}
+
+pub fn rollback_config_history(revision: u64) -> Result<(), ConfigCommandError> {
+    log::info!("Rolling back conf.yml to revision {}", revision);
+    conf::util::rollback_config(revision)?;
+    Ok(())
+}
@@ -0,0 +1,274 @@
+use crate::conf;
+use crate::commands::config::ConfigCommandError;
+use chrono::Utc;
+use ipnet::IpNet;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+use wg_quickrs_lib::helpers::{get_connection_id, get_peer_public_key, wg_generate_key, wg_public_key_from_private_key};
+use wg_quickrs_lib::types::network::*;
+use wg_quickrs_lib::types::wg_quick::{WgQuickConfig, WgQuickParseError, WgQuickPeer};
+use wg_quickrs_lib::validation::network::*;
+
+/// Import wg-quick/wg configuration files into the network.
+/// Every file's [Interface] becomes a peer, every [Peer] section matched by its public key becomes a connection.
+/// [Peer] sections with a public key that is not in the network become peers without a private key.
+pub fn import_wg_quick(files: &[PathBuf]) -> Result<(), ConfigCommandError> {
+    let mut config = conf::util::get_config()?;
+    let import_err = |path: &PathBuf, e: String| ConfigCommandError::ImportFailed(path.display().to_string(), e);
+
+    let mut parsed = Vec::new();
+    for path in files {
+        let contents = fs::read_to_string(path)?;
+        let wg_quick: WgQuickConfig = contents.parse().map_err(|e: WgQuickParseError| import_err(path, e.to_string()))?;
+        for ignored in &wg_quick.ignored {
+            log::warn!("{}: ignoring '{}', it has no equivalent in wg-quickrs", path.display(), ignored);
+        }
+        let private_key = wg_quick.interface.private_key
+            .ok_or_else(|| import_err(path, "[Interface] is missing a PrivateKey".into()))?;
+        parsed.push((path, wg_quick, private_key));
+    }
+
+    // public keys of the peers that are already in the network or being imported
+    let mut peer_ids_by_public_key: HashMap<WireGuardKey, Uuid> = config.network.peers.iter()
+        .filter_map(|(id, peer)| get_peer_public_key(peer).map(|public_key| (public_key, *id)))
+        .collect();
+    let mut file_peer_ids = Vec::new();
+    for (path, wg_quick, private_key) in &parsed {
+        let public_key = wg_public_key_from_private_key(private_key);
+        if let Some(peer_id) = peer_ids_by_public_key.get(&public_key) {
+            log::warn!("{}: [Interface] is already in the network as peer {}, only importing its connections", path.display(), peer_id);
+            if let Some(listen_port) = wg_quick.interface.listen_port {
+                if *peer_id == config.network.this_peer {
+                    // the agent's own interface listens on the VPN port, its endpoint is where the others reach it
+                    log::info!("{}: setting the VPN port to ListenPort {}", path.display(), listen_port);
+                    config.agent.vpn.port = listen_port;
+                    if let Some(peer) = config.network.peers.get_mut(peer_id) {
+                        peer.endpoint.address = with_port(peer.endpoint.address.clone(), listen_port);
+                    }
+                } else {
+                    log::warn!("{}: ignoring ListenPort = {}, peer {} is already in the network", path.display(), listen_port, peer_id);
+                }
+            }
+            file_peer_ids.push(*peer_id);
+            continue;
+        }
+
+        let peer_id = Uuid::new_v4();
+        let peer = import_peer(path, wg_quick, *private_key, &public_key, &parsed, &config.network)
+            .map_err(|e| import_err(path, e))?;
+        log::info!("{}: importing [Interface] as peer '{}' ({}) with address {}", path.display(), peer.name, peer_id, peer.address);
+        config.network.peers.insert(peer_id, peer);
+        peer_ids_by_public_key.insert(public_key, peer_id);
+        file_peer_ids.push(peer_id);
+    }
+
+    // peers that only appear in [Peer] sections, their private keys stay on their own devices
+    for (path, wg_quick, _) in &parsed {
+        for wg_peer in &wg_quick.peers {
+            if peer_ids_by_public_key.contains_key(&wg_peer.public_key) {
+                continue;
+            }
+            let peer_id = Uuid::new_v4();
+            let peer = import_public_key_peer(peer_id, wg_peer, &config.network)
+                .map_err(|e| import_err(path, e))?;
+            log::info!("{}: importing [Peer] {} as peer '{}' ({}) with address {}, its private key is not stored", path.display(), wg_peer.public_key, peer.name, peer_id, peer.address);
+            config.network.peers.insert(peer_id, peer);
+            peer_ids_by_public_key.insert(wg_peer.public_key, peer_id);
+        }
+    }
+
+    // connections, with the AllowedIPs of each side that is known from an imported file
+    let mut connections: BTreeMap<ConnectionId, (Connection, bool, bool)> = BTreeMap::new();
+    for ((path, wg_quick, _), this_peer_id) in parsed.iter().zip(&file_peer_ids) {
+        for wg_peer in &wg_quick.peers {
+            let other_peer_id = &peer_ids_by_public_key[&wg_peer.public_key];
+            if other_peer_id == this_peer_id {
+                continue;
+            }
+            let connection_id = get_connection_id(*this_peer_id, *other_peer_id);
+            if config.network.connections.contains_key(&connection_id) {
+                log::warn!("{}: skipping [Peer] {}, connection {} already exists", path.display(), wg_peer.public_key, connection_id);
+                continue;
+            }
+
+            let (connection, a_to_b_known, b_to_a_known) = connections.entry(connection_id.clone()).or_insert_with(|| (Connection {
+                enabled: true,
+                pre_shared_key: wg_generate_key(),
+                persistent_keepalive: PersistentKeepalive {
+                    enabled: false,
+                    period: config.network.defaults.connection.persistent_keepalive.period,
+                },
+                allowed_ips_a_to_b: Vec::new(),
+                allowed_ips_b_to_a: Vec::new(),
+            }, false, false));
+            if connection_id.a == *this_peer_id {
+                connection.allowed_ips_a_to_b = wg_peer.allowed_ips.clone();
+                *a_to_b_known = true;
+            } else {
+                connection.allowed_ips_b_to_a = wg_peer.allowed_ips.clone();
+                *b_to_a_known = true;
+            }
+            if let Some(pre_shared_key) = wg_peer.pre_shared_key {
+                connection.pre_shared_key = pre_shared_key;
+            }
+            if let Some(period) = wg_peer.persistent_keepalive {
+                connection.persistent_keepalive = PersistentKeepalive { enabled: true, period };
+            }
+        }
+    }
+
+    for (connection_id, (mut connection, a_to_b_known, b_to_a_known)) in connections {
+        // a side without a file only routes to the other peer's own addresses
+        if !a_to_b_known {
+            connection.allowed_ips_a_to_b = host_networks(&config.network.peers[&connection_id.b]);
+        }
+        if !b_to_a_known {
+            connection.allowed_ips_b_to_a = host_networks(&config.network.peers[&connection_id.a]);
+        }
+        log::info!("importing connection {}", connection_id);
+        config.network.connections.insert(connection_id, connection);
+    }
+
+    config.network.updated_at = Utc::now();
+    conf::util::set_config(&mut config)?;
+    log::info!("Successfully imported {} file(s)", files.len());
+    Ok(())
+}
+
+fn import_peer(
+    path: &Path,
+    wg_quick: &WgQuickConfig,
+    private_key: WireGuardKey,
+    public_key: &WireGuardKey,
+    parsed: &[(&PathBuf, WgQuickConfig, WireGuardKey)],
+    network: &Network,
+) -> Result<Peer, String> {
+    let interface = &wg_quick.interface;
+    let name = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let name = parse_and_validate_peer_name(&name).map_err(|e| format!("name: {}", e))?;
+
+    let next_addresses = conf::network::get_next_available_address(network);
+    let address_v4 = interface.addresses.iter().find_map(|a| match a { IpNet::V4(a) => Some(a), _ => None });
+    let address = match address_v4 {
+        Some(address) => validate_peer_address(&address.addr(), network).map_err(|e| format!("Address {}: {}", address, e))?,
+        None => {
+            let (address, _) = next_addresses.ok_or("no IPv4 addresses left in the network subnet")?;
+            log::warn!("{}: [Interface] has no IPv4 Address, using {}", path.display(), address);
+            address
+        }
+    };
+    let address_v6 = if network.subnet_v6.is_some() {
+        match interface.addresses.iter().find_map(|a| match a { IpNet::V6(a) => Some(a), _ => None }) {
+            Some(address) => Some(validate_peer_address_v6(&address.addr(), network).map_err(|e| format!("Address {}: {}", address, e))?),
+            None => next_addresses.and_then(|(_, address_v6)| address_v6),
+        }
+    } else {
+        None
+    };
+
+    // the endpoint of this interface is only known from the [Peer] sections of the other files,
+    // its port is the ListenPort, like in the configs wg-quickrs generates
+    let endpoint_address = parsed.iter()
+        .flat_map(|(_, other, _)| &other.peers)
+        .filter(|wg_peer| &wg_peer.public_key == public_key)
+        .find_map(|wg_peer| wg_peer.endpoint.clone());
+    let endpoint_address = match (endpoint_address, interface.listen_port) {
+        (Some(address), Some(listen_port)) => Some(with_port(address, listen_port)),
+        (None, Some(listen_port)) => {
+            log::warn!("{}: ignoring ListenPort = {}, no imported file has an Endpoint for [Interface]", path.display(), listen_port);
+            None
+        }
+        (address, None) => address,
+    };
+
+    let defaults = &network.defaults.peer;
+    let now = Utc::now();
+    Ok(Peer {
+        name,
+        address,
+        address_v6,
+        host_address: address_v4.is_some_and(|a| a.prefix_len() == 32) && network.subnet.prefix_len() != 32,
+        endpoint: Endpoint {
+            enabled: endpoint_address.is_some(),
+            address: endpoint_address.unwrap_or(EndpointAddress::None),
+        },
+        kind: defaults.kind.clone(),
+        icon: defaults.icon.clone(),
+        dns: Dns {
+            enabled: !interface.dns.is_empty(),
+            addresses: if interface.dns.is_empty() { defaults.dns.addresses.clone() } else { interface.dns.clone() },
+        },
+        mtu: Mtu {
+            enabled: interface.mtu.is_some(),
+            value: interface.mtu.unwrap_or(defaults.mtu.value),
+        },
+        scripts: interface.scripts.clone(),
+        private_key: Some(private_key),
+        public_key: None,
+        amnezia_parameters: defaults.amnezia_parameters.clone(),
+        created_at: now,
+        updated_at: now,
+    })
+}
+
+fn import_public_key_peer(peer_id: Uuid, wg_peer: &WgQuickPeer, network: &Network) -> Result<Peer, String> {
+    // the peer's own address is usually the host route in its AllowedIPs
+    let address = wg_peer.allowed_ips.iter()
+        .filter_map(|a| match a { IpNet::V4(a) => Some(a.addr()), _ => None })
+        .find(|a| validate_peer_address(a, network).is_ok());
+    let next_addresses = conf::network::get_next_available_address(network);
+    let address = match address {
+        Some(address) => address,
+        None => next_addresses.ok_or("no IPv4 addresses left in the network subnet")?.0,
+    };
+    let address_v6 = if network.subnet_v6.is_some() {
+        wg_peer.allowed_ips.iter()
+            .filter_map(|a| match a { IpNet::V6(a) => Some(a.addr()), _ => None })
+            .find(|a| validate_peer_address_v6(a, network).is_ok())
+            .or_else(|| next_addresses.and_then(|(_, address_v6)| address_v6))
+    } else {
+        None
+    };
+
+    let defaults = &network.defaults.peer;
+    let now = Utc::now();
+    Ok(Peer {
+        name: format!("peer-{}", &peer_id.to_string()[..8]),
+        address,
+        address_v6,
+        host_address: false,
+        endpoint: Endpoint {
+            enabled: wg_peer.endpoint.is_some(),
+            address: wg_peer.endpoint.clone().unwrap_or(EndpointAddress::None),
+        },
+        kind: defaults.kind.clone(),
+        icon: defaults.icon.clone(),
+        dns: defaults.dns.clone(),
+        mtu: defaults.mtu.clone(),
+        scripts: defaults.scripts.clone(),
+        private_key: None,
+        public_key: Some(wg_peer.public_key),
+        amnezia_parameters: defaults.amnezia_parameters.clone(),
+        created_at: now,
+        updated_at: now,
+    })
+}
+
+fn with_port(address: EndpointAddress, port: u16) -> EndpointAddress {
+    match address {
+        EndpointAddress::None => EndpointAddress::None,
+        EndpointAddress::Ipv4AndPort(address) => EndpointAddress::Ipv4AndPort(Ipv4AndPort { port, ..address }),
+        EndpointAddress::Ipv6AndPort(address) => EndpointAddress::Ipv6AndPort(Ipv6AndPort { port, ..address }),
+        EndpointAddress::HostnameAndPort(address) => EndpointAddress::HostnameAndPort(HostnameAndPort { port, ..address }),
+    }
+}
+
+fn host_networks(peer: &Peer) -> AllowedIPs {
+    let mut networks = vec![IpNet::from(std::net::IpAddr::V4(peer.address))];
+    if let Some(address_v6) = peer.address_v6 {
+        networks.push(IpNet::from(std::net::IpAddr::V6(address_v6)));
+    }
+    networks
+}
@@ -11,12 +11,13 @@ fn format_endpoint_address(addr: &wg_quickrs_lib::types::network::EndpointAddres
     match addr {
         EndpointAddress::None => "none".to_string(),
         EndpointAddress::Ipv4AndPort(ip_port) => format!("{}:{}", ip_port.ipv4, ip_port.port),
+        EndpointAddress::Ipv6AndPort(ip_port) => format!("[{}]:{}", ip_port.ipv6, ip_port.port),
         EndpointAddress::HostnameAndPort(host_port) => format!("{}:{}", host_port.hostname, host_port.port),
     }
 }
 
 /// List all peers in human-readable format
-/// Format: "name (peerid) @ address / {endpoint if enabled}"
+/// Format: "name (peerid) @ address{, address_v6 if set} / {endpoint if enabled}"
 pub fn list_network_peers() -> Result<(), ConfigCommandError> {
     let config = conf::util::get_config()?;
 
@@ -32,7 +33,12 @@ pub fn list_network_peers() -> Result<(), ConfigCommandError> {
             String::new()
         };
 
-        println!("{} ({}) @ {}{}", peer.name, peer_id, peer.address, endpoint_str);
+        let address_v6_str = match peer.address_v6 {
+            Some(address_v6) => format!(", {}", address_v6),
+            None => String::new(),
+        };
+
+        println!("{} ({}) @ {}{}{}", peer.name, peer_id, peer.address, address_v6_str, endpoint_str);
     }
 
     Ok(())
@@ -79,3 +85,62 @@ pub fn list_network_reservations() -> Result<(), ConfigCommandError> {
 
     Ok(())
 }
+
+/// List all users of the web UI and API
+/// Format: "name (role)"
+pub fn list_web_users() -> Result<(), ConfigCommandError> {
+    let config = conf::util::get_config()?;
+
+    if config.agent.web.users.is_empty() {
+        println!("No users found.");
+        return Ok(());
+    }
+
+    for (name, user) in &config.agent.web.users {
+        println!("{} ({})", name, user.role);
+    }
+
+    Ok(())
+}
+
+/// List all peer tokens
+/// Format: "id: name (peerid){, key rotation if allowed}"
+pub fn list_peer_tokens() -> Result<(), ConfigCommandError> {
+    let config = conf::util::get_config()?;
+
+    if config.agent.web.peer_tokens.is_empty() {
+        println!("No peer tokens found.");
+        return Ok(());
+    }
+
+    for (id, token) in &config.agent.web.peer_tokens {
+        let peer_name = config.network.peers.get(&token.peer_id)
+            .map(|p| p.name.as_str())
+            .unwrap_or("unknown");
+        let key_rotation_str = if token.key_rotation { ", key rotation" } else { "" };
+        println!("{}: {} ({}){}", id, peer_name, token.peer_id, key_rotation_str);
+    }
+
+    Ok(())
+}
+
+/// List all API keys
+/// Format: "name (role) created: {created_at}, last used: {last_used_at or never}"
+pub fn list_api_keys() -> Result<(), ConfigCommandError> {
+    let config = conf::util::get_config()?;
+
+    if config.agent.web.api_keys.is_empty() {
+        println!("No API keys found.");
+        return Ok(());
+    }
+
+    let last_used = conf::api_keys::read_last_used();
+    for (name, key) in &config.agent.web.api_keys {
+        let last_used_str = last_used.get(&key.id)
+            .map(|used_at| used_at.to_string())
+            .unwrap_or_else(|| "never".to_string());
+        println!("{} ({}) created: {}, last used: {}", name, key.role, key.created_at, last_used_str);
+    }
+
+    Ok(())
+}
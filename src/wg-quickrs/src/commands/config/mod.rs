@@ -6,6 +6,8 @@ mod remove;
 mod reset;
 mod add;
 mod generate_conf;
+mod import;
+mod history;
 
 use std::io;
 use std::net::Ipv4Addr;
@@ -22,6 +24,8 @@ use wg_quickrs_cli::config::get::*;
 use wg_quickrs_cli::config::list::*;
 use wg_quickrs_cli::config::remove::*;
 use wg_quickrs_cli::config::add::*;
+use wg_quickrs_cli::config::import::*;
+use wg_quickrs_cli::config::history::*;
 use wg_quickrs_lib::types::network::ConnectionId;
 use wg_quickrs_lib::types::misc::WireGuardLibError;
 use wg_quickrs_lib::validation::error::ValidationError;
@@ -33,8 +37,12 @@ use crate::commands::config::remove::*;
 use crate::commands::config::reset::*;
 use crate::commands::config::add::*;
 use crate::commands::config::generate_conf::*;
+use crate::commands::config::import::*;
+use crate::commands::config::history::*;
 use crate::conf;
+use crate::conf::render::RenderError;
 use crate::conf::util::ConfUtilError;
+use crate::conf::history::HistoryError;
 
 #[derive(Error, Debug)]
 pub enum ConfigCommandError {
@@ -46,6 +54,8 @@ pub enum ConfigCommandError {
     ConfUtilError(#[from] ConfUtilError),
     #[error(transparent)]
     WireGuardLibError(#[from] WireGuardLibError),
+    #[error(transparent)]
+    Render(#[from] RenderError),
     #[error("cannot enable firewall gateway: gateway is not set")]
     GatewayNotSet(),
     #[error("failed to read input: {0}")]
@@ -58,6 +68,20 @@ pub enum ConfigCommandError {
     ConnectionNotFound(String),
     #[error("reservation not found: {0}")]
     ReservationNotFound(Ipv4Addr),
+    #[error("user not found: {0}")]
+    UserNotFound(String),
+    #[error("user already exists: {0}")]
+    UserExists(String),
+    #[error("peer token not found: {0}")]
+    PeerTokenNotFound(String),
+    #[error("cannot issue a peer token for this_peer: {0}")]
+    PeerTokenForThisPeer(Uuid),
+    #[error("API key not found: {0}")]
+    ApiKeyNotFound(String),
+    #[error("API key already exists: {0}")]
+    ApiKeyExists(String),
+    #[error("OpenID Connect role mapping not found: {0}")]
+    OidcRoleMappingNotFound(String),
     #[error("invalid connection id format: {0}")]
     InvalidConnectionId(String),
     #[error("invalid uuid format: {0}")]
@@ -66,6 +90,10 @@ pub enum ConfigCommandError {
     MissingArgument(String),
     #[error("cannot remove this_peer: {0}")]
     CannotRemoveThisPeer(Uuid),
+    #[error("failed to import {0}: {1}")]
+    ImportFailed(String, String),
+    #[error(transparent)]
+    History(#[from] HistoryError),
 }
 
 impl From<argon2::password_hash::Error> for ConfigCommandError {
@@ -103,9 +131,14 @@ pub fn handle_config_command(target: &ConfigCommands) -> Result<(), ConfigComman
                     EnableAgentWebCommands::Http => toggle_agent_web_http(true),
                     EnableAgentWebCommands::Https => toggle_agent_web_https(true),
                     EnableAgentWebCommands::Password => toggle_agent_web_password(true),
+                    EnableAgentWebCommands::Metrics => toggle_agent_web_metrics(true),
+                    EnableAgentWebCommands::MetricsToken => toggle_agent_web_metrics_token(true),
+                    EnableAgentWebCommands::PersistentJwtKey => toggle_agent_web_persistent_jwt_key(true),
+                    EnableAgentWebCommands::Oidc => toggle_agent_web_oidc(true),
                 },
                 EnableAgentCommands::Vpn { target } => match target {
                     Some(EnableAgentVpnCommands::WgUserspace) => toggle_agent_vpn_wg_userspace(true),
+                    Some(EnableAgentVpnCommands::TelemetryHistory) => toggle_agent_vpn_telemetry_history(true),
                     None => toggle_agent_vpn(true),
                 },
             },
@@ -115,6 +148,7 @@ pub fn handle_config_command(target: &ConfigCommands) -> Result<(), ConfigComman
                     EnablePeerCommands::Icon => enable_peer_icon(id),
                     EnablePeerCommands::Dns => enable_peer_dns(id),
                     EnablePeerCommands::Mtu => enable_peer_mtu(id),
+                    EnablePeerCommands::HostAddress => enable_peer_host_address(id),
                 },
                 EnableNetworkCommands::Connection { id } => enable_connection(id),
                 EnableNetworkCommands::Defaults { target } => match target {
@@ -136,9 +170,14 @@ pub fn handle_config_command(target: &ConfigCommands) -> Result<(), ConfigComman
                     DisableAgentWebCommands::Http => toggle_agent_web_http(false),
                     DisableAgentWebCommands::Https => toggle_agent_web_https(false),
                     DisableAgentWebCommands::Password => toggle_agent_web_password(false),
+                    DisableAgentWebCommands::Metrics => toggle_agent_web_metrics(false),
+                    DisableAgentWebCommands::MetricsToken => toggle_agent_web_metrics_token(false),
+                    DisableAgentWebCommands::PersistentJwtKey => toggle_agent_web_persistent_jwt_key(false),
+                    DisableAgentWebCommands::Oidc => toggle_agent_web_oidc(false),
                 },
                 DisableAgentCommands::Vpn { target } => match target {
                     Some(DisableAgentVpnCommands::WgUserspace) => toggle_agent_vpn_wg_userspace(false),
+                    Some(DisableAgentVpnCommands::TelemetryHistory) => toggle_agent_vpn_telemetry_history(false),
                     None => toggle_agent_vpn(false),
                 },
             },
@@ -148,6 +187,7 @@ pub fn handle_config_command(target: &ConfigCommands) -> Result<(), ConfigComman
                     DisablePeerCommands::Icon => disable_peer_icon(id),
                     DisablePeerCommands::Dns => disable_peer_dns(id),
                     DisablePeerCommands::Mtu => disable_peer_mtu(id),
+                    DisablePeerCommands::HostAddress => disable_peer_host_address(id),
                 },
                 DisableNetworkCommands::Connection { id } => disable_connection(id),
                 DisableNetworkCommands::Defaults { target } => match target {
@@ -175,6 +215,14 @@ pub fn handle_config_command(target: &ConfigCommands) -> Result<(), ConfigComman
                         SetAgentWebHttpsCommands::TlsCert { value } => set_agent_web_http_tls_cert(value),
                         SetAgentWebHttpsCommands::TlsKey { value } => set_agent_web_http_tls_key(value),
                     },
+                    SetAgentWebCommands::Oidc { target } => match target {
+                        SetAgentWebOidcCommands::Issuer { value } => set_agent_web_oidc_issuer(value),
+                        SetAgentWebOidcCommands::ClientId { value } => set_agent_web_oidc_client_id(value),
+                        SetAgentWebOidcCommands::ClientSecret { client_secret } => set_agent_web_oidc_client_secret(client_secret),
+                        SetAgentWebOidcCommands::RedirectUrl { value } => set_agent_web_oidc_redirect_url(value),
+                        SetAgentWebOidcCommands::UsernameClaim { value } => set_agent_web_oidc_username_claim(value),
+                        SetAgentWebOidcCommands::RoleClaim { value } => set_agent_web_oidc_role_claim(value),
+                    },
                 },
                 SetAgentCommands::Vpn { target } => match target {
                     SetAgentVpnCommands::Port { value } => set_agent_vpn_port(*value),
@@ -182,19 +230,31 @@ pub fn handle_config_command(target: &ConfigCommands) -> Result<(), ConfigComman
                     SetAgentVpnCommands::WgUserspace { target } => match target {
                         SetAgentVpnWgUserspaceCommands::Binary { value } => set_agent_vpn_wg_userspace_binary(value),
                     },
+                    SetAgentVpnCommands::NetworkBackend { value } => set_agent_vpn_network_backend(value),
+                    SetAgentVpnCommands::Telemetry { target } => match target {
+                        SetAgentVpnTelemetryCommands::Mode { value } => set_agent_vpn_telemetry_mode(value),
+                        SetAgentVpnTelemetryCommands::Interval { value } => set_agent_vpn_telemetry_interval(value),
+                        SetAgentVpnTelemetryCommands::Retention { value } => set_agent_vpn_telemetry_retention(value),
+                    },
+                },
+                SetAgentCommands::ConfigHistory { target } => match target {
+                    SetAgentConfigHistoryCommands::Retention { value } => set_agent_config_history_retention(value),
                 },
             },
             SetCommands::Network { target } => match target {
                 SetNetworkCommands::Name { name } => set_network_name(name.clone()),
                 SetNetworkCommands::Subnet { subnet } => set_network_subnet(subnet),
+                SetNetworkCommands::SubnetV6 { subnet } => set_network_subnet_v6(subnet),
                 SetNetworkCommands::Peer { id, target } => match target {
                     SetPeerCommands::Name { name } => set_peer_name(id, name.clone()),
                     SetPeerCommands::Address { address } => set_peer_address(id, *address),
+                    SetPeerCommands::AddressV6 { address } => set_peer_address_v6(id, *address),
                     SetPeerCommands::Endpoint { endpoint } => set_peer_endpoint(id, endpoint),
                     SetPeerCommands::Kind { kind } => set_peer_kind(id, kind),
                     SetPeerCommands::Icon { src } => set_peer_icon(id, src),
                     SetPeerCommands::Dns { addresses } => set_peer_dns(id, addresses),
                     SetPeerCommands::Mtu { value } => set_peer_mtu(id, *value),
+                    SetPeerCommands::PublicKey { public_key } => set_peer_public_key(id, public_key),
                     SetPeerCommands::AmneziaParameters { target } => match target {
                         SetPeerAmneziaParametersCommands::Jc { value } => set_peer_amnezia_parameters_jc(id, *value),
                         SetPeerAmneziaParametersCommands::Jmin { value } => set_peer_amnezia_parameters_jmin(id, *value),
@@ -238,6 +298,7 @@ pub fn handle_config_command(target: &ConfigCommands) -> Result<(), ConfigComman
                     ResetAgentWebCommands::Password { password } => {
                         reset_web_password(password)
                     },
+                    ResetAgentWebCommands::MetricsToken => reset_metrics_token(),
                 },
             },
             ResetCommands::Network { target } => match target {
@@ -280,6 +341,19 @@ pub fn handle_config_command(target: &ConfigCommands) -> Result<(), ConfigComman
                                     GetAgentWebPasswordCommands::Hash => get_agent_web_password_hash(),
                                 },
                             },
+                            GetAgentWebCommands::Metrics { target } => match target {
+                                None => get_agent_web_metrics(),
+                                Some(metrics_cmd) => match metrics_cmd {
+                                    GetAgentWebMetricsCommands::Enabled => get_agent_web_metrics_enabled(),
+                                    GetAgentWebMetricsCommands::Token { target } => match target {
+                                        None => get_agent_web_metrics_token(),
+                                        Some(token_cmd) => match token_cmd {
+                                            GetAgentWebMetricsTokenCommands::Enabled => get_agent_web_metrics_token_enabled(),
+                                            GetAgentWebMetricsTokenCommands::Hash => get_agent_web_metrics_token_hash(),
+                                        },
+                                    },
+                                },
+                            },
                         },
                     },
                     GetAgentCommands::Vpn { target } => match target {
@@ -295,8 +369,25 @@ pub fn handle_config_command(target: &ConfigCommands) -> Result<(), ConfigComman
                                     GetAgentVpnWgUserspaceCommands::Binary => get_agent_vpn_wg_userspace_binary(),
                                 },
                             },
+                            GetAgentVpnCommands::NetworkBackend => get_agent_vpn_network_backend(),
+                            GetAgentVpnCommands::Telemetry { target } => match target {
+                                None => get_agent_vpn_telemetry(),
+                                Some(telemetry_cmd) => match telemetry_cmd {
+                                    GetAgentVpnTelemetryCommands::Mode => get_agent_vpn_telemetry_mode(),
+                                    GetAgentVpnTelemetryCommands::Interval => get_agent_vpn_telemetry_interval(),
+                                    GetAgentVpnTelemetryCommands::Retention => get_agent_vpn_telemetry_retention(),
+                                    GetAgentVpnTelemetryCommands::History { target } => match target {
+                                        None => get_agent_vpn_telemetry_history(),
+                                        Some(GetAgentVpnTelemetryHistoryCommands::Enabled) => get_agent_vpn_telemetry_history_enabled(),
+                                    },
+                                },
+                            },
                         },
                     },
+                    GetAgentCommands::ConfigHistory { target } => match target {
+                        None => get_agent_config_history(),
+                        Some(GetAgentConfigHistoryCommands::Retention) => get_agent_config_history_retention(),
+                    },
                 },
             },
             GetCommands::Network { target } => match target {
@@ -304,6 +395,7 @@ pub fn handle_config_command(target: &ConfigCommands) -> Result<(), ConfigComman
                 Some(network_cmd) => match network_cmd {
                     GetNetworkCommands::Name => get_network_name(),
                     GetNetworkCommands::Subnet => get_network_subnet(),
+                    GetNetworkCommands::SubnetV6 => get_network_subnet_v6(),
                     GetNetworkCommands::ThisPeer => get_network_this_peer(),
                     GetNetworkCommands::Peers { id, target } => match (id, target) {
                         (None, None) => get_network_peers(),
@@ -311,6 +403,8 @@ pub fn handle_config_command(target: &ConfigCommands) -> Result<(), ConfigComman
                         (Some(peer_id), Some(peer_cmd)) => match peer_cmd {
                             GetNetworkPeersCommands::Name => get_network_peer_name(peer_id),
                             GetNetworkPeersCommands::Address => get_network_peer_address(peer_id),
+                            GetNetworkPeersCommands::AddressV6 => get_network_peer_address_v6(peer_id),
+                            GetNetworkPeersCommands::HostAddress => get_network_peer_host_address(peer_id),
                             GetNetworkPeersCommands::Endpoint { target } => match target {
                                 None => get_network_peer_endpoint(peer_id),
                                 Some(endpoint_cmd) => match endpoint_cmd {
@@ -342,6 +436,7 @@ pub fn handle_config_command(target: &ConfigCommands) -> Result<(), ConfigComman
                             },
                             GetNetworkPeersCommands::Scripts => get_network_peer_scripts(peer_id),
                             GetNetworkPeersCommands::PrivateKey => get_network_peer_private_key(peer_id),
+                            GetNetworkPeersCommands::PublicKey => get_network_peer_public_key(peer_id),
                             GetNetworkPeersCommands::AmneziaParameters { target } => match target {
                                 None => get_network_peer_amnezia_parameters(peer_id),
                                 Some(amnezia_cmd) => match amnezia_cmd {
@@ -503,15 +598,35 @@ pub fn handle_config_command(target: &ConfigCommands) -> Result<(), ConfigComman
             ListCommands::Peers => list_network_peers(),
             ListCommands::Connections => list_network_connections(),
             ListCommands::Reservations => list_network_reservations(),
+            ListCommands::Users => list_web_users(),
+            ListCommands::PeerTokens => list_peer_tokens(),
+            ListCommands::ApiKeys => list_api_keys(),
         },
         ConfigCommands::Remove { target } => match target {
             RemoveCommands::Peer { id } => remove_network_peer(id),
             RemoveCommands::Connection { id } => remove_network_connection(id),
             RemoveCommands::Reservation { address } => remove_network_reservation(address),
+            RemoveCommands::User { name } => remove_user(name),
+            RemoveCommands::PeerToken { id } => remove_peer_token(id),
+            RemoveCommands::ApiKey { name } => remove_api_key(name),
+            RemoveCommands::OidcRoleMapping { value } => remove_oidc_role_mapping(value),
         },
         ConfigCommands::Add { target } => match target {
             AddCommands::Peer { options } => add_peer(options),
             AddCommands::Connection { options } => add_connection(options),
+            AddCommands::User { options } => add_user(options),
+            AddCommands::PeerToken { options } => add_peer_token(options),
+            AddCommands::ApiKey { options } => add_api_key(options),
+            AddCommands::OidcRoleMapping { value, role } => add_oidc_role_mapping(value, role),
+        },
+        ConfigCommands::Import { target } => match target {
+            ImportCommands::WgQuick { files } => import_wg_quick(files),
+        },
+        ConfigCommands::History { target } => match target {
+            HistoryCommands::List => list_config_history(),
+            HistoryCommands::Show { revision } => show_config_history(*revision),
+            HistoryCommands::Diff { revision, against } => diff_config_history(*revision, *against),
+            HistoryCommands::Rollback { revision } => rollback_config_history(*revision),
         },
         ConfigCommands::Conf { options } => generate_peer_conf(options),
     }
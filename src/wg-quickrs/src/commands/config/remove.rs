@@ -41,6 +41,11 @@ pub fn remove_network_peer(id: &Uuid) -> Result<(), ConfigCommandError> {
         reservation.peer_id != *id
     });
 
+    // Also revoke any tokens for this peer
+    config.agent.web.peer_tokens.retain(|_, token| {
+        token.peer_id != *id
+    });
+
     log::info!("Removed peer {} ({})", peer_name, id);
     conf::util::set_config(&mut config)?;
     Ok(())
@@ -88,3 +93,54 @@ pub fn remove_network_reservation(address: &Ipv4Addr) -> Result<(), ConfigComman
     Ok(())
 }
 
+/// Remove a user of the web UI and API by name, its tokens stop working right away
+pub fn remove_user(name: &str) -> Result<(), ConfigCommandError> {
+    let mut config = conf::util::get_config()?;
+
+    let Some(user) = config.agent.web.users.remove(name) else {
+        return Err(ConfigCommandError::UserNotFound(name.to_string()));
+    };
+
+    log::info!("Removed user {} ({})", name, user.role);
+    conf::util::set_config(&mut config)?;
+    Ok(())
+}
+
+/// Revoke a peer token by its ID
+pub fn remove_peer_token(id: &str) -> Result<(), ConfigCommandError> {
+    let mut config = conf::util::get_config()?;
+
+    let Some(token) = config.agent.web.peer_tokens.remove(id) else {
+        return Err(ConfigCommandError::PeerTokenNotFound(id.to_string()));
+    };
+
+    log::info!("Removed peer token {} (peer: {})", id, token.peer_id);
+    conf::util::set_config(&mut config)?;
+    Ok(())
+}
+
+/// Revoke an API key by name, requests sending it are refused right away
+pub fn remove_api_key(name: &str) -> Result<(), ConfigCommandError> {
+    let mut config = conf::util::get_config()?;
+
+    let Some(key) = config.agent.web.api_keys.remove(name) else {
+        return Err(ConfigCommandError::ApiKeyNotFound(name.to_string()));
+    };
+
+    log::info!("Removed API key {} ({})", name, key.role);
+    conf::util::set_config(&mut config)?;
+    Ok(())
+}
+
+/// Remove the role mapping of an OpenID Connect role claim value
+pub fn remove_oidc_role_mapping(value: &str) -> Result<(), ConfigCommandError> {
+    let mut config = conf::util::get_config()?;
+
+    let Some(role) = config.agent.web.oidc.role_mapping.remove(value) else {
+        return Err(ConfigCommandError::OidcRoleMappingNotFound(value.to_string()));
+    };
+
+    log::info!("Removed OpenID Connect role mapping {} ({})", value, role);
+    conf::util::set_config(&mut config)?;
+    Ok(())
+}
@@ -1,21 +1,20 @@
 use std::io;
 use std::io::Write;
+use rand::distr::Alphanumeric;
+use rand::{rng, Rng};
 use uuid::Uuid;
 use crate::commands::config::{parse_connection_id, ConfigCommandError};
 use crate::commands::helpers;
 use crate::conf;
 
-pub fn reset_web_password(reset_web_password_opts: &Option<String>) -> Result<(), ConfigCommandError> {
-    // get the wireguard config a file path
-    let mut config = conf::util::get_config()?;
-
-    log::info!("Resetting the web password...");
-    let password = match reset_web_password_opts {
+/// The password given on the command line, or read from stdin when it is not given
+pub(crate) fn read_new_password(password_opt: &Option<String>) -> Result<String, ConfigCommandError> {
+    match password_opt {
         Some(pwd) => {
             log::warn!(
                 "THIS IS HIGHLY INSECURE! Please set the password without the --password flag. The plaintext password could be visible in your shell history."
             );
-            pwd.clone()
+            Ok(pwd.clone())
         }
         None => {
             print!("Enter your new password: ");
@@ -23,9 +22,17 @@ pub fn reset_web_password(reset_web_password_opts: &Option<String>) -> Result<()
 
             let mut pwd = String::new();
             io::stdin().read_line(&mut pwd).map_err(ConfigCommandError::ReadFailed)?;
-            pwd.trim().to_string()
+            Ok(pwd.trim().to_string())
         }
-    };
+    }
+}
+
+pub fn reset_web_password(reset_web_password_opts: &Option<String>) -> Result<(), ConfigCommandError> {
+    // get the wireguard config a file path
+    let mut config = conf::util::get_config()?;
+
+    log::info!("Resetting the web password...");
+    let password = read_new_password(reset_web_password_opts)?;
     let password_hash = helpers::calculate_password_hash(password.trim())?;
 
     config.agent.web.password.hash = password_hash;
@@ -33,12 +40,24 @@ pub fn reset_web_password(reset_web_password_opts: &Option<String>) -> Result<()
     Ok(())
 }
 
+/// Generates a new metrics bearer token, only its hash is kept so it is printed this one time
+pub fn reset_metrics_token() -> Result<(), ConfigCommandError> {
+    let mut config = conf::util::get_config()?;
+
+    log::info!("Resetting the metrics bearer token...");
+    let token: String = rng().sample_iter(&Alphanumeric).take(40).map(char::from).collect();
+    config.agent.web.metrics.token.hash = conf::api_keys::hash_secret(&token);
+    conf::util::set_config(&mut config)?;
+    println!("{}", token);
+    Ok(())
+}
 
 /// Reset peer private key (generates new WireGuard key)
 pub fn reset_peer_private_key(id: &Uuid) -> Result<(), ConfigCommandError> {
     let mut config = conf::util::get_config()?;
     let peer = config.network.peers.get_mut(id).ok_or(ConfigCommandError::PeerNotFound(*id))?;
-    peer.private_key = wg_quickrs_lib::helpers::wg_generate_key();
+    peer.private_key = Some(wg_quickrs_lib::helpers::wg_generate_key());
+    peer.public_key = None;
     log::info!("Reset peer {} private key", id);
     conf::util::set_config(&mut config)?;
     Ok(())
@@ -4,11 +4,12 @@
 
 use crate::conf;
 use crate::commands::config::{parse_connection_id, ConfigCommandError};
-use std::net::Ipv4Addr;
+use std::io::{self, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::path::PathBuf;
 use std::str::FromStr;
 use uuid::Uuid;
-use wg_quickrs_lib::validation::agent::{validate_tls_file};
+use wg_quickrs_lib::validation::agent::{parse_and_validate_config_history_retention, parse_and_validate_network_backend, parse_and_validate_telemetry_interval, parse_and_validate_telemetry_mode, parse_and_validate_telemetry_retention, validate_oidc_claim, validate_oidc_client_id, validate_oidc_url, validate_tls_file};
 use wg_quickrs_lib::validation::error::ValidationError;
 use crate::WG_QUICKRS_CONFIG_FOLDER;
 
@@ -106,6 +107,73 @@ impl_setter!(
 
 impl_port_setter!(set_agent_web_https_port, agent.web.https, "HTTPS");
 
+impl_setter!(
+    set_agent_web_oidc_issuer,
+    String,
+    agent.web.oidc.issuer,
+    "OpenID Connect issuer",
+    transform: |issuer: &String| validate_oidc_url(issuer)
+);
+
+impl_setter!(
+    set_agent_web_oidc_client_id,
+    String,
+    agent.web.oidc.client_id,
+    "OpenID Connect client ID",
+    transform: |client_id: &String| validate_oidc_client_id(client_id)
+);
+
+impl_setter!(
+    set_agent_web_oidc_redirect_url,
+    String,
+    agent.web.oidc.redirect_url,
+    "OpenID Connect redirect URL",
+    transform: |redirect_url: &String| validate_oidc_url(redirect_url)
+);
+
+impl_setter!(
+    set_agent_web_oidc_username_claim,
+    String,
+    agent.web.oidc.username_claim,
+    "OpenID Connect username claim",
+    transform: |claim: &String| validate_oidc_claim(claim)
+);
+
+impl_setter!(
+    set_agent_web_oidc_role_claim,
+    String,
+    agent.web.oidc.role_claim,
+    "OpenID Connect role claim",
+    transform: |claim: &String| validate_oidc_claim(claim)
+);
+
+/// Set the OpenID Connect client secret, read from stdin when it is not given
+pub fn set_agent_web_oidc_client_secret(client_secret_opt: &Option<String>) -> Result<(), ConfigCommandError> {
+    let mut config = conf::util::get_config()?;
+
+    let client_secret = match client_secret_opt {
+        Some(client_secret) => {
+            log::warn!(
+                "THIS IS HIGHLY INSECURE! Please set the client secret without the --client-secret flag. The plaintext secret could be visible in your shell history."
+            );
+            client_secret.clone()
+        }
+        None => {
+            print!("Enter the client secret: ");
+            io::stdout().flush().map_err(ConfigCommandError::ReadFailed)?; // Ensure the prompt is shown before waiting for input
+
+            let mut client_secret = String::new();
+            io::stdin().read_line(&mut client_secret).map_err(ConfigCommandError::ReadFailed)?;
+            client_secret.trim().to_string()
+        }
+    };
+
+    log::info!("Setting OpenID Connect client secret");
+    config.agent.web.oidc.client_secret = client_secret;
+    conf::util::set_config(&mut config)?;
+    Ok(())
+}
+
 // ============================================================================
 // Agent VPN Configuration Functions
 // ============================================================================
@@ -129,6 +197,50 @@ impl_setter!(
     display: |p: &PathBuf| format!("{}", p.display())
 );
 
+impl_setter!(
+    set_agent_vpn_network_backend,
+    String,
+    agent.vpn.network_backend,
+    "network backend",
+    transform: |backend: &String| parse_and_validate_network_backend(backend)
+);
+
+impl_setter!(
+    set_agent_vpn_telemetry_mode,
+    String,
+    agent.vpn.telemetry.mode,
+    "telemetry mode",
+    transform: |mode: &String| parse_and_validate_telemetry_mode(mode)
+);
+
+impl_setter!(
+    set_agent_vpn_telemetry_interval,
+    String,
+    agent.vpn.telemetry.interval,
+    "telemetry interval",
+    transform: |interval: &String| parse_and_validate_telemetry_interval(interval)
+);
+
+impl_setter!(
+    set_agent_vpn_telemetry_retention,
+    String,
+    agent.vpn.telemetry.retention,
+    "telemetry retention",
+    transform: |retention: &String| -> Result<u64, ConfigCommandError> {
+        // the retention is checked against the interval it is sampled at
+        let interval = conf::util::get_config()?.agent.vpn.telemetry.interval;
+        Ok(parse_and_validate_telemetry_retention(retention, interval)?)
+    }
+);
+
+impl_setter!(
+    set_agent_config_history_retention,
+    String,
+    agent.config_history.retention,
+    "config history retention",
+    transform: |retention: &String| parse_and_validate_config_history_retention(retention)
+);
+
 // ============================================================================
 // Network Configuration Functions
 // ============================================================================
@@ -153,6 +265,16 @@ pub fn set_network_subnet(subnet_str: &str) -> Result<(), ConfigCommandError> {
     Ok(())
 }
 
+/// Set network IPv6 subnet
+pub fn set_network_subnet_v6(subnet_str: &str) -> Result<(), ConfigCommandError> {
+    let mut config = conf::util::get_config()?;
+    let subnet = wg_quickrs_lib::validation::network::parse_and_validate_ipv6_subnet(subnet_str)?;
+    config.network.subnet_v6 = Some(subnet);
+    log::info!("Set network IPv6 subnet to: {}", subnet);
+    conf::util::set_config(&mut config)?;
+    Ok(())
+}
+
 /// Set network AmneziaWG S1 parameter
 pub fn set_network_amnezia_parameters_s1(value: u16) -> Result<(), ConfigCommandError> {
     let mut config = conf::util::get_config()?;
@@ -227,6 +349,16 @@ pub fn set_peer_address(id: &Uuid, address: Ipv4Addr) -> Result<(), ConfigComman
     Ok(())
 }
 
+/// Set peer IPv6 address
+pub fn set_peer_address_v6(id: &Uuid, address: Ipv6Addr) -> Result<(), ConfigCommandError> {
+    let mut config = conf::util::get_config()?;
+    let peer = config.network.peers.get_mut(id).ok_or(ConfigCommandError::PeerNotFound(*id))?;
+    peer.address_v6 = Some(address);
+    log::info!("Set peer {} IPv6 address to: {}", id, address);
+    conf::util::set_config(&mut config)?;
+    Ok(())
+}
+
 /// Set peer endpoint
 pub fn set_peer_endpoint(id: &Uuid, endpoint_str: &str) -> Result<(), ConfigCommandError> {
     let mut config = conf::util::get_config()?;
@@ -239,6 +371,18 @@ pub fn set_peer_endpoint(id: &Uuid, endpoint_str: &str) -> Result<(), ConfigComm
     Ok(())
 }
 
+/// Set peer public key, the private key is dropped as the peer holds it on its own device
+pub fn set_peer_public_key(id: &Uuid, public_key_str: &str) -> Result<(), ConfigCommandError> {
+    let mut config = conf::util::get_config()?;
+    let peer = config.network.peers.get_mut(id).ok_or(ConfigCommandError::PeerNotFound(*id))?;
+    let public_key = wg_quickrs_lib::validation::network::parse_and_validate_wg_key(public_key_str)?;
+    peer.private_key = None;
+    peer.public_key = Some(public_key);
+    log::info!("Set peer {} public key to: {}", id, public_key);
+    conf::util::set_config(&mut config)?;
+    Ok(())
+}
+
 /// Set peer kind
 pub fn set_peer_kind(id: &Uuid, kind: &str) -> Result<(), ConfigCommandError> {
     let mut config = conf::util::get_config()?;
@@ -265,9 +409,9 @@ pub fn set_peer_dns(id: &Uuid, addresses_str: &str) -> Result<(), ConfigCommandE
     let mut config = conf::util::get_config()?;
     let peer = config.network.peers.get_mut(id).ok_or(ConfigCommandError::PeerNotFound(*id))?;
 
-    let addresses: Vec<Ipv4Addr> = addresses_str.split(',')
-        .map(|s| s.trim().parse::<Ipv4Addr>()
-            .map_err(|_| ConfigCommandError::Validation(ValidationError::NotIPv4Address())))
+    let addresses: Vec<IpAddr> = addresses_str.split(',')
+        .map(|s| s.trim().parse::<IpAddr>()
+            .map_err(|_| ConfigCommandError::Validation(ValidationError::NotIPAddress())))
         .collect::<Result<Vec<_>, _>>()?;
 
     peer.dns.addresses = addresses;
@@ -325,8 +469,8 @@ pub fn set_connection_allowed_ips_a_to_b(id_str: &str, ips_str: &str) -> Result<
     let connection = config.network.connections.get_mut(&conn_id)
         .ok_or_else(|| ConfigCommandError::ConnectionNotFound(id_str.to_string()))?;
 
-    let ips: Vec<ipnet::Ipv4Net> = ips_str.split(',')
-        .map(|s| ipnet::Ipv4Net::from_str(s.trim())
+    let ips: Vec<ipnet::IpNet> = ips_str.split(',')
+        .map(|s| ipnet::IpNet::from_str(s.trim())
             .map_err(|_| ConfigCommandError::Validation(ValidationError::NotCIDR())))
         .collect::<Result<Vec<_>, _>>()?;
 
@@ -343,8 +487,8 @@ pub fn set_connection_allowed_ips_b_to_a(id_str: &str, ips_str: &str) -> Result<
     let connection = config.network.connections.get_mut(&conn_id)
         .ok_or_else(|| ConfigCommandError::ConnectionNotFound(id_str.to_string()))?;
 
-    let ips: Vec<ipnet::Ipv4Net> = ips_str.split(',')
-        .map(|s| ipnet::Ipv4Net::from_str(s.trim())
+    let ips: Vec<ipnet::IpNet> = ips_str.split(',')
+        .map(|s| ipnet::IpNet::from_str(s.trim())
             .map_err(|_| ConfigCommandError::Validation(ValidationError::NotCIDR())))
         .collect::<Result<Vec<_>, _>>()?;
 
@@ -394,9 +538,9 @@ pub fn set_defaults_peer_icon(src: &str) -> Result<(), ConfigCommandError> {
 pub fn set_defaults_peer_dns(addresses_str: &str) -> Result<(), ConfigCommandError> {
     let mut config = conf::util::get_config()?;
 
-    let addresses: Vec<Ipv4Addr> = addresses_str.split(',')
-        .map(|s| s.trim().parse::<Ipv4Addr>()
-            .map_err(|_| ConfigCommandError::Validation(ValidationError::NotIPv4Address())))
+    let addresses: Vec<IpAddr> = addresses_str.split(',')
+        .map(|s| s.trim().parse::<IpAddr>()
+            .map_err(|_| ConfigCommandError::Validation(ValidationError::NotIPAddress())))
         .collect::<Result<Vec<_>, _>>()?;
 
     config.network.defaults.peer.dns.addresses = addresses;
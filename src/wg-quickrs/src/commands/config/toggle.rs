@@ -40,6 +40,27 @@ macro_rules! impl_toggle {
 
 /// Macro for peer-specific toggle functions
 macro_rules! impl_peer_toggle {
+    // For plain boolean fields
+    ($enable_fn:ident, $disable_fn:ident, $field:ident, $field_name:expr, flag) => {
+        pub fn $enable_fn(id: &Uuid) -> Result<(), ConfigCommandError> {
+            let mut config = conf::util::get_config()?;
+            let peer = config.network.peers.get_mut(id).ok_or(ConfigCommandError::PeerNotFound(*id))?;
+            peer.$field = true;
+            log::info!("Enabled peer {} {}", id, $field_name);
+            conf::util::set_config(&mut config)?;
+            Ok(())
+        }
+
+        pub fn $disable_fn(id: &Uuid) -> Result<(), ConfigCommandError> {
+            let mut config = conf::util::get_config()?;
+            let peer = config.network.peers.get_mut(id).ok_or(ConfigCommandError::PeerNotFound(*id))?;
+            peer.$field = false;
+            log::info!("Disabled peer {} {}", id, $field_name);
+            conf::util::set_config(&mut config)?;
+            Ok(())
+        }
+    };
+    // For structs with an `enabled` field
     ($enable_fn:ident, $disable_fn:ident, $($field:ident).+, $field_name:expr) => {
         pub fn $enable_fn(id: &Uuid) -> Result<(), ConfigCommandError> {
             let mut config = conf::util::get_config()?;
@@ -136,6 +157,42 @@ impl_toggle!(
     }
 );
 
+impl_toggle!(
+    toggle_agent_web_metrics,
+    agent.web.metrics =>
+    |_: &wg_quickrs_lib::types::config::Config| "Prometheus metrics endpoint...".to_string()
+);
+
+impl_toggle!(
+    toggle_agent_web_metrics_token,
+    agent.web.metrics.token =>
+    |_: &wg_quickrs_lib::types::config::Config| "bearer token for the metrics endpoint...".to_string(),
+    validate: |c: &wg_quickrs_lib::types::config::Config| -> Result<(), ConfigCommandError> {
+        if !conf::api_keys::is_secret_hash(&c.agent.web.metrics.token.hash) {
+            return Err(ConfigCommandError::PasswordHash("metrics token is not set, reset it first".into()));
+        }
+        Ok(())
+    }
+);
+
+impl_toggle!(
+    toggle_agent_web_persistent_jwt_key,
+    agent.web.persistent_jwt_key =>
+    |_: &wg_quickrs_lib::types::config::Config| "persistent JWT signing key...".to_string()
+);
+
+impl_toggle!(
+    toggle_agent_web_oidc,
+    agent.web.oidc =>
+    |c: &wg_quickrs_lib::types::config::Config| format!("OpenID Connect login (issuer={})...", c.agent.web.oidc.issuer),
+    validate: |c: &wg_quickrs_lib::types::config::Config| -> Result<(), ConfigCommandError> {
+        if !c.agent.web.password.enabled {
+            log::warn!("Password authentication is disabled, the API is open to everyone without signing in");
+        }
+        Ok(())
+    }
+);
+
 impl_toggle!(
     toggle_agent_vpn,
     agent.vpn =>
@@ -148,6 +205,12 @@ impl_toggle!(
     |_c: &wg_quickrs_lib::types::config::Config| "WireGuard userspace mode...".to_string()
 );
 
+impl_toggle!(
+    toggle_agent_vpn_telemetry_history,
+    agent.vpn.telemetry.history =>
+    |_c: &wg_quickrs_lib::types::config::Config| "persistent telemetry history...".to_string()
+);
+
 impl_toggle!(
     toggle_network_amnezia_parameters,
     network.amnezia_parameters =>
@@ -159,6 +222,7 @@ impl_peer_toggle!(enable_peer_endpoint, disable_peer_endpoint, endpoint, "endpoi
 impl_peer_toggle!(enable_peer_icon, disable_peer_icon, icon, "icon");
 impl_peer_toggle!(enable_peer_dns, disable_peer_dns, dns, "DNS");
 impl_peer_toggle!(enable_peer_mtu, disable_peer_mtu, mtu, "MTU");
+impl_peer_toggle!(enable_peer_host_address, disable_peer_host_address, host_address, "host address", flag);
 
 // Connection toggles
 impl_connection_toggle!(enable_connection, disable_connection);
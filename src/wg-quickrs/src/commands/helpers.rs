@@ -1,8 +1,8 @@
 use argon2::password_hash::SaltString;
 use argon2::{Argon2, PasswordHasher};
-use ipnet::Ipv4Net;
+use ipnet::IpNet;
 use rand::{rng, RngCore};
-use std::net::Ipv4Addr;
+use std::net::IpAddr;
 use wg_quickrs_lib::types::network::{AllowedIPs, Script};
 use wg_quickrs_lib::validation::error::ValidationResult;
 use wg_quickrs_lib::validation::network::parse_and_validate_peer_script;
@@ -182,12 +182,12 @@ pub fn get_dns_addresses(
     cli_no_prompt: Option<bool>,
     step_str: String,
     cli_enabled: Option<bool>,
-    cli_addresses: Vec<Ipv4Addr>,
+    cli_addresses: Vec<IpAddr>,
     enabled_flag: &str,
     addresses_flag: &str,
     enabled_help: &str,
     addresses_help: &str,
-) -> Vec<Ipv4Addr> {
+) -> Vec<IpAddr> {
     get_dns_addresses_with_defaults(
         cli_no_prompt,
         step_str,
@@ -207,13 +207,13 @@ pub fn get_dns_addresses_with_defaults(
     cli_no_prompt: Option<bool>,
     step_str: String,
     cli_enabled: Option<bool>,
-    cli_addresses: Vec<Ipv4Addr>,
+    cli_addresses: Vec<IpAddr>,
     enabled_flag: &str,
     addresses_flag: &str,
     enabled_help: &str,
     addresses_help: &str,
-    default_addresses: Vec<Ipv4Addr>,
-) -> Vec<Ipv4Addr> {
+    default_addresses: Vec<IpAddr>,
+) -> Vec<IpAddr> {
     let mut addresses = Vec::new();
 
     // Check if DNS is enabled at all
@@ -255,10 +255,10 @@ pub fn get_dns_addresses_with_defaults(
     // Prompt for DNS addresses in a loop
     let mut dns_address_counter = 0;
     loop {
-        let dns_address: Ipv4Addr = prompt(
+        let dns_address: IpAddr = prompt(
             &format!("\t{} {} (CLI option '{}')", step_str, addresses_help, addresses_flag),
             if dns_address_counter < default_addresses.len() { Some(default_addresses[dns_address_counter].to_string()) } else { None },
-            |s: &str| s.trim().parse().map_err(|_| wg_quickrs_lib::validation::error::ValidationError::NotIPv4Address()),
+            |s: &str| s.trim().parse().map_err(|_| wg_quickrs_lib::validation::error::ValidationError::NotIPAddress()),
         );
         addresses.push(dns_address);
         dns_address_counter += 1;
@@ -281,10 +281,10 @@ pub fn get_dns_addresses_with_defaults(
 pub fn get_allowed_ips(
     cli_no_prompt: Option<bool>,
     step_str: String,
-    cli_allowed_ips: Vec<Ipv4Net>,
+    cli_allowed_ips: Vec<IpNet>,
     flag: &str,
     help: &str,
-    default_allowed_ips: Vec<Ipv4Net>,
+    default_allowed_ips: Vec<IpNet>,
 ) -> AllowedIPs {
     let mut allowed_ips = Vec::new();
 
@@ -306,7 +306,7 @@ pub fn get_allowed_ips(
     // Prompt for allowed IPs in a loop
     let mut allowed_ip_counter = 0;
     loop {
-        let allowed_ip: Ipv4Net = prompt(
+        let allowed_ip: IpNet = prompt(
             &format!("\t{} {} (CLI option '{}')", step_str, help, flag),
             if allowed_ip_counter < default_allowed_ips.len() {
                 Some(default_allowed_ips[allowed_ip_counter].to_string())
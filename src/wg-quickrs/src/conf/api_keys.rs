@@ -0,0 +1,76 @@
+use crate::WG_QUICKRS_CONFIG_FOLDER;
+use aws_lc_rs::constant_time::verify_slices_are_equal;
+use aws_lc_rs::digest::{SHA256, digest};
+use chrono::{DateTime, Duration, Utc};
+use once_cell::sync::Lazy;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tempfile::NamedTempFile;
+
+/// Kept apart from conf.yml, so using a key neither rewrites the config nor adds a history revision
+const LAST_USED_FILE: &str = "api_keys_last_used.json";
+/// Uses closer together than this are not written to disk again
+const LAST_USED_PRECISION: Duration = Duration::seconds(60);
+
+/// Prefixes the hash of an API key's secret
+const HASH_PREFIX: &str = "sha256:";
+
+/// When each API key was last used, keyed by the key's id
+static LAST_USED: Lazy<Mutex<BTreeMap<String, DateTime<Utc>>>> = Lazy::new(|| Mutex::new(read_last_used()));
+
+fn last_used_path() -> PathBuf {
+    WG_QUICKRS_CONFIG_FOLDER.get().unwrap().join(LAST_USED_FILE)
+}
+
+/// When each API key was last used as recorded on disk, keys that were never used are missing
+pub(crate) fn read_last_used() -> BTreeMap<String, DateTime<Utc>> {
+    fs::read_to_string(last_used_path())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn write_last_used(last_used: &BTreeMap<String, DateTime<Utc>>) -> std::io::Result<()> {
+    let path = last_used_path();
+    let mut temp_file = NamedTempFile::new_in(path.parent().unwrap())?;
+    temp_file.write_all(serde_json::to_string(last_used)?.as_bytes())?;
+    temp_file.persist(&path).map_err(|e| e.error)?;
+    Ok(())
+}
+
+/// Records that the API key with `id` was just used, keys no longer in `known_ids` are forgotten
+pub(crate) fn record_use<'a>(id: &str, known_ids: impl Iterator<Item = &'a String>) {
+    let Ok(mut last_used) = LAST_USED.lock() else {
+        return;
+    };
+    let now = Utc::now();
+    if last_used.get(id).is_some_and(|used_at| now - *used_at < LAST_USED_PRECISION) {
+        return;
+    }
+    last_used.insert(id.to_string(), now);
+    let known_ids: Vec<&String> = known_ids.collect();
+    last_used.retain(|id, _| known_ids.contains(&id));
+    if let Err(e) = write_last_used(&last_used) {
+        log::warn!("Failed to record when API key {} was last used: {}", id, e);
+    }
+}
+
+/// The hash kept of an API key's secret; the secrets are random and long, so unlike passwords they need no slow hash
+/// and every request can be checked without taking one of the few Argon2 slots
+pub(crate) fn hash_secret(secret: &str) -> String {
+    let hash: String = digest(&SHA256, secret.as_bytes()).as_ref().iter().map(|b| format!("{b:02x}")).collect();
+    format!("{HASH_PREFIX}{hash}")
+}
+
+/// Whether `hash` was made by `hash_secret`
+pub(crate) fn is_secret_hash(hash: &str) -> bool {
+    hash.strip_prefix(HASH_PREFIX).is_some_and(|hex| hex.len() == 64 && hex.bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
+/// Whether `secret` is the one `hash` was made of, in constant time
+pub(crate) fn secret_matches(hash: &str, secret: &str) -> bool {
+    verify_slices_are_equal(hash.as_bytes(), hash_secret(secret).as_bytes()).is_ok()
+}
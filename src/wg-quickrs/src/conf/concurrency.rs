@@ -0,0 +1,114 @@
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use std::collections::{BTreeSet, VecDeque};
+use std::sync::Mutex;
+
+/// How many applied changes are remembered to merge patches that are based on an older digest
+const CHANGES_CAPACITY: usize = 64;
+
+/// Fields of peers and defaults that are patched one sub-field at a time
+const NESTED_FIELDS: [&str; 2] = ["scripts", "amnezia_parameters"];
+
+struct AppliedChange {
+    from_digest: String,
+    to_digest: String,
+    fields: BTreeSet<String>,
+}
+
+static CHANGES: Lazy<Mutex<VecDeque<AppliedChange>>> = Lazy::new(|| Mutex::new(VecDeque::with_capacity(CHANGES_CAPACITY)));
+
+/// Why a patch based on an older digest cannot be applied
+pub(crate) enum Conflict {
+    /// the network changed in a way that was not recorded, e.g. the config file was edited
+    UnknownDigest,
+    /// fields that were changed since the digest and are changed again by the patch
+    Fields(Vec<String>),
+}
+
+fn insert_fields(fields: &mut BTreeSet<String>, prefix: &str, value: &Value) {
+    for (field, value) in value.as_object().into_iter().flatten() {
+        if value.is_null() {
+            continue;
+        }
+        let path = format!("{prefix}/{field}");
+        if NESTED_FIELDS.contains(&field.as_str()) {
+            insert_fields(fields, &path, value);
+        } else {
+            fields.insert(path);
+        }
+    }
+}
+
+/// Paths of everything a serialized `ChangeSum` touches, e.g. `peers/<id>/name` for a changed
+/// field or `peers/<id>` for an added or removed peer
+pub(crate) fn touched_fields(change_sum: &Value) -> BTreeSet<String> {
+    let mut fields = BTreeSet::new();
+    let changed_fields = &change_sum["changed_fields"];
+    for kind in ["peers", "connections"] {
+        for (id, value) in changed_fields[kind].as_object().into_iter().flatten() {
+            insert_fields(&mut fields, &format!("{kind}/{id}"), value);
+        }
+        for id in change_sum[format!("added_{kind}")].as_object().into_iter().flatten().map(|(id, _)| id) {
+            fields.insert(format!("{kind}/{id}"));
+        }
+        for id in change_sum[format!("removed_{kind}")].as_array().into_iter().flatten().filter_map(Value::as_str) {
+            fields.insert(format!("{kind}/{id}"));
+        }
+    }
+    for kind in ["peer", "connection"] {
+        insert_fields(&mut fields, &format!("defaults/{kind}"), &changed_fields["defaults"][kind]);
+    }
+    insert_fields(&mut fields, "amnezia_parameters", &changed_fields["amnezia_parameters"]);
+    fields
+}
+
+fn overlaps(a: &str, b: &str) -> bool {
+    a == b || a.strip_prefix(b).is_some_and(|rest| rest.starts_with('/'))
+        || b.strip_prefix(a).is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// Remembers a change that moved the network from one digest to another
+pub(crate) fn record(from_digest: &str, to_digest: &str, fields: BTreeSet<String>) {
+    let mut changes = CHANGES.lock().unwrap_or_else(|e| e.into_inner());
+    if changes.len() == CHANGES_CAPACITY {
+        changes.pop_front();
+    }
+    changes.push_back(AppliedChange {
+        from_digest: from_digest.to_string(),
+        to_digest: to_digest.to_string(),
+        fields,
+    });
+}
+
+/// Checks whether a patch touching `fields` that was based on `base_digest` can still be applied
+/// on top of `current_digest` without overwriting any of the changes made in between
+pub(crate) fn check(base_digest: &str, current_digest: &str, fields: &BTreeSet<String>) -> Result<(), Conflict> {
+    if base_digest == current_digest {
+        return Ok(());
+    }
+    let changes = CHANGES.lock().unwrap_or_else(|e| e.into_inner());
+    let start = changes.iter().rposition(|change| change.from_digest == base_digest)
+        .ok_or(Conflict::UnknownDigest)?;
+
+    let mut digest = base_digest;
+    let mut conflicting = BTreeSet::new();
+    for change in changes.range(start..) {
+        // a gap in the chain means something else changed the network
+        if change.from_digest != digest {
+            return Err(Conflict::UnknownDigest);
+        }
+        digest = &change.to_digest;
+        for field in fields {
+            if change.fields.iter().any(|changed| overlaps(field, changed)) {
+                conflicting.insert(field.clone());
+            }
+        }
+    }
+    if digest != current_digest {
+        return Err(Conflict::UnknownDigest);
+    }
+    if !conflicting.is_empty() {
+        return Err(Conflict::Fields(conflicting.into_iter().collect()));
+    }
+    Ok(())
+}
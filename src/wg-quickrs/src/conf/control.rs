@@ -0,0 +1,362 @@
+use crate::WG_QUICKRS_CONFIG_FOLDER;
+use crate::conf::history::Change;
+use crate::conf::respond;
+use crate::web::api::PatchQuery;
+use actix_web::{HttpResponse, web};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use std::fs;
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use thiserror::Error;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+use tokio::net::UnixListener;
+use tokio::sync::{mpsc, oneshot};
+use wg_quickrs_lib::types::api::ChangeSum;
+use wg_quickrs_lib::types::network::Network;
+
+/// Unix domain socket in the config folder the running agent takes changes from the CLI on
+const SOCKET_FILE: &str = "agent.sock";
+/// How long a request or the agent's response may take
+const SOCKET_TIMEOUT: Duration = Duration::from_secs(30);
+/// Peer fields the agent keeps up to date on its own when it applies a change
+const IGNORED_PEER_FIELDS: [&str; 2] = ["created_at", "updated_at"];
+
+/// Whether this agent bound the control socket, only then it removes it on exit
+static SOCKET_BOUND: AtomicBool = AtomicBool::new(false);
+
+#[derive(Error, Debug)]
+pub enum ControlError {
+    #[error("the agent is running but its control socket at {0} is not accessible: {1}")]
+    Connect(PathBuf, std::io::Error),
+    #[error("failed to talk to the running agent: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid response from the running agent: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("the running agent rejected the change ({0}): {1}")]
+    Rejected(u16, String),
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum ControlRequest {
+    /// Same as `PATCH /api/network/config`, with the digest of the network it is based on in the change
+    Patch { author: String, change_sum: Box<ChangeSum> },
+    /// Loads conf.yml again, for changes that can't be expressed as a `ChangeSum`
+    Reload,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct ControlResponse {
+    status: u16,
+    body: String,
+}
+
+/// Asks the agent's run loop to reload conf.yml, the reply carries why it couldn't
+pub(crate) type ReloadRequest = oneshot::Sender<Result<(), String>>;
+
+fn socket_path() -> PathBuf {
+    WG_QUICKRS_CONFIG_FOLDER.get().unwrap().join(SOCKET_FILE)
+}
+
+async fn into_control_response(response: HttpResponse) -> ControlResponse {
+    let status = response.status().as_u16();
+    let body = actix_web::body::to_bytes(response.into_body()).await
+        .map(|body| String::from_utf8_lossy(&body).to_string())
+        .unwrap_or_default();
+    ControlResponse { status, body }
+}
+
+async fn handle_request(request: ControlRequest, reload: &mpsc::UnboundedSender<ReloadRequest>) -> ControlResponse {
+    match request {
+        ControlRequest::Patch { author, change_sum } => {
+            let body = match serde_json::to_vec(&change_sum) {
+                Ok(body) => web::Bytes::from(body),
+                Err(e) => return ControlResponse { status: 400, body: e.to_string() },
+            };
+            let query = web::Query(PatchQuery { dry_run: false });
+            let response = respond::patch_network_config(Change::control(author), None, query, body)
+                .unwrap_or_else(|e| e);
+            into_control_response(response).await
+        }
+        ControlRequest::Reload => {
+            let (reply, result) = oneshot::channel();
+            if reload.send(reply).is_err() {
+                return ControlResponse { status: 503, body: "the agent is shutting down".to_string() };
+            }
+            match result.await {
+                Ok(Ok(())) => ControlResponse { status: 200, body: "reloaded".to_string() },
+                Ok(Err(e)) => ControlResponse { status: 400, body: e },
+                Err(_) => ControlResponse { status: 503, body: "the agent is shutting down".to_string() },
+            }
+        }
+    }
+}
+
+async fn handle_connection(stream: tokio::net::UnixStream, reload: &mpsc::UnboundedSender<ReloadRequest>) -> std::io::Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut line = String::new();
+    match tokio::time::timeout(SOCKET_TIMEOUT, tokio::io::BufReader::new(reader).read_line(&mut line)).await {
+        Ok(result) => result?,
+        Err(_) => return Ok(()),
+    };
+    // the CLI connects without a request to find out whether the agent is running
+    if line.trim().is_empty() {
+        return Ok(());
+    }
+
+    let response = match serde_json::from_str::<ControlRequest>(&line) {
+        Ok(request) => handle_request(request, reload).await,
+        Err(e) => ControlResponse { status: 400, body: format!("invalid request: {e}") },
+    };
+    let mut response = serde_json::to_vec(&response).map_err(std::io::Error::other)?;
+    response.push(b'\n');
+    writer.write_all(&response).await
+}
+
+/// Only the user running the agent may change its config: the socket is bound in a directory only they can enter
+/// and moved into place once restricted, so it is never reachable with the permissions the umask gives it
+fn bind_private(path: &Path) -> std::io::Result<UnixListener> {
+    let private_dir = tempfile::Builder::new().prefix(".agent-sock-").tempdir_in(path.parent().unwrap())?;
+    let private_path = private_dir.path().join(SOCKET_FILE);
+    let listener = UnixListener::bind(&private_path)?;
+    fs::set_permissions(&private_path, fs::Permissions::from_mode(0o600))?;
+    fs::rename(&private_path, path)?;
+    Ok(listener)
+}
+
+/// Takes requests from the CLI until the agent exits, one at a time so they are applied in order
+pub(crate) async fn run_control_socket(reload: mpsc::UnboundedSender<ReloadRequest>) {
+    let path = socket_path();
+    match UnixStream::connect(&path) {
+        Err(e) if e.kind() == ErrorKind::NotFound => {}
+        // left behind by an agent that didn't exit cleanly
+        Err(e) if e.kind() == ErrorKind::ConnectionRefused => {
+            let _ = fs::remove_file(&path);
+        }
+        Ok(_) => {
+            log::error!("Another agent is listening on the control socket {}, not taking it over", path.display());
+            return;
+        }
+        Err(e) => {
+            log::error!("Unable to check the control socket {}: {}", path.display(), e);
+            return;
+        }
+    }
+    let listener = match bind_private(&path) {
+        Ok(listener) => listener,
+        Err(e) => {
+            log::error!("Unable to bind the control socket to {}: {}", path.display(), e);
+            return;
+        }
+    };
+    SOCKET_BOUND.store(true, Ordering::SeqCst);
+    log::info!("control socket listening on {}", path.display());
+
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                if let Err(e) = handle_connection(stream, &reload).await {
+                    log::warn!("Control socket connection failed: {}", e);
+                }
+            }
+            Err(e) => log::warn!("Unable to accept a control socket connection: {}", e),
+        }
+    }
+}
+
+pub(crate) fn remove_control_socket() {
+    if SOCKET_BOUND.load(Ordering::SeqCst) {
+        let _ = fs::remove_file(socket_path());
+    }
+}
+
+fn connect() -> Result<Option<UnixStream>, ControlError> {
+    let path = socket_path();
+    match UnixStream::connect(&path) {
+        Ok(stream) => Ok(Some(stream)),
+        // no agent, or one that is gone without removing its socket
+        Err(e) if matches!(e.kind(), ErrorKind::NotFound | ErrorKind::ConnectionRefused) => Ok(None),
+        Err(e) => Err(ControlError::Connect(path, e)),
+    }
+}
+
+/// Whether an agent is running and listening on its control socket
+pub(crate) fn agent_listening() -> Result<bool, ControlError> {
+    Ok(connect()?.is_some())
+}
+
+fn request(request: &ControlRequest) -> Result<(), ControlError> {
+    let Some(mut stream) = connect()? else {
+        return Err(ControlError::Rejected(503, "the agent stopped".to_string()));
+    };
+    stream.set_read_timeout(Some(SOCKET_TIMEOUT))?;
+    let mut request = serde_json::to_vec(request)?;
+    request.push(b'\n');
+    stream.write_all(&request)?;
+
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line)?;
+    let response: ControlResponse = serde_json::from_str(&line)?;
+    if !(200..300).contains(&response.status) {
+        return Err(ControlError::Rejected(response.status, response.body));
+    }
+    Ok(())
+}
+
+/// Has the running agent apply the change like one made over the API
+pub(crate) fn patch(change: &Change, change_sum: &ChangeSum) -> Result<(), ControlError> {
+    request(&ControlRequest::Patch { author: change.author.clone(), change_sum: Box::new(change_sum.clone()) })
+}
+
+/// Has the running agent load conf.yml again
+pub(crate) fn reload() -> Result<(), ControlError> {
+    request(&ControlRequest::Reload)
+}
+
+/// The fields of `new` that differ from `old`, `None` when a field was removed, since a `ChangeSum` can't clear one
+fn changed_fields(old: &Value, new: &Value, ignored: &[&str]) -> Option<Map<String, Value>> {
+    let (Some(old), Some(new)) = (old.as_object(), new.as_object()) else {
+        return None;
+    };
+    let mut fields = Map::new();
+    for (key, old_value) in old {
+        if ignored.contains(&key.as_str()) {
+            continue;
+        }
+        match new.get(key) {
+            Some(new_value) if new_value == old_value => {}
+            Some(Value::Null) | None => return None,
+            Some(new_value) => {
+                fields.insert(key.clone(), new_value.clone());
+            }
+        }
+    }
+    for (key, new_value) in new {
+        if !old.contains_key(key) && !ignored.contains(&key.as_str()) {
+            fields.insert(key.clone(), new_value.clone());
+        }
+    }
+    Some(fields)
+}
+
+/// Added, removed and changed entries of a map like the peers or connections of a network
+struct ChangedEntries {
+    added: Map<String, Value>,
+    removed: Vec<Value>,
+    changed: Map<String, Value>,
+}
+
+fn changed_entries(old: &Value, new: &Value, ignored: &[&str]) -> Option<ChangedEntries> {
+    let (Some(old), Some(new)) = (old.as_object(), new.as_object()) else {
+        return None;
+    };
+    let added = new.iter()
+        .filter(|(id, _)| !old.contains_key(*id))
+        .map(|(id, entry)| {
+            let mut entry = entry.clone();
+            if let Some(entry) = entry.as_object_mut() {
+                entry.retain(|key, _| !ignored.contains(&key.as_str()));
+            }
+            (id.clone(), entry)
+        })
+        .collect();
+    let removed = old.keys()
+        .filter(|id| !new.contains_key(*id))
+        .map(|id| json!(id))
+        .collect();
+    let mut changed = Map::new();
+    for (id, old_entry) in old {
+        if let Some(new_entry) = new.get(id) {
+            let fields = changed_fields(old_entry, new_entry, ignored)?;
+            if !fields.is_empty() {
+                changed.insert(id.clone(), Value::Object(fields));
+            }
+        }
+    }
+    Some(ChangedEntries { added, removed, changed })
+}
+
+fn non_empty<T: Into<Value>>(value: T) -> Value {
+    let value = value.into();
+    let empty = match &value {
+        Value::Object(map) => map.is_empty(),
+        Value::Array(list) => list.is_empty(),
+        _ => false,
+    };
+    if empty { Value::Null } else { value }
+}
+
+/// `new` as a change to `old` the agent can apply the same way as one made over the API.
+/// `None` when nothing changed, or the change touches what a `ChangeSum` doesn't cover, e.g. the subnet,
+/// the reservations or the scripts of the agent's own peer.
+pub(crate) fn change_sum_between(old: &Network, new: &Network) -> Option<ChangeSum> {
+    let old_json = json!(old);
+    let new_json = json!(new);
+    let covered = ["peers", "connections", "defaults", "amnezia_parameters", "updated_at"];
+    let uncovered = |network: &Value| -> Value {
+        network.as_object().map(|network| {
+            network.iter()
+                .filter(|(key, _)| !covered.contains(&key.as_str()))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect::<Map<_, _>>()
+        }).map(Value::Object).unwrap_or_default()
+    };
+    if uncovered(&old_json) != uncovered(&new_json) {
+        return None;
+    }
+
+    let peers = changed_entries(&old_json["peers"], &new_json["peers"], &IGNORED_PEER_FIELDS)?;
+    // the agent only takes these for its own peer from conf.yml
+    if let Some(this_peer) = peers.changed.get(&old.this_peer.to_string())
+        && (this_peer.get("scripts").is_some() || this_peer.get("public_key").is_some())
+    {
+        return None;
+    }
+    let connections = changed_entries(&old_json["connections"], &new_json["connections"], &[])?;
+    let defaults = changed_fields(&old_json["defaults"], &new_json["defaults"], &[])?;
+    let amnezia_parameters = changed_fields(&old_json["amnezia_parameters"], &new_json["amnezia_parameters"], &[])?;
+
+    let changed = json!({
+        "peers": non_empty(peers.changed),
+        "connections": non_empty(connections.changed),
+        "defaults": non_empty(defaults),
+        "amnezia_parameters": non_empty(amnezia_parameters),
+    });
+    let change_sum_json = json!({
+        "changed_fields": if changed.as_object()?.values().all(Value::is_null) { Value::Null } else { changed },
+        "added_peers": non_empty(peers.added),
+        "added_connections": non_empty(connections.added),
+        "removed_peers": non_empty(peers.removed),
+        "removed_connections": non_empty(connections.removed),
+    });
+    if change_sum_json.as_object()?.values().all(Value::is_null) {
+        return None;
+    }
+
+    // fields a `ChangeSum` has no place for would be dropped on the way, those changes go through conf.yml
+    let change_sum: ChangeSum = serde_json::from_value(change_sum_json.clone()).ok()?;
+    let mut round_trip = json!(change_sum);
+    round_trip.as_object_mut()?.remove("digest");
+    if strip_nulls(round_trip) != strip_nulls(change_sum_json) {
+        return None;
+    }
+    Some(change_sum)
+}
+
+fn strip_nulls(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .filter(|(_, value)| !value.is_null())
+                .map(|(key, value)| (key, strip_nulls(value)))
+                .collect()
+        ),
+        Value::Array(list) => Value::Array(list.into_iter().map(strip_nulls).collect()),
+        value => value,
+    }
+}
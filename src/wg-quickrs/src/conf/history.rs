@@ -0,0 +1,303 @@
+use crate::WG_QUICKRS_CONFIG_FOLDER;
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use similar::TextDiff;
+use std::fs::{self, DirBuilder, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tempfile::NamedTempFile;
+use thiserror::Error;
+use wg_quickrs_lib::types::config::{Agent, ConfigFile};
+
+const HISTORY_FOLDER: &str = "history";
+const INDEX_FILE: &str = "index.jsonl";
+/// How many changed paths are spelled out in the summary of a revision
+const SUMMARY_PATHS: usize = 8;
+/// Fields that change with every write and say nothing about the change
+const IGNORED_PATHS: [&str; 2] = ["version", "network.updated_at"];
+
+#[derive(Error, Debug)]
+pub enum HistoryError {
+    #[error("config history io error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("config history index is corrupted: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("revision {0} is not in the config history")]
+    NotFound(u64),
+    #[error("revision {0} is not a valid config file: {1}")]
+    Parse(u64, serde_norway::Error),
+    #[error("failed to acquire lock: {0}")]
+    MutexLockFailed(String),
+}
+
+pub type HistoryResult<T> = Result<T, HistoryError>;
+
+/// Who changed the config and how, recorded with the revision the change creates
+#[derive(Clone, Debug)]
+pub(crate) struct Change {
+    pub(crate) author: String,
+    pub(crate) rollback_of: Option<u64>,
+}
+
+impl Change {
+    /// A change made on the command line by the user running it
+    pub(crate) fn cli() -> Self {
+        let user = ["SUDO_USER", "USER", "LOGNAME"].iter()
+            .find_map(|var| std::env::var(var).ok().filter(|user| !user.is_empty()))
+            .unwrap_or_else(|| "unknown".to_string());
+        Change { author: format!("cli:{user}"), rollback_of: None }
+    }
+
+    /// A change made over the API, `client_id` is only known when password auth is enabled
+    pub(crate) fn api(client_id: Option<&str>) -> Self {
+        let author = match client_id {
+            Some(client_id) => format!("api:{client_id}"),
+            None => "api".to_string(),
+        };
+        Change { author, rollback_of: None }
+    }
+
+    /// A change handed to the running agent over its control socket, by the author the CLI recorded
+    pub(crate) fn control(author: String) -> Self {
+        Change { author, rollback_of: None }
+    }
+
+    pub(crate) fn rollback_of(self, revision: u64) -> Self {
+        Change { rollback_of: Some(revision), ..self }
+    }
+}
+
+/// One line of the history index, the config itself is kept in `<revision>.yml` next to it
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct Revision {
+    pub(crate) revision: u64,
+    pub(crate) timestamp: DateTime<Utc>,
+    pub(crate) author: String,
+    pub(crate) summary: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) rollback_of: Option<u64>,
+}
+
+static HISTORY_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+fn history_folder() -> PathBuf {
+    WG_QUICKRS_CONFIG_FOLDER.get().unwrap().join(HISTORY_FOLDER)
+}
+
+fn snapshot_path(revision: u64) -> PathBuf {
+    history_folder().join(format!("{revision}.yml"))
+}
+
+/// Every kept revision, oldest first
+pub(crate) fn list() -> HistoryResult<Vec<Revision>> {
+    let file = match File::open(history_folder().join(INDEX_FILE)) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut revisions = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        // a crash while appending can leave a partial last line behind
+        match serde_json::from_str(&line) {
+            Ok(revision) => revisions.push(revision),
+            Err(e) => log::warn!("Skipping unreadable line in the config history: {}", e),
+        }
+    }
+    Ok(revisions)
+}
+
+/// A kept revision and the conf.yml it stored
+pub(crate) fn read(revision: u64) -> HistoryResult<(Revision, String)> {
+    let entry = list()?.into_iter()
+        .find(|entry| entry.revision == revision)
+        .ok_or(HistoryError::NotFound(revision))?;
+    let config_file_str = match fs::read_to_string(snapshot_path(revision)) {
+        Ok(config_file_str) => config_file_str,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Err(HistoryError::NotFound(revision)),
+        Err(e) => return Err(e.into()),
+    };
+    Ok((entry, config_file_str))
+}
+
+pub(crate) fn read_config_file(revision: u64) -> HistoryResult<ConfigFile> {
+    let (_, config_file_str) = read(revision)?;
+    serde_norway::from_str(&config_file_str).map_err(|e| HistoryError::Parse(revision, e))
+}
+
+/// Rollbacks restore the network and the agent settings, but not the credentials: a rollback must not bring back
+/// a removed user, peer token or API key, or a password that was changed since
+pub(crate) fn keep_current_credentials(restored: &mut ConfigFile, current: &Agent) {
+    let web = &mut restored.agent.web;
+    web.password = current.web.password.clone();
+    web.metrics.token = current.web.metrics.token.clone();
+    web.users = current.web.users.clone();
+    web.peer_tokens = current.web.peer_tokens.clone();
+    web.api_keys = current.web.api_keys.clone();
+    web.oidc = current.web.oidc.clone();
+    // tokens of peers the restored network does not have are revoked with them
+    let peers = &restored.network.peers;
+    web.peer_tokens.retain(|_, token| peers.contains_key(&token.peer_id));
+}
+
+/// The revision that was stored right before `revision`, `None` for the oldest kept one
+pub(crate) fn previous(revision: u64) -> HistoryResult<Option<u64>> {
+    Ok(list()?.iter().map(|entry| entry.revision).filter(|r| *r < revision).max())
+}
+
+/// Unified diff between two renderings of a config
+pub(crate) fn diff(from: &str, to: &str, from_label: &str, to_label: &str) -> String {
+    TextDiff::from_lines(from, to)
+        .unified_diff()
+        .header(from_label, to_label)
+        .to_string()
+}
+
+fn changed_paths(prefix: &str, old: &Value, new: &Value, paths: &mut Vec<String>) {
+    if IGNORED_PATHS.contains(&prefix) || old == new {
+        return;
+    }
+    let join = |key: &str| if prefix.is_empty() { key.to_string() } else { format!("{prefix}.{key}") };
+    match (old.as_object(), new.as_object()) {
+        (Some(old), Some(new)) => {
+            for (key, old_value) in old {
+                match new.get(key) {
+                    Some(new_value) => changed_paths(&join(key), old_value, new_value, paths),
+                    None if !IGNORED_PATHS.contains(&join(key).as_str()) => paths.push(format!("-{}", join(key))),
+                    None => {}
+                }
+            }
+            for key in new.keys().filter(|key| !old.contains_key(*key)) {
+                if !IGNORED_PATHS.contains(&join(key).as_str()) {
+                    paths.push(format!("+{}", join(key)));
+                }
+            }
+        }
+        _ => paths.push(format!("~{prefix}")),
+    }
+}
+
+/// The paths that differ between two configs, e.g. `~network.peers.<id>.address, +network.peers.<id>`
+fn summarize(previous: Option<&str>, current: &ConfigFile) -> String {
+    let Some(previous) = previous else {
+        return "initial config".to_string();
+    };
+    let previous = serde_norway::from_str::<ConfigFile>(previous).ok()
+        .and_then(|previous| serde_json::to_value(previous).ok());
+    let (Some(previous), Ok(current)) = (previous, serde_json::to_value(current)) else {
+        return "replaced an unreadable config".to_string();
+    };
+
+    let mut paths = Vec::new();
+    changed_paths("", &previous, &current, &mut paths);
+    if paths.is_empty() {
+        return "no changes".to_string();
+    }
+    let more = paths.len().saturating_sub(SUMMARY_PATHS);
+    paths.truncate(SUMMARY_PATHS);
+    let mut summary = paths.join(", ");
+    if more > 0 {
+        summary.push_str(&format!(" and {more} more"));
+    }
+    summary
+}
+
+fn write_snapshot(revision: u64, config_file_str: &str) -> HistoryResult<()> {
+    // snapshots hold the same private keys as conf.yml
+    OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(snapshot_path(revision))?
+        .write_all(config_file_str.as_bytes())?;
+    Ok(())
+}
+
+fn append_index(entry: &Revision) -> HistoryResult<()> {
+    let line = format!("{}\n", serde_json::to_string(entry)?);
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(history_folder().join(INDEX_FILE))?
+        .write_all(line.as_bytes())?;
+    Ok(())
+}
+
+/// Drops the oldest revisions until at most `retention` are left
+fn prune(mut revisions: Vec<Revision>, retention: u32) -> HistoryResult<()> {
+    let excess = revisions.len().saturating_sub(retention as usize);
+    if excess == 0 {
+        return Ok(());
+    }
+    let kept = revisions.split_off(excess);
+
+    let folder = history_folder();
+    let mut temp_file = NamedTempFile::new_in(&folder)?;
+    for entry in &kept {
+        writeln!(temp_file, "{}", serde_json::to_string(entry)?)?;
+    }
+    temp_file.persist(folder.join(INDEX_FILE)).map_err(|e| e.error)?;
+
+    for entry in revisions {
+        if let Err(e) = fs::remove_file(snapshot_path(entry.revision))
+            && e.kind() != std::io::ErrorKind::NotFound
+        {
+            return Err(e.into());
+        }
+    }
+    Ok(())
+}
+
+/// Keeps `config_file_str` as the next revision, `previous` is what conf.yml held before it was written
+pub(crate) fn record(previous: Option<&str>, config_file: &ConfigFile, config_file_str: &str, change: &Change) -> HistoryResult<u64> {
+    let _guard = HISTORY_LOCK
+        .lock()
+        .map_err(|e| HistoryError::MutexLockFailed(e.to_string()))?;
+    DirBuilder::new().recursive(true).mode(0o700).create(history_folder())?;
+
+    let mut revisions = list()?;
+    // the config as it was before the history existed can be rolled back to as well
+    if revisions.is_empty()
+        && let Some(previous) = previous
+        && previous != config_file_str
+    {
+        // stored the way it would have been written, so diffing against it only shows real changes
+        let previous_str = serde_norway::from_str::<ConfigFile>(previous).ok()
+            .and_then(|previous| serde_norway::to_string(&previous).ok())
+            .unwrap_or_else(|| previous.to_string());
+        let baseline = Revision {
+            revision: 1,
+            timestamp: Utc::now(),
+            author: "unknown".to_string(),
+            summary: "config before the first recorded change".to_string(),
+            rollback_of: None,
+        };
+        write_snapshot(baseline.revision, &previous_str)?;
+        append_index(&baseline)?;
+        revisions.push(baseline);
+    }
+
+    let entry = Revision {
+        revision: revisions.last().map_or(1, |last| last.revision + 1),
+        timestamp: Utc::now(),
+        author: change.author.clone(),
+        summary: summarize(previous, config_file),
+        rollback_of: change.rollback_of,
+    };
+    write_snapshot(entry.revision, config_file_str)?;
+    append_index(&entry)?;
+    let revision = entry.revision;
+    revisions.push(entry);
+
+    prune(revisions, config_file.agent.config_history.retention)?;
+    Ok(revision)
+}
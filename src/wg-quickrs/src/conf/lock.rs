@@ -0,0 +1,81 @@
+use crate::WG_QUICKRS_CONFIG_FOLDER;
+use std::fs::{File, OpenOptions, TryLockError};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+/// Advisory lock file shared by the CLI and the agent
+const LOCK_FILE: &str = ".lock";
+const LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Error, Debug)]
+pub enum LockError {
+    #[error("failed to lock the config folder at {0}: {1}")]
+    Io(PathBuf, std::io::Error),
+    #[error("timed out waiting for another wg-quickrs process to release the config folder lock at {0}")]
+    Timeout(PathBuf),
+}
+
+/// Held until the process exits, e.g. by a CLI command that reads, changes and writes conf.yml
+static PROCESS_LOCK: Mutex<Option<File>> = Mutex::new(None);
+
+fn holds_process_lock() -> bool {
+    PROCESS_LOCK.lock().map(|file| file.is_some()).unwrap_or(false)
+}
+
+/// The config folder stays locked until this is dropped
+pub(crate) struct ConfigLock {
+    // `None` when the process holds the lock already, closing the file releases the lock
+    _file: Option<File>,
+}
+
+fn acquire() -> Result<File, LockError> {
+    let path = WG_QUICKRS_CONFIG_FOLDER.get().unwrap().join(LOCK_FILE);
+    let file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(false)
+        .mode(0o600)
+        .open(&path)
+        .map_err(|e| LockError::Io(path.clone(), e))?;
+
+    let start = Instant::now();
+    loop {
+        match file.try_lock() {
+            Ok(()) => return Ok(file),
+            Err(TryLockError::WouldBlock) if start.elapsed() < LOCK_TIMEOUT => sleep(LOCK_RETRY_INTERVAL),
+            Err(TryLockError::WouldBlock) => return Err(LockError::Timeout(path)),
+            Err(TryLockError::Error(e)) => return Err(LockError::Io(path, e)),
+        }
+    }
+}
+
+/// Locks the config folder for a single write, other processes wait for it
+pub(crate) fn lock() -> Result<ConfigLock, LockError> {
+    if holds_process_lock() {
+        return Ok(ConfigLock { _file: None });
+    }
+    Ok(ConfigLock { _file: Some(acquire()?) })
+}
+
+/// Keeps the config folder locked until the process exits, so nothing changes conf.yml between reading and writing it
+pub(crate) fn lock_for_process() -> Result<(), LockError> {
+    if !holds_process_lock() {
+        let file = acquire()?;
+        if let Ok(mut process_lock) = PROCESS_LOCK.lock() {
+            *process_lock = Some(file);
+        }
+    }
+    Ok(())
+}
+
+/// Releases the lock taken by `lock_for_process`, e.g. before handing a change to the running agent
+pub(crate) fn unlock_for_process() {
+    if let Ok(mut process_lock) = PROCESS_LOCK.lock() {
+        process_lock.take();
+    }
+}
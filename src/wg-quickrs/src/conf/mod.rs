@@ -1,3 +1,10 @@
+pub mod api_keys;
+pub mod concurrency;
+pub mod control;
+pub mod history;
+pub mod lock;
 pub mod network;
+pub mod preview;
+pub mod render;
 pub mod respond;
 pub mod util;
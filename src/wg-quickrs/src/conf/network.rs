@@ -1,14 +1,29 @@
 use std::collections::HashSet;
-use std::net::Ipv4Addr;
+use std::net::{Ipv4Addr, Ipv6Addr};
 use wg_quickrs_lib::types::network::Network;
 
 
-/// Get the next available IPv4 address in subnet not ending with .0 or .255 and not in `taken`
-pub fn get_next_available_address(network: &Network) -> Option<Ipv4Addr> {
+/// Get the next available addresses for a new peer.
+/// The IPv4 address is in subnet not ending with .0 or .255 and not taken by a peer or a reservation.
+/// The IPv6 address is only allocated when the network has an IPv6 subnet, skipping the subnet-router anycast address.
+pub fn get_next_available_address(network: &Network) -> Option<(Ipv4Addr, Option<Ipv6Addr>)> {
     let taken: HashSet<Ipv4Addr> = network.peers.values()
         .map(|peer| peer.address)
         .chain(network.reservations.keys().cloned())
         .collect();
-    
-    network.subnet.hosts().find(|&ip| !taken.contains(&ip))
+
+    let address = network.subnet.hosts().find(|&ip| !taken.contains(&ip))?;
+    let address_v6 = match network.subnet_v6 {
+        Some(subnet_v6) => {
+            let taken_v6: HashSet<Ipv6Addr> = network.peers.values()
+                .filter_map(|peer| peer.address_v6)
+                .chain(network.reservations.values().filter_map(|res| res.address_v6))
+                .collect();
+
+            Some(subnet_v6.hosts().find(|&ip| ip != subnet_v6.network() && !taken_v6.contains(&ip))?)
+        }
+        None => None,
+    };
+
+    Some((address, address_v6))
 }
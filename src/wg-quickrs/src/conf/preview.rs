@@ -0,0 +1,164 @@
+use crate::conf::respond::redact_network_secrets;
+use crate::wireguard::wg_control::{WgControlError, preview_sync};
+use serde::Serialize;
+use serde_json::json;
+use similar::TextDiff;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use thiserror::Error;
+use uuid::Uuid;
+use wg_quickrs_lib::helpers::{get_peer_wg_config, wg_key_fingerprint};
+use wg_quickrs_lib::types::config::Config;
+use wg_quickrs_lib::types::misc::WireGuardLibError;
+use wg_quickrs_lib::types::network::{Network, NetworkWDigest};
+use wg_quickrs_lib::validation::network::parse_and_validate_wg_key;
+
+#[derive(Error, Debug)]
+pub enum PreviewError {
+    #[error(transparent)]
+    WireGuardLibError(#[from] WireGuardLibError),
+    #[error(transparent)]
+    WgControlError(#[from] WgControlError),
+}
+
+/// What a change of the configuration would do, computed without writing or applying it
+#[derive(Serialize, Debug)]
+pub(crate) struct ConfigPreview {
+    /// the resulting network, with its secrets redacted like in the summary
+    pub(crate) network: serde_json::Value,
+    pub(crate) digest: String,
+    /// unified diffs of the wg-quick configs that change, by peer id
+    pub(crate) peer_conf_diffs: BTreeMap<Uuid, String>,
+    /// what `wg syncconf` would change on the interface, `None` when the agent doesn't run the tunnel
+    pub(crate) syncconf: Option<SyncPreview>,
+}
+
+#[derive(Serialize, Debug)]
+pub(crate) struct SyncPreview {
+    pub(crate) private_key_changed: bool,
+    pub(crate) listen_port: Option<u16>,
+    pub(crate) peers: Vec<PeerSyncPreview>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum PeerSyncAction {
+    Add,
+    Update,
+    Remove,
+}
+
+/// A peer of the interface, fields that stay the same are left out
+#[derive(Serialize, Debug)]
+pub(crate) struct PeerSyncPreview {
+    pub(crate) public_key: String,
+    pub(crate) action: PeerSyncAction,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub(crate) preshared_key_changed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) endpoint: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) persistent_keepalive: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) allowed_ips: Option<Vec<String>>,
+}
+
+/// Human-readable form for the CLI, diffs don't survive being quoted in YAML
+impl fmt::Display for ConfigPreview {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "# resulting network (digest: {})", self.digest)?;
+        write!(f, "{}", serde_norway::to_string(&self.network).map_err(|_| fmt::Error)?)?;
+        writeln!(f, "# changed peer configs")?;
+        for diff in self.peer_conf_diffs.values() {
+            write!(f, "{}", diff)?;
+        }
+        match &self.syncconf {
+            Some(syncconf) => {
+                writeln!(f, "# wg syncconf")?;
+                write!(f, "{}", serde_norway::to_string(syncconf).map_err(|_| fmt::Error)?)
+            }
+            None => writeln!(f, "# wg syncconf: the VPN is disabled on this agent"),
+        }
+    }
+}
+
+/// Replaces the keys of a generated wg-quick config with their fingerprints
+fn redact_wg_conf(wg_conf: &str) -> String {
+    wg_conf.lines()
+        .map(|line| match line.split_once(" = ") {
+            Some((key @ ("PrivateKey" | "PresharedKey"), value)) => match parse_and_validate_wg_key(value) {
+                Ok(value) => format!("{} = {}\n", key, wg_key_fingerprint(&value)),
+                // e.g. the placeholder of a peer without a private key
+                Err(_) => format!("{}\n", line),
+            },
+            _ => format!("{}\n", line),
+        })
+        .collect()
+}
+
+fn peer_wg_conf(network: &Network, peer_id: &Uuid) -> Result<String, WireGuardLibError> {
+    if !network.peers.contains_key(peer_id) {
+        return Ok(String::new());
+    }
+    Ok(redact_wg_conf(&get_peer_wg_config(network, peer_id, false)?))
+}
+
+fn peer_conf_diffs(current: &Network, next: &Network) -> Result<BTreeMap<Uuid, String>, WireGuardLibError> {
+    let peer_ids: BTreeSet<&Uuid> = current.peers.keys().chain(next.peers.keys()).collect();
+    let mut diffs = BTreeMap::new();
+    for peer_id in peer_ids {
+        let current_conf = peer_wg_conf(current, peer_id)?;
+        let next_conf = peer_wg_conf(next, peer_id)?;
+        if current_conf != next_conf {
+            let diff = TextDiff::from_lines(&current_conf, &next_conf)
+                .unified_diff()
+                .header(&format!("a/{}.conf", peer_id), &format!("b/{}.conf", peer_id))
+                .to_string();
+            diffs.insert(*peer_id, diff);
+        }
+    }
+    Ok(diffs)
+}
+
+fn sync_preview(current: &Config, next: &Config) -> Result<SyncPreview, PreviewError> {
+    let (device, update) = preview_sync(current, next)?;
+    let peers = update.peers.into_iter()
+        .map(|peer| {
+            let action = if peer.remove {
+                PeerSyncAction::Remove
+            } else if device.peers.iter().any(|p| p.public_key == peer.public_key) {
+                PeerSyncAction::Update
+            } else {
+                PeerSyncAction::Add
+            };
+            PeerSyncPreview {
+                public_key: peer.public_key.to_string(),
+                action,
+                preshared_key_changed: peer.preshared_key.is_some(),
+                endpoint: peer.endpoint.map(|endpoint| endpoint.to_string()),
+                persistent_keepalive: peer.persistent_keepalive,
+                allowed_ips: peer.replace_allowed_ips
+                    .then(|| peer.allowed_ips.iter().map(|ip| ip.to_string()).collect()),
+            }
+        })
+        .collect();
+    Ok(SyncPreview {
+        private_key_changed: update.private_key.is_some(),
+        listen_port: update.listen_port,
+        peers,
+    })
+}
+
+/// Previews replacing the `current` configuration with an already validated `next` one
+pub(crate) fn preview(current: &Config, next: &Config) -> Result<ConfigPreview, PreviewError> {
+    let network_w_digest = NetworkWDigest::try_from(next.network.clone())?;
+    let mut network = json!(next.network);
+    redact_network_secrets(&mut network, &next.network);
+
+    Ok(ConfigPreview {
+        network,
+        digest: network_w_digest.digest,
+        peer_conf_diffs: peer_conf_diffs(&current.network, &next.network)?,
+        syncconf: if next.agent.vpn.enabled { Some(sync_preview(current, next)?) } else { None },
+    })
+}
@@ -0,0 +1,73 @@
+use image::{DynamicImage, ImageFormat, Luma};
+use qrcode::QrCode;
+use qrcode::render::{svg, unicode};
+use std::io::Cursor;
+use thiserror::Error;
+use wg_quickrs_cli::config::conf::ConfFormat;
+
+#[derive(Error, Debug)]
+pub enum RenderError {
+    #[error("unable to encode config as a QR code: {0}")]
+    QrEncode(#[from] qrcode::types::QrError),
+    #[error("unable to encode QR code as PNG: {0}")]
+    PngEncode(#[from] image::ImageError),
+}
+
+/// Render a generated wg-quick config in the requested output format.
+pub fn render_peer_conf(wg_conf: &str, format: ConfFormat) -> Result<Vec<u8>, RenderError> {
+    let qr_code = || QrCode::new(wg_conf.as_bytes());
+    let rendered = match format {
+        ConfFormat::Conf => wg_conf.as_bytes().to_vec(),
+        ConfFormat::Qr => {
+            // force black-on-white so the code scans on both dark and light terminal themes
+            let qr = qr_code()?.render::<unicode::Dense1x2>()
+                .dark_color(unicode::Dense1x2::Dark)
+                .light_color(unicode::Dense1x2::Light)
+                .build();
+            qr.lines()
+                .map(|line| format!("\x1b[30;47m{}\x1b[0m\n", line))
+                .collect::<String>()
+                .into_bytes()
+        }
+        ConfFormat::Png => {
+            let img = qr_code()?.render::<Luma<u8>>().min_dimensions(512, 512).build();
+            let mut png = Vec::new();
+            DynamicImage::ImageLuma8(img).write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+            png
+        }
+        ConfFormat::Svg => {
+            qr_code()?.render::<svg::Color>().min_dimensions(512, 512).build().into_bytes()
+        }
+    };
+    Ok(rendered)
+}
+
+/// Get the HTTP content type of a rendered config.
+pub fn content_type(format: ConfFormat) -> &'static str {
+    match format {
+        ConfFormat::Conf | ConfFormat::Qr => "text/plain; charset=utf-8",
+        ConfFormat::Png => "image/png",
+        ConfFormat::Svg => "image/svg+xml",
+    }
+}
+
+/// Get the download file name of a rendered config, e.g. `wg-quickrs-home-my-laptop.conf`.
+/// The peer name is sanitized the same way as the web UI does it.
+pub fn file_name(network_name: &str, peer_name: &str, format: ConfFormat) -> String {
+    let mut sanitized = String::new();
+    for c in peer_name.chars() {
+        let c = if c.is_ascii_alphanumeric() || "_=+.-".contains(c) { c } else { '-' };
+        if !(c == '-' && sanitized.ends_with('-')) {
+            sanitized.push(c);
+        }
+    }
+    let sanitized: String = sanitized.trim_end_matches('-').chars().take(32).collect();
+
+    let extension = match format {
+        ConfFormat::Conf => "conf",
+        ConfFormat::Qr => "txt",
+        ConfFormat::Png => "png",
+        ConfFormat::Svg => "svg",
+    };
+    format!("{}-{}.{}", network_name, sanitized, extension)
+}
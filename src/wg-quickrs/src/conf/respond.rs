@@ -1,15 +1,27 @@
 use crate::conf::util;
+use crate::conf::concurrency;
+use crate::conf::history::{self, Change, HistoryError};
+use crate::conf::preview;
 use crate::conf::network;
+use crate::conf::render;
 use crate::wireguard::cmd::sync_conf;
-use wg_quickrs_lib::types::api::{SummaryDigest, ChangeSum};
+use crate::web::events;
+use crate::WG_QUICKRS_CONFIG_FOLDER;
+use wg_quickrs_lib::types::api::{SummaryDigest, ChangeSum, NetworkSecrets};
 use wg_quickrs_lib::validation::network::{*, validate_amnezia_enabled, validate_amnezia_s1, validate_amnezia_s1_s2, validate_amnezia_jc, validate_amnezia_jmin, validate_amnezia_jmax, validate_amnezia_jmin_jmax};
 use actix_web::{HttpResponse, web};
+use actix_web::http::StatusCode;
+use actix_web::http::header::{CacheControl, CacheDirective, ContentDisposition, ContentEncoding, ETag, EntityTag};
 use chrono::{Duration, Utc};
 use serde_json::json;
 use uuid::Uuid;
-use wg_quickrs_lib::helpers::remove_expired_reservations;
-use wg_quickrs_lib::types::network::{ReservationData, NetworkWDigest};
+use wg_quickrs_lib::helpers::{get_peer_wg_config, remove_expired_reservations, get_peer_public_key, wg_generate_key, wg_key_fingerprint, wg_public_key_from_private_key};
+use wg_quickrs_cli::config::conf::ConfFormat;
+use clap::ValueEnum;
+use wg_quickrs_lib::types::network::{Network, ReservationData, NetworkWDigest, WireGuardKey};
 use wg_quickrs_lib::types::config::ConfigFile;
+use wg_quickrs_lib::validation::config_file::validate_config_file;
+use wg_quickrs_lib::validation::error::ValidationError;
 
 macro_rules! get_mg_config_w_digest {
     () => {{
@@ -21,33 +33,233 @@ macro_rules! get_mg_config_w_digest {
     }};
 }
 
+/// Writes `$next` and only then replaces the config held in `$c` with it, so a failed write changes nothing
 macro_rules! post_mg_config_w_digest {
-    ($c:expr) => {{
-        let config_file = ConfigFile::from(&$c.to_config());
-        $c.network_w_digest.network.updated_at = Utc::now();
-        $c.network_w_digest = NetworkWDigest::try_from($c.network_w_digest.network.clone())
+    ($c:expr, $next:expr, $change:expr) => {{
+        let mut next = $next;
+        let config_file = ConfigFile::from(&next.to_config());
+        next.network_w_digest.network.updated_at = Utc::now();
+        next.network_w_digest = NetworkWDigest::try_from(next.network_w_digest.network.clone())
             .map_err(|_| HttpResponse::InternalServerError().body("unable to compute config digest"))?;
 
-        let config_file_str = serde_norway::to_string(&config_file)
-            .map_err(|_| HttpResponse::InternalServerError().body("unable to serialize config"))?;
-
-        util::write_config(config_file_str)
-            .map_err(|_| HttpResponse::InternalServerError().body("unable to write config"))?;
+        let revision = util::write_config(&config_file, $change).map_err(write_config_error_response)?;
+        *$c = next;
+        revision
     }};
 }
 
+fn write_config_error_response(e: util::ConfUtilError) -> HttpResponse {
+    log::error!("{e}");
+    match e {
+        util::ConfUtilError::ChangedOnDisk(_) => HttpResponse::Conflict()
+            .body("conf.yml was changed outside of the agent, reload the agent (SIGHUP) to load it before making changes"),
+        _ => HttpResponse::InternalServerError().body("unable to write config"),
+    }
+}
+
 pub(crate) fn get_network_summary(query: web::Query<crate::web::api::SummaryBody>) -> Result<HttpResponse, HttpResponse> {
     let summary = util::get_summary()
         .map_err(|_| HttpResponse::InternalServerError().body("unable to get summary"))?;
     let response_data = if query.only_digest {
         json!(SummaryDigest::from(&summary))
     } else {
-        json!(summary)
+        let mut response_data = json!(summary);
+        redact_network_secrets(&mut response_data["network"], &summary.network);
+        response_data
     };
-    Ok(HttpResponse::Ok().json(response_data))
+    Ok(HttpResponse::Ok()
+        .insert_header(ETag(EntityTag::new_strong(summary.digest)))
+        .json(response_data))
+}
+
+/// Replace the peer private keys and connection pre-shared keys of a serialized network with
+/// public keys and key fingerprints. The secrets are only served by `post_network_secrets_reveal`.
+pub(crate) fn redact_network_secrets(network_json: &mut serde_json::Value, network: &Network) {
+    for (peer_id, peer) in &network.peers {
+        if let Some(peer_json) = network_json["peers"].get_mut(peer_id.to_string()).and_then(|p| p.as_object_mut()) {
+            peer_json.remove("private_key");
+            peer_json.insert("public_key".into(), json!(get_peer_public_key(peer)));
+            // peers without a private key on the agent have nothing to fingerprint
+            if let Some(private_key) = &peer.private_key {
+                peer_json.insert("private_key_fingerprint".into(), json!(wg_key_fingerprint(private_key)));
+            }
+        }
+    }
+    for (connection_id, connection) in &network.connections {
+        if let Some(connection_json) = network_json["connections"].get_mut(connection_id.to_string()).and_then(|c| c.as_object_mut()) {
+            connection_json.remove("pre_shared_key");
+            connection_json.insert("pre_shared_key_fingerprint".into(), json!(wg_key_fingerprint(&connection.pre_shared_key)));
+        }
+    }
+}
+
+/// Same redaction as `redact_network_secrets` for a change that is pushed to every listening client
+fn redact_change_sum_secrets(change_sum: &ChangeSum) -> serde_json::Value {
+    let mut change_sum_json = json!(change_sum);
+    let redact_private_key = |peer_json: Option<&mut serde_json::Value>, private_key: Option<&WireGuardKey>| {
+        if let Some(peer_json) = peer_json.and_then(|p| p.as_object_mut())
+            && let Some(private_key) = private_key
+        {
+            peer_json.remove("private_key");
+            peer_json.insert("public_key".into(), json!(wg_public_key_from_private_key(private_key)));
+            peer_json.insert("private_key_fingerprint".into(), json!(wg_key_fingerprint(private_key)));
+        }
+    };
+    for (peer_id, peer) in change_sum.added_peers.iter().flatten() {
+        redact_private_key(change_sum_json["added_peers"].get_mut(peer_id.to_string()), peer.private_key.as_ref());
+    }
+    if let Some(changed_fields) = &change_sum.changed_fields {
+        for (peer_id, peer) in changed_fields.peers.iter().flatten() {
+            redact_private_key(change_sum_json["changed_fields"]["peers"].get_mut(peer_id.to_string()), peer.private_key.as_ref());
+        }
+    }
+
+    let redact_pre_shared_key = |connection_json: Option<&mut serde_json::Value>, pre_shared_key: Option<&WireGuardKey>| {
+        if let Some(connection_json) = connection_json.and_then(|c| c.as_object_mut())
+            && let Some(pre_shared_key) = pre_shared_key
+        {
+            connection_json.remove("pre_shared_key");
+            connection_json.insert("pre_shared_key_fingerprint".into(), json!(wg_key_fingerprint(pre_shared_key)));
+        }
+    };
+    for (connection_id, connection) in change_sum.added_connections.iter().flatten() {
+        redact_pre_shared_key(change_sum_json["added_connections"].get_mut(connection_id.to_string()), Some(&connection.pre_shared_key));
+    }
+    if let Some(changed_fields) = &change_sum.changed_fields {
+        for (connection_id, connection) in changed_fields.connections.iter().flatten() {
+            redact_pre_shared_key(change_sum_json["changed_fields"]["connections"].get_mut(connection_id.to_string()), connection.pre_shared_key.as_ref());
+        }
+    }
+    change_sum_json
+}
+
+pub(crate) fn get_network_events(authorized: impl Fn() -> bool + 'static) -> Result<HttpResponse, HttpResponse> {
+    let config = util::get_config()
+        .map_err(|_| HttpResponse::InternalServerError().body("unable to get config"))?;
+    let interval = std::time::Duration::from_millis(config.agent.vpn.telemetry.interval);
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(CacheControl(vec![CacheDirective::NoCache]))
+        // keeps the compression middleware from buffering the events
+        .insert_header(ContentEncoding::Identity)
+        .streaming(events::subscribe(interval, authorized)))
+}
+
+pub(crate) fn post_network_secrets_reveal() -> Result<HttpResponse, HttpResponse> {
+    let config = util::get_config()
+        .map_err(|_| HttpResponse::InternalServerError().body("unable to get config"))?;
+    log::info!("revealed network secrets");
+    Ok(HttpResponse::Ok().json(NetworkSecrets::from(&config.network)))
+}
+
+pub(crate) fn get_network_peer_conf(peer_id: &str, query: web::Query<crate::web::api::PeerConfQuery>) -> Result<HttpResponse, HttpResponse> {
+    let peer_id = Uuid::parse_str(peer_id)
+        .map_err(|_| HttpResponse::BadRequest().body(format!("invalid peer id: {}", peer_id)))?;
+    let format = match &query.format {
+        Some(format) => ConfFormat::from_str(format, true)
+            .map_err(|_| HttpResponse::BadRequest().body(format!("invalid format: {}", format)))?,
+        None => ConfFormat::default(),
+    };
+
+    let config = util::get_config()
+        .map_err(|_| HttpResponse::InternalServerError().body("unable to get config"))?;
+    let wg_conf = get_peer_wg_config(&config.network, &peer_id, query.stripped)
+        .map_err(|e| HttpResponse::NotFound().body(e.to_string()))?;
+    let rendered = render::render_peer_conf(&wg_conf, format)
+        .map_err(|e| HttpResponse::InternalServerError().body(e.to_string()))?;
+
+    let mut response = HttpResponse::Ok();
+    response.content_type(render::content_type(format));
+    // the terminal QR code is meant to be printed, everything else is downloaded as a file
+    if format != ConfFormat::Qr {
+        let peer_name = &config.network.peers[&peer_id].name;
+        response.insert_header(ContentDisposition::attachment(render::file_name(&config.network.name, peer_name, format)));
+    }
+    Ok(response.body(rendered))
+}
+
+/// The connections of a peer, each with the id and name of the peer on the other side
+pub(crate) fn get_network_peer_connections(peer_id: &str) -> Result<HttpResponse, HttpResponse> {
+    let peer_id = Uuid::parse_str(peer_id)
+        .map_err(|_| HttpResponse::BadRequest().body(format!("invalid peer id: {}", peer_id)))?;
+
+    let config = util::get_config()
+        .map_err(|_| HttpResponse::InternalServerError().body("unable to get config"))?;
+    if !config.network.peers.contains_key(&peer_id) {
+        return Err(HttpResponse::NotFound().body(format!("peer not found: {}", peer_id)));
+    }
+    let connections: serde_json::Map<String, serde_json::Value> = config.network.connections.keys()
+        .filter(|connection_id| connection_id.contains(&peer_id))
+        .map(|connection_id| {
+            let other_peer_id = if connection_id.a == peer_id { connection_id.b } else { connection_id.a };
+            (connection_id.to_string(), json!({
+                "peer_id": other_peer_id,
+                "peer_name": config.network.peers.get(&other_peer_id).map(|peer| peer.name.as_str()),
+            }))
+        })
+        .collect();
+    Ok(HttpResponse::Ok().json(connections))
+}
+
+/// Replaces a peer's private key like a patch would and responds with the peer's new config
+pub(crate) fn post_network_peer_private_key_rotate(change: Change, peer_id_str: &str) -> Result<HttpResponse, HttpResponse> {
+    let peer_id = Uuid::parse_str(peer_id_str)
+        .map_err(|_| HttpResponse::BadRequest().body(format!("invalid peer id: {}", peer_id_str)))?;
+
+    let config = util::get_config()
+        .map_err(|_| HttpResponse::InternalServerError().body("unable to get config"))?;
+    let peer = config.network.peers.get(&peer_id)
+        .ok_or_else(|| HttpResponse::NotFound().body(format!("peer not found: {}", peer_id)))?;
+    if peer.private_key.is_none() {
+        return Err(HttpResponse::Conflict().body("peer keeps its private key on its own device"));
+    }
+
+    let change_sum = json!({
+        "changed_fields": {
+            "peers": { peer_id.to_string(): { "private_key": wg_generate_key() } }
+        }
+    });
+    let query = web::Query(crate::web::api::PatchQuery { dry_run: false });
+    patch_network_config(change, None, query, web::Bytes::from(change_sum.to_string()))?;
+    log::info!("rotated the private key of peer {}", peer_id);
+
+    let query = web::Query(crate::web::api::PeerConfQuery { format: None, stripped: false });
+    get_network_peer_conf(peer_id_str, query)
 }
 
-pub(crate) fn patch_network_config(body: web::Bytes) -> Result<HttpResponse, HttpResponse> {
+/// Everything that is wrong with a `ChangeSum`, so that a rejected patch reports all of it at once
+#[derive(Default)]
+struct Violations(Vec<(StatusCode, String, String)>);
+
+impl Violations {
+    fn push(&mut self, status: StatusCode, path: impl Into<String>, error: impl ToString) {
+        self.0.push((status, path.into(), error.to_string()));
+    }
+
+    /// The validated value, or `None` once the reason it is invalid is recorded
+    fn check<T>(&mut self, path: impl Into<String>, result: Result<T, ValidationError>) -> Option<T> {
+        result.map_err(|e| self.push(StatusCode::BAD_REQUEST, path, e)).ok()
+    }
+
+    /// Responds with the status shared by all violations, or `400 Bad Request` when they differ
+    fn into_result(self) -> Result<(), HttpResponse> {
+        let Some((status, _, _)) = self.0.first() else {
+            return Ok(());
+        };
+        let status = if self.0.iter().all(|(s, _, _)| s == status) { *status } else { StatusCode::BAD_REQUEST };
+        for (_, path, error) in &self.0 {
+            log::debug!("rejected change: {}: {}", path, error);
+        }
+        let errors: Vec<_> = self.0.into_iter()
+            .map(|(_, path, error)| json!({"path": path, "error": error}))
+            .collect();
+        Err(HttpResponse::build(status).json(json!({"errors": errors})))
+    }
+}
+
+/// `if_match` is the digest of an `If-Match` header, `None` when it is missing or `*`
+pub(crate) fn patch_network_config(change: Change, if_match: Option<String>, query: web::Query<crate::web::api::PatchQuery>, body: web::Bytes) -> Result<HttpResponse, HttpResponse> {
     let body_raw = String::from_utf8_lossy(&body);
     let change_sum: ChangeSum = match serde_json::from_str(&body_raw) {
         Ok(val) => val,
@@ -55,6 +267,12 @@ pub(crate) fn patch_network_config(body: web::Bytes) -> Result<HttpResponse, Htt
             return Err(HttpResponse::BadRequest().body(format!("invalid JSON: {}", err)));
         }
     };
+    let base_digest = match (if_match, &change_sum.digest) {
+        (Some(header), Some(body)) if header != *body => {
+            return Err(HttpResponse::BadRequest().body("digest: does not match the If-Match header"));
+        }
+        (header, body) => header.or_else(|| body.clone()),
+    };
 
     log::debug!("update config with the change_sum = \n{:?}", change_sum);
 
@@ -62,275 +280,303 @@ pub(crate) fn patch_network_config(body: web::Bytes) -> Result<HttpResponse, Htt
     let this_peer_id = c.network_w_digest.network.this_peer;
     let mut changed_config = false;
 
-    remove_expired_reservations(&mut c.network_w_digest.network);
+    // a patch based on an older network is merged only when it doesn't touch anything changed since
+    let from_digest = c.network_w_digest.digest.clone();
+    let touched_fields = concurrency::touched_fields(&json!(change_sum));
+    if let Some(base_digest) = &base_digest {
+        concurrency::check(base_digest, &from_digest, &touched_fields).map_err(|conflict| {
+            let (error, conflicting_fields) = match conflict {
+                concurrency::Conflict::UnknownDigest => ("the network was changed since the given digest".to_string(), vec![]),
+                concurrency::Conflict::Fields(fields) => (format!("the network was changed since the given digest: {}", fields.join(", ")), fields),
+            };
+            log::info!("rejected a stale change: {}", error);
+            HttpResponse::Conflict()
+                .insert_header(ETag(EntityTag::new_strong(from_digest.clone())))
+                .json(json!({
+                    "error": error,
+                    "digest": from_digest,
+                    "conflicting_fields": conflicting_fields,
+                }))
+        })?;
+    }
+
+    // every change is applied to a staged copy, which only replaces the network when all of them are valid
+    let mut staged = c.network_w_digest.network.clone();
+    let mut violations = Violations::default();
+    // addresses are checked once everything is staged, so that peers can swap them in a single patch
+    let mut staged_addresses: Vec<(String, Uuid)> = Vec::new();
+    let mut staged_addresses_v6: Vec<(String, Uuid)> = Vec::new();
+
+    remove_expired_reservations(&mut staged);
 
     // process changed_fields
     if let Some(changed_fields) = &change_sum.changed_fields {
         if let Some(changed_fields_peers) = &changed_fields.peers {
             for (peer_id, peer_details) in changed_fields_peers {
-                let mut network_copy = c.network_w_digest.network.clone();
-                if let Some(peer_config) = c.network_w_digest.network.peers.get_mut(peer_id) {
-                    if let Some(name) = &peer_details.name {
-                        peer_config.name = parse_and_validate_peer_name(name).map_err(|e| {
-                            HttpResponse::BadRequest().body(format!("changed_fields.peers.{}.name: {}", peer_id, e))
-                        })?;
-                    }
-                    if let Some(address) = &peer_details.address {
-                        network_copy.peers.retain(|id, _| id != peer_id);
-                        peer_config.address = validate_peer_address(address, &network_copy).map_err(|e| {
-                            HttpResponse::BadRequest().body(format!("changed_fields.peers.{}.address: {}", peer_id, e))
-                        })?;
-                    }
-                    if let Some(endpoint) = &peer_details.endpoint {
-                        peer_config.endpoint = validate_peer_endpoint(endpoint).map_err(|e| {
-                            HttpResponse::BadRequest().body(format!("changed_fields.peers.{}.endpoint: {}", peer_id, e))
-                        })?;
-                    }
-                    if let Some(kind) = &peer_details.kind {
-                        peer_config.kind = parse_and_validate_peer_kind(kind).map_err(|e| {
-                            HttpResponse::BadRequest().body(format!("changed_fields.peers.{}.kind: {}", peer_id, e))
-                        })?;
-                    }
-                    if let Some(icon) = &peer_details.icon {
-                        peer_config.icon = validate_peer_icon(icon).map_err(|e| {
-                            HttpResponse::BadRequest().body(format!("changed_fields.peers.{}.icon: {}", peer_id, e))
-                        })?;
-                    }
-                    if let Some(dns) = &peer_details.dns {
-                        peer_config.dns = validate_peer_dns(dns).map_err(|e| {
-                            HttpResponse::BadRequest().body(format!("changed_fields.peers.{}.dns: {}", peer_id, e))
-                        })?;
-                    }
-                    if let Some(mtu) = &peer_details.mtu {
-                        peer_config.mtu = validate_peer_mtu(mtu).map_err(|e| {
-                            HttpResponse::BadRequest().body(format!("changed_fields.peers.{}.mtu: {}", peer_id, e))
-                        })?;
-                    }
-                    if let Some(private_key) = &peer_details.private_key {
-                        peer_config.private_key = *private_key;
-                        // If deserialization succeeds, private_key is already validated.
+                let path = format!("changed_fields.peers.{}", peer_id);
+                let Some(peer_config) = staged.peers.get_mut(peer_id) else {
+                    violations.push(StatusCode::NOT_FOUND, path, format!("peer '{}' does not exist", peer_id));
+                    continue;
+                };
+                if let Some(name) = &peer_details.name
+                    && let Some(name) = violations.check(format!("{path}.name"), parse_and_validate_peer_name(name))
+                {
+                    peer_config.name = name;
+                }
+                if let Some(address) = peer_details.address {
+                    peer_config.address = address;
+                    staged_addresses.push((format!("{path}.address"), *peer_id));
+                }
+                if let Some(address_v6) = peer_details.address_v6 {
+                    peer_config.address_v6 = Some(address_v6);
+                    staged_addresses_v6.push((format!("{path}.address_v6"), *peer_id));
+                }
+                if let Some(host_address) = peer_details.host_address {
+                    peer_config.host_address = host_address;
+                }
+                if let Some(endpoint) = &peer_details.endpoint
+                    && let Some(endpoint) = violations.check(format!("{path}.endpoint"), validate_peer_endpoint(endpoint))
+                {
+                    peer_config.endpoint = endpoint;
+                }
+                if let Some(kind) = &peer_details.kind
+                    && let Some(kind) = violations.check(format!("{path}.kind"), parse_and_validate_peer_kind(kind))
+                {
+                    peer_config.kind = kind;
+                }
+                if let Some(icon) = &peer_details.icon
+                    && let Some(icon) = violations.check(format!("{path}.icon"), validate_peer_icon(icon))
+                {
+                    peer_config.icon = icon;
+                }
+                if let Some(dns) = &peer_details.dns
+                    && let Some(dns) = violations.check(format!("{path}.dns"), validate_peer_dns(dns))
+                {
+                    peer_config.dns = dns;
+                }
+                if let Some(mtu) = &peer_details.mtu
+                    && let Some(mtu) = violations.check(format!("{path}.mtu"), validate_peer_mtu(mtu))
+                {
+                    peer_config.mtu = mtu;
+                }
+                if let Some(private_key) = &peer_details.private_key {
+                    peer_config.private_key = Some(*private_key);
+                    peer_config.public_key = None;
+                    // If deserialization succeeds, private_key is already validated.
+                }
+                if let Some(public_key) = &peer_details.public_key {
+                    // Security check: the agent can't run its interface without its private key
+                    if *peer_id == this_peer_id {
+                        violations.push(StatusCode::FORBIDDEN, format!("{path}.public_key"), "cannot drop the private key of this peer");
+                    } else if peer_details.private_key.is_some() {
+                        violations.push(StatusCode::BAD_REQUEST, format!("{path}.private_key"), ValidationError::AmbiguousPeerKey());
+                    } else {
+                        peer_config.private_key = None;
+                        peer_config.public_key = Some(*public_key);
+                        // If deserialization succeeds, public_key is already validated.
                     }
+                }
 
-                    if let Some(scripts) = &peer_details.scripts {
-                        // Security check: prevent modifying scripts for this_peer
-                        if *peer_id == this_peer_id {
-                            return Err(HttpResponse::Forbidden().body("cannot modify scripts for this peer remotely"));
-                        }
-
-                        if let Some(scripts) = &scripts.pre_up {
-                            peer_config.scripts.pre_up = validate_peer_scripts(scripts).map_err(|e| {
-                                HttpResponse::BadRequest().body(format!("changed_fields.peers.{}.scripts.pre_up: {}", peer_id, e))
-                            })?;
+                if let Some(scripts) = &peer_details.scripts {
+                    // Security check: prevent modifying scripts for this_peer
+                    if *peer_id == this_peer_id {
+                        violations.push(StatusCode::FORBIDDEN, format!("{path}.scripts"), "cannot modify scripts for this peer remotely");
+                    } else {
+                        if let Some(scripts) = &scripts.pre_up
+                            && let Some(scripts) = violations.check(format!("{path}.scripts.pre_up"), validate_peer_scripts(scripts))
+                        {
+                            peer_config.scripts.pre_up = scripts;
                         }
-                        if let Some(scripts) = &scripts.post_up {
-                            peer_config.scripts.post_up = validate_peer_scripts(scripts).map_err(|e| {
-                                HttpResponse::BadRequest().body(format!("changed_fields.peers.{}.scripts.post_up: {}", peer_id, e))
-                            })?;
+                        if let Some(scripts) = &scripts.post_up
+                            && let Some(scripts) = violations.check(format!("{path}.scripts.post_up"), validate_peer_scripts(scripts))
+                        {
+                            peer_config.scripts.post_up = scripts;
                         }
-                        if let Some(scripts) = &scripts.pre_down {
-                            peer_config.scripts.pre_down = validate_peer_scripts(scripts).map_err(|e| {
-                                HttpResponse::BadRequest().body(format!("changed_fields.peers.{}.scripts.pre_down: {}", peer_id, e))
-                            })?;
+                        if let Some(scripts) = &scripts.pre_down
+                            && let Some(scripts) = violations.check(format!("{path}.scripts.pre_down"), validate_peer_scripts(scripts))
+                        {
+                            peer_config.scripts.pre_down = scripts;
                         }
-                        if let Some(scripts) = &scripts.post_down {
-                            peer_config.scripts.post_down = validate_peer_scripts(scripts).map_err(|e| {
-                                HttpResponse::BadRequest().body(format!("changed_fields.peers.{}.scripts.post_down: {}", peer_id, e))
-                            })?;
+                        if let Some(scripts) = &scripts.post_down
+                            && let Some(scripts) = violations.check(format!("{path}.scripts.post_down"), validate_peer_scripts(scripts))
+                        {
+                            peer_config.scripts.post_down = scripts;
                         }
                     }
+                }
 
-                    if let Some(amnezia_parameters) = &peer_details.amnezia_parameters {
-                        if let Some(jc) = amnezia_parameters.jc {
-                            validate_amnezia_jc(jc).map_err(|e| {
-                                HttpResponse::BadRequest().body(format!("changed_fields.peers.{}.amnezia_parameters.jc: {}", peer_id, e))
-                            })?;
-                            peer_config.amnezia_parameters.jc = jc;
-                        }
-                        if let Some(jmin) = amnezia_parameters.jmin {
-                            validate_amnezia_jmin(jmin).map_err(|e| {
-                                HttpResponse::BadRequest().body(format!("changed_fields.peers.{}.amnezia_parameters.jmin: {}", peer_id, e))
-                            })?;
-                            peer_config.amnezia_parameters.jmin = jmin;
-                        }
-                        if let Some(jmax) = amnezia_parameters.jmax {
-                            validate_amnezia_jmax(jmax).map_err(|e| {
-                                HttpResponse::BadRequest().body(format!("changed_fields.peers.{}.amnezia_parameters.jmax: {}", peer_id, e))
-                            })?;
-                            peer_config.amnezia_parameters.jmax = jmax;
-                        }
-                        // Validate jmin and jmax relationship if either is present
-                        if amnezia_parameters.jmin.is_some() || amnezia_parameters.jmax.is_some() {
-                            validate_amnezia_jmin_jmax(
-                                peer_config.amnezia_parameters.jmin,
-                                peer_config.amnezia_parameters.jmax
-                            ).map_err(|e| {
-                                HttpResponse::BadRequest().body(format!("changed_fields.peers.{}.amnezia_parameters: {}", peer_id, e))
-                            })?;
-                        }
+                if let Some(amnezia_parameters) = &peer_details.amnezia_parameters {
+                    if let Some(jc) = amnezia_parameters.jc
+                        && let Some(jc) = violations.check(format!("{path}.amnezia_parameters.jc"), validate_amnezia_jc(jc))
+                    {
+                        peer_config.amnezia_parameters.jc = jc;
+                    }
+                    if let Some(jmin) = amnezia_parameters.jmin
+                        && let Some(jmin) = violations.check(format!("{path}.amnezia_parameters.jmin"), validate_amnezia_jmin(jmin))
+                    {
+                        peer_config.amnezia_parameters.jmin = jmin;
+                    }
+                    if let Some(jmax) = amnezia_parameters.jmax
+                        && let Some(jmax) = violations.check(format!("{path}.amnezia_parameters.jmax"), validate_amnezia_jmax(jmax))
+                    {
+                        peer_config.amnezia_parameters.jmax = jmax;
+                    }
+                    // Validate jmin and jmax relationship if either is present
+                    if amnezia_parameters.jmin.is_some() || amnezia_parameters.jmax.is_some() {
+                        violations.check(format!("{path}.amnezia_parameters"), validate_amnezia_jmin_jmax(
+                            peer_config.amnezia_parameters.jmin,
+                            peer_config.amnezia_parameters.jmax
+                        ));
                     }
-                    changed_config = true;
-                } else {
-                    return Err(HttpResponse::NotFound().body(format!("peer '{}' does not exist", peer_id)));
                 }
+                changed_config = true;
             }
         }
         if let Some(changed_fields_connections) = &changed_fields.connections {
             for (connection_id, connection_details) in changed_fields_connections {
-                if let Some(connection_config) =
-                    c.network_w_digest.network.connections.get_mut(connection_id)
+                let path = format!("changed_fields.connections.{}", connection_id);
+                let Some(connection_config) = staged.connections.get_mut(connection_id) else {
+                    violations.push(StatusCode::NOT_FOUND, path, format!("connection '{}' does not exist", connection_id));
+                    continue;
+                };
+                if let Some(enabled) = connection_details.enabled {
+                    connection_config.enabled = enabled;
+                }
+                if let Some(pre_shared_key) = connection_details.pre_shared_key {
+                    connection_config.pre_shared_key = pre_shared_key;
+                    // If deserialization succeeds, pre_shared_key is already validated.
+                }
+                if let Some(allowed_ips_a_to_b) = &connection_details.allowed_ips_a_to_b {
+                    connection_config.allowed_ips_a_to_b = allowed_ips_a_to_b.clone();
+                    // If deserialization succeeds, allowed_ips_a_to_b is already validated.
+                }
+                if let Some(allowed_ips_b_to_a) = &connection_details.allowed_ips_b_to_a {
+                    connection_config.allowed_ips_b_to_a = allowed_ips_b_to_a.clone();
+                    // If deserialization succeeds, allowed_ips_b_to_a is already validated.
+                }
+                if let Some(persistent_keepalive) = &connection_details.persistent_keepalive
+                    && let Some(persistent_keepalive) = violations.check(format!("{path}.persistent_keepalive"), validate_conn_persistent_keepalive(persistent_keepalive))
                 {
-                    if let Some(enabled) = connection_details.enabled {
-                        connection_config.enabled = enabled;
-                    }
-                    if let Some(pre_shared_key) = connection_details.pre_shared_key {
-                        connection_config.pre_shared_key = pre_shared_key;
-                        // If deserialization succeeds, pre_shared_key is already validated.
-                    }
-                    if let Some(allowed_ips_a_to_b) = &connection_details.allowed_ips_a_to_b {
-                        connection_config.allowed_ips_a_to_b = allowed_ips_a_to_b.clone();
-                        // If deserialization succeeds, allowed_ips_a_to_b is already validated.
-                    }
-                    if let Some(allowed_ips_b_to_a) = &connection_details.allowed_ips_b_to_a {
-                        connection_config.allowed_ips_b_to_a = allowed_ips_b_to_a.clone();
-                        // If deserialization succeeds, allowed_ips_b_to_a is already validated.
-                    }
-                    if let Some(persistent_keepalive) = &connection_details.persistent_keepalive {
-                        connection_config.persistent_keepalive = validate_conn_persistent_keepalive(persistent_keepalive).map_err(|e| {
-                            HttpResponse::BadRequest().body(format!("changed_fields.connections.{}.persistent_keepalive: {}", connection_id, e))
-                        })?;
-                    }
-                    changed_config = true;
-                } else {
-                    return Err(HttpResponse::NotFound().body(format!("connection '{}' does not exist", connection_id)));
+                    connection_config.persistent_keepalive = persistent_keepalive;
                 }
+                changed_config = true;
             }
         }
         if let Some(changed_fields_defaults) = &changed_fields.defaults {
             if let Some(default_peer) = &changed_fields_defaults.peer {
-                if let Some(kind) = &default_peer.kind {
-                    c.network_w_digest.network.defaults.peer.kind = parse_and_validate_peer_kind(kind).map_err(|e| {
-                        HttpResponse::BadRequest().body(format!("changed_fields.defaults.peer.kind: {}", e))
-                    })?;
+                let path = "changed_fields.defaults.peer";
+                let defaults = &mut staged.defaults.peer;
+                if let Some(kind) = &default_peer.kind
+                    && let Some(kind) = violations.check(format!("{path}.kind"), parse_and_validate_peer_kind(kind))
+                {
+                    defaults.kind = kind;
                 }
-                if let Some(icon) = &default_peer.icon {
-                    c.network_w_digest.network.defaults.peer.icon = validate_peer_icon(icon).map_err(|e| {
-                        HttpResponse::BadRequest().body(format!("changed_fields.defaults.peer.icon: {}", e))
-                    })?;
+                if let Some(icon) = &default_peer.icon
+                    && let Some(icon) = violations.check(format!("{path}.icon"), validate_peer_icon(icon))
+                {
+                    defaults.icon = icon;
                 }
-                if let Some(dns) = &default_peer.dns {
-                    c.network_w_digest.network.defaults.peer.dns = validate_peer_dns(dns).map_err(|e| {
-                        HttpResponse::BadRequest().body(format!("changed_fields.defaults.peer.dns: {}", e))
-                    })?;
+                if let Some(dns) = &default_peer.dns
+                    && let Some(dns) = violations.check(format!("{path}.dns"), validate_peer_dns(dns))
+                {
+                    defaults.dns = dns;
                 }
-                if let Some(mtu) = &default_peer.mtu {
-                    c.network_w_digest.network.defaults.peer.mtu = validate_peer_mtu(mtu).map_err(|e| {
-                        HttpResponse::BadRequest().body(format!("changed_fields.defaults.peer.mtu: {}", e))
-                    })?;
+                if let Some(mtu) = &default_peer.mtu
+                    && let Some(mtu) = violations.check(format!("{path}.mtu"), validate_peer_mtu(mtu))
+                {
+                    defaults.mtu = mtu;
                 }
                 if let Some(scripts) = &default_peer.scripts {
-                    if let Some(pre_up) = &scripts.pre_up {
-                        c.network_w_digest.network.defaults.peer.scripts.pre_up = validate_peer_scripts(pre_up).map_err(|e| {
-                            HttpResponse::BadRequest().body(format!("changed_fields.defaults.peer.scripts.pre_up: {}", e))
-                        })?;
+                    if let Some(pre_up) = &scripts.pre_up
+                        && let Some(pre_up) = violations.check(format!("{path}.scripts.pre_up"), validate_peer_scripts(pre_up))
+                    {
+                        defaults.scripts.pre_up = pre_up;
                     }
-                    if let Some(post_up) = &scripts.post_up {
-                        c.network_w_digest.network.defaults.peer.scripts.post_up = validate_peer_scripts(post_up).map_err(|e| {
-                            HttpResponse::BadRequest().body(format!("changed_fields.defaults.peer.scripts.post_up: {}", e))
-                        })?;
+                    if let Some(post_up) = &scripts.post_up
+                        && let Some(post_up) = violations.check(format!("{path}.scripts.post_up"), validate_peer_scripts(post_up))
+                    {
+                        defaults.scripts.post_up = post_up;
                     }
-                    if let Some(pre_down) = &scripts.pre_down {
-                        c.network_w_digest.network.defaults.peer.scripts.pre_down = validate_peer_scripts(pre_down).map_err(|e| {
-                            HttpResponse::BadRequest().body(format!("changed_fields.defaults.peer.scripts.pre_down: {}", e))
-                        })?;
+                    if let Some(pre_down) = &scripts.pre_down
+                        && let Some(pre_down) = violations.check(format!("{path}.scripts.pre_down"), validate_peer_scripts(pre_down))
+                    {
+                        defaults.scripts.pre_down = pre_down;
                     }
-                    if let Some(post_down) = &scripts.post_down {
-                        c.network_w_digest.network.defaults.peer.scripts.post_down = validate_peer_scripts(post_down).map_err(|e| {
-                            HttpResponse::BadRequest().body(format!("changed_fields.defaults.peer.scripts.post_down: {}", e))
-                        })?;
+                    if let Some(post_down) = &scripts.post_down
+                        && let Some(post_down) = violations.check(format!("{path}.scripts.post_down"), validate_peer_scripts(post_down))
+                    {
+                        defaults.scripts.post_down = post_down;
                     }
                 }
                 if let Some(amnezia_parameters) = &default_peer.amnezia_parameters {
-                    if let Some(jc) = amnezia_parameters.jc {
-                        validate_amnezia_jc(jc).map_err(|e| {
-                            HttpResponse::BadRequest().body(format!("changed_fields.defaults.peer.amnezia_parameters.jc: {}", e))
-                        })?;
-                        c.network_w_digest.network.defaults.peer.amnezia_parameters.jc = jc;
+                    if let Some(jc) = amnezia_parameters.jc
+                        && let Some(jc) = violations.check(format!("{path}.amnezia_parameters.jc"), validate_amnezia_jc(jc))
+                    {
+                        defaults.amnezia_parameters.jc = jc;
                     }
-                    if let Some(jmin) = amnezia_parameters.jmin {
-                        validate_amnezia_jmin(jmin).map_err(|e| {
-                            HttpResponse::BadRequest().body(format!("changed_fields.defaults.peer.amnezia_parameters.jmin: {}", e))
-                        })?;
-                        c.network_w_digest.network.defaults.peer.amnezia_parameters.jmin = jmin;
+                    if let Some(jmin) = amnezia_parameters.jmin
+                        && let Some(jmin) = violations.check(format!("{path}.amnezia_parameters.jmin"), validate_amnezia_jmin(jmin))
+                    {
+                        defaults.amnezia_parameters.jmin = jmin;
                     }
-                    if let Some(jmax) = amnezia_parameters.jmax {
-                        validate_amnezia_jmax(jmax).map_err(|e| {
-                            HttpResponse::BadRequest().body(format!("changed_fields.defaults.peer.amnezia_parameters.jmax: {}", e))
-                        })?;
-                        c.network_w_digest.network.defaults.peer.amnezia_parameters.jmax = jmax;
+                    if let Some(jmax) = amnezia_parameters.jmax
+                        && let Some(jmax) = violations.check(format!("{path}.amnezia_parameters.jmax"), validate_amnezia_jmax(jmax))
+                    {
+                        defaults.amnezia_parameters.jmax = jmax;
                     }
                     // Validate jmin and jmax relationship if either is present
                     if amnezia_parameters.jmin.is_some() || amnezia_parameters.jmax.is_some() {
-                        validate_amnezia_jmin_jmax(
-                            c.network_w_digest.network.defaults.peer.amnezia_parameters.jmin,
-                            c.network_w_digest.network.defaults.peer.amnezia_parameters.jmax
-                        ).map_err(|e| {
-                            HttpResponse::BadRequest().body(format!("changed_fields.defaults.peer.amnezia_parameters: {}", e))
-                        })?;
+                        violations.check(format!("{path}.amnezia_parameters"), validate_amnezia_jmin_jmax(
+                            defaults.amnezia_parameters.jmin,
+                            defaults.amnezia_parameters.jmax
+                        ));
                     }
                 }
                 changed_config = true;
             }
             if let Some(default_connection) = &changed_fields_defaults.connection {
-                if let Some(persistent_keepalive) = &default_connection.persistent_keepalive {
-                    c.network_w_digest.network.defaults.connection.persistent_keepalive = validate_conn_persistent_keepalive(persistent_keepalive).map_err(|e| {
-                        HttpResponse::BadRequest().body(format!("changed_fields.defaults.connection.persistent_keepalive: {}", e))
-                    })?;
+                if let Some(persistent_keepalive) = &default_connection.persistent_keepalive
+                    && let Some(persistent_keepalive) = violations.check("changed_fields.defaults.connection.persistent_keepalive", validate_conn_persistent_keepalive(persistent_keepalive))
+                {
+                    staged.defaults.connection.persistent_keepalive = persistent_keepalive;
                 }
                 changed_config = true;
             }
         }
         if let Some(amnezia_parameters) = &changed_fields.amnezia_parameters {
-            if let Some(enabled) = amnezia_parameters.enabled {
-                validate_amnezia_enabled(
-                    enabled,
-                    &c.agent.vpn.wg
-                ).map_err(|e| {
-                    HttpResponse::BadRequest().body(format!("changed_fields.network.amnezia_parameters.enabled: {}", e))
-                })?;
-                c.network_w_digest.network.amnezia_parameters.enabled = enabled;
+            let path = "changed_fields.network.amnezia_parameters";
+            let staged_amnezia_parameters = &mut staged.amnezia_parameters;
+            if let Some(enabled) = amnezia_parameters.enabled
+                && violations.check(format!("{path}.enabled"), validate_amnezia_enabled(enabled, &c.agent.vpn.wg)).is_some()
+            {
+                staged_amnezia_parameters.enabled = enabled;
             }
-            if let Some(s1) = amnezia_parameters.s1 {
-                validate_amnezia_s1(s1).map_err(|e| {
-                    HttpResponse::BadRequest().body(format!("changed_fields.network.amnezia_parameters.s1: {}", e))
-                })?;
-                c.network_w_digest.network.amnezia_parameters.s1 = s1;
+            if let Some(s1) = amnezia_parameters.s1
+                && let Some(s1) = violations.check(format!("{path}.s1"), validate_amnezia_s1(s1))
+            {
+                staged_amnezia_parameters.s1 = s1;
             }
             if let Some(s2) = amnezia_parameters.s2 {
-                validate_amnezia_s1_s2(c.network_w_digest.network.amnezia_parameters.s1, s2).map_err(|e| {
-                    HttpResponse::BadRequest().body(format!("changed_fields.network.amnezia_parameters.s2: {}", e))
-                })?;
-                c.network_w_digest.network.amnezia_parameters.s2 = s2;
+                staged_amnezia_parameters.s2 = s2;
             }
-            // Validate s1 and s2 relationship if both are present
+            // Validate s1 and s2 relationship if either is present
             if amnezia_parameters.s1.is_some() || amnezia_parameters.s2.is_some() {
-                validate_amnezia_s1_s2(
-                    c.network_w_digest.network.amnezia_parameters.s1,
-                    c.network_w_digest.network.amnezia_parameters.s2
-                ).map_err(|e| {
-                    HttpResponse::BadRequest().body(format!("changed_fields.network.amnezia_parameters: {}", e))
-                })?;
+                violations.check(path, validate_amnezia_s1_s2(
+                    staged_amnezia_parameters.s1,
+                    staged_amnezia_parameters.s2
+                ));
             }
             if let Some(h1) = amnezia_parameters.h1 {
-                c.network_w_digest.network.amnezia_parameters.h1 = h1;
+                staged_amnezia_parameters.h1 = h1;
             }
             if let Some(h2) = amnezia_parameters.h2 {
-                c.network_w_digest.network.amnezia_parameters.h2 = h2;
+                staged_amnezia_parameters.h2 = h2;
             }
             if let Some(h3) = amnezia_parameters.h3 {
-                c.network_w_digest.network.amnezia_parameters.h3 = h3;
+                staged_amnezia_parameters.h3 = h3;
             }
             if let Some(h4) = amnezia_parameters.h4 {
-                c.network_w_digest.network.amnezia_parameters.h4 = h4;
+                staged_amnezia_parameters.h4 = h4;
             }
             changed_config = true;
         }
@@ -339,130 +585,141 @@ pub(crate) fn patch_network_config(body: web::Bytes) -> Result<HttpResponse, Htt
     // process added_peers
     if let Some(added_peers) = &change_sum.added_peers {
         for (peer_id, peer_details) in added_peers {
-            {
-                if c.network_w_digest.network.peers.contains_key(peer_id) {
-                    return Err(HttpResponse::Forbidden().body(format!("peer '{}' already exists", peer_id)));
-                }
-                if let Some(value) = c.network_w_digest.network.reservations.get(&peer_details.address)
-                    && value.peer_id != *peer_id {
-                    return Err(HttpResponse::Forbidden().body(format!("address '{}' is reserved for another peer_id", peer_details.address)));
-                }
-                // ensure the address is taken off the reservation list so check_internal_address succeeds (this won't be posted if it fails early)
-                c.network_w_digest
-                    .network
-                    .reservations
-                    .retain(|address, _|  *address != peer_details.address);
-
-                // If deserialization succeeds, peer_id is already validated.
-                parse_and_validate_peer_name(&peer_details.name).map_err(|e| {
-                    HttpResponse::BadRequest().body(format!("added_peers.{}.name: {}", peer_id, e))
-                })?;
-                validate_peer_address(&peer_details.address, &c.network_w_digest.network).map_err(|e| {
-                    HttpResponse::BadRequest().body(format!("added_peers.{}.address: {}", peer_id, e))
-                })?;
-                validate_peer_endpoint(&peer_details.endpoint).map_err(|e| {
-                    HttpResponse::BadRequest().body(format!("added_peers.{}.endpoint: {}", peer_id, e))
-                })?;
-                parse_and_validate_peer_kind(&peer_details.kind).map_err(|e| {
-                    HttpResponse::BadRequest().body(format!("added_peers.{}.kind: {}", peer_id, e))
-                })?;
-                validate_peer_icon(&peer_details.icon).map_err(|e| {
-                    HttpResponse::BadRequest().body(format!("added_peers.{}.icon: {}", peer_id, e))
-                })?;
-                validate_peer_dns(&peer_details.dns).map_err(|e| {
-                    HttpResponse::BadRequest().body(format!("added_peers.{}.dns: {}", peer_id, e))
-                })?;
-                // If deserialization succeeds, dns is already validated.
-                validate_peer_mtu(&peer_details.mtu).map_err(|e| {
-                    HttpResponse::BadRequest().body(format!("added_peers.{}.mtu: {}", peer_id, e))
-                })?;
-                // If deserialization succeeds, private_key is already validated.
-                validate_peer_scripts(&peer_details.scripts.pre_up).map_err(|e| {
-                    HttpResponse::BadRequest().body(format!("added_peers.{}.scripts.pre_up: {}", peer_id, e))
-                })?;
-                validate_peer_scripts(&peer_details.scripts.post_up).map_err(|e| {
-                    HttpResponse::BadRequest().body(format!("added_peers.{}.scripts.post_up: {}", peer_id, e))
-                })?;
-                validate_peer_scripts(&peer_details.scripts.pre_down).map_err(|e| {
-                    HttpResponse::BadRequest().body(format!("added_peers.{}.scripts.pre_down: {}", peer_id, e))
-                })?;
-                validate_peer_scripts(&peer_details.scripts.post_down).map_err(|e| {
-                    HttpResponse::BadRequest().body(format!("added_peers.{}.scripts.post_down: {}", peer_id, e))
-                })?;
-                let mut added_peer = wg_quickrs_lib::types::network::Peer::from(peer_details);
-                added_peer.created_at = Utc::now();
-                added_peer.updated_at = added_peer.created_at;
-                c.network_w_digest.network.peers.insert(*peer_id, added_peer);
-                changed_config = true;
+            let path = format!("added_peers.{}", peer_id);
+            if staged.peers.contains_key(peer_id) {
+                violations.push(StatusCode::FORBIDDEN, path, format!("peer '{}' already exists", peer_id));
+                continue;
+            }
+            if let Some(value) = staged.reservations.get(&peer_details.address)
+                && value.peer_id != *peer_id {
+                violations.push(StatusCode::FORBIDDEN, format!("{path}.address"), format!("address '{}' is reserved for another peer_id", peer_details.address));
+                continue;
+            }
+            // ensure the address is taken off the reservation list so the address check succeeds
+            staged.reservations.retain(|address, _| *address != peer_details.address);
+
+            // If deserialization succeeds, peer_id is already validated.
+            violations.check(format!("{path}.name"), parse_and_validate_peer_name(&peer_details.name));
+            staged_addresses.push((format!("{path}.address"), *peer_id));
+            if peer_details.address_v6.is_some() {
+                staged_addresses_v6.push((format!("{path}.address_v6"), *peer_id));
             }
+            violations.check(format!("{path}.endpoint"), validate_peer_endpoint(&peer_details.endpoint));
+            violations.check(format!("{path}.kind"), parse_and_validate_peer_kind(&peer_details.kind));
+            violations.check(format!("{path}.icon"), validate_peer_icon(&peer_details.icon));
+            violations.check(format!("{path}.dns"), validate_peer_dns(&peer_details.dns));
+            violations.check(format!("{path}.mtu"), validate_peer_mtu(&peer_details.mtu));
+            // If deserialization succeeds, private_key and public_key are already validated.
+            violations.check(format!("{path}.private_key"), validate_peer_keys(&peer_details.private_key, &peer_details.public_key));
+            violations.check(format!("{path}.scripts.pre_up"), validate_peer_scripts(&peer_details.scripts.pre_up));
+            violations.check(format!("{path}.scripts.post_up"), validate_peer_scripts(&peer_details.scripts.post_up));
+            violations.check(format!("{path}.scripts.pre_down"), validate_peer_scripts(&peer_details.scripts.pre_down));
+            violations.check(format!("{path}.scripts.post_down"), validate_peer_scripts(&peer_details.scripts.post_down));
+            let mut added_peer = wg_quickrs_lib::types::network::Peer::from(peer_details);
+            added_peer.created_at = Utc::now();
+            added_peer.updated_at = added_peer.created_at;
+            staged.peers.insert(*peer_id, added_peer);
+            changed_config = true;
         }
     }
 
     // process removed_peers
     if let Some(removed_peers) = &change_sum.removed_peers {
         for peer_id in removed_peers {
-            {
-                if *peer_id == this_peer_id {
-                    return Err(HttpResponse::Forbidden().body("cannot remove this peer"));
-                }
-                c.network_w_digest.network.peers.remove(peer_id);
-                // automatically remove connections
-                for connection_id in c.network_w_digest.network.connections.clone().keys().filter(|&x| x.contains(peer_id)) {
-                    c.network_w_digest.network.connections.remove(connection_id);
-                }
-                changed_config = true;
+            if *peer_id == this_peer_id {
+                violations.push(StatusCode::FORBIDDEN, format!("removed_peers.{}", peer_id), "cannot remove this peer");
+                continue;
             }
+            staged.peers.remove(peer_id);
+            // automatically remove connections
+            staged.connections.retain(|connection_id, _| !connection_id.contains(peer_id));
+            changed_config = true;
         }
     }
 
     // process added_connections
     if let Some(added_connections) = &change_sum.added_connections {
         for (connection_id, connection_details) in added_connections {
-            {
-                if !c.network_w_digest.network.peers.contains_key(&connection_id.a) {
-                    return Err(HttpResponse::BadRequest().body(format!("added_connections.{}: 'peer_id' does not exist", connection_id.a)));
-                }
-                if !c.network_w_digest.network.peers.contains_key(&connection_id.b) {
-                    return Err(HttpResponse::BadRequest().body(format!("added_connections.{}: 'peer_id' does not exist", connection_id.b)));
+            let path = format!("added_connections.{}", connection_id);
+            // checked after the removed peers, so that a connection can't outlive one of its peers
+            let mut missing_peer = false;
+            for peer_id in [&connection_id.a, &connection_id.b] {
+                if !staged.peers.contains_key(peer_id) {
+                    violations.push(StatusCode::BAD_REQUEST, path.clone(), format!("peer '{}' does not exist", peer_id));
+                    missing_peer = true;
                 }
-                if c.network_w_digest.network.connections.contains_key(connection_id) {
-                    return Err(HttpResponse::Forbidden().body(format!("connection '{}' already exists", connection_id)));
-                }
-                if connection_id.a == connection_id.b {
-                    return Err(HttpResponse::Forbidden().body(format!("loopback connection detected: {}", connection_id)));
-                }
-
-                // If deserialization succeeds, pre_shared_key is already validated.
-                // If deserialization succeeds, allowed_ips_a_to_b is already validated.
-                // If deserialization succeeds, allowed_ips_b_to_a is already validated.
-                validate_conn_persistent_keepalive(&connection_details.persistent_keepalive).map_err(|e| {
-                    HttpResponse::BadRequest().body(format!("added_connections.{}.persistent_keepalive: {}", connection_id, e))
-                })?;
-
-                c.network_w_digest
-                    .network
-                    .connections
-                    .insert(connection_id.clone(), connection_details.clone());
-                changed_config = true;
             }
+            if missing_peer {
+                continue;
+            }
+            if staged.connections.contains_key(connection_id) {
+                violations.push(StatusCode::FORBIDDEN, path, format!("connection '{}' already exists", connection_id));
+                continue;
+            }
+            if connection_id.a == connection_id.b {
+                violations.push(StatusCode::FORBIDDEN, path, format!("loopback connection detected: {}", connection_id));
+                continue;
+            }
+
+            // If deserialization succeeds, pre_shared_key is already validated.
+            // If deserialization succeeds, allowed_ips_a_to_b is already validated.
+            // If deserialization succeeds, allowed_ips_b_to_a is already validated.
+            violations.check(format!("{path}.persistent_keepalive"), validate_conn_persistent_keepalive(&connection_details.persistent_keepalive));
+
+            staged.connections.insert(connection_id.clone(), connection_details.clone());
+            changed_config = true;
         }
     }
 
     // process removed_connections
     if let Some(removed_connections) = &change_sum.removed_connections {
         for connection_id in removed_connections {
-            {
-                c.network_w_digest.network.connections.remove(connection_id);
-                changed_config = true;
+            staged.connections.remove(connection_id);
+            changed_config = true;
+        }
+    }
+
+    // every changed or added address has to be free in the network as it would be stored,
+    // which also catches added peers that collide with each other
+    for (path, peer_id) in &staged_addresses {
+        if let Some(peer) = staged.peers.remove(peer_id) {
+            violations.check(path.clone(), validate_peer_address(&peer.address, &staged));
+            staged.peers.insert(*peer_id, peer);
+        }
+    }
+    for (path, peer_id) in &staged_addresses_v6 {
+        if let Some(peer) = staged.peers.remove(peer_id) {
+            if let Some(address_v6) = &peer.address_v6 {
+                violations.check(path.clone(), validate_peer_address_v6(address_v6, &staged));
             }
+            staged.peers.insert(*peer_id, peer);
         }
     }
+
+    violations.into_result()?;
     if !changed_config {
         log::debug!("nothing to update");
         return Err(HttpResponse::BadRequest().body("nothing to update"));
     }
-    post_mg_config_w_digest!(c);
+    if query.dry_run {
+        let current = c.to_config();
+        let mut next = current.clone();
+        next.network = staged;
+        let preview = preview::preview(&current, &next).map_err(|e| {
+            log::error!("{e}");
+            HttpResponse::InternalServerError().body("unable to preview the change")
+        })?;
+        return Ok(HttpResponse::Ok().json(preview));
+    }
+    let mut next = c.clone();
+    next.network_w_digest.network = staged;
+    // tokens of removed peers are revoked with them
+    let network = &next.network_w_digest.network;
+    next.agent.web.peer_tokens.retain(|_, token| network.peers.contains_key(&token.peer_id));
+    post_mg_config_w_digest!(c, next, &change);
     log::info!("config updated");
+    concurrency::record(&from_digest, &c.network_w_digest.digest, touched_fields);
+    events::publish_change(Some(redact_change_sum_secrets(&change_sum)), &c.network_w_digest.digest);
 
     if c.agent.vpn.enabled {
         sync_conf(&c.clone().to_config()).map_err(|e| {
@@ -471,27 +728,135 @@ pub(crate) fn patch_network_config(body: web::Bytes) -> Result<HttpResponse, Htt
         })?;
     }
 
-    Ok(HttpResponse::Ok().json(json!(change_sum)))
+    Ok(HttpResponse::Ok()
+        .insert_header(ETag(EntityTag::new_strong(c.network_w_digest.digest.clone())))
+        .json(json!(change_sum)))
 }
 
-pub(crate) fn post_network_reserve_address() -> Result<HttpResponse, HttpResponse> {
+pub(crate) fn post_network_reserve_address(change: Change) -> Result<HttpResponse, HttpResponse> {
     let mut c = get_mg_config_w_digest!();
-    remove_expired_reservations(&mut c.network_w_digest.network);
-    let next_address = network::get_next_available_address(&c.network_w_digest.network)
+    let from_digest = c.network_w_digest.digest.clone();
+    let mut next = c.clone();
+    remove_expired_reservations(&mut next.network_w_digest.network);
+    let (next_address, next_address_v6) = network::get_next_available_address(&next.network_w_digest.network)
         .ok_or_else(|| HttpResponse::Conflict().body("No more IP addresses available in the pool".to_string()))?;
 
     let reservation_peer_id = Uuid::new_v4();
     let reservation_valid_until = Utc::now() + Duration::minutes(10);
-    c.network_w_digest.network.reservations.insert(next_address, ReservationData {
+    next.network_w_digest.network.reservations.insert(next_address, ReservationData {
         peer_id: reservation_peer_id,
+        address_v6: next_address_v6,
         valid_until: reservation_valid_until,
     });
-    post_mg_config_w_digest!(c);
+    post_mg_config_w_digest!(c, next, &change);
+    concurrency::record(&from_digest, &c.network_w_digest.digest, [format!("reservations/{next_address}")].into());
+    events::publish_change(None, &c.network_w_digest.digest);
     log::info!("reserved address {} for {} until {}", next_address, reservation_peer_id, reservation_valid_until);
     
     Ok(HttpResponse::Ok().json(json!({
         "address": next_address,
+        "address_v6": next_address_v6,
         "peer_id": reservation_peer_id,
         "valid_until": reservation_valid_until
     })))
 }
+
+/// A kept config with its network secrets redacted like in the summary, and the hashes and the OpenID Connect client secret left out
+fn redact_config_file(config_file: &ConfigFile) -> serde_json::Value {
+    let mut config_json = json!(config_file);
+    redact_network_secrets(&mut config_json["network"], &config_file.network);
+    for pointer in ["/agent/web/password", "/agent/web/metrics/token"] {
+        if let Some(password_json) = config_json.pointer_mut(pointer).and_then(|p| p.as_object_mut()) {
+            password_json.remove("hash");
+        }
+    }
+    for pointer in ["/agent/web/users", "/agent/web/peer_tokens", "/agent/web/api_keys"] {
+        if let Some(entries_json) = config_json.pointer_mut(pointer).and_then(|e| e.as_object_mut()) {
+            for entry_json in entries_json.values_mut().filter_map(|e| e.as_object_mut()) {
+                entry_json.remove("hash");
+            }
+        }
+    }
+    if let Some(oidc_json) = config_json.pointer_mut("/agent/web/oidc").and_then(|o| o.as_object_mut()) {
+        oidc_json.remove("client_secret");
+    }
+    config_json
+}
+
+fn history_error_response(e: HistoryError) -> HttpResponse {
+    match e {
+        HistoryError::NotFound(_) => HttpResponse::NotFound().body(e.to_string()),
+        _ => {
+            log::error!("{e}");
+            HttpResponse::InternalServerError().body("unable to read config history")
+        }
+    }
+}
+
+pub(crate) fn get_config_history() -> Result<HttpResponse, HttpResponse> {
+    let revisions = history::list().map_err(history_error_response)?;
+    Ok(HttpResponse::Ok().json(revisions))
+}
+
+pub(crate) fn get_config_history_revision(revision: u64) -> Result<HttpResponse, HttpResponse> {
+    let (entry, _) = history::read(revision).map_err(history_error_response)?;
+    let config_file = history::read_config_file(revision).map_err(history_error_response)?;
+    Ok(HttpResponse::Ok().json(json!({
+        "revision": entry,
+        "config": redact_config_file(&config_file),
+    })))
+}
+
+pub(crate) fn get_config_history_diff(revision: u64, query: web::Query<crate::web::api::HistoryDiffQuery>) -> Result<HttpResponse, HttpResponse> {
+    let from = match query.against {
+        Some(against) => Some(against),
+        None => history::previous(revision).map_err(history_error_response)?,
+    };
+    let render = |revision: u64| -> Result<String, HttpResponse> {
+        let config_file = history::read_config_file(revision).map_err(history_error_response)?;
+        serde_norway::to_string(&redact_config_file(&config_file))
+            .map_err(|_| HttpResponse::InternalServerError().body("unable to serialize config"))
+    };
+    let to_str = render(revision)?;
+    let (from_str, from_label) = match from {
+        Some(from) => (render(from)?, format!("a/{from}.yml")),
+        // the oldest kept revision is diffed against nothing
+        None => (String::new(), "/dev/null".to_string()),
+    };
+    Ok(HttpResponse::Ok().json(json!({
+        "from": from,
+        "to": revision,
+        "diff": history::diff(&from_str, &to_str, &from_label, &format!("b/{revision}.yml")),
+    })))
+}
+
+pub(crate) fn post_config_history_rollback(change: Change, revision: u64) -> Result<HttpResponse, HttpResponse> {
+    let mut config_file = history::read_config_file(revision).map_err(history_error_response)?;
+    util::check_version(&config_file)
+        .map_err(|e| HttpResponse::BadRequest().body(format!("revision {revision} can not be restored: {e}")))?;
+    let mut c = get_mg_config_w_digest!();
+    history::keep_current_credentials(&mut config_file, &c.agent);
+    validate_config_file(&mut config_file, WG_QUICKRS_CONFIG_FOLDER.get().unwrap())
+        .map_err(|e| HttpResponse::BadRequest().body(format!("revision {revision} can not be restored: {e}")))?;
+
+    let mut next = c.clone();
+    next.agent = config_file.agent;
+    next.network_w_digest.network = config_file.network;
+    let new_revision = post_mg_config_w_digest!(c, next, &change.rollback_of(revision));
+    log::info!("rolled back config to revision {}", revision);
+    events::publish_change(None, &c.network_w_digest.digest);
+
+    if c.agent.vpn.enabled {
+        sync_conf(&c.clone().to_config()).map_err(|e| {
+            log::error!("{e}");
+            HttpResponse::InternalServerError().body("unable to synchronize config")
+        })?;
+    }
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ETag(EntityTag::new_strong(c.network_w_digest.digest.clone())))
+        .json(json!({
+            "revision": new_revision,
+            "digest": c.network_w_digest.digest,
+        })))
+}
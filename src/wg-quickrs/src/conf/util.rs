@@ -1,4 +1,8 @@
 use crate::{WG_QUICKRS_CONFIG_FILE, WG_QUICKRS_CONFIG_FOLDER};
+use crate::conf::control::{self, ControlError};
+use crate::conf::history::{self, Change, HistoryError};
+use crate::conf::lock::{self, LockError};
+use crate::conf::preview::{self, PreviewError};
 use crate::wireguard::cmd::{get_telemetry, status_tunnel};
 use wg_quickrs_lib::types::config::{Config, ConfigFile, ConfigWNetworkDigest};
 use wg_quickrs_lib::types::api::{Summary};
@@ -10,8 +14,10 @@ use std::fs;
 use std::fs::File;
 use std::io::Write;
 use std::path::PathBuf;
-use std::sync::{RwLock, OnceLock};
+use std::sync::{Mutex, RwLock, OnceLock};
+use std::sync::atomic::{AtomicBool, Ordering};
 use chrono::Utc;
+use tempfile::NamedTempFile;
 use thiserror::Error;
 use semver::Version;
 
@@ -41,9 +47,27 @@ pub enum ConfUtilError {
     WireGuardLibError(#[from] wg_quickrs_lib::types::misc::WireGuardLibError),
     #[error("{0}")]
     ConfigFile(#[from] ConfigFileValidationError),
+    #[error("failed to preview the change: {0}")]
+    Preview(#[from] PreviewError),
+    #[error("{0}")]
+    History(#[from] HistoryError),
+    #[error("{0}")]
+    Lock(#[from] LockError),
+    #[error("config file at {0} was changed by another process since it was loaded, not overwriting it")]
+    ChangedOnDisk(PathBuf),
+    #[error("{0}")]
+    Control(#[from] ControlError),
 }
 
 pub static CONFIG_W_NETWORK_DIGEST: OnceLock<RwLock<ConfigWNetworkDigest>> = OnceLock::new();
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+/// conf.yml as this process last read or wrote it, to notice edits made by anything else
+static CONFIG_FILE_ON_DISK: Mutex<Option<String>> = Mutex::new(None);
+
+/// Makes `set_config` print a preview of the change instead of storing it
+pub(crate) fn set_dry_run(dry_run: bool) {
+    DRY_RUN.store(dry_run, Ordering::Relaxed);
+}
 
 fn set_or_init_config_w_digest(config_w_network_digest: ConfigWNetworkDigest) -> Result<(), ConfUtilError> {
     let mut_opt = CONFIG_W_NETWORK_DIGEST.get();
@@ -81,15 +105,9 @@ fn get_config_w_digest() -> Result<ConfigWNetworkDigest, ConfUtilError> {
     let config_file_path = WG_QUICKRS_CONFIG_FILE.get().unwrap();
     let config_str = fs::read_to_string(config_file_path)
         .map_err(|e| ConfUtilError::Read(config_file_path.clone(), e))?;
+    remember_config_file_on_disk(config_str.clone())?;
     let mut config_file: ConfigFile = serde_norway::from_str(&config_str).map_err(ConfUtilError::Parse)?;
-    let build_version = Version::parse(wg_quickrs_version!()).unwrap();
-    let conf_ver = Version::parse(config_file.version.as_str()).map_err(ConfUtilError::InvalidVersion)?;
-    if build_version.major != conf_ver.major {
-        return Err(ConfUtilError::VersionNotSupported(
-            format!("{}.x.x", build_version.major),
-            config_file.version
-        ));
-    }
+    check_version(&config_file)?;
     // Validate config_file fields
     let config_folder_path = WG_QUICKRS_CONFIG_FOLDER.get().unwrap();
     validate_config_file(&mut config_file, config_folder_path)?;
@@ -100,6 +118,18 @@ fn get_config_w_digest() -> Result<ConfigWNetworkDigest, ConfUtilError> {
     Ok(config_w_digest)
 }
 
+pub(crate) fn check_version(config_file: &ConfigFile) -> Result<(), ConfUtilError> {
+    let build_version = Version::parse(wg_quickrs_version!()).unwrap();
+    let conf_ver = Version::parse(config_file.version.as_str()).map_err(ConfUtilError::InvalidVersion)?;
+    if build_version.major != conf_ver.major {
+        return Err(ConfUtilError::VersionNotSupported(
+            format!("{}.x.x", build_version.major),
+            config_file.version.clone()
+        ));
+    }
+    Ok(())
+}
+
 pub(crate) fn get_summary() -> Result<Summary, ConfUtilError> {
     let config_w_digest = get_config_w_digest()?;
     let status = status_tunnel().unwrap_or_else(|e| {
@@ -126,23 +156,148 @@ pub(crate) fn get_summary() -> Result<Summary, ConfUtilError> {
 }
 
 pub(crate) fn set_config(config: &mut Config) -> Result<(), ConfUtilError> {
+    set_config_as(config, &Change::cli())
+}
+
+/// Stores a kept revision of conf.yml as a new revision, validated like any other change
+pub(crate) fn rollback_config(revision: u64) -> Result<(), ConfUtilError> {
+    let mut config_file = history::read_config_file(revision)?;
+    check_version(&config_file)?;
+    history::keep_current_credentials(&mut config_file, &get_config()?.agent);
+    let mut config = Config::from(&config_file);
+    set_config_as(&mut config, &Change::cli().rollback_of(revision))
+}
+
+fn set_config_as(config: &mut Config, change: &Change) -> Result<(), ConfUtilError> {
     let mut config_file = ConfigFile::from(&config.clone());
     let config_folder_path = WG_QUICKRS_CONFIG_FOLDER.get().unwrap();
     validate_config_file(&mut config_file, config_folder_path)?;
 
+    if DRY_RUN.load(Ordering::Relaxed) {
+        let preview = preview::preview(&get_config()?, config)?;
+        println!("# dry run, {} was not written", WG_QUICKRS_CONFIG_FILE.get().unwrap().display());
+        print!("{}", preview);
+        return Ok(());
+    }
+
     let config_w_digest = ConfigWNetworkDigest::from_config(config.clone())?;
+    if control::agent_listening()? {
+        apply_on_agent(&config_file, config, change)?;
+    } else {
+        write_config(&config_file, change)?;
+    }
     set_or_init_config_w_digest(config_w_digest)?;
+    Ok(())
+}
+
+/// Hands the change to the running agent, so it is validated, synchronized to WireGuard and ordered with
+/// the changes made over the API like one of them
+fn apply_on_agent(config_file: &ConfigFile, config: &Config, change: &Change) -> Result<(), ConfUtilError> {
+    let current = get_config_w_digest()?;
+    // the agent takes the lock on the config folder itself to write conf.yml
+    lock::unlock_for_process();
+
+    let agent_unchanged = serde_json::to_value(&current.agent).ok() == serde_json::to_value(&config.agent).ok();
+    if agent_unchanged
+        && change.rollback_of.is_none()
+        && let Some(mut change_sum) = control::change_sum_between(&current.network_w_digest.network, &config.network)
+    {
+        change_sum.digest = Some(current.network_w_digest.digest);
+        control::patch(change, &change_sum)?;
+        log::info!("applied the change on the running agent");
+        return Ok(());
+    }
 
-    let config_file_str = serde_norway::to_string(&config_file).map_err(ConfUtilError::Serialization)?;
-    write_config(config_file_str)
+    // the rest is written to conf.yml and loaded by the agent from there
+    write_config(config_file, change)?;
+    control::reload()?;
+    log::info!("reloaded the running agent");
+    Ok(())
 }
 
-pub(crate) fn write_config(config_file_str: String) -> Result<(), ConfUtilError> {
+/// Loads conf.yml again when another process changed it, returns the config it replaced and the loaded one.
+/// Nothing is loaded while conf.yml is still what this process last read or wrote.
+pub(crate) fn reload_config() -> Result<Option<(Config, Config)>, ConfUtilError> {
+    get_config_w_digest()?;
+    // locked in the same order as by the web handlers, the loaded config before conf.yml
+    let mut config_w_digest = CONFIG_W_NETWORK_DIGEST
+        .get()
+        .ok_or(ConfUtilError::MutexSetFailed())?
+        .write()
+        .map_err(|e| ConfUtilError::MutexLockFailed(e.to_string()))?;
+    let _lock = lock::lock()?;
+
+    let config_file_path = WG_QUICKRS_CONFIG_FILE.get().unwrap();
+    let config_str = fs::read_to_string(config_file_path)
+        .map_err(|e| ConfUtilError::Read(config_file_path.clone(), e))?;
+    let loaded = CONFIG_FILE_ON_DISK
+        .lock()
+        .map_err(|e| ConfUtilError::MutexLockFailed(e.to_string()))?
+        .clone();
+    if loaded.as_deref() == Some(config_str.as_str()) {
+        return Ok(None);
+    }
+
+    let mut config_file: ConfigFile = serde_norway::from_str(&config_str).map_err(ConfUtilError::Parse)?;
+    check_version(&config_file)?;
+    validate_config_file(&mut config_file, WG_QUICKRS_CONFIG_FOLDER.get().unwrap())?;
+
+    let previous = config_w_digest.to_config();
+    let config = Config::from(&config_file);
+    *config_w_digest = ConfigWNetworkDigest::from_config(config.clone())?;
+    remember_config_file_on_disk(config_str)?;
+    log::info!("reloaded config file");
+    Ok(Some((previous, config)))
+}
+
+fn remember_config_file_on_disk(config_file_str: String) -> Result<(), ConfUtilError> {
+    *CONFIG_FILE_ON_DISK
+        .lock()
+        .map_err(|e| ConfUtilError::MutexLockFailed(e.to_string()))? = Some(config_file_str);
+    Ok(())
+}
+
+/// Writes to a temporary file next to conf.yml and renames it over conf.yml, so a crash leaves either
+/// the old or the new config behind
+fn write_atomically(file_path: &PathBuf, config_file_str: &str) -> std::io::Result<()> {
+    let folder = file_path.parent().unwrap();
+    let mut temp_file = NamedTempFile::new_in(folder)?;
+    temp_file.write_all(config_file_str.as_bytes())?;
+    if let Ok(metadata) = fs::metadata(file_path) {
+        temp_file.as_file().set_permissions(metadata.permissions())?;
+    }
+    temp_file.as_file().sync_all()?;
+    temp_file.persist(file_path).map_err(|e| e.error)?;
+    // the rename itself is only durable once the folder is synced
+    File::open(folder)?.sync_all()
+}
+
+/// Writes conf.yml and keeps it in the config history, returns the revision unless keeping it failed
+pub(crate) fn write_config(config_file: &ConfigFile, change: &Change) -> Result<Option<u64>, ConfUtilError> {
+    let config_file_str = serde_norway::to_string(config_file).map_err(ConfUtilError::Serialization)?;
     let file_path = WG_QUICKRS_CONFIG_FILE.get().unwrap();
-    let mut file = File::create(file_path).map_err(|e| ConfUtilError::Write(file_path.clone(), e))?;
-    file.write_all(config_file_str.as_bytes())
+    let _lock = lock::lock()?;
+
+    let previous = fs::read_to_string(file_path).ok();
+    let loaded = CONFIG_FILE_ON_DISK
+        .lock()
+        .map_err(|e| ConfUtilError::MutexLockFailed(e.to_string()))?
+        .clone();
+    // a process that never read conf.yml (e.g. agent init) has nothing to compare against
+    if loaded.is_some() && previous != loaded {
+        return Err(ConfUtilError::ChangedOnDisk(file_path.clone()));
+    }
+    write_atomically(file_path, &config_file_str)
         .map_err(|e| ConfUtilError::Write(file_path.clone(), e))?;
+    remember_config_file_on_disk(config_file_str.clone())?;
 
     log::info!("updated config file");
-    Ok(())
+    // the change is stored either way, a missing revision is not worth failing it
+    match history::record(previous.as_deref(), config_file, &config_file_str, change) {
+        Ok(revision) => Ok(Some(revision)),
+        Err(e) => {
+            log::error!("Failed to record config history => {}", e);
+            Ok(None)
+        }
+    }
 }
@@ -32,6 +32,8 @@ pub enum CommandError {
     AgentRun(#[from] commands::agent::run::AgentRunError),
     #[error("{0}")]
     ConfigCommand(#[from] commands::config::ConfigCommandError),
+    #[error("{0}")]
+    ConfigLock(#[from] conf::lock::LockError),
 }
 
 #[actix_web::main]
@@ -94,10 +96,13 @@ async fn entrypoint(args: Cli) -> Result<(), CommandError> {
         wg_quickrs_cli::Commands::Agent { target } => {
             match target {
                 wg_quickrs_cli::agent::AgentCommands::Init(init_opts) => commands::agent::init::initialize_agent(init_opts)?,
-                wg_quickrs_cli::agent::AgentCommands::Run => commands::agent::run::run_agent().await?,
+                wg_quickrs_cli::agent::AgentCommands::Run(run_opts) => commands::agent::run::run_agent(run_opts).await?,
             }
         },
-        wg_quickrs_cli::Commands::Config { target } => {
+        wg_quickrs_cli::Commands::Config { dry_run, target } => {
+            conf::util::set_dry_run(*dry_run);
+            // a running agent waits for the command to finish before writing conf.yml itself
+            conf::lock::lock_for_process()?;
             commands::config::handle_config_command(target)?;
         }
     };
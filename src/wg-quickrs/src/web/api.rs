@@ -1,33 +1,117 @@
+use crate::WG_QUICKRS_CONFIG_FOLDER;
 use crate::conf;
+use crate::conf::history::Change;
+use crate::web::throttle;
 use crate::wireguard;
-use actix_web::{HttpRequest, HttpResponse, Responder, get, patch, post, web};
-use argon2::{Argon2, PasswordHash, PasswordVerifier};
+use actix_web::{HttpRequest, HttpResponse, Responder, get, patch, post, routes, web};
+use actix_web::http::{StatusCode, header};
+use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
+use argon2::password_hash::SaltString;
 use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation, decode, encode};
-use once_cell::sync::Lazy;
+use once_cell::sync::{Lazy, OnceCell};
 use rand::{RngCore, rng};
 use serde::{Deserialize, Serialize};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+use wg_quickrs_lib::types::api::{ChangeSum, TelemetryResolution};
+use wg_quickrs_lib::types::config::{Config, WebRole};
 use wg_quickrs_lib::types::misc::VERSION_BUILD_INFO;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 struct Claims {
     sub: String, // Subject (user id)
     exp: u64,    // Expiration time as a timestamp
+    /// `None` for peer tokens
+    #[serde(default)]
+    role: Option<WebRole>,
+    /// `sub` names one of the configured users, whose current role applies instead
+    #[serde(default)]
+    user: bool,
+    /// `sub` is the id of one of the configured peer tokens
+    #[serde(default)]
+    peer_token: bool,
+    /// signed in with the OpenID Connect provider, the token stops working when it is disabled
+    #[serde(default)]
+    oidc: bool,
 }
 
-// Secret key for signing tokens
-static JWT_SECRETS: Lazy<(EncodingKey, DecodingKey)> = Lazy::new(|| {
-    let mut key = [0u8; 32];
-    rng().fill_bytes(&mut key);
+/// What a request may do
+#[derive(Clone, Copy)]
+enum Grant {
+    Role(WebRole),
+    /// only the peer's own config, connections and telemetry
+    Peer { peer_id: Uuid, key_rotation: bool },
+}
+
+/// Who a request was authenticated as
+struct Session {
+    client_id: String,
+    grant: Grant,
+    user: bool,
+}
+
+fn client_id(session: &Option<Session>) -> Option<&str> {
+    session.as_ref().map(|session| session.client_id.as_str())
+}
+
+/// API keys are `wgq_<id>.<secret>`, unlike the tokens of /api/token they are sent as they are
+const API_KEY_PREFIX: &str = "wgq_";
+/// Signing key kept in the config folder when `persistent_jwt_key` is enabled
+const JWT_KEY_FILE: &str = "jwt.key";
+
+fn jwt_secrets_from(key: &[u8]) -> (EncodingKey, DecodingKey) {
     (
-        EncodingKey::from_secret(&key),
-        DecodingKey::from_secret(&key),
+        EncodingKey::from_secret(key),
+        DecodingKey::from_secret(key),
     )
-});
+}
+
+fn random_jwt_key() -> [u8; 32] {
+    let mut key = [0u8; 32];
+    rng().fill_bytes(&mut key);
+    key
+}
+
+// Secret key for signing tokens, a new one on every start
+static JWT_SECRETS: Lazy<(EncodingKey, DecodingKey)> = Lazy::new(|| jwt_secrets_from(&random_jwt_key()));
+static PERSISTENT_JWT_SECRETS: OnceCell<(EncodingKey, DecodingKey)> = OnceCell::new();
+
+/// Reads the signing key from the config folder, it is created there the first time
+fn read_or_create_jwt_key() -> std::io::Result<Vec<u8>> {
+    let path = WG_QUICKRS_CONFIG_FOLDER.get().unwrap().join(JWT_KEY_FILE);
+    match fs::read(&path) {
+        Ok(key) if key.len() >= 32 => return Ok(key),
+        Ok(_) => return Err(std::io::Error::new(ErrorKind::InvalidData, format!("{} is shorter than 32 bytes", path.display()))),
+        Err(e) if e.kind() == ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+    let key = random_jwt_key();
+    let mut file = OpenOptions::new().write(true).create_new(true).mode(0o600).open(&path)?;
+    file.write_all(&key)?;
+    file.sync_all()?;
+    log::info!("Created the JWT signing key at {}", path.display());
+    Ok(key.to_vec())
+}
+
+/// Keys signing and checking the tokens of /api/token
+fn jwt_secrets(config: &Config) -> Result<&'static (EncodingKey, DecodingKey), HttpResponse> {
+    if !config.agent.web.persistent_jwt_key.enabled {
+        return Ok(&JWT_SECRETS);
+    }
+    PERSISTENT_JWT_SECRETS
+        .get_or_try_init(|| read_or_create_jwt_key().map(|key| jwt_secrets_from(&key)))
+        .map_err(|e| {
+            log::error!("Failed to read the JWT signing key: {}", e);
+            HttpResponse::InternalServerError().body("Server configuration error")
+        })
+}
 
 #[get("/api/version")]
 async fn get_version(req: HttpRequest) -> impl Responder {
-    if let Err(e) = enforce_auth(req) {
+    if let Err(e) = enforce_auth(req, WebRole::Viewer) {
         return e;
     }
 
@@ -41,38 +125,244 @@ pub(crate) struct SummaryBody {
 
 #[get("/api/network/summary")]
 async fn get_network_summary(req: HttpRequest, query: web::Query<SummaryBody>) -> impl Responder {
-    if let Err(e) = enforce_auth(req) {
+    if let Err(e) = enforce_auth(req, WebRole::Viewer) {
         return e;
     }
     conf::respond::get_network_summary(query).unwrap_or_else(|e| e)
 }
 
-#[patch("/api/network/config")]
-async fn patch_network_config(req: HttpRequest, body: web::Bytes) -> impl Responder {
-    if let Err(e) = enforce_auth(req) {
+#[get("/api/network/events")]
+async fn get_network_events(req: HttpRequest) -> impl Responder {
+    if let Err(e) = enforce_auth(req.clone(), WebRole::Viewer) {
         return e;
     }
-    conf::respond::patch_network_config(body).unwrap_or_else(|e| e)
+    // the stream outlives the request, so revoked credentials and expired tokens are caught before every event
+    conf::respond::get_network_events(move || enforce_auth(req.clone(), WebRole::Viewer).is_ok()).unwrap_or_else(|e| e)
+}
+
+#[derive(serde::Deserialize)]
+pub(crate) struct PeerConfQuery {
+    pub(crate) format: Option<String>,
+    #[serde(default)]
+    pub(crate) stripped: bool,
+}
+
+#[routes]
+#[get("/api/network/peers/{id}/conf")]
+#[get("/api/network/peer/{id}/conf")]
+async fn get_network_peer_conf(req: HttpRequest, path: web::Path<String>, query: web::Query<PeerConfQuery>) -> impl Responder {
+    let peer_id = path.into_inner();
+    if let Err(e) = enforce_peer_auth(req, WebRole::Operator, &peer_ids(&peer_id)) {
+        return e;
+    }
+    conf::respond::get_network_peer_conf(&peer_id, query).unwrap_or_else(|e| e)
+}
+
+#[get("/api/network/peers/{id}/connections")]
+async fn get_network_peer_connections(req: HttpRequest, path: web::Path<String>) -> impl Responder {
+    let peer_id = path.into_inner();
+    if let Err(e) = enforce_peer_auth(req, WebRole::Viewer, &peer_ids(&peer_id)) {
+        return e;
+    }
+    conf::respond::get_network_peer_connections(&peer_id).unwrap_or_else(|e| e)
+}
+
+#[post("/api/network/peers/{id}/private-key/rotate")]
+async fn post_network_peer_private_key_rotate(req: HttpRequest, path: web::Path<String>) -> impl Responder {
+    let peer_id = path.into_inner();
+    let session = match enforce_peer_auth(req, WebRole::Operator, &peer_ids(&peer_id)) {
+        Ok(session) => session,
+        Err(e) => return e,
+    };
+    if let Some(Session { grant: Grant::Peer { key_rotation: false, .. }, .. }) = session {
+        return HttpResponse::Forbidden()
+            .content_type("text/plain; charset=utf-8")
+            .body("Peer token does not allow key rotation");
+    }
+    conf::respond::post_network_peer_private_key_rotate(Change::api(client_id(&session)), &peer_id).unwrap_or_else(|e| e)
+}
+
+#[derive(serde::Deserialize)]
+pub(crate) struct TelemetryHistoryQuery {
+    pub(crate) from: Option<i64>,
+    pub(crate) to: Option<i64>,
+    pub(crate) resolution: Option<TelemetryResolution>,
+}
+
+#[get("/api/network/connections/{id}/telemetry")]
+async fn get_network_connection_telemetry(req: HttpRequest, path: web::Path<String>, query: web::Query<TelemetryHistoryQuery>) -> impl Responder {
+    let connection_id = path.into_inner();
+    if let Err(e) = enforce_peer_auth(req, WebRole::Viewer, &peer_ids(&connection_id)) {
+        return e;
+    }
+    wireguard::respond::get_connection_telemetry(&connection_id, query).unwrap_or_else(|e| e)
+}
+
+/// The digest of an `If-Match` header, quoted as an entity tag or not, `None` when it is missing or `*`
+fn parse_if_match(req: &HttpRequest) -> Result<Option<String>, HttpResponse> {
+    let Some(value) = req.headers().get(header::IF_MATCH) else {
+        return Ok(None);
+    };
+    let value = value.to_str()
+        .map_err(|_| HttpResponse::BadRequest().body("If-Match: invalid header value"))?
+        .trim();
+    if value == "*" {
+        return Ok(None);
+    }
+    let digest = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')).unwrap_or(value);
+    if digest.is_empty() || digest.contains([',', '"']) {
+        return Err(HttpResponse::BadRequest().body("If-Match: expected a single digest"));
+    }
+    Ok(Some(digest.to_string()))
+}
+
+#[derive(serde::Deserialize)]
+pub(crate) struct PatchQuery {
+    #[serde(default)]
+    pub(crate) dry_run: bool,
+}
+
+/// Defaults and AmneziaWG parameters apply to every peer, unlike the peers and connections operators manage
+fn changes_network_settings(body: &web::Bytes) -> bool {
+    // a body that does not parse is rejected with a proper message by the patch itself
+    serde_json::from_slice::<ChangeSum>(body).ok()
+        .and_then(|change_sum| change_sum.changed_fields)
+        .is_some_and(|changed_fields| changed_fields.defaults.is_some() || changed_fields.amnezia_parameters.is_some())
+}
+
+#[patch("/api/network/config")]
+async fn patch_network_config(req: HttpRequest, query: web::Query<PatchQuery>, body: web::Bytes) -> impl Responder {
+    let if_match = parse_if_match(&req);
+    let session = match enforce_auth(req, WebRole::Operator) {
+        Ok(session) => session,
+        Err(e) => return e,
+    };
+    if let Some(Session { grant: Grant::Role(role), .. }) = &session
+        && *role < WebRole::Admin
+        && changes_network_settings(&body)
+    {
+        return forbidden(WebRole::Admin);
+    }
+    if_match
+        .and_then(|if_match| conf::respond::patch_network_config(Change::api(client_id(&session)), if_match, query, body))
+        .unwrap_or_else(|e| e)
+}
+
+#[post("/api/network/secrets/reveal")]
+async fn post_network_secrets_reveal(req: HttpRequest, body: web::Bytes) -> impl Responder {
+    let session = match enforce_auth(req, WebRole::Admin) {
+        Ok(session) => session,
+        Err(e) => return e,
+    };
+
+    // revealing secrets requires the password to be confirmed again, a token alone is not enough
+    if let Some(session) = session {
+        let config = match conf::util::get_config() {
+            Ok(config) => config,
+            Err(_) => {
+                return HttpResponse::InternalServerError().body("Unable to get config");
+            }
+        };
+        #[derive(Deserialize)]
+        struct RevealBody {
+            password: String,
+        }
+        let body_raw = String::from_utf8_lossy(&body);
+        let reveal_body: RevealBody = match serde_json::from_str(&body_raw) {
+            Ok(val) => val,
+            Err(err) => {
+                return HttpResponse::BadRequest().body(format!("invalid JSON: {err}"));
+            }
+        };
+        // users confirm their own password
+        let hash = match config.agent.web.users.get(&session.client_id) {
+            Some(user) if session.user => &user.hash,
+            _ => &config.agent.web.password.hash,
+        };
+        if let Err(e) = verify_password(hash, &reveal_body.password) {
+            return e;
+        }
+    }
+    conf::respond::post_network_secrets_reveal().unwrap_or_else(|e| e)
 }
 
 #[post("/api/network/reserve/address")]
 async fn post_network_reserve_address(req: HttpRequest) -> impl Responder {
-    if let Err(e) = enforce_auth(req) {
+    let session = match enforce_auth(req, WebRole::Operator) {
+        Ok(session) => session,
+        Err(e) => return e,
+    };
+    conf::respond::post_network_reserve_address(Change::api(client_id(&session))).unwrap_or_else(|e| e)
+}
+
+#[get("/api/config/history")]
+async fn get_config_history(req: HttpRequest) -> impl Responder {
+    if let Err(e) = enforce_auth(req, WebRole::Operator) {
+        return e;
+    }
+    conf::respond::get_config_history().unwrap_or_else(|e| e)
+}
+
+#[get("/api/config/history/{revision}")]
+async fn get_config_history_revision(req: HttpRequest, path: web::Path<u64>) -> impl Responder {
+    if let Err(e) = enforce_auth(req, WebRole::Operator) {
+        return e;
+    }
+    conf::respond::get_config_history_revision(path.into_inner()).unwrap_or_else(|e| e)
+}
+
+#[derive(serde::Deserialize)]
+pub(crate) struct HistoryDiffQuery {
+    pub(crate) against: Option<u64>,
+}
+
+#[get("/api/config/history/{revision}/diff")]
+async fn get_config_history_diff(req: HttpRequest, path: web::Path<u64>, query: web::Query<HistoryDiffQuery>) -> impl Responder {
+    if let Err(e) = enforce_auth(req, WebRole::Operator) {
         return e;
     }
-    conf::respond::post_network_reserve_address().unwrap_or_else(|e| e)
+    conf::respond::get_config_history_diff(path.into_inner(), query).unwrap_or_else(|e| e)
+}
+
+#[post("/api/config/history/{revision}/rollback")]
+async fn post_config_history_rollback(req: HttpRequest, path: web::Path<u64>) -> impl Responder {
+    let session = match enforce_auth(req, WebRole::Admin) {
+        Ok(session) => session,
+        Err(e) => return e,
+    };
+    conf::respond::post_config_history_rollback(Change::api(client_id(&session)), path.into_inner()).unwrap_or_else(|e| e)
 }
 
 #[post("/api/wireguard/status")]
 async fn post_wireguard_status(req: HttpRequest, body: web::Bytes) -> impl Responder {
-    if let Err(e) = enforce_auth(req) {
+    if let Err(e) = enforce_auth(req, WebRole::Admin) {
         return e;
     }
     wireguard::respond::post_wireguard_server_status(body).unwrap_or_else(|e| e)
 }
 
+#[get("/metrics")]
+async fn get_metrics(req: HttpRequest) -> impl Responder {
+    if let Err(e) = enforce_metrics_auth(req) {
+        return e;
+    }
+    wireguard::respond::get_metrics().unwrap_or_else(|e| e)
+}
+
+#[derive(Deserialize)]
+struct LoginBody {
+    client_id: String,
+    password: Option<String>,
+    /// signs in as one of the configured users instead of with the shared password
+    #[serde(default)]
+    username: Option<String>,
+    /// signs in with a token limited to a single peer instead of a password
+    #[serde(default)]
+    peer_token: Option<String>,
+}
+
 #[post("/api/token")]
-async fn post_token(body: web::Bytes) -> impl Responder {
+async fn post_token(req: HttpRequest, body: web::Bytes) -> impl Responder {
     // check password-based auth
     let config = match conf::util::get_config() {
         Ok(config) => config,
@@ -84,11 +374,6 @@ async fn post_token(body: web::Bytes) -> impl Responder {
         return HttpResponse::NoContent().body("Token authentication not enabled");
     }
 
-    #[derive(Serialize, Deserialize)]
-    struct LoginBody {
-        client_id: String,
-        password: String,
-    }
     let body_raw = String::from_utf8_lossy(&body);
     let status_body: LoginBody = match serde_json::from_str(&body_raw) {
         Ok(val) => val,
@@ -96,38 +381,147 @@ async fn post_token(body: web::Bytes) -> impl Responder {
             return HttpResponse::BadRequest().body(format!("invalid JSON: {err}"));
         }
     };
-    let client_id = &status_body.client_id;
-    let password = &status_body.password;
 
-    // check password-based auth
-    let parsed_hash = match PasswordHash::new(&config.agent.web.password.hash) {
-        Ok(hash) => hash,
+    // failed logins are counted per client IP and per account, whichever is locked out longer applies
+    let ip = throttle::client_ip(&req);
+    let account = match (&status_body.peer_token, &status_body.username) {
+        (Some(peer_token), _) => format!("peer-token:{}", peer_token.split_once('.').map_or(peer_token.as_str(), |(id, _)| id)),
+        (None, Some(username)) => format!("user:{username}"),
+        (None, None) => format!("client:{}", status_body.client_id),
+    };
+    // only accounts that exist are counted, the shared password and unknown names are counted by IP alone,
+    // as clients could otherwise make up any number of them
+    let known_account = match (&status_body.peer_token, &status_body.username) {
+        (Some(peer_token), _) => peer_token.split_once('.').is_some_and(|(id, _)| config.agent.web.peer_tokens.contains_key(id)),
+        (None, Some(username)) => config.agent.web.users.contains_key(username),
+        (None, None) => false,
+    };
+    let mut throttle_keys = vec![format!("ip:{ip}")];
+    if known_account {
+        throttle_keys.push(format!("account:{account}"));
+    }
+    if let Some(retry_after) = throttle::retry_after(&throttle_keys) {
+        return too_many_requests(retry_after, "Too many failed login attempts");
+    }
+
+    let claims = match login_claims(&config, &status_body) {
+        Ok(claims) => claims,
         Err(e) => {
-            log::error!("Invalid password hash format in configuration: {}", e);
-            return HttpResponse::InternalServerError().body("Server configuration error");
+            if e.status() == StatusCode::UNAUTHORIZED {
+                // the format fail2ban filters match on, the IP comes first as the account is chosen by the client
+                log::warn!("Failed login from {} for {:?}", ip, account);
+                throttle::record_failure(&throttle_keys);
+            }
+            return e;
         }
     };
-    if Argon2::default().verify_password(password.as_bytes(), &parsed_hash).is_err() {
-        return HttpResponse::Unauthorized().body("Invalid credentials");
+    throttle::record_success(&throttle_keys);
+
+    match encode_token(&config, &claims) {
+        Ok(token) => HttpResponse::Ok().body(token),
+        Err(e) => e,
     }
+}
 
-    let expiration = match SystemTime::now().duration_since(UNIX_EPOCH) {
-        Ok(duration) => duration.as_secs() + 3600, // 1-hour expiry
-        Err(_) => return HttpResponse::InternalServerError().body("SystemTime before UNIX EPOCH!"),
-    };
+fn encode_token(config: &Config, claims: &Claims) -> Result<String, HttpResponse> {
+    encode(&Header::default(), claims, &jwt_secrets(config)?.0)
+        .map_err(|_| HttpResponse::InternalServerError().body("Token creation error"))
+}
 
-    let claims = Claims {
-        sub: client_id.clone(),
-        exp: expiration,
-    };
+fn claims_for(sub: String, role: Option<WebRole>) -> Result<Claims, HttpResponse> {
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(duration) => Ok(Claims {
+            sub,
+            exp: duration.as_secs() + 3600, // 1-hour expiry
+            role,
+            user: false,
+            peer_token: false,
+            oidc: false,
+        }),
+        Err(_) => Err(HttpResponse::InternalServerError().body("SystemTime before UNIX EPOCH!")),
+    }
+}
 
-    match encode(&Header::default(), &claims, &JWT_SECRETS.0) {
-        Ok(token) => HttpResponse::Ok().body(token),
-        Err(_) => HttpResponse::InternalServerError().body("Token creation error"),
+/// The same token `/api/token` issues, for a user the OpenID Connect provider signed in
+pub(crate) fn issue_oidc_token(config: &Config, username: &str, role: WebRole) -> Result<String, HttpResponse> {
+    let claims = claims_for(format!("oidc:{username}"), Some(role))?;
+    encode_token(config, &Claims { oidc: true, ..claims })
+}
+
+/// Checked instead when a login names a user or peer token that does not exist, so it takes as long as a wrong password
+/// and the response time does not tell which accounts exist
+static DUMMY_HASH: Lazy<String> = Lazy::new(|| {
+    let mut salt = [0; 8];
+    rng().fill_bytes(&mut salt);
+    let salt = SaltString::encode_b64(&salt).expect("8 bytes are a valid salt");
+    Argon2::default().hash_password(b"", &salt).expect("hashing with a valid salt succeeds").to_string()
+});
+
+/// Checks the credentials of a login, the claims of its token if they are valid
+fn login_claims(config: &Config, login: &LoginBody) -> Result<Claims, HttpResponse> {
+    match (&login.peer_token, &login.password, &login.username) {
+        // peer tokens are `<id>.<secret>`, the id finds the hash to check the secret against
+        (Some(peer_token), _, _) => {
+            let Some((id, secret)) = peer_token.split_once('.') else {
+                return Err(HttpResponse::Unauthorized().body("Invalid credentials"));
+            };
+            let token = config.agent.web.peer_tokens.get(id);
+            verify_password(token.map_or(DUMMY_HASH.as_str(), |token| token.hash.as_str()), secret)?;
+            if token.is_none() {
+                return Err(HttpResponse::Unauthorized().body("Invalid credentials"));
+            }
+            claims_for(id.to_string(), None).map(|claims| Claims { peer_token: true, ..claims })
+        }
+        (None, Some(password), Some(username)) => {
+            let user = config.agent.web.users.get(username);
+            verify_password(user.map_or(DUMMY_HASH.as_str(), |user| user.hash.as_str()), password)?;
+            let Some(user) = user else {
+                return Err(HttpResponse::Unauthorized().body("Invalid credentials"));
+            };
+            claims_for(username.clone(), Some(user.role)).map(|claims| Claims { user: true, ..claims })
+        }
+        // the shared password predates users and keeps full access
+        (None, Some(password), None) => {
+            verify_password(&config.agent.web.password.hash, password)?;
+            claims_for(login.client_id.clone(), Some(WebRole::Admin))
+        }
+        (None, None, _) => Err(HttpResponse::BadRequest().body("either password or peer_token is required")),
+    }
+}
+
+pub(crate) fn too_many_requests(retry_after: Duration, message: &str) -> HttpResponse {
+    HttpResponse::TooManyRequests()
+        .insert_header((header::RETRY_AFTER, retry_after.as_secs_f64().ceil().max(1.0).to_string()))
+        .content_type("text/plain; charset=utf-8")
+        .body(message.to_string())
+}
+
+fn verify_password(hash: &str, password: &str) -> Result<(), HttpResponse> {
+    // taken before the hash is checked, so a flood of requests cannot keep every core busy with Argon2
+    let Some(_permit) = throttle::verification_permit() else {
+        return Err(too_many_requests(Duration::from_secs(1), "Too many password verifications at once"));
+    };
+    let parsed_hash = match PasswordHash::new(hash) {
+        Ok(hash) => hash,
+        Err(e) => {
+            log::error!("Invalid password hash format in configuration: {}", e);
+            return Err(HttpResponse::InternalServerError().body("Server configuration error"));
+        }
+    };
+    if Argon2::default().verify_password(password.as_bytes(), &parsed_hash).is_err() {
+        return Err(HttpResponse::Unauthorized().body("Invalid credentials"));
     }
+    Ok(())
 }
 
-fn enforce_auth(req: HttpRequest) -> Result<(), HttpResponse> {
+fn forbidden(required: WebRole) -> HttpResponse {
+    HttpResponse::Forbidden()
+        .content_type("text/plain; charset=utf-8")
+        .body(format!("Requires the {required} role"))
+}
+
+/// Who the token was issued to, `None` when password auth is disabled
+fn authenticate(req: HttpRequest) -> Result<Option<Session>, HttpResponse> {
     // check password-based auth
     let config = match conf::util::get_config() {
         Ok(config) => config,
@@ -136,7 +530,7 @@ fn enforce_auth(req: HttpRequest) -> Result<(), HttpResponse> {
         }
     };
     if !config.agent.web.password.enabled {
-        return Ok(());
+        return Ok(None);
     }
 
     if let Some(auth_header) = req.headers().get("Authorization")
@@ -144,13 +538,112 @@ fn enforce_auth(req: HttpRequest) -> Result<(), HttpResponse> {
         && let Some(token) = auth_str.strip_prefix("Bearer ")
     {
         let validation = Validation::new(Algorithm::HS256);
+        let invalid_token = || HttpResponse::Unauthorized()
+            .content_type("text/plain; charset=utf-8")
+            .body("Invalid token");
 
-        return match decode::<Claims>(token, &JWT_SECRETS.1, &validation) {
-            Ok(_) => Ok(()),
-            Err(_) => Err(HttpResponse::Unauthorized()
-                .content_type("text/plain; charset=utf-8")
-                .body("Invalid token")),
+        // API keys do not expire, they work until they are removed
+        if let Some(api_key) = token.strip_prefix(API_KEY_PREFIX) {
+            // wrong keys count toward the lockout of the client IP like failed logins
+            let ip = throttle::client_ip(&req);
+            let throttle_keys = [format!("ip:{ip}")];
+            if let Some(retry_after) = throttle::retry_after(&throttle_keys) {
+                return Err(too_many_requests(retry_after, "Too many failed login attempts"));
+            }
+            let (id, secret) = api_key.split_once('.').unwrap_or((api_key, ""));
+            let Some((name, key)) = config.agent.web.api_keys.iter()
+                .find(|(_, key)| key.id == id)
+                .filter(|(_, key)| conf::api_keys::secret_matches(&key.hash, secret))
+            else {
+                log::warn!("Failed login from {} for {:?}", ip, format!("api-key:{id}"));
+                throttle::record_failure(&throttle_keys);
+                return Err(invalid_token());
+            };
+            conf::api_keys::record_use(id, config.agent.web.api_keys.values().map(|key| &key.id));
+            return Ok(Some(Session { client_id: format!("api-key:{name}"), grant: Grant::Role(key.role), user: false }));
+        }
+
+        let claims = decode::<Claims>(token, &jwt_secrets(&config)?.1, &validation)
+            .map_err(|_| invalid_token())?
+            .claims;
+        // removing a user or a peer token, or changing a user's role, takes effect right away, not when its tokens expire
+        let (client_id, grant) = if claims.peer_token {
+            let peer_token = config.agent.web.peer_tokens.get(&claims.sub).ok_or_else(invalid_token)?;
+            (format!("peer-token:{}", claims.sub), Grant::Peer { peer_id: peer_token.peer_id, key_rotation: peer_token.key_rotation })
+        } else if claims.user {
+            let user = config.agent.web.users.get(&claims.sub).ok_or_else(invalid_token)?;
+            (claims.sub, Grant::Role(user.role))
+        } else if claims.oidc && !config.agent.web.oidc.enabled {
+            return Err(invalid_token());
+        } else {
+            (claims.sub, Grant::Role(claims.role.ok_or_else(invalid_token)?))
         };
+        return Ok(Some(Session { client_id, grant, user: claims.user }));
+    }
+
+    Err(HttpResponse::Unauthorized()
+        .content_type("text/plain; charset=utf-8")
+        .body("Authorization header missing or invalid"))
+}
+
+/// Who the token was issued to if it has at least the `required` role, `None` when password auth is disabled
+fn enforce_auth(req: HttpRequest, required: WebRole) -> Result<Option<Session>, HttpResponse> {
+    enforce_peer_auth(req, required, &[])
+}
+
+/// Like `enforce_auth`, but also lets peer tokens of one of `peer_ids` through
+fn enforce_peer_auth(req: HttpRequest, required: WebRole, peer_ids: &[Uuid]) -> Result<Option<Session>, HttpResponse> {
+    let session = authenticate(req)?;
+    match session.as_ref().map(|session| session.grant) {
+        None => {}
+        Some(Grant::Role(role)) if role >= required => {}
+        Some(Grant::Role(_)) => return Err(forbidden(required)),
+        Some(Grant::Peer { peer_id, .. }) if peer_ids.contains(&peer_id) => {}
+        Some(Grant::Peer { .. }) => return Err(HttpResponse::Forbidden()
+            .content_type("text/plain; charset=utf-8")
+            .body("Peer tokens only have access to their own peer")),
+    }
+    Ok(session)
+}
+
+/// The peers a peer or connection id from a path names, a malformed id names none
+fn peer_ids(id: &str) -> Vec<Uuid> {
+    id.split('*').filter_map(|id| Uuid::parse_str(id).ok()).collect()
+}
+
+/// The exporter has its own bearer token, scrapers cannot log in for a JWT
+fn enforce_metrics_auth(req: HttpRequest) -> Result<(), HttpResponse> {
+    let config = match conf::util::get_config() {
+        Ok(config) => config,
+        Err(_) => {
+            return Err(HttpResponse::InternalServerError().body("Unable to get config"));
+        }
+    };
+    if !config.agent.web.metrics.enabled {
+        return Err(HttpResponse::NotFound().body("Metrics endpoint is disabled"));
+    }
+    if !config.agent.web.metrics.token.enabled {
+        return Ok(());
+    }
+
+    if let Some(auth_header) = req.headers().get("Authorization")
+        && let Ok(auth_str) = auth_header.to_str()
+        && let Some(token) = auth_str.strip_prefix("Bearer ")
+    {
+        // checked like API keys, so scrapes never wait for an Argon2 slot and wrong tokens lock the client IP out
+        let ip = throttle::client_ip(&req);
+        let throttle_keys = [format!("ip:{ip}")];
+        if let Some(retry_after) = throttle::retry_after(&throttle_keys) {
+            return Err(too_many_requests(retry_after, "Too many failed login attempts"));
+        }
+        if conf::api_keys::secret_matches(&config.agent.web.metrics.token.hash, token) {
+            return Ok(());
+        }
+        log::warn!("Failed login from {} for {:?}", ip, "metrics");
+        throttle::record_failure(&throttle_keys);
+        return Err(HttpResponse::Unauthorized()
+            .content_type("text/plain; charset=utf-8")
+            .body("Invalid token"));
     }
 
     Err(HttpResponse::Unauthorized()
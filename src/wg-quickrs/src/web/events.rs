@@ -0,0 +1,110 @@
+use crate::conf::util;
+use actix_web::web::Bytes;
+use futures_util::Stream;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::convert::Infallible;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::{RecvError, TryRecvError};
+use wg_quickrs_lib::types::api::SummaryDigest;
+
+/// How many change events a slow client may fall behind before it is told to refetch everything
+const EVENTS_CAPACITY: usize = 64;
+
+/// Pushed whenever the network changes
+#[derive(Serialize, Debug, Clone)]
+pub(crate) struct NetworkChange {
+    /// the applied change with its secrets redacted, `None` when the network changed some other way
+    pub(crate) change_sum: Option<serde_json::Value>,
+    pub(crate) digest: String,
+}
+
+static EVENTS: Lazy<broadcast::Sender<NetworkChange>> = Lazy::new(|| broadcast::channel(EVENTS_CAPACITY).0);
+
+fn frame(event: &str, data: &impl Serialize) -> String {
+    match serde_json::to_string(data) {
+        Ok(data) => format!("event: {event}\ndata: {data}\n\n"),
+        Err(e) => {
+            log::error!("Failed to serialize {} event: {}", event, e);
+            String::new()
+        }
+    }
+}
+
+/// Tells every open stream about a change of the network
+pub(crate) fn publish_change(change_sum: Option<serde_json::Value>, digest: &str) {
+    // an error only means nobody is listening
+    let _ = EVENTS.send(NetworkChange { change_sum, digest: digest.to_string() });
+}
+
+struct EventStream {
+    changes: broadcast::Receiver<NetworkChange>,
+    ticker: tokio::time::Interval,
+    last_digest: Option<String>,
+    authorized: Box<dyn Fn() -> bool>,
+}
+
+impl EventStream {
+    fn change_frame(&mut self, change: NetworkChange) -> String {
+        self.last_digest = Some(change.digest.clone());
+        frame("change", &change)
+    }
+
+    /// Digest of the current summary, preceded by the changes that happened since the last tick
+    fn tick_frames(&mut self) -> String {
+        let mut frames = String::new();
+        loop {
+            match self.changes.try_recv() {
+                Ok(change) => frames.push_str(&self.change_frame(change)),
+                Err(TryRecvError::Lagged(_)) => continue,
+                Err(_) => break,
+            }
+        }
+
+        let summary = match util::get_summary() {
+            Ok(summary) => summary,
+            Err(e) => {
+                log::error!("Failed to get summary for the event stream: {}", e);
+                return frames;
+            }
+        };
+        // changes that did not go through the API, e.g. a reloaded config file
+        if self.last_digest.as_ref().is_some_and(|digest| *digest != summary.digest) {
+            frames.push_str(&self.change_frame(NetworkChange { change_sum: None, digest: summary.digest.clone() }));
+        }
+        self.last_digest = Some(summary.digest.clone());
+        frames.push_str(&frame("digest", &SummaryDigest::from(&summary)));
+        frames
+    }
+}
+
+/// Server-sent events with a `digest` on every tick and a `change` whenever the network changes,
+/// the stream ends as soon as `authorized` no longer holds before an event
+pub(crate) fn subscribe(interval: Duration, authorized: impl Fn() -> bool + 'static) -> impl Stream<Item = Result<Bytes, Infallible>> {
+    let stream = EventStream {
+        changes: EVENTS.subscribe(),
+        ticker: tokio::time::interval(interval),
+        last_digest: None,
+        authorized: Box::new(authorized),
+    };
+
+    futures_util::stream::unfold(stream, |mut stream| async move {
+        let frames = tokio::select! {
+            change = stream.changes.recv() => match change {
+                Ok(change) => stream.change_frame(change),
+                // the client missed changes, a change without a sum makes it refetch the network
+                Err(RecvError::Lagged(_)) => match util::get_summary() {
+                    Ok(summary) => stream.change_frame(NetworkChange { change_sum: None, digest: summary.digest }),
+                    Err(_) => String::new(),
+                },
+                Err(RecvError::Closed) => return None,
+            },
+            _ = stream.ticker.tick() => stream.tick_frames(),
+        };
+        if !(stream.authorized)() {
+            return None;
+        }
+        Some((Ok(Bytes::from(frames)), stream))
+    })
+}
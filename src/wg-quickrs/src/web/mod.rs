@@ -1,3 +1,6 @@
 pub mod api;
 pub mod app;
+pub(crate) mod events;
+pub(crate) mod oidc;
 pub mod server;
+pub(crate) mod throttle;
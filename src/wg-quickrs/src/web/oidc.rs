@@ -0,0 +1,348 @@
+use crate::conf;
+use crate::conf::util::ConfUtilError;
+use crate::web::api::{issue_oidc_token, too_many_requests};
+use crate::web::throttle;
+use actix_web::cookie::{Cookie, SameSite};
+use actix_web::http::header;
+use actix_web::{HttpRequest, HttpResponse, Responder, get, web};
+use aws_lc_rs::constant_time::verify_slices_are_equal;
+use aws_lc_rs::digest::{SHA256, digest};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation, decode, decode_header};
+use once_cell::sync::Lazy;
+use rand::distr::Alphanumeric;
+use rand::{Rng, rng};
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use wg_quickrs_lib::types::config::{AgentWebOidc, Config, WebRole};
+
+const DISCOVERY_PATH: &str = "/.well-known/openid-configuration";
+/// Time a user has to sign in with the provider
+const LOGIN_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+/// Logins started but not finished yet, the oldest is dropped once there are more
+const MAX_PENDING_LOGINS: usize = 1000;
+const PROVIDER_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long the provider's discovery document is reused before it is looked up again
+const DISCOVERY_CACHE_TTL: Duration = Duration::from_secs(5 * 60);
+/// Holds the `state` of the login the browser started, so the callback only finishes logins started by the same browser
+const STATE_COOKIE: &str = "wg_quickrs_oidc_state";
+
+#[derive(Error, Debug)]
+pub enum OidcError {
+    #[error("OpenID Connect login is disabled")]
+    Disabled(),
+    #[error("unable to get config: {0}")]
+    Config(#[from] ConfUtilError),
+    #[error("the login is unknown or expired, start it again")]
+    UnknownLogin(),
+    #[error("the login was not started in this browser, start it again")]
+    ForeignLogin(),
+    #[error("the provider refused the login: {0}")]
+    Refused(String),
+    #[error("failed to reach the provider: {0}")]
+    Provider(#[from] reqwest::Error),
+    #[error("the provider's {0} is invalid: {1}")]
+    InvalidProviderResponse(&'static str, String),
+    #[error("the ID token is invalid: {0}")]
+    InvalidIdToken(String),
+    #[error("no role is mapped to the {0} claim of {1}")]
+    NoRole(String, String),
+}
+
+fn oidc_error_response(e: &OidcError) -> HttpResponse {
+    let mut response = match e {
+        OidcError::Disabled() => HttpResponse::NotFound(),
+        OidcError::Config(_) => HttpResponse::InternalServerError(),
+        OidcError::UnknownLogin() | OidcError::ForeignLogin() => HttpResponse::BadRequest(),
+        OidcError::Refused(_) | OidcError::InvalidIdToken(_) => HttpResponse::Unauthorized(),
+        OidcError::Provider(_) | OidcError::InvalidProviderResponse(..) => HttpResponse::BadGateway(),
+        OidcError::NoRole(..) => HttpResponse::Forbidden(),
+    };
+    response.content_type("text/plain; charset=utf-8").body(e.to_string())
+}
+
+/// The parts of the provider's discovery document the authorization code flow needs
+#[derive(Clone, Deserialize)]
+struct ProviderMetadata {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: String,
+}
+
+/// What the callback checks the provider's answer against, keyed by the login's `state`
+struct PendingLogin {
+    nonce: String,
+    code_verifier: String,
+    started_at: Instant,
+}
+
+static PENDING_LOGINS: Lazy<Mutex<HashMap<String, PendingLogin>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+/// The last discovery document, with the issuer it belongs to and when it was looked up
+static DISCOVERY: Lazy<Mutex<Option<(String, ProviderMetadata, Instant)>>> = Lazy::new(|| Mutex::new(None));
+
+fn random_string(len: usize) -> String {
+    rng().sample_iter(&Alphanumeric).take(len).map(char::from).collect()
+}
+
+/// The config, if logins with the provider are possible, tokens are only checked with password auth enabled
+fn enabled_config() -> Result<Config, OidcError> {
+    let config = conf::util::get_config()?;
+    if !config.agent.web.password.enabled || !config.agent.web.oidc.enabled {
+        return Err(OidcError::Disabled());
+    }
+    Ok(config)
+}
+
+fn http_client() -> Result<reqwest::Client, OidcError> {
+    // only installed by the HTTPS server otherwise, the agent may serve plain HTTP
+    if rustls::crypto::CryptoProvider::get_default().is_none() {
+        let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+    }
+    Ok(reqwest::Client::builder().timeout(PROVIDER_TIMEOUT).build()?)
+}
+
+/// Cached for a few minutes, so starting logins does not make a request to the provider each time
+async fn discover(client: &reqwest::Client, oidc: &AgentWebOidc) -> Result<ProviderMetadata, OidcError> {
+    if let Ok(cached) = DISCOVERY.lock()
+        && let Some((issuer, metadata, fetched_at)) = cached.as_ref()
+        && *issuer == oidc.issuer
+        && fetched_at.elapsed() < DISCOVERY_CACHE_TTL
+    {
+        return Ok(metadata.clone());
+    }
+
+    let url = format!("{}{}", oidc.issuer.trim_end_matches('/'), DISCOVERY_PATH);
+    let metadata: ProviderMetadata = client.get(url).send().await?.error_for_status()?.json().await?;
+    if metadata.issuer.trim_end_matches('/') != oidc.issuer.trim_end_matches('/') {
+        return Err(OidcError::InvalidProviderResponse(
+            "discovery document",
+            format!("issuer {} is not the configured {}", metadata.issuer, oidc.issuer),
+        ));
+    }
+    if let Ok(mut cached) = DISCOVERY.lock() {
+        *cached = Some((oidc.issuer.clone(), metadata.clone(), Instant::now()));
+    }
+    Ok(metadata)
+}
+
+fn remember_login(state: String, login: PendingLogin) {
+    let Ok(mut pending) = PENDING_LOGINS.lock() else {
+        return;
+    };
+    pending.retain(|_, login| login.started_at.elapsed() < LOGIN_TIMEOUT);
+    if pending.len() >= MAX_PENDING_LOGINS
+        && let Some(oldest) = pending.iter().min_by_key(|(_, login)| login.started_at).map(|(state, _)| state.clone())
+    {
+        pending.remove(&oldest);
+    }
+    pending.insert(state, login);
+}
+
+/// Each login can only be finished once
+fn take_login(state: &str) -> Option<PendingLogin> {
+    PENDING_LOGINS.lock().ok()?
+        .remove(state)
+        .filter(|login| login.started_at.elapsed() < LOGIN_TIMEOUT)
+}
+
+#[get("/api/oidc")]
+async fn get_oidc() -> impl Responder {
+    match enabled_config() {
+        Ok(_) => HttpResponse::Ok().json(json!({ "enabled": true })),
+        Err(OidcError::Disabled()) => HttpResponse::Ok().json(json!({ "enabled": false })),
+        Err(e) => oidc_error_response(&e),
+    }
+}
+
+fn state_cookie(req: &HttpRequest, state: &str) -> Cookie<'static> {
+    Cookie::build(STATE_COOKIE, state.to_string())
+        .path("/api/oidc")
+        .http_only(true)
+        // sent along when the provider redirects the browser back, but not on requests other sites make
+        .same_site(SameSite::Lax)
+        .secure(req.connection_info().scheme() == "https")
+        .max_age(actix_web::cookie::time::Duration::seconds(LOGIN_TIMEOUT.as_secs() as i64))
+        .finish()
+}
+
+#[get("/api/oidc/login")]
+async fn get_oidc_login(req: HttpRequest) -> impl Responder {
+    // a login counts as failed until it is finished, so a client cannot start them without limit
+    let throttle_keys = [format!("oidc-login:{}", throttle::client_ip(&req))];
+    if let Some(retry_after) = throttle::retry_after(&throttle_keys) {
+        return too_many_requests(retry_after, "Too many unfinished OpenID Connect logins");
+    }
+    match authorization_url().await {
+        Ok((url, state)) => {
+            throttle::record_failure(&throttle_keys);
+            HttpResponse::Found()
+                .insert_header((header::LOCATION, url))
+                .cookie(state_cookie(&req, &state))
+                .finish()
+        }
+        Err(e) => oidc_error_response(&e),
+    }
+}
+
+/// Where the browser signs in with the provider and the login's `state`, PKCE and the nonce tie the answer to this login
+async fn authorization_url() -> Result<(String, String), OidcError> {
+    let config = enabled_config()?;
+    let oidc = &config.agent.web.oidc;
+    let metadata = discover(&http_client()?, oidc).await?;
+
+    let state = random_string(32);
+    let nonce = random_string(32);
+    let code_verifier = random_string(64);
+    let code_challenge = URL_SAFE_NO_PAD.encode(digest(&SHA256, code_verifier.as_bytes()));
+    let scope = oidc.scopes.join(" ");
+    let url = reqwest::Url::parse_with_params(&metadata.authorization_endpoint, [
+        ("response_type", "code"),
+        ("client_id", oidc.client_id.as_str()),
+        ("redirect_uri", oidc.redirect_url.as_str()),
+        ("scope", scope.as_str()),
+        ("state", state.as_str()),
+        ("nonce", nonce.as_str()),
+        ("code_challenge", code_challenge.as_str()),
+        ("code_challenge_method", "S256"),
+    ]).map_err(|e| OidcError::InvalidProviderResponse("authorization endpoint", e.to_string()))?;
+
+    remember_login(state.clone(), PendingLogin { nonce, code_verifier, started_at: Instant::now() });
+    Ok((url.to_string(), state))
+}
+
+#[derive(Deserialize)]
+pub(crate) struct CallbackQuery {
+    code: Option<String>,
+    state: Option<String>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+#[get("/api/oidc/callback")]
+async fn get_oidc_callback(req: HttpRequest, query: web::Query<CallbackQuery>) -> impl Responder {
+    let browser_state = req.cookie(STATE_COOKIE).map(|cookie| cookie.value().to_string());
+    let signed_in = match enabled_config() {
+        Ok(config) => sign_in(&config, browser_state.as_deref(), query.into_inner()).await.map(|signed_in| (config, signed_in)),
+        Err(e) => Err(e),
+    };
+    let mut removal = state_cookie(&req, "");
+    removal.make_removal();
+    let (config, (username, role)) = match signed_in {
+        Ok(signed_in) => signed_in,
+        Err(e) => {
+            log::warn!("Failed OpenID Connect login from {}: {}", throttle::client_ip(&req), e);
+            let mut response = oidc_error_response(&e);
+            let _ = response.add_removal_cookie(&removal);
+            return response;
+        }
+    };
+    throttle::record_success(&[format!("oidc-login:{}", throttle::client_ip(&req))]);
+    log::info!("Signed in {} with OpenID Connect as {}", username, role);
+    match issue_oidc_token(&config, &username, role) {
+        // in the fragment the token is not sent anywhere, the web UI takes it from there
+        Ok(token) => HttpResponse::Found()
+            .insert_header((header::LOCATION, format!("/#token={token}")))
+            .cookie(removal)
+            .finish(),
+        Err(e) => e,
+    }
+}
+
+/// Redeems the provider's authorization code, returns who signed in and the role their claims map to
+async fn sign_in(config: &Config, browser_state: Option<&str>, query: CallbackQuery) -> Result<(String, WebRole), OidcError> {
+    let oidc = &config.agent.web.oidc;
+    let state = query.state.as_deref().ok_or(OidcError::UnknownLogin())?;
+    // otherwise a callback link of a login someone else started would sign this browser in as them
+    if browser_state.is_none_or(|browser_state| verify_slices_are_equal(browser_state.as_bytes(), state.as_bytes()).is_err()) {
+        return Err(OidcError::ForeignLogin());
+    }
+    let login = take_login(state).ok_or(OidcError::UnknownLogin())?;
+    if let Some(error) = query.error {
+        return Err(OidcError::Refused(match query.error_description {
+            Some(description) => format!("{error}: {description}"),
+            None => error,
+        }));
+    }
+    let code = query.code.ok_or_else(|| OidcError::Refused("no authorization code".to_string()))?;
+
+    let client = http_client()?;
+    let metadata = discover(&client, oidc).await?;
+    let response = client.post(&metadata.token_endpoint)
+        .basic_auth(&oidc.client_id, Some(&oidc.client_secret))
+        .form(&[
+            ("grant_type", "authorization_code"),
+            ("code", code.as_str()),
+            ("redirect_uri", oidc.redirect_url.as_str()),
+            ("code_verifier", login.code_verifier.as_str()),
+        ])
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        return Err(OidcError::Refused(format!("token endpoint answered {}: {}", status, response.text().await.unwrap_or_default())));
+    }
+    #[derive(Deserialize)]
+    struct TokenResponse {
+        id_token: String,
+    }
+    let token_response: TokenResponse = response.json().await?;
+
+    let claims = verify_id_token(&client, &metadata, oidc, &token_response.id_token, &login.nonce).await?;
+    let username = [oidc.username_claim.as_str(), "sub"].into_iter()
+        .find_map(|claim| claims.get(claim).and_then(Value::as_str))
+        .ok_or_else(|| OidcError::InvalidIdToken("no sub claim".to_string()))?
+        .to_string();
+    let role = mapped_role(oidc, &claims).ok_or_else(|| OidcError::NoRole(oidc.role_claim.clone(), username.clone()))?;
+    Ok((username, role))
+}
+
+async fn verify_id_token(
+    client: &reqwest::Client,
+    metadata: &ProviderMetadata,
+    oidc: &AgentWebOidc,
+    id_token: &str,
+    nonce: &str,
+) -> Result<Map<String, Value>, OidcError> {
+    let invalid = |e: jsonwebtoken::errors::Error| OidcError::InvalidIdToken(e.to_string());
+    let header = decode_header(id_token).map_err(invalid)?;
+    // symmetric algorithms would be keyed with the client secret, only keys the provider publishes are accepted
+    if matches!(header.alg, Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512) {
+        return Err(OidcError::InvalidIdToken(format!("{:?} signatures are not supported", header.alg)));
+    }
+
+    let jwks: JwkSet = client.get(&metadata.jwks_uri).send().await?.error_for_status()?.json().await?;
+    let jwk = match &header.kid {
+        Some(kid) => jwks.find(kid),
+        None if jwks.keys.len() == 1 => jwks.keys.first(),
+        None => None,
+    }.ok_or_else(|| OidcError::InvalidIdToken("signed with a key the provider does not publish".to_string()))?;
+
+    let mut validation = Validation::new(header.alg);
+    validation.set_audience(&[&oidc.client_id]);
+    validation.set_issuer(&[&metadata.issuer]);
+    validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+    let claims = decode::<Map<String, Value>>(id_token, &DecodingKey::from_jwk(jwk).map_err(invalid)?, &validation)
+        .map_err(invalid)?
+        .claims;
+    if claims.get("nonce").and_then(Value::as_str) != Some(nonce) {
+        return Err(OidcError::InvalidIdToken("the nonce is not the one of this login".to_string()));
+    }
+    Ok(claims)
+}
+
+/// The highest role any value of the role claim maps to
+fn mapped_role(oidc: &AgentWebOidc, claims: &Map<String, Value>) -> Option<WebRole> {
+    let values: Vec<&str> = match claims.get(&oidc.role_claim) {
+        Some(Value::String(value)) => vec![value.as_str()],
+        Some(Value::Array(values)) => values.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    };
+    values.into_iter().filter_map(|value| oidc.role_mapping.get(value)).copied().max()
+}
@@ -2,17 +2,20 @@ use std::net::{IpAddr, SocketAddr};
 use crate::WG_QUICKRS_CONFIG_FOLDER;
 use crate::web::api;
 use crate::web::app;
+use crate::web::oidc;
 use crate::helpers::shell_cmd;
 use crate::wireguard::wg_quick::HookType;
 #[cfg(debug_assertions)]
 use actix_cors::Cors;
 use actix_web::{App, HttpServer, middleware};
+use actix_web::dev::ServerHandle;
 use wg_quickrs_lib::types::config::Config;
 use rustls::{
     ServerConfig,
     pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject},
 };
 use std::path::PathBuf;
+use std::sync::Mutex;
 use thiserror::Error;
 use tokio::try_join;
 
@@ -22,6 +25,25 @@ pub enum ServerError {
     TlsSetupFailed(String),
 }
 
+/// Handles of the running HTTP/HTTPS servers, used to stop them when their settings change
+static SERVER_HANDLES: Mutex<Vec<ServerHandle>> = Mutex::new(Vec::new());
+
+fn register_server(handle: ServerHandle) {
+    if let Ok(mut handles) = SERVER_HANDLES.lock() {
+        handles.push(handle);
+    }
+}
+
+/// Stops the HTTP/HTTPS servers without waiting for open connections, `run_web_server` returns after their hooks ran
+pub(crate) fn stop_web_server() {
+    if let Ok(mut handles) = SERVER_HANDLES.lock() {
+        for handle in handles.drain(..) {
+            // the stop command is sent right away, the returned future only reports its completion
+            drop(handle.stop(false));
+        }
+    }
+}
+
 fn execute_script(script: &str, port: u16, hook_type: HookType) {
     log::debug!("[#] Executing http(s) {:?} hooks", hook_type);
     let script_w_vars = format!("PORT={port}\n{script}");
@@ -45,11 +67,25 @@ pub(crate) async fn run_web_server(config: &Config) -> std::io::Result<()> {
             .wrap(middleware::Compress::default())
             .service(app::web_ui_index)
             .service(api::post_token)
+            .service(oidc::get_oidc)
+            .service(oidc::get_oidc_login)
+            .service(oidc::get_oidc_callback)
             .service(api::get_network_summary)
+            .service(api::get_network_events)
+            .service(api::get_network_peer_conf)
+            .service(api::get_network_peer_connections)
+            .service(api::post_network_peer_private_key_rotate)
+            .service(api::get_network_connection_telemetry)
+            .service(api::post_network_secrets_reveal)
             .service(api::post_network_reserve_address)
             .service(api::get_version)
+            .service(api::get_metrics)
             .service(api::patch_network_config)
             .service(api::post_wireguard_status)
+            .service(api::get_config_history)
+            .service(api::get_config_history_revision)
+            .service(api::get_config_history_diff)
+            .service(api::post_config_history_rollback)
             .service(app::web_ui_dist);
 
         #[cfg(debug_assertions)]
@@ -85,7 +121,9 @@ pub(crate) async fn run_web_server(config: &Config) -> std::io::Result<()> {
             match HttpServer::new(app_factory).bind(bind_addr) {
                 Ok(http_server) => {
                     log::info!("HTTP server listening on http://{}", bind_addr);
-                    http_server.run().await.unwrap_or_else(|e| {
+                    let http_server = http_server.run();
+                    register_server(http_server.handle());
+                    http_server.await.unwrap_or_else(|e| {
                         log::error!("Unable to run the http server: {e}");
                     });
                 }
@@ -134,7 +172,9 @@ pub(crate) async fn run_web_server(config: &Config) -> std::io::Result<()> {
                 match HttpServer::new(app_factory).bind_rustls_0_23(bind_addr, tls_config) {
                     Ok(https_server) => {
                         log::info!("HTTPS server listening on https://{}", bind_addr);
-                        https_server.run().await.unwrap_or_else(|e| {
+                        let https_server = https_server.run();
+                        register_server(https_server.handle());
+                        https_server.await.unwrap_or_else(|e| {
                             log::error!("Unable to run the https server: {e}");
                         });
                     }
@@ -180,13 +220,16 @@ pub(crate) async fn run_web_server(config: &Config) -> std::io::Result<()> {
 }
 
 fn load_tls_config(tls_cert: &PathBuf, tls_key: &PathBuf) -> Result<ServerConfig, ServerError> {
-    rustls::crypto::aws_lc_rs::default_provider()
-        .install_default()
-        .map_err(|_e| {
-            ServerError::TlsSetupFailed(
-                "Failed to install aws-lc-rs default crypto provider".to_string(),
-            )
-        })?;
+    // installed by the first start, the HTTPS server is started again when its settings are reloaded
+    if rustls::crypto::CryptoProvider::get_default().is_none() {
+        rustls::crypto::aws_lc_rs::default_provider()
+            .install_default()
+            .map_err(|_e| {
+                ServerError::TlsSetupFailed(
+                    "Failed to install aws-lc-rs default crypto provider".to_string(),
+                )
+            })?;
+    }
 
     let cert_chain = CertificateDer::pem_file_iter(tls_cert)
         .map_err(|_e| {
@@ -0,0 +1,104 @@
+use actix_web::HttpRequest;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// Failures allowed before a client IP or account is locked out
+const FREE_FAILURES: u32 = 5;
+/// Lockout after the first failure past the free ones, doubled by every further failure
+const BASE_LOCKOUT: Duration = Duration::from_secs(1);
+const MAX_LOCKOUT: Duration = Duration::from_secs(15 * 60);
+/// Failures are forgotten once none happened for this long
+const FAILURE_WINDOW: Duration = Duration::from_secs(60 * 60);
+/// Entries kept at most, the forgotten ones are cleaned up first and then the ones that failed longest ago
+const MAX_TRACKED: usize = 10_000;
+/// Argon2 verifications running at once, the rest are turned away instead of exhausting the CPU
+const MAX_CONCURRENT_VERIFICATIONS: usize = 4;
+
+struct Failures {
+    count: u32,
+    last_failure: Instant,
+    locked_until: Instant,
+}
+
+/// Failed logins keyed by client IP (`ip:<address>`) and by account (`account:<name>`)
+static FAILURES: Lazy<Mutex<HashMap<String, Failures>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+static VERIFICATIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// The address of the TCP connection, behind a reverse proxy the proxy's
+pub(crate) fn client_ip(req: &HttpRequest) -> String {
+    req.peer_addr().map_or_else(|| "unknown".to_string(), |addr| addr.ip().to_string())
+}
+
+/// How long the longest lockout of `keys` still lasts, `None` when none of them is locked out
+pub(crate) fn retry_after(keys: &[String]) -> Option<Duration> {
+    let failures = FAILURES.lock().ok()?;
+    let now = Instant::now();
+    keys.iter()
+        .filter_map(|key| failures.get(key))
+        .map(|failures| failures.locked_until.saturating_duration_since(now))
+        .filter(|remaining| !remaining.is_zero())
+        .max()
+}
+
+pub(crate) fn record_failure(keys: &[String]) {
+    let Ok(mut failures) = FAILURES.lock() else {
+        return;
+    };
+    let now = Instant::now();
+    let new_keys = keys.iter().filter(|key| !failures.contains_key(*key)).count();
+    if failures.len() + new_keys > MAX_TRACKED {
+        failures.retain(|_, failures| now.duration_since(failures.last_failure) < FAILURE_WINDOW);
+    }
+    // clients spreading their attempts over many addresses must not grow the map without limit
+    let excess = (failures.len() + new_keys).saturating_sub(MAX_TRACKED);
+    if excess > 0 {
+        let mut oldest: Vec<(Instant, String)> = failures.iter()
+            .map(|(key, failures)| (failures.last_failure, key.clone()))
+            .collect();
+        oldest.select_nth_unstable(excess - 1);
+        for (_, key) in oldest.into_iter().take(excess) {
+            failures.remove(&key);
+        }
+    }
+    for key in keys {
+        let entry = failures.entry(key.clone()).or_insert(Failures { count: 0, last_failure: now, locked_until: now });
+        if now.duration_since(entry.last_failure) >= FAILURE_WINDOW {
+            entry.count = 0;
+        }
+        entry.count += 1;
+        entry.last_failure = now;
+        if let Some(doublings) = entry.count.checked_sub(FREE_FAILURES + 1) {
+            entry.locked_until = now + BASE_LOCKOUT.saturating_mul(1 << doublings.min(16)).min(MAX_LOCKOUT);
+        }
+    }
+}
+
+pub(crate) fn record_success(keys: &[String]) {
+    if let Ok(mut failures) = FAILURES.lock() {
+        for key in keys {
+            failures.remove(key);
+        }
+    }
+}
+
+/// Frees its slot of the verifications running at once when dropped
+pub(crate) struct VerificationPermit;
+
+impl Drop for VerificationPermit {
+    fn drop(&mut self) {
+        VERIFICATIONS.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// A slot to run one Argon2 verification in, `None` when all of them are taken
+pub(crate) fn verification_permit() -> Option<VerificationPermit> {
+    VERIFICATIONS
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |running| {
+            (running < MAX_CONCURRENT_VERIFICATIONS).then_some(running + 1)
+        })
+        .ok()
+        .map(|_| VerificationPermit)
+}
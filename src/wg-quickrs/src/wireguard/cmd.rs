@@ -1,49 +1,60 @@
 use crate::{conf};
 use once_cell::sync::Lazy;
-use wg_quickrs_lib::helpers::get_peer_wg_config;
-use wg_quickrs_lib::types::config::{Config};
-use wg_quickrs_lib::types::api::{Telemetry, TelemetryData, TelemetryDatum};
+use wg_quickrs_lib::types::config::{AgentVpnTelemetry, Config, TelemetryMode};
+use wg_quickrs_lib::types::api::{Telemetry, TelemetryData, TelemetryDatum, TelemetryGap};
 use wg_quickrs_lib::types::misc::{WireGuardStatus};
 use std::collections::{BTreeMap, VecDeque};
-use std::io::Write;
-use std::path::PathBuf;
-use std::sync::{Arc, RwLock};
+use std::sync::RwLock;
 use std::time::Duration;
-use std::time::{SystemTime, UNIX_EPOCH};
-use chrono::Utc;
-use tempfile::NamedTempFile;
+use chrono::{NaiveDateTime, TimeDelta, Utc};
 use thiserror::Error;
 use tokio::signal::unix::{signal, SignalKind};
 use wg_quickrs_lib::types::network::ConnectionId;
-use crate::helpers::{shell_cmd, ShellError};
-use crate::wireguard::wg_quick;
-
-const TELEMETRY_CAPACITY: usize = 21;
-const TELEMETRY_INTERVAL: u64 = 1000;
-type TelemetryType = Lazy<Arc<RwLock<VecDeque<TelemetryData>>>>;
-static TELEMETRY: TelemetryType =
-    Lazy::new(|| Arc::new(RwLock::new(VecDeque::with_capacity(TELEMETRY_CAPACITY))));
-
-static LAST_TELEMETRY_QUERY_TS: Lazy<Arc<RwLock<u64>>> = Lazy::new(|| Arc::new(RwLock::new(0)));
-
-fn update_timestamp(ts: &Arc<RwLock<u64>>) {
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
-    let mut ts = ts.write().unwrap();
-    *ts = now;
+use crate::helpers::ShellError;
+use crate::wireguard::wg_control::{WgControl, WgControlError};
+use crate::wireguard::{history, wg_quick};
+
+/// Samples served with the network summary
+#[derive(Default)]
+struct TelemetryBuffer {
+    data: VecDeque<TelemetryData>,
+    gaps: Vec<TelemetryGap>,
+    last_sample: Option<NaiveDateTime>,
+    last_query: Option<NaiveDateTime>,
+    /// the tunnel went down since the last sample, so its counters started over
+    restarted: bool,
 }
 
-fn get_since_timestamp(ts: &Arc<RwLock<u64>>) -> u64 {
-    let start = *ts.read().unwrap();
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
-    now.saturating_sub(start) * 1000
+impl TelemetryBuffer {
+    fn push(&mut self, datum: BTreeMap<ConnectionId, TelemetryDatum>, settings: &AgentVpnTelemetry) {
+        let now = Utc::now().naive_utc();
+        // anything longer than a missed sample is reported instead of being averaged over
+        if let Some(last) = self.last_sample
+            && (self.restarted || now - last > TimeDelta::milliseconds(settings.interval as i64 * 2))
+        {
+            self.gaps.push(TelemetryGap { from: last, to: now });
+        }
+        self.restarted = false;
+        self.data.push_back(TelemetryData { datum, timestamp: now });
+        self.last_sample = Some(now);
+        self.prune(settings);
+    }
+
+    fn prune(&mut self, settings: &AgentVpnTelemetry) {
+        let cutoff = Utc::now().naive_utc()
+            - TimeDelta::seconds(settings.retention as i64)
+            - TimeDelta::milliseconds(settings.interval as i64 / 2);
+        while self.data.len() > settings.capacity()
+            || self.data.front().is_some_and(|data| data.timestamp < cutoff)
+        {
+            self.data.pop_front();
+        }
+        self.gaps.retain(|gap| gap.to >= cutoff);
+    }
 }
 
+static TELEMETRY: Lazy<RwLock<TelemetryBuffer>> = Lazy::new(|| RwLock::new(TelemetryBuffer::default()));
+
 #[derive(Error, Debug)]
 pub enum WireGuardCommandError {
     #[error("failed to acquire lock: {0}")]
@@ -52,12 +63,12 @@ pub enum WireGuardCommandError {
     InterfaceMissing,
     #[error("{0}")]
     ShellError(#[from] ShellError),
-    #[error("failed to write file at {0} failed: {1}")]
-    FileWriteError(PathBuf, std::io::Error),
-    #[error("failed to sync WireGuard interface")]
-    InterfaceSyncFailed(),
+    #[error("{0}")]
+    WgControlError(#[from] WgControlError),
     #[error("tunnel operation failed: {0}")]
     TunnelError(#[from] wg_quick::TunnelError),
+    #[error("{0}")]
+    ConfUtilError(#[from] conf::util::ConfUtilError),
 }
 
 static WG_TUNNEL_MANAGER: Lazy<RwLock<wg_quick::TunnelManager>> = Lazy::new(|| RwLock::new(wg_quick::TunnelManager::new(Default::default())));
@@ -84,13 +95,19 @@ pub(crate) async fn run_vpn_server(
 
         let mut signal_terminate = signal(SignalKind::terminate()).unwrap();
         let mut signal_interrupt = signal(SignalKind::interrupt()).unwrap();
-        let mut ticker = tokio::time::interval(Duration::from_millis(TELEMETRY_INTERVAL));
+        let mut tick = loop_tick(config);
+        let mut ticker = tokio::time::interval(Duration::from_millis(tick));
 
         tokio::select! {
             _ = async {
                 loop {
                     ticker.tick().await;
-                    run_loop();
+                    // a changed telemetry interval is picked up without restarting the tunnel
+                    if let Ok(config) = conf::util::get_config() && loop_tick(&config) != tick {
+                        tick = loop_tick(&config);
+                        ticker = tokio::time::interval(Duration::from_millis(tick));
+                    }
+                    run_loop(tick);
                 }
             } => {},
             _ = signal_terminate.recv() => log::info!("Received SIGTERM"),
@@ -103,7 +120,12 @@ pub(crate) async fn run_vpn_server(
         .await
 }
 
-fn run_loop() {
+/// The persistent history still needs its samples when the in-memory ones are far apart
+fn loop_tick(config: &Config) -> u64 {
+    config.agent.vpn.telemetry.interval.min(history::SAMPLE_INTERVAL as u64 * 1000)
+}
+
+fn run_loop(tick: u64) {
     match WG_STATUS.read() {
         Ok(status) => {
             if status.clone() != WireGuardStatus::UP {
@@ -116,12 +138,6 @@ fn run_loop() {
         }
     }
 
-    if get_since_timestamp(&LAST_TELEMETRY_QUERY_TS)
-        > TELEMETRY_INTERVAL * TELEMETRY_CAPACITY as u64
-    {
-        return;
-    }
-
     let config = match conf::util::get_config() {
         Ok(config) => config,
         Err(e) => {
@@ -129,37 +145,64 @@ fn run_loop() {
             return;
         }
     };
+    let settings = &config.agent.vpn.telemetry;
+
+    let record_telemetry = match TELEMETRY.read() {
+        Ok(buf) => {
+            let now = Utc::now().naive_utc();
+            let collecting = match settings.mode {
+                TelemetryMode::Always => true,
+                TelemetryMode::OnDemand => buf.last_query
+                    .is_some_and(|last| now - last <= TimeDelta::seconds(settings.retention as i64)),
+                TelemetryMode::Off => false,
+            };
+            // ticks can come more often than the interval, half a tick of slack absorbs the jitter
+            collecting && buf.last_sample.is_none_or(|last| {
+                now - last + TimeDelta::milliseconds(tick as i64 / 2) >= TimeDelta::milliseconds(settings.interval as i64)
+            })
+        }
+        Err(e) => {
+            log::error!("{}", WireGuardCommandError::MutexLockFailed(e.to_string()));
+            return;
+        }
+    };
+    // the history is recorded even when nobody is looking at the UI
+    let record_history = settings.history.enabled && history::sample_due();
+    if !record_telemetry && !record_history {
+        return;
+    }
 
     match show_dump(&config) {
         Ok(telemetry) => {
-            let mut buf = TELEMETRY.write().unwrap();
-            if buf.len() == TELEMETRY_CAPACITY {
-                buf.pop_front();
+            if record_history && let Err(e) = history::record(&telemetry, false) {
+                log::error!("Failed to record telemetry history => {}", e);
+            }
+            if record_telemetry {
+                TELEMETRY.write().unwrap().push(telemetry, settings);
             }
-            buf.push_back(TelemetryData {
-                datum: telemetry,
-                timestamp: Utc::now().naive_utc(),
-            });
         }
         Err(e) => log::error!("Failed to get telemetry data => {}", e),
     }
 }
 
 pub(crate) fn get_telemetry() -> Result<Option<Telemetry>, WireGuardCommandError> {
-    if get_since_timestamp(&LAST_TELEMETRY_QUERY_TS)
-        > TELEMETRY_INTERVAL * TELEMETRY_CAPACITY as u64
-    {
-        *TELEMETRY.write().unwrap() = VecDeque::with_capacity(TELEMETRY_CAPACITY);
-    }
-    update_timestamp(&LAST_TELEMETRY_QUERY_TS);
-
-    match TELEMETRY.read() {
-        Ok(buf) => Ok(Some(Telemetry {
-            max_len: TELEMETRY_CAPACITY as u8,
-            data: buf.iter().cloned().collect(),
-        })),
-        Err(e) => Err(WireGuardCommandError::MutexLockFailed(e.to_string())),
+    let config = conf::util::get_config()?;
+    let settings = &config.agent.vpn.telemetry;
+    if settings.mode == TelemetryMode::Off {
+        return Ok(None);
     }
+
+    let mut buf = TELEMETRY
+        .write()
+        .map_err(|e| WireGuardCommandError::MutexLockFailed(e.to_string()))?;
+    buf.last_query = Some(Utc::now().naive_utc());
+    buf.prune(settings);
+
+    Ok(Some(Telemetry {
+        max_len: settings.capacity() as u32,
+        data: buf.data.iter().cloned().collect(),
+        gaps: buf.gaps.clone(),
+    }))
 }
 
 pub(crate) fn status_tunnel() -> Result<WireGuardStatus, WireGuardCommandError> {
@@ -169,6 +212,14 @@ pub(crate) fn status_tunnel() -> Result<WireGuardStatus, WireGuardCommandError>
     Ok(wg_status.clone())
 }
 
+/// Reads the counters straight from the device, regardless of whether the UI is polling
+pub(crate) fn get_device_telemetry(config: &Config) -> Result<Option<BTreeMap<ConnectionId, TelemetryDatum>>, WireGuardCommandError> {
+    if status_tunnel()? != WireGuardStatus::UP {
+        return Ok(None);
+    }
+    show_dump(config).map(Some)
+}
+
 fn show_dump(config: &Config) -> Result<BTreeMap<ConnectionId, TelemetryDatum>, WireGuardCommandError> {
     let tunnel_manager = WG_TUNNEL_MANAGER
         .read()
@@ -176,39 +227,29 @@ fn show_dump(config: &Config) -> Result<BTreeMap<ConnectionId, TelemetryDatum>,
 
     let real_interface = tunnel_manager.real_interface.as_ref().ok_or(WireGuardCommandError::InterfaceMissing)?;
 
-    let wg = config.agent.vpn.wg.to_str().unwrap();
-    let output = shell_cmd(&[wg, "show", real_interface, "dump"])?;
+    let device = WgControl::new(config, real_interface).get_device()?;
     let mut telemetry = BTreeMap::<ConnectionId, TelemetryDatum>::new();
 
-    let dump = String::from_utf8_lossy(&output.stdout);
-    for line in dump.trim().lines().skip(1) {
-        let parts: Vec<&str> = line.split('\t').collect();
-        if parts.len() < 8 {
-            continue;
-        }
-        let public_key = parts[0];
-
+    for peer in device.peers {
         for (peer_id, peer_details) in config.network.peers.clone() {
-            if wg_quickrs_lib::helpers::wg_public_key_from_private_key(&peer_details.private_key).to_base64() != public_key
+            if wg_quickrs_lib::helpers::get_peer_public_key(&peer_details) != Some(peer.public_key)
             {
                 continue;
             }
 
-            let transfer_rx = parts[5].parse::<u64>().unwrap_or(0);
-            let transfer_tx = parts[6].parse::<u64>().unwrap_or(0);
             let connection_id =
                 wg_quickrs_lib::helpers::get_connection_id(config.network.this_peer, peer_id);
 
             let (transfer_a_to_b, transfer_b_to_a) = if connection_id.a == config.network.this_peer {
-                (transfer_tx, transfer_rx)
+                (peer.tx_bytes, peer.rx_bytes)
             } else {
-                (transfer_rx, transfer_tx)
+                (peer.rx_bytes, peer.tx_bytes)
             };
 
             telemetry.insert(
                 connection_id.clone(),
                 TelemetryDatum {
-                    latest_handshake_at: parts[4].parse::<u64>().unwrap_or(0),
+                    latest_handshake_at: peer.last_handshake,
                     transfer_a_to_b,
                     transfer_b_to_a,
                 },
@@ -226,25 +267,32 @@ pub(crate) fn sync_conf(config: &Config) -> Result<(), WireGuardCommandError> {
 
     tunnel_manager.config = Some(config.clone());
 
-    let wg_conf_stripped = get_peer_wg_config(&config.network, &config.network.this_peer, true)
-        .map_err(|e| WireGuardCommandError::MutexLockFailed(e.to_string()))?;
-
-    let mut temp = NamedTempFile::new()
-        .map_err(|e| WireGuardCommandError::MutexLockFailed(e.to_string()))?;
-
-    temp.write_all(wg_conf_stripped.as_ref())
-        .map_err(|e| WireGuardCommandError::FileWriteError(PathBuf::from(temp.path()), e))?;
-
-    let temp_path = temp.path().to_owned();
-    let temp_path_str = temp_path.to_str().unwrap();
-
-    let wg = config.agent.vpn.wg.to_str().unwrap();
-    let _ = shell_cmd(&[wg, "syncconf", tunnel_manager.real_interface.as_ref().unwrap(), temp_path_str])
-        .map_err(|_| WireGuardCommandError::InterfaceSyncFailed())?;
+    let real_interface = tunnel_manager.real_interface.as_ref().ok_or(WireGuardCommandError::InterfaceMissing)?;
+    WgControl::new(config, real_interface).sync_config(config)?;
     Ok(())
 }
 
+/// Records what the tunnel moved since the last sample before its counters are gone
+fn flush_telemetry_history() {
+    let is_up = WG_STATUS.read().map(|status| *status == WireGuardStatus::UP).unwrap_or(false);
+    if is_up
+        && let Ok(config) = conf::util::get_config()
+        && config.agent.vpn.telemetry.history.enabled
+    {
+        match show_dump(&config) {
+            Ok(telemetry) => {
+                if let Err(e) = history::record(&telemetry, true) {
+                    log::error!("Failed to record telemetry history => {}", e);
+                }
+            }
+            Err(e) => log::error!("Failed to get telemetry data => {}", e),
+        }
+    }
+    history::reset_counters();
+}
+
 pub(crate) fn disable_tunnel() -> Result<(), WireGuardCommandError> {
+    flush_telemetry_history();
     *WG_STATUS
         .write()
         .map_err(|e| WireGuardCommandError::MutexLockFailed(e.to_string()))? =
@@ -260,7 +308,9 @@ pub(crate) fn disable_tunnel() -> Result<(), WireGuardCommandError> {
             .map_err(|e| WireGuardCommandError::MutexLockFailed(e.to_string()))? =
             WireGuardStatus::DOWN;
 
-        *TELEMETRY.write().unwrap() = VecDeque::with_capacity(TELEMETRY_CAPACITY);
+        if let Ok(mut buf) = TELEMETRY.write() {
+            buf.restarted = true;
+        }
 
         Ok(())
 }
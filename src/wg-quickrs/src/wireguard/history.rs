@@ -0,0 +1,255 @@
+use crate::WG_QUICKRS_CONFIG_FOLDER;
+use chrono::{Duration, NaiveDateTime, Utc};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tempfile::NamedTempFile;
+use thiserror::Error;
+use wg_quickrs_lib::helpers::{rollup_telemetry, telemetry_bucket_start};
+use wg_quickrs_lib::types::api::{TelemetryDatum, TelemetryHistory, TelemetryHistoryPoint, TelemetryResolution};
+use wg_quickrs_lib::types::network::ConnectionId;
+
+const HISTORY_FOLDER: &str = "telemetry";
+/// seconds between two raw samples written to disk
+pub(crate) const SAMPLE_INTERVAL: i64 = 60;
+const RAW_RETENTION_DAYS: i64 = 2;
+const HOURLY_RETENTION_DAYS: i64 = 90;
+
+#[derive(Error, Debug)]
+pub enum HistoryError {
+    #[error("telemetry history io error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("telemetry history is corrupted: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("failed to acquire lock: {0}")]
+    MutexLockFailed(String),
+}
+
+pub type HistoryResult<T> = Result<T, HistoryError>;
+
+/// One line of a history file
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Record {
+    connection_id: ConnectionId,
+    #[serde(flatten)]
+    point: TelemetryHistoryPoint,
+}
+
+#[derive(Default)]
+struct HistoryState {
+    last_sample: Option<NaiveDateTime>,
+    /// transfer counters at the last sample, the device reports totals since the peer was added
+    counters: BTreeMap<ConnectionId, (u64, u64)>,
+}
+
+static HISTORY_STATE: Lazy<Mutex<HistoryState>> = Lazy::new(|| Mutex::new(HistoryState::default()));
+
+fn tier_path(resolution: TelemetryResolution) -> PathBuf {
+    WG_QUICKRS_CONFIG_FOLDER.get().unwrap()
+        .join(HISTORY_FOLDER)
+        .join(format!("{}.jsonl", resolution))
+}
+
+fn read_tier(resolution: TelemetryResolution) -> HistoryResult<Vec<Record>> {
+    let file = match File::open(tier_path(resolution)) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut records = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        // a crash while appending can leave a partial last line behind
+        match serde_json::from_str(&line) {
+            Ok(record) => records.push(record),
+            Err(e) => log::warn!("Skipping unreadable line in {} telemetry history: {}", resolution, e),
+        }
+    }
+    Ok(records)
+}
+
+fn append_tier(resolution: TelemetryResolution, records: &[Record]) -> HistoryResult<()> {
+    if records.is_empty() {
+        return Ok(());
+    }
+    let path = tier_path(resolution);
+    fs::create_dir_all(path.parent().unwrap())?;
+
+    let mut buf = String::new();
+    for record in records {
+        buf.push_str(&serde_json::to_string(record)?);
+        buf.push('\n');
+    }
+    OpenOptions::new().create(true).append(true).open(path)?.write_all(buf.as_bytes())?;
+    Ok(())
+}
+
+fn rewrite_tier(resolution: TelemetryResolution, records: &[Record]) -> HistoryResult<()> {
+    let path = tier_path(resolution);
+    let folder = path.parent().unwrap();
+    fs::create_dir_all(folder)?;
+
+    let mut temp_file = NamedTempFile::new_in(folder)?;
+    for record in records {
+        writeln!(temp_file, "{}", serde_json::to_string(record)?)?;
+    }
+    temp_file.persist(&path).map_err(|e| e.error)?;
+    Ok(())
+}
+
+/// End of the last bucket in a tier, everything after it is only available in finer tiers
+fn covered_until(records: &[Record], resolution: TelemetryResolution) -> Option<NaiveDateTime> {
+    let bucket = Duration::seconds(resolution.bucket_secs().unwrap_or(0));
+    records.iter().map(|r| r.point.timestamp + bucket).max()
+}
+
+fn rollup_records(records: &[&Record], resolution: TelemetryResolution) -> Vec<Record> {
+    let mut by_connection = BTreeMap::<ConnectionId, Vec<TelemetryHistoryPoint>>::new();
+    for record in records {
+        by_connection.entry(record.connection_id.clone()).or_default().push(record.point.clone());
+    }
+    by_connection.into_iter()
+        .flat_map(|(connection_id, points)| {
+            rollup_telemetry(&points, resolution).into_iter()
+                .map(move |point| Record { connection_id: connection_id.clone(), point })
+        })
+        .collect()
+}
+
+/// Rolls the complete buckets of `from` that are not yet in `to` up into `to`
+fn rollup_tier(from: TelemetryResolution, to: TelemetryResolution, now: &NaiveDateTime) -> HistoryResult<()> {
+    let start = covered_until(&read_tier(to)?, to);
+    let end = telemetry_bucket_start(now, to);
+
+    let source = read_tier(from)?;
+    let pending: Vec<&Record> = source.iter()
+        .filter(|r| start.is_none_or(|start| r.point.timestamp >= start) && r.point.timestamp < end)
+        .collect();
+    append_tier(to, &rollup_records(&pending, to))
+}
+
+fn prune_tier(resolution: TelemetryResolution, keep_after: NaiveDateTime) -> HistoryResult<()> {
+    let records = read_tier(resolution)?;
+    let kept: Vec<Record> = records.iter().filter(|r| r.point.timestamp >= keep_after).cloned().collect();
+    if kept.len() != records.len() {
+        rewrite_tier(resolution, &kept)?;
+    }
+    Ok(())
+}
+
+fn compact(now: &NaiveDateTime) -> HistoryResult<()> {
+    rollup_tier(TelemetryResolution::Raw, TelemetryResolution::Hourly, now)?;
+    rollup_tier(TelemetryResolution::Hourly, TelemetryResolution::Daily, now)?;
+    prune_tier(TelemetryResolution::Raw, *now - Duration::days(RAW_RETENTION_DAYS))?;
+    prune_tier(TelemetryResolution::Hourly, *now - Duration::days(HOURLY_RETENTION_DAYS))?;
+    Ok(())
+}
+
+pub(crate) fn sample_due() -> bool {
+    let now = Utc::now().naive_utc();
+    match HISTORY_STATE.lock() {
+        Ok(state) => state.last_sample.is_none_or(|last| now - last >= Duration::seconds(SAMPLE_INTERVAL)),
+        Err(_) => false,
+    }
+}
+
+/// Appends the transfer since the previous sample of every connection, `force` skips the sample interval
+pub(crate) fn record(telemetry: &BTreeMap<ConnectionId, TelemetryDatum>, force: bool) -> HistoryResult<()> {
+    let mut state = HISTORY_STATE
+        .lock()
+        .map_err(|e| HistoryError::MutexLockFailed(e.to_string()))?;
+    let now = Utc::now().naive_utc();
+    if !force && state.last_sample.is_some_and(|last| now - last < Duration::seconds(SAMPLE_INTERVAL)) {
+        return Ok(());
+    }
+
+    let mut records = Vec::new();
+    for (connection_id, datum) in telemetry {
+        let (last_a_to_b, last_b_to_a) = state.counters.get(connection_id).copied().unwrap_or((0, 0));
+        // counters start over when the tunnel or the peer is recreated
+        let transfer_a_to_b = datum.transfer_a_to_b.checked_sub(last_a_to_b).unwrap_or(datum.transfer_a_to_b);
+        let transfer_b_to_a = datum.transfer_b_to_a.checked_sub(last_b_to_a).unwrap_or(datum.transfer_b_to_a);
+        state.counters.insert(connection_id.clone(), (datum.transfer_a_to_b, datum.transfer_b_to_a));
+
+        records.push(Record {
+            connection_id: connection_id.clone(),
+            point: TelemetryHistoryPoint {
+                timestamp: now,
+                latest_handshake_at: datum.latest_handshake_at,
+                transfer_a_to_b,
+                transfer_b_to_a,
+            },
+        });
+    }
+    append_tier(TelemetryResolution::Raw, &records)?;
+
+    let crossed_hour = state.last_sample.is_none_or(|last| {
+        telemetry_bucket_start(&last, TelemetryResolution::Hourly) != telemetry_bucket_start(&now, TelemetryResolution::Hourly)
+    });
+    state.last_sample = Some(now);
+    if crossed_hour {
+        compact(&now)?;
+    }
+    Ok(())
+}
+
+/// Forgets the last seen counters, the next sample counts everything the new tunnel reports
+pub(crate) fn reset_counters() {
+    if let Ok(mut state) = HISTORY_STATE.lock() {
+        state.counters.clear();
+    }
+}
+
+/// Picks the finest resolution that still holds data at `from`
+pub(crate) fn default_resolution(from: &NaiveDateTime) -> TelemetryResolution {
+    let now = Utc::now().naive_utc();
+    if *from >= now - Duration::days(RAW_RETENTION_DAYS) {
+        TelemetryResolution::Raw
+    } else if *from >= now - Duration::days(HOURLY_RETENTION_DAYS) {
+        TelemetryResolution::Hourly
+    } else {
+        TelemetryResolution::Daily
+    }
+}
+
+pub(crate) fn query(
+    connection_id: &ConnectionId,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+    resolution: TelemetryResolution,
+) -> HistoryResult<TelemetryHistory> {
+    // the requested tier, then whatever finer tiers hold that was not rolled up yet
+    let mut points = Vec::new();
+    let mut cursor: Option<NaiveDateTime> = None;
+    for tier in [TelemetryResolution::Daily, TelemetryResolution::Hourly, TelemetryResolution::Raw] {
+        if tier > resolution {
+            continue;
+        }
+        let records = read_tier(tier)?;
+        points.extend(records.iter()
+            .filter(|r| &r.connection_id == connection_id)
+            .filter(|r| cursor.is_none_or(|cursor| r.point.timestamp >= cursor))
+            .filter(|r| r.point.timestamp >= from && r.point.timestamp < to)
+            .map(|r| r.point.clone()));
+        cursor = cursor.max(covered_until(&records, tier));
+    }
+    let points = rollup_telemetry(&points, resolution);
+
+    Ok(TelemetryHistory {
+        connection_id: connection_id.clone(),
+        resolution,
+        from,
+        to,
+        transfer_a_to_b: points.iter().map(|p| p.transfer_a_to_b).sum(),
+        transfer_b_to_a: points.iter().map(|p| p.transfer_b_to_a).sum(),
+        points,
+    })
+}
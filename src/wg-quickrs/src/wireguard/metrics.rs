@@ -0,0 +1,117 @@
+use chrono::Utc;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use uuid::Uuid;
+use wg_quickrs_lib::types::api::TelemetryDatum;
+use wg_quickrs_lib::types::config::Config;
+use wg_quickrs_lib::types::misc::WireGuardStatus;
+use wg_quickrs_lib::types::network::ConnectionId;
+
+pub(crate) const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Prometheus text exposition of a single metric family
+struct Family<'a> {
+    out: &'a mut String,
+    name: &'static str,
+}
+
+impl<'a> Family<'a> {
+    fn new(out: &'a mut String, name: &'static str, kind: &str, help: &str) -> Self {
+        let _ = writeln!(out, "# HELP {name} {help}");
+        let _ = writeln!(out, "# TYPE {name} {kind}");
+        Family { out, name }
+    }
+
+    fn sample(&mut self, labels: &[(&str, &str)], value: impl std::fmt::Display) {
+        let _ = write!(self.out, "{}", self.name);
+        if !labels.is_empty() {
+            let labels: Vec<String> = labels.iter()
+                .map(|(key, value)| format!("{key}=\"{}\"", escape_label(value)))
+                .collect();
+            let _ = write!(self.out, "{{{}}}", labels.join(","));
+        }
+        let _ = writeln!(self.out, " {value}");
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn peer_name(config: &Config, id: &Uuid) -> String {
+    config.network.peers.get(id).map(|peer| peer.name.clone()).unwrap_or_default()
+}
+
+/// Renders the current state of the agent, `telemetry` is `None` while the tunnel is not up
+pub(crate) fn render(
+    config: &Config,
+    status: &WireGuardStatus,
+    telemetry: Option<&BTreeMap<ConnectionId, TelemetryDatum>>,
+) -> String {
+    let mut out = String::new();
+    let network = &config.network;
+
+    let mut family = Family::new(&mut out, "wg_quickrs_wireguard_status", "gauge", "State of the WireGuard tunnel, 1 for the current state");
+    for (name, value) in [("unknown", WireGuardStatus::UNKNOWN), ("down", WireGuardStatus::DOWN), ("up", WireGuardStatus::UP)] {
+        family.sample(&[("status", name)], u8::from(*status == value));
+    }
+
+    let now = Utc::now();
+    let reservations = network.reservations.values().filter(|r| r.valid_until > now).count();
+    Family::new(&mut out, "wg_quickrs_reservations", "gauge", "Number of unexpired address reservations")
+        .sample(&[], reservations);
+
+    // a peer is configured on this agent's interface when its connection to this peer is enabled
+    let mut family = Family::new(&mut out, "wg_quickrs_peer_enabled", "gauge", "Whether the peer is configured on this agent's WireGuard interface");
+    for (peer_id, peer) in &network.peers {
+        let enabled = *peer_id == network.this_peer || network.connections.iter()
+            .any(|(id, connection)| connection.enabled && id.contains(&network.this_peer) && id.contains(peer_id));
+        family.sample(&[("peer_id", &peer_id.to_string()), ("peer_name", &peer.name)], u8::from(enabled));
+    }
+
+    let connection_labels: BTreeMap<&ConnectionId, [(&str, String); 5]> = network.connections.keys()
+        .map(|id| (id, [
+            ("connection_id", format!("{}*{}", id.a, id.b)),
+            ("peer_a_id", id.a.to_string()),
+            ("peer_a_name", peer_name(config, &id.a)),
+            ("peer_b_id", id.b.to_string()),
+            ("peer_b_name", peer_name(config, &id.b)),
+        ]))
+        .collect();
+    let labels_of = |id: &ConnectionId| -> Option<Vec<(&str, &str)>> {
+        connection_labels.get(id).map(|labels| labels.iter().map(|(k, v)| (*k, v.as_str())).collect())
+    };
+
+    let mut family = Family::new(&mut out, "wg_quickrs_connection_enabled", "gauge", "Whether the connection is enabled in the configuration");
+    for (id, connection) in &network.connections {
+        if let Some(labels) = labels_of(id) {
+            family.sample(&labels, u8::from(connection.enabled));
+        }
+    }
+
+    let telemetry = telemetry.cloned().unwrap_or_default();
+    let mut family = Family::new(&mut out, "wg_quickrs_connection_transfer_a_to_b_bytes_total", "counter", "Bytes sent from peer a to peer b since the tunnel came up");
+    for (id, datum) in &telemetry {
+        if let Some(labels) = labels_of(id) {
+            family.sample(&labels, datum.transfer_a_to_b);
+        }
+    }
+    let mut family = Family::new(&mut out, "wg_quickrs_connection_transfer_b_to_a_bytes_total", "counter", "Bytes sent from peer b to peer a since the tunnel came up");
+    for (id, datum) in &telemetry {
+        if let Some(labels) = labels_of(id) {
+            family.sample(&labels, datum.transfer_b_to_a);
+        }
+    }
+    // connections without a handshake yet are left out rather than reported as decades old
+    let mut family = Family::new(&mut out, "wg_quickrs_connection_latest_handshake_age_seconds", "gauge", "Seconds since the latest handshake of the connection");
+    for (id, datum) in &telemetry {
+        if datum.latest_handshake_at == 0 {
+            continue;
+        }
+        if let Some(labels) = labels_of(id) {
+            family.sample(&labels, (now.timestamp() as u64).saturating_sub(datum.latest_handshake_at));
+        }
+    }
+
+    out
+}
@@ -1,5 +1,9 @@
 pub(crate) mod cmd;
+pub(crate) mod history;
+pub(crate) mod metrics;
+mod netlink_linux;
 pub(crate) mod respond;
+pub(crate) mod wg_control;
 pub(crate) mod wg_quick;
 mod wg_quick_darwin;
 mod wg_quick_linux;
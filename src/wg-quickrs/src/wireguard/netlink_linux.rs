@@ -0,0 +1,415 @@
+#![cfg(target_os = "linux")]
+use std::fmt::Debug;
+use std::net::IpAddr;
+use ipnet::IpNet;
+use netlink_packet_core::{NetlinkDeserializable, NetlinkHeader, NetlinkMessage, NetlinkPayload, NetlinkSerializable, NLM_F_ACK, NLM_F_CREATE, NLM_F_DUMP, NLM_F_EXCL, NLM_F_REQUEST};
+use netlink_packet_route::address::{AddressAttribute, AddressMessage};
+use netlink_packet_route::link::{InfoKind, LinkAttribute, LinkFlags, LinkInfo, LinkMessage};
+use netlink_packet_route::route::{RouteAddress, RouteAttribute, RouteHeader, RouteMessage, RouteMetric, RouteProtocol, RouteScope, RouteType};
+use netlink_packet_route::rule::{RuleAction, RuleAttribute, RuleFlags, RuleMessage};
+use netlink_packet_route::{AddressFamily, RouteNetlinkMessage};
+use netlink_packet_generic::ctrl::nlas::GenlCtrlAttrs;
+use netlink_packet_generic::ctrl::{GenlCtrl, GenlCtrlCmd};
+use netlink_packet_generic::{GenlFamily, GenlMessage};
+use netlink_packet_wireguard::{WireguardAttribute, WireguardCmd, WireguardMessage};
+use netlink_sys::protocols::{NETLINK_GENERIC, NETLINK_ROUTE};
+use netlink_sys::{Socket, SocketAddr};
+use thiserror::Error;
+
+pub const RT_TABLE_MAIN: u32 = RouteHeader::RT_TABLE_MAIN as u32;
+
+#[derive(Error, Debug)]
+pub enum NetlinkError {
+    #[error("netlink socket error: {0}")]
+    Socket(std::io::Error),
+    #[error("netlink request failed: {0}")]
+    Request(std::io::Error),
+    #[error("netlink reply could not be decoded: {0}")]
+    Decode(String),
+    #[error("link not found: {0}")]
+    LinkNotFound(String),
+    #[error("generic netlink family not found: {0} (is the kernel module loaded?)")]
+    FamilyNotFound(String),
+}
+
+pub type NetlinkResult<T> = Result<T, NetlinkError>;
+
+/// Sends a request on a netlink socket and collects the replies until the kernel acks it or ends the dump
+pub(crate) fn request<M>(socket: &Socket, seq: u32, message: M, flags: u16) -> NetlinkResult<Vec<M>>
+where
+    M: NetlinkSerializable + NetlinkDeserializable + Debug,
+{
+    let mut header = NetlinkHeader::default();
+    header.flags = NLM_F_REQUEST | flags;
+    header.sequence_number = seq;
+    let mut packet = NetlinkMessage::new(header, NetlinkPayload::InnerMessage(message));
+    packet.finalize();
+    let mut buf = vec![0; packet.buffer_len()];
+    packet.serialize(&mut buf);
+    socket.send(&buf, 0).map_err(NetlinkError::Socket)?;
+
+    let mut replies = Vec::new();
+    loop {
+        let (data, _) = socket.recv_from_full().map_err(NetlinkError::Socket)?;
+        let mut offset = 0;
+        while offset < data.len() {
+            let reply = NetlinkMessage::<M>::deserialize(&data[offset..])
+                .map_err(|e| NetlinkError::Decode(e.to_string()))?;
+            let length = reply.header.length as usize;
+            if reply.header.sequence_number == seq {
+                match reply.payload {
+                    NetlinkPayload::Done(_) => return Ok(replies),
+                    NetlinkPayload::Error(e) if e.code.is_none() => return Ok(replies),
+                    NetlinkPayload::Error(e) => return Err(NetlinkError::Request(e.to_io())),
+                    NetlinkPayload::InnerMessage(m) => replies.push(m),
+                    _ => {}
+                }
+            }
+            if length == 0 {
+                break;
+            }
+            offset += length;
+        }
+        // a reply to a non-dump request without NLM_F_ACK is a single message
+        if flags & (NLM_F_ACK | NLM_F_DUMP) == 0 && !replies.is_empty() {
+            return Ok(replies);
+        }
+    }
+}
+
+pub fn family_of(addr: &IpAddr) -> AddressFamily {
+    if addr.is_ipv6() { AddressFamily::Inet6 } else { AddressFamily::Inet }
+}
+
+/// Synchronous rtnetlink connection, one request in flight at a time
+pub struct RtNetlink {
+    socket: Socket,
+    seq: u32,
+}
+
+impl RtNetlink {
+    pub fn open() -> NetlinkResult<Self> {
+        let mut socket = Socket::new(NETLINK_ROUTE).map_err(NetlinkError::Socket)?;
+        socket.bind_auto().map_err(NetlinkError::Socket)?;
+        socket.connect(&SocketAddr::new(0, 0)).map_err(NetlinkError::Socket)?;
+        Ok(Self { socket, seq: 0 })
+    }
+
+    fn request(&mut self, message: RouteNetlinkMessage, flags: u16) -> NetlinkResult<Vec<RouteNetlinkMessage>> {
+        self.seq = self.seq.wrapping_add(1);
+        request(&self.socket, self.seq, message, flags)
+    }
+
+    fn ack(&mut self, message: RouteNetlinkMessage, flags: u16) -> NetlinkResult<()> {
+        self.request(message, NLM_F_ACK | flags)?;
+        Ok(())
+    }
+
+    // links
+
+    fn get_link(&mut self, message: LinkMessage) -> NetlinkResult<Option<LinkMessage>> {
+        match self.request(RouteNetlinkMessage::GetLink(message), 0) {
+            Ok(replies) => Ok(replies.into_iter().find_map(|m| match m {
+                RouteNetlinkMessage::NewLink(link) => Some(link),
+                _ => None,
+            })),
+            Err(NetlinkError::Request(e)) if e.raw_os_error() == Some(libc::ENODEV) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn link_index(&mut self, name: &str) -> NetlinkResult<Option<u32>> {
+        let mut message = LinkMessage::default();
+        message.attributes.push(LinkAttribute::IfName(name.to_string()));
+        Ok(self.get_link(message)?.map(|link| link.header.index))
+    }
+
+    fn require_link_index(&mut self, name: &str) -> NetlinkResult<u32> {
+        self.link_index(name)?.ok_or_else(|| NetlinkError::LinkNotFound(name.to_string()))
+    }
+
+    pub fn link_mtu(&mut self, index: u32) -> NetlinkResult<Option<u32>> {
+        let mut message = LinkMessage::default();
+        message.header.index = index;
+        Ok(self.get_link(message)?.and_then(|link| {
+            link.attributes.into_iter().find_map(|a| match a {
+                LinkAttribute::Mtu(mtu) => Some(mtu),
+                _ => None,
+            })
+        }))
+    }
+
+    pub fn add_wireguard_link(&mut self, name: &str) -> NetlinkResult<()> {
+        let mut message = LinkMessage::default();
+        message.attributes.push(LinkAttribute::IfName(name.to_string()));
+        message.attributes.push(LinkAttribute::LinkInfo(vec![LinkInfo::Kind(InfoKind::Wireguard)]));
+        self.ack(RouteNetlinkMessage::NewLink(message), NLM_F_CREATE | NLM_F_EXCL)
+    }
+
+    pub fn set_link_mtu_up(&mut self, name: &str, mtu: u32) -> NetlinkResult<()> {
+        let mut message = LinkMessage::default();
+        message.header.index = self.require_link_index(name)?;
+        message.header.flags = LinkFlags::Up;
+        message.header.change_mask = LinkFlags::Up;
+        message.attributes.push(LinkAttribute::Mtu(mtu));
+        self.ack(RouteNetlinkMessage::SetLink(message), 0)
+    }
+
+    pub fn del_link(&mut self, name: &str) -> NetlinkResult<()> {
+        let mut message = LinkMessage::default();
+        message.header.index = self.require_link_index(name)?;
+        self.ack(RouteNetlinkMessage::DelLink(message), 0)
+    }
+
+    // addresses
+
+    pub fn add_address(&mut self, name: &str, addr: &IpNet) -> NetlinkResult<()> {
+        let mut message = AddressMessage::default();
+        message.header.family = family_of(&addr.addr());
+        message.header.prefix_len = addr.prefix_len();
+        message.header.index = self.require_link_index(name)?;
+        if addr.addr().is_ipv4() {
+            message.attributes.push(AddressAttribute::Local(addr.addr()));
+        }
+        message.attributes.push(AddressAttribute::Address(addr.addr()));
+        self.ack(RouteNetlinkMessage::NewAddress(message), NLM_F_CREATE | NLM_F_EXCL)
+    }
+
+    pub fn addresses(&mut self, name: &str, family: AddressFamily) -> NetlinkResult<Vec<IpAddr>> {
+        let index = self.require_link_index(name)?;
+        let mut message = AddressMessage::default();
+        message.header.family = family;
+        let replies = self.request(RouteNetlinkMessage::GetAddress(message), NLM_F_DUMP)?;
+        Ok(replies.into_iter().filter_map(|m| match m {
+            RouteNetlinkMessage::NewAddress(a) if a.header.index == index && a.header.family == family => {
+                a.attributes.into_iter().find_map(|attr| match attr {
+                    AddressAttribute::Address(ip) => Some(ip),
+                    _ => None,
+                })
+            }
+            _ => None,
+        }).collect())
+    }
+
+    // routes
+
+    fn dump_routes(&mut self, family: AddressFamily) -> NetlinkResult<Vec<RouteMessage>> {
+        let mut message = RouteMessage::default();
+        message.header.address_family = family;
+        let replies = self.request(RouteNetlinkMessage::GetRoute(message), NLM_F_DUMP)?;
+        Ok(replies.into_iter().filter_map(|m| match m {
+            RouteNetlinkMessage::NewRoute(r) => Some(r),
+            _ => None,
+        }).collect())
+    }
+
+    /// Outgoing link and MTU of the route the kernel would pick for `addr`
+    pub fn route_get(&mut self, addr: IpAddr) -> NetlinkResult<Option<(Option<u32>, Option<u32>)>> {
+        let mut message = RouteMessage::default();
+        message.header.address_family = family_of(&addr);
+        message.header.destination_prefix_length = if addr.is_ipv6() { 128 } else { 32 };
+        message.attributes.push(RouteAttribute::Destination(RouteAddress::from(addr)));
+        let replies = match self.request(RouteNetlinkMessage::GetRoute(message), 0) {
+            Ok(replies) => replies,
+            Err(NetlinkError::Request(e)) if e.raw_os_error() == Some(libc::ENETUNREACH) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        Ok(replies.into_iter().find_map(|m| match m {
+            RouteNetlinkMessage::NewRoute(r) => Some((route_oif(&r), route_mtu(&r))),
+            _ => None,
+        }))
+    }
+
+    /// Outgoing link and MTU of the IPv4 default route in the main table
+    pub fn default_route(&mut self) -> NetlinkResult<Option<(Option<u32>, Option<u32>)>> {
+        Ok(self.dump_routes(AddressFamily::Inet)?.into_iter()
+            .find(|r| route_table(r) == RT_TABLE_MAIN && r.header.destination_prefix_length == 0)
+            .map(|r| (route_oif(&r), route_mtu(&r))))
+    }
+
+    /// Whether a route on `name` in the main table already covers `cidr`
+    pub fn route_exists(&mut self, name: &str, cidr: &IpNet) -> NetlinkResult<bool> {
+        let index = self.require_link_index(name)?;
+        Ok(self.dump_routes(family_of(&cidr.addr()))?.iter().any(|r| {
+            route_table(r) == RT_TABLE_MAIN
+                && route_oif(r) == Some(index)
+                && route_destination(r).is_some_and(|dst| dst.contains(cidr))
+        }))
+    }
+
+    pub fn add_route(&mut self, name: &str, cidr: &IpNet, table: u32) -> NetlinkResult<()> {
+        let index = self.require_link_index(name)?;
+        let mut message = RouteMessage::default();
+        message.header.address_family = family_of(&cidr.addr());
+        message.header.destination_prefix_length = cidr.prefix_len();
+        message.header.table = u8::try_from(table).unwrap_or(RouteHeader::RT_TABLE_UNSPEC);
+        message.header.protocol = RouteProtocol::Boot;
+        message.header.scope = RouteScope::Link;
+        message.header.kind = RouteType::Unicast;
+        if cidr.prefix_len() > 0 {
+            message.attributes.push(RouteAttribute::Destination(RouteAddress::from(cidr.network())));
+        }
+        message.attributes.push(RouteAttribute::Oif(index));
+        message.attributes.push(RouteAttribute::Table(table));
+        self.ack(RouteNetlinkMessage::NewRoute(message), NLM_F_CREATE | NLM_F_EXCL)
+    }
+
+    pub fn table_has_routes(&mut self, family: AddressFamily, table: u32) -> NetlinkResult<bool> {
+        Ok(self.dump_routes(family)?.iter().any(|r| route_table(r) == table))
+    }
+
+    // rules
+
+    fn new_rule(family: AddressFamily, table: u32) -> RuleMessage {
+        let mut message = RuleMessage::default();
+        message.header.family = family;
+        message.header.table = u8::try_from(table).unwrap_or(RouteHeader::RT_TABLE_UNSPEC);
+        message.header.action = RuleAction::ToTable;
+        message.attributes.push(RuleAttribute::Table(table));
+        message
+    }
+
+    /// `ip rule add not fwmark <table> table <table>`
+    pub fn add_rule_not_fwmark(&mut self, family: AddressFamily, table: u32) -> NetlinkResult<()> {
+        let mut message = Self::new_rule(family, table);
+        message.header.flags = RuleFlags::Invert;
+        message.attributes.push(RuleAttribute::FwMark(table));
+        self.ack(RouteNetlinkMessage::NewRule(message), NLM_F_CREATE)
+    }
+
+    /// `ip rule add table main suppress_prefixlength 0`
+    pub fn add_rule_suppress_main(&mut self, family: AddressFamily) -> NetlinkResult<()> {
+        let mut message = Self::new_rule(family, RT_TABLE_MAIN);
+        message.attributes.push(RuleAttribute::SuppressPrefixLen(0));
+        self.ack(RouteNetlinkMessage::NewRule(message), NLM_F_CREATE)
+    }
+
+    /// Deletes every rule of `family` that the `matches` predicate selects
+    pub fn del_rules(&mut self, family: AddressFamily, matches: impl Fn(&RuleMessage) -> bool) -> NetlinkResult<()> {
+        let mut message = RuleMessage::default();
+        message.header.family = family;
+        let rules: Vec<RuleMessage> = self.request(RouteNetlinkMessage::GetRule(message), NLM_F_DUMP)?
+            .into_iter()
+            .filter_map(|m| match m {
+                RouteNetlinkMessage::NewRule(r) if matches(&r) => Some(r),
+                _ => None,
+            })
+            .collect();
+        for rule in rules {
+            self.ack(RouteNetlinkMessage::DelRule(rule), 0)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn rule_table(rule: &RuleMessage) -> u32 {
+    rule.attributes.iter().find_map(|a| match a {
+        RuleAttribute::Table(table) => Some(*table),
+        _ => None,
+    }).unwrap_or(rule.header.table as u32)
+}
+
+pub fn rule_suppresses_default(rule: &RuleMessage) -> bool {
+    rule.attributes.contains(&RuleAttribute::SuppressPrefixLen(0))
+}
+
+fn route_table(route: &RouteMessage) -> u32 {
+    route.attributes.iter().find_map(|a| match a {
+        RouteAttribute::Table(table) => Some(*table),
+        _ => None,
+    }).unwrap_or(route.header.table as u32)
+}
+
+fn route_oif(route: &RouteMessage) -> Option<u32> {
+    route.attributes.iter().find_map(|a| match a {
+        RouteAttribute::Oif(index) => Some(*index),
+        _ => None,
+    })
+}
+
+fn route_mtu(route: &RouteMessage) -> Option<u32> {
+    route.attributes.iter().find_map(|a| match a {
+        RouteAttribute::Metrics(metrics) => metrics.iter().find_map(|m| match m {
+            RouteMetric::Mtu(mtu) if *mtu > 0 => Some(*mtu),
+            _ => None,
+        }),
+        _ => None,
+    })
+}
+
+fn route_destination(route: &RouteMessage) -> Option<IpNet> {
+    let prefix = route.header.destination_prefix_length;
+    let addr = route.attributes.iter().find_map(|a| match a {
+        RouteAttribute::Destination(RouteAddress::Inet(v4)) => Some(IpAddr::V4(*v4)),
+        RouteAttribute::Destination(RouteAddress::Inet6(v6)) => Some(IpAddr::V6(*v6)),
+        _ => None,
+    });
+    match (addr, route.header.address_family) {
+        (Some(addr), _) => IpNet::new(addr, prefix).ok(),
+        (None, AddressFamily::Inet6) => IpNet::new(IpAddr::V6(std::net::Ipv6Addr::UNSPECIFIED), 0).ok(),
+        (None, _) => IpNet::new(IpAddr::V4(std::net::Ipv4Addr::UNSPECIFIED), 0).ok(),
+    }
+}
+
+/// Synchronous connection to the WireGuard generic netlink family
+pub struct WgNetlink {
+    socket: Socket,
+    seq: u32,
+    family_id: u16,
+}
+
+impl WgNetlink {
+    pub fn open() -> NetlinkResult<Self> {
+        let mut socket = Socket::new(NETLINK_GENERIC).map_err(NetlinkError::Socket)?;
+        socket.bind_auto().map_err(NetlinkError::Socket)?;
+        socket.connect(&SocketAddr::new(0, 0)).map_err(NetlinkError::Socket)?;
+
+        let family_name = WireguardMessage::family_name();
+        let message = GenlMessage::from_payload(GenlCtrl {
+            cmd: GenlCtrlCmd::GetFamily,
+            nlas: vec![GenlCtrlAttrs::FamilyName(family_name.to_string())],
+        });
+        let replies = match request(&socket, 1, message, NLM_F_ACK) {
+            Err(NetlinkError::Request(e)) if e.raw_os_error() == Some(libc::ENOENT) => {
+                return Err(NetlinkError::FamilyNotFound(family_name.to_string()));
+            }
+            replies => replies?,
+        };
+        let family_id = replies.iter()
+            .flat_map(|m| m.payload.nlas.iter())
+            .find_map(|nla| match nla {
+                GenlCtrlAttrs::FamilyId(id) => Some(*id),
+                _ => None,
+            })
+            .ok_or_else(|| NetlinkError::FamilyNotFound(family_name.to_string()))?;
+
+        Ok(Self { socket, seq: 1, family_id })
+    }
+
+    fn request(&mut self, message: WireguardMessage, flags: u16) -> NetlinkResult<Vec<WireguardMessage>> {
+        self.seq = self.seq.wrapping_add(1);
+        let mut message = GenlMessage::from_payload(message);
+        message.set_resolved_family_id(self.family_id);
+        let replies = request(&self.socket, self.seq, message, flags).map_err(|e| match e {
+            NetlinkError::Request(e) if e.raw_os_error() == Some(libc::ENODEV) => NetlinkError::LinkNotFound(String::new()),
+            e => e,
+        })?;
+        Ok(replies.into_iter().map(|m| m.payload).collect())
+    }
+
+    /// Dumps the device, peers with many allowed IPs may be split across several messages
+    pub fn get_device(&mut self, name: &str) -> NetlinkResult<Vec<WireguardMessage>> {
+        self.request(WireguardMessage {
+            cmd: WireguardCmd::GetDevice,
+            attributes: vec![WireguardAttribute::IfName(name.to_string())],
+        }, NLM_F_DUMP).map_err(|e| match e {
+            NetlinkError::LinkNotFound(_) => NetlinkError::LinkNotFound(name.to_string()),
+            e => e,
+        })
+    }
+
+    pub fn set_device(&mut self, attributes: Vec<WireguardAttribute>) -> NetlinkResult<()> {
+        self.request(WireguardMessage { cmd: WireguardCmd::SetDevice, attributes }, NLM_F_ACK)?;
+        Ok(())
+    }
+}
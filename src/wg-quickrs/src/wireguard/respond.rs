@@ -1,6 +1,10 @@
-use crate::wireguard::cmd::{disable_tunnel, enable_tunnel, WG_STATUS};
+use crate::wireguard::cmd::{disable_tunnel, enable_tunnel, get_device_telemetry, status_tunnel, WG_STATUS};
+use crate::wireguard::{history, metrics};
 use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Duration, Utc};
 use serde_json::json;
+use uuid::Uuid;
+use wg_quickrs_lib::helpers::get_connection_id;
 use wg_quickrs_lib::types::misc::WireGuardStatus;
 use crate::conf;
 
@@ -46,3 +50,58 @@ pub(crate) fn post_wireguard_server_status(body: web::Bytes) -> Result<HttpRespo
         }
     }
 }
+
+pub(crate) fn get_connection_telemetry(id: &str, query: web::Query<crate::web::api::TelemetryHistoryQuery>) -> Result<HttpResponse, HttpResponse> {
+    let config = conf::util::get_config()
+        .map_err(|e| HttpResponse::InternalServerError().body(format!("failed to get config: {e}")))?;
+    if !config.agent.vpn.telemetry.history.enabled {
+        return Err(HttpResponse::Forbidden().body("telemetry history is disabled in configuration"));
+    }
+
+    let connection_id = id.split_once('*')
+        .and_then(|(a, b)| Some(get_connection_id(Uuid::parse_str(a).ok()?, Uuid::parse_str(b).ok()?)))
+        .ok_or_else(|| HttpResponse::BadRequest().body(format!("invalid connection id: {}", id)))?;
+    if !config.network.connections.contains_key(&connection_id) {
+        return Err(HttpResponse::NotFound().body(format!("connection not found: {}", id)));
+    }
+
+    let parse_ms = |ms: i64| DateTime::from_timestamp_millis(ms)
+        .map(|t| t.naive_utc())
+        .ok_or_else(|| HttpResponse::BadRequest().body(format!("invalid timestamp: {}", ms)));
+    let to = match query.to {
+        Some(ms) => parse_ms(ms)?,
+        None => Utc::now().naive_utc(),
+    };
+    let from = match query.from {
+        Some(ms) => parse_ms(ms)?,
+        None => to - Duration::days(1),
+    };
+    if from >= to {
+        return Err(HttpResponse::BadRequest().body("from must be before to"));
+    }
+    let resolution = query.resolution.unwrap_or_else(|| history::default_resolution(&from));
+
+    match history::query(&connection_id, from, to, resolution) {
+        Ok(telemetry_history) => Ok(HttpResponse::Ok().json(telemetry_history)),
+        Err(e) => {
+            log::error!("{e}");
+            Err(HttpResponse::InternalServerError().body(format!("failed to read telemetry history: {e}")))
+        }
+    }
+}
+
+pub(crate) fn get_metrics() -> Result<HttpResponse, HttpResponse> {
+    let config = conf::util::get_config()
+        .map_err(|e| HttpResponse::InternalServerError().body(format!("failed to get config: {e}")))?;
+    let status = status_tunnel()
+        .map_err(|e| HttpResponse::InternalServerError().body(format!("failed to get WireGuard status: {e}")))?;
+    // a failing dump still leaves the configuration metrics worth scraping
+    let telemetry = get_device_telemetry(&config).unwrap_or_else(|e| {
+        log::error!("Failed to get telemetry data => {}", e);
+        None
+    });
+
+    Ok(HttpResponse::Ok()
+        .content_type(metrics::CONTENT_TYPE)
+        .body(metrics::render(&config, &status, telemetry.as_ref())))
+}
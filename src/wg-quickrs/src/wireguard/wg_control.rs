@@ -0,0 +1,794 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use ipnet::IpNet;
+use tempfile::NamedTempFile;
+use thiserror::Error;
+use wg_quickrs_lib::helpers::get_peer_wg_config;
+use wg_quickrs_lib::types::config::Config;
+use wg_quickrs_lib::types::misc::WireGuardLibError;
+use wg_quickrs_lib::types::network::{EndpointAddress, WireGuardKey};
+use wg_quickrs_lib::types::wg_quick::{WgQuickConfig, WgQuickParseError};
+use crate::helpers::{shell_cmd, ShellError};
+#[cfg(target_os = "linux")]
+use crate::wireguard::netlink_linux::{NetlinkError, WgNetlink};
+
+const UAPI_SOCKET_DIR: &str = "/var/run/wireguard";
+
+#[derive(Error, Debug)]
+pub enum WgControlError {
+    #[error("io error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("{0}")]
+    ShellError(#[from] ShellError),
+    #[error("{0}")]
+    WireGuardLibError(#[from] WireGuardLibError),
+    #[error("failed to parse the generated WireGuard configuration: {0}")]
+    ConfigParseError(#[from] WgQuickParseError),
+    #[error("uapi request failed with errno {0}")]
+    UapiErrno(i32),
+    #[error("unexpected uapi reply: {0}")]
+    UapiReply(String),
+    #[error("unexpected wg output: {0}")]
+    ToolOutput(String),
+    #[cfg(target_os = "linux")]
+    #[error("{0}")]
+    NetlinkError(#[from] NetlinkError),
+}
+
+pub type WgControlResult<T> = Result<T, WgControlError>;
+
+/// State of a WireGuard device as reported by the kernel or the userspace implementation
+#[derive(Default, Debug, Clone)]
+pub struct WgDevice {
+    pub private_key: Option<WireGuardKey>,
+    pub listen_port: u16,
+    pub fwmark: u32,
+    pub peers: Vec<WgPeer>,
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct WgPeer {
+    pub public_key: WireGuardKey,
+    pub preshared_key: Option<WireGuardKey>,
+    pub endpoint: Option<SocketAddr>,
+    pub persistent_keepalive: u16,
+    pub allowed_ips: Vec<IpNet>,
+    /// seconds since the epoch, 0 if there was no handshake yet
+    pub last_handshake: u64,
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+}
+
+/// Changes to apply to a device, fields left as None are not touched
+#[derive(Default, Debug, Clone)]
+pub struct WgDeviceUpdate {
+    pub private_key: Option<WireGuardKey>,
+    pub listen_port: Option<u16>,
+    pub fwmark: Option<u32>,
+    pub replace_peers: bool,
+    pub peers: Vec<WgPeerUpdate>,
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct WgPeerUpdate {
+    pub public_key: WireGuardKey,
+    pub remove: bool,
+    /// the all-zero key removes the preshared key of the peer
+    pub preshared_key: Option<WireGuardKey>,
+    pub endpoint: Option<SocketAddr>,
+    pub persistent_keepalive: Option<u16>,
+    pub replace_allowed_ips: bool,
+    pub allowed_ips: Vec<IpNet>,
+}
+
+impl WgDeviceUpdate {
+    fn is_empty(&self) -> bool {
+        self.private_key.is_none() && self.listen_port.is_none() && self.fwmark.is_none()
+            && !self.replace_peers && self.peers.is_empty()
+    }
+}
+
+/// How a WireGuard device is configured and queried
+#[derive(Debug, Clone)]
+pub enum WgControl {
+    /// userspace implementation (wireguard-go, boringtun, ...) through its UAPI socket
+    Uapi(PathBuf),
+    /// kernel module through the WireGuard generic netlink family
+    #[cfg(target_os = "linux")]
+    Netlink(String),
+    /// wg/awg tool and interface, AmneziaWG parameters are not part of the protocols above
+    Tool(String, String),
+}
+
+impl WgControl {
+    pub fn new(config: &Config, iface: &str) -> Self {
+        if config.network.amnezia_parameters.enabled {
+            return WgControl::Tool(config.agent.vpn.wg.display().to_string(), iface.to_string());
+        }
+        let socket = Path::new(UAPI_SOCKET_DIR).join(format!("{}.sock", iface));
+        #[cfg(target_os = "linux")]
+        if !socket.exists() {
+            return WgControl::Netlink(iface.to_string());
+        }
+        WgControl::Uapi(socket)
+    }
+
+    pub fn get_device(&self) -> WgControlResult<WgDevice> {
+        match self {
+            WgControl::Uapi(socket) => uapi_get(socket),
+            #[cfg(target_os = "linux")]
+            WgControl::Netlink(iface) => netlink_get(iface),
+            WgControl::Tool(wg, iface) => tool_get(wg, iface),
+        }
+    }
+
+    fn set_device(&self, update: &WgDeviceUpdate) -> WgControlResult<()> {
+        if update.is_empty() {
+            return Ok(());
+        }
+        match self {
+            WgControl::Uapi(socket) => uapi_set(socket, update),
+            #[cfg(target_os = "linux")]
+            WgControl::Netlink(iface) => netlink_set(iface, update),
+            WgControl::Tool(..) => Err(WgControlError::ToolOutput("incremental updates are not supported through the wg tool".into())),
+        }
+    }
+
+    pub fn set_fwmark(&self, fwmark: u32) -> WgControlResult<()> {
+        match self {
+            WgControl::Tool(wg, iface) => {
+                shell_cmd(&[wg, "set", iface, "fwmark", &fwmark.to_string()])?;
+                Ok(())
+            }
+            _ => {
+                log::debug!("[+] wg set fwmark {}", fwmark);
+                self.set_device(&WgDeviceUpdate { fwmark: Some(fwmark), ..Default::default() })
+            }
+        }
+    }
+
+    /// Replaces the whole device configuration, like `wg setconf`
+    pub fn set_config(&self, config: &Config) -> WgControlResult<()> {
+        if let WgControl::Tool(wg, iface) = self {
+            return tool_conf(wg, "setconf", iface, config);
+        }
+        log::debug!("[+] wg setconf");
+        self.set_device(&desired_device(config)?)
+    }
+
+    /// Applies only what changed between the device and the configuration, like `wg syncconf`
+    pub fn sync_config(&self, config: &Config) -> WgControlResult<()> {
+        if let WgControl::Tool(wg, iface) = self {
+            return tool_conf(wg, "syncconf", iface, config);
+        }
+        let update = sync_update(&self.get_device()?, desired_device(config)?);
+        log::debug!("[+] wg syncconf ({} peer changes)", update.peers.len());
+        self.set_device(&update)
+    }
+}
+
+/// The changes `sync_config` would apply to a device that runs `current`, without touching it,
+/// along with that device
+pub fn preview_sync(current: &Config, desired: &Config) -> WgControlResult<(WgDevice, WgDeviceUpdate)> {
+    let current = desired_device(current)?;
+    let device = WgDevice {
+        private_key: current.private_key,
+        listen_port: current.listen_port.unwrap_or_default(),
+        fwmark: 0,
+        peers: current.peers.into_iter().map(|peer| WgPeer {
+            public_key: peer.public_key,
+            preshared_key: peer.preshared_key,
+            endpoint: peer.endpoint,
+            persistent_keepalive: peer.persistent_keepalive.unwrap_or_default(),
+            allowed_ips: peer.allowed_ips,
+            ..Default::default()
+        }).collect(),
+    };
+    let update = sync_update(&device, desired_device(desired)?);
+    Ok((device, update))
+}
+
+fn desired_device(config: &Config) -> WgControlResult<WgDeviceUpdate> {
+    let wg_conf = get_peer_wg_config(&config.network, &config.network.this_peer, true)?;
+    let wg_conf = WgQuickConfig::from_str(&wg_conf)?;
+
+    Ok(WgDeviceUpdate {
+        private_key: wg_conf.interface.private_key,
+        listen_port: wg_conf.interface.listen_port,
+        fwmark: None,
+        replace_peers: true,
+        peers: wg_conf.peers.iter().map(|peer| WgPeerUpdate {
+            public_key: peer.public_key,
+            remove: false,
+            preshared_key: peer.pre_shared_key,
+            endpoint: peer.endpoint.as_ref().and_then(resolve_endpoint),
+            persistent_keepalive: Some(peer.persistent_keepalive.unwrap_or(0)),
+            replace_allowed_ips: true,
+            allowed_ips: peer.allowed_ips.clone(),
+        }).collect(),
+    })
+}
+
+fn resolve_endpoint(endpoint: &EndpointAddress) -> Option<SocketAddr> {
+    match endpoint {
+        EndpointAddress::None => None,
+        EndpointAddress::Ipv4AndPort(ipv4_port) => Some(SocketAddr::new(IpAddr::V4(ipv4_port.ipv4), ipv4_port.port)),
+        EndpointAddress::Ipv6AndPort(ipv6_port) => Some(SocketAddr::new(IpAddr::V6(ipv6_port.ipv6), ipv6_port.port)),
+        EndpointAddress::HostnameAndPort(host_port) => {
+            match (host_port.hostname.as_str(), host_port.port).to_socket_addrs() {
+                Ok(mut addrs) => addrs.next(),
+                Err(e) => {
+                    log::warn!("[!] Failed to resolve endpoint {}: {}", host_port.hostname, e);
+                    None
+                }
+            }
+        }
+    }
+}
+
+/// Turns a full configuration into the changes needed on top of the current device state
+fn sync_update(current: &WgDevice, desired: WgDeviceUpdate) -> WgDeviceUpdate {
+    let mut update = WgDeviceUpdate {
+        private_key: desired.private_key.filter(|key| current.private_key != Some(*key)),
+        listen_port: desired.listen_port.filter(|port| current.listen_port != *port),
+        ..Default::default()
+    };
+
+    for peer in &current.peers {
+        if !desired.peers.iter().any(|p| p.public_key == peer.public_key) {
+            update.peers.push(WgPeerUpdate { public_key: peer.public_key, remove: true, ..Default::default() });
+        }
+    }
+
+    for mut peer in desired.peers {
+        if let Some(current_peer) = current.peers.iter().find(|p| p.public_key == peer.public_key) {
+            let preshared_key = peer.preshared_key.unwrap_or_default();
+            peer.preshared_key = (preshared_key != current_peer.preshared_key.unwrap_or_default()).then_some(preshared_key);
+            if peer.endpoint == current_peer.endpoint {
+                peer.endpoint = None;
+            }
+            if peer.persistent_keepalive == Some(current_peer.persistent_keepalive) {
+                peer.persistent_keepalive = None;
+            }
+            let mut allowed_ips = peer.allowed_ips.clone();
+            let mut current_allowed_ips = current_peer.allowed_ips.clone();
+            allowed_ips.sort();
+            current_allowed_ips.sort();
+            if allowed_ips == current_allowed_ips {
+                peer.replace_allowed_ips = false;
+                peer.allowed_ips.clear();
+            }
+            if peer.preshared_key.is_none() && peer.endpoint.is_none()
+                && peer.persistent_keepalive.is_none() && !peer.replace_allowed_ips {
+                continue;
+            }
+        }
+        update.peers.push(peer);
+    }
+
+    update
+}
+
+// UAPI (https://www.wireguard.com/xplatform/)
+
+fn hex_key(value: &str) -> WgControlResult<WireGuardKey> {
+    let mut key = WireGuardKey::default();
+    base16ct::mixed::decode(value, &mut key.0)
+        .map_err(|_| WgControlError::UapiReply(format!("invalid key {}", value)))?;
+    Ok(key)
+}
+
+fn hex_encode(key: &WireGuardKey) -> String {
+    let mut buf = [0u8; 64];
+    base16ct::lower::encode_str(key.as_bytes(), &mut buf).unwrap_or_default().to_string()
+}
+
+fn non_zero_key(key: WireGuardKey) -> Option<WireGuardKey> {
+    (key != WireGuardKey::default()).then_some(key)
+}
+
+fn parse_value<T: FromStr>(key: &str, value: &str) -> WgControlResult<T> {
+    value.parse().map_err(|_| WgControlError::UapiReply(format!("invalid {}={}", key, value)))
+}
+
+/// Reads `key=value` lines up to the blank line that ends a reply and checks its errno
+fn uapi_read(reply: impl Read) -> WgControlResult<Vec<(String, String)>> {
+    let mut pairs = Vec::new();
+    for line in BufReader::new(reply).lines() {
+        let line = line?;
+        if line.is_empty() {
+            break;
+        }
+        let (key, value) = line.split_once('=').ok_or_else(|| WgControlError::UapiReply(line.clone()))?;
+        if key == "errno" {
+            let errno: i32 = parse_value(key, value)?;
+            if errno != 0 {
+                return Err(WgControlError::UapiErrno(errno));
+            }
+            continue;
+        }
+        pairs.push((key.to_string(), value.to_string()));
+    }
+    Ok(pairs)
+}
+
+fn uapi_get(socket: &Path) -> WgControlResult<WgDevice> {
+    let mut stream = UnixStream::connect(socket)?;
+    stream.write_all(b"get=1\n\n")?;
+    uapi_device(stream)
+}
+
+fn uapi_device(reply: impl Read) -> WgControlResult<WgDevice> {
+    let mut device = WgDevice::default();
+    for (key, value) in uapi_read(reply)? {
+        match (key.as_str(), device.peers.last_mut()) {
+            ("private_key", _) => device.private_key = non_zero_key(hex_key(&value)?),
+            ("listen_port", _) => device.listen_port = parse_value(&key, &value)?,
+            ("fwmark", _) => device.fwmark = parse_value(&key, &value)?,
+            ("public_key", _) => device.peers.push(WgPeer { public_key: hex_key(&value)?, ..Default::default() }),
+            ("preshared_key", Some(peer)) => peer.preshared_key = non_zero_key(hex_key(&value)?),
+            ("endpoint", Some(peer)) => peer.endpoint = Some(parse_value(&key, &value)?),
+            ("persistent_keepalive_interval", Some(peer)) => peer.persistent_keepalive = parse_value(&key, &value)?,
+            ("allowed_ip", Some(peer)) => peer.allowed_ips.push(parse_value(&key, &value)?),
+            ("last_handshake_time_sec", Some(peer)) => peer.last_handshake = parse_value(&key, &value)?,
+            ("rx_bytes", Some(peer)) => peer.rx_bytes = parse_value(&key, &value)?,
+            ("tx_bytes", Some(peer)) => peer.tx_bytes = parse_value(&key, &value)?,
+            _ => {}
+        }
+    }
+    Ok(device)
+}
+
+fn uapi_set(socket: &Path, update: &WgDeviceUpdate) -> WgControlResult<()> {
+    let mut request = String::from("set=1\n");
+    if let Some(private_key) = &update.private_key {
+        request.push_str(&format!("private_key={}\n", hex_encode(private_key)));
+    }
+    if let Some(listen_port) = update.listen_port {
+        request.push_str(&format!("listen_port={}\n", listen_port));
+    }
+    if let Some(fwmark) = update.fwmark {
+        request.push_str(&format!("fwmark={}\n", fwmark));
+    }
+    if update.replace_peers {
+        request.push_str("replace_peers=true\n");
+    }
+    for peer in &update.peers {
+        request.push_str(&format!("public_key={}\n", hex_encode(&peer.public_key)));
+        if peer.remove {
+            request.push_str("remove=true\n");
+            continue;
+        }
+        if let Some(preshared_key) = &peer.preshared_key {
+            request.push_str(&format!("preshared_key={}\n", hex_encode(preshared_key)));
+        }
+        if let Some(endpoint) = peer.endpoint {
+            request.push_str(&format!("endpoint={}\n", endpoint));
+        }
+        if let Some(persistent_keepalive) = peer.persistent_keepalive {
+            request.push_str(&format!("persistent_keepalive_interval={}\n", persistent_keepalive));
+        }
+        if peer.replace_allowed_ips {
+            request.push_str("replace_allowed_ips=true\n");
+        }
+        for allowed_ip in &peer.allowed_ips {
+            request.push_str(&format!("allowed_ip={}\n", allowed_ip));
+        }
+    }
+    request.push('\n');
+
+    let mut stream = UnixStream::connect(socket)?;
+    stream.write_all(request.as_bytes())?;
+    uapi_read(stream)?;
+    Ok(())
+}
+
+// generic netlink
+
+#[cfg(target_os = "linux")]
+fn netlink_get(iface: &str) -> WgControlResult<WgDevice> {
+    use netlink_packet_wireguard::{WireguardAllowedIpAttr, WireguardAttribute, WireguardPeerAttribute};
+
+    let mut device = WgDevice::default();
+    for message in WgNetlink::open()?.get_device(iface)? {
+        for attribute in message.attributes {
+            match attribute {
+                WireguardAttribute::PrivateKey(key) => device.private_key = non_zero_key(WireGuardKey(key)),
+                WireguardAttribute::ListenPort(port) => device.listen_port = port,
+                WireguardAttribute::Fwmark(fwmark) => device.fwmark = fwmark,
+                WireguardAttribute::Peers(peers) => {
+                    for nl_peer in peers {
+                        let mut peer = WgPeer::default();
+                        for peer_attribute in nl_peer.0 {
+                            match peer_attribute {
+                                WireguardPeerAttribute::PublicKey(key) => peer.public_key = WireGuardKey(key),
+                                WireguardPeerAttribute::PresharedKey(key) => peer.preshared_key = non_zero_key(WireGuardKey(key)),
+                                WireguardPeerAttribute::Endpoint(endpoint) => peer.endpoint = Some(endpoint),
+                                WireguardPeerAttribute::PersistentKeepalive(interval) => peer.persistent_keepalive = interval,
+                                WireguardPeerAttribute::LastHandshake(time) => peer.last_handshake = time.seconds.max(0) as u64,
+                                WireguardPeerAttribute::RxBytes(bytes) => peer.rx_bytes = bytes,
+                                WireguardPeerAttribute::TxBytes(bytes) => peer.tx_bytes = bytes,
+                                WireguardPeerAttribute::AllowedIps(allowed_ips) => {
+                                    for allowed_ip in allowed_ips {
+                                        let addr = allowed_ip.0.iter().find_map(|a| match a {
+                                            WireguardAllowedIpAttr::IpAddr(addr) => Some(*addr),
+                                            _ => None,
+                                        });
+                                        let cidr = allowed_ip.0.iter().find_map(|a| match a {
+                                            WireguardAllowedIpAttr::Cidr(cidr) => Some(*cidr),
+                                            _ => None,
+                                        });
+                                        if let (Some(addr), Some(cidr)) = (addr, cidr)
+                                            && let Ok(net) = IpNet::new(addr, cidr) {
+                                            peer.allowed_ips.push(net);
+                                        }
+                                    }
+                                }
+                                _ => {}
+                            }
+                        }
+                        // peers with many allowed IPs continue in the next message
+                        match device.peers.iter_mut().find(|p| p.public_key == peer.public_key) {
+                            Some(existing) => existing.allowed_ips.extend(peer.allowed_ips),
+                            None => device.peers.push(peer),
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(device)
+}
+
+/// Room for the peers of one set request, the nest around them has a u16 length,
+/// so larger updates are split across several messages like wireguard-tools does
+#[cfg(target_os = "linux")]
+const NETLINK_PEERS_BUDGET: usize = 4096;
+
+#[cfg(target_os = "linux")]
+fn netlink_set(iface: &str, update: &WgDeviceUpdate) -> WgControlResult<()> {
+    let mut netlink = WgNetlink::open()?;
+    for attributes in netlink_messages(iface, update) {
+        netlink.set_device(attributes)?;
+    }
+    Ok(())
+}
+
+/// Only the first message carries the device settings and replaces the peers, a peer whose allowed IPs
+/// do not fit continues in the next message, which adds to the allowed IPs set so far
+#[cfg(target_os = "linux")]
+fn netlink_messages(iface: &str, update: &WgDeviceUpdate) -> Vec<Vec<netlink_packet_wireguard::WireguardAttribute>> {
+    use netlink_packet_core::Emitable;
+    use netlink_packet_wireguard::{WireguardAddressFamily, WireguardAllowedIp, WireguardAllowedIpAttr, WireguardAttribute, WireguardDeviceFlags, WireguardPeer, WireguardPeerAttribute, WireguardPeerFlags};
+
+    let mut messages = vec![Vec::new()];
+    let mut len = 0;
+    for peer in &update.peers {
+        let mut flags = WireguardPeerFlags::empty();
+        if peer.remove {
+            flags |= WireguardPeerFlags::RemoveMe;
+        }
+        if peer.replace_allowed_ips {
+            flags |= WireguardPeerFlags::ReplaceAllowedIps;
+        }
+        let mut peer_attributes = vec![
+            WireguardPeerAttribute::PublicKey(peer.public_key.0),
+            WireguardPeerAttribute::Flags(flags),
+        ];
+        if let Some(preshared_key) = peer.preshared_key {
+            peer_attributes.push(WireguardPeerAttribute::PresharedKey(preshared_key.0));
+        }
+        if let Some(endpoint) = peer.endpoint {
+            peer_attributes.push(WireguardPeerAttribute::Endpoint(endpoint));
+        }
+        if let Some(persistent_keepalive) = peer.persistent_keepalive {
+            peer_attributes.push(WireguardPeerAttribute::PersistentKeepalive(persistent_keepalive));
+        }
+
+        let mut allowed_ips = peer.allowed_ips.iter().map(|net| {
+            let family = if net.addr().is_ipv6() { WireguardAddressFamily::Ipv6 } else { WireguardAddressFamily::Ipv4 };
+            WireguardAllowedIp(vec![
+                WireguardAllowedIpAttr::Family(family),
+                WireguardAllowedIpAttr::IpAddr(net.addr()),
+                WireguardAllowedIpAttr::Cidr(net.prefix_len()),
+            ])
+        }).peekable();
+        loop {
+            // the peer with an empty allowed IPs nest
+            let mut peer_len = WireguardPeer(peer_attributes.clone()).buffer_len() + 4;
+            if len > 0 && len + peer_len > NETLINK_PEERS_BUDGET {
+                messages.push(Vec::new());
+                len = 0;
+            }
+            let mut batch = Vec::new();
+            while let Some(allowed_ip) = allowed_ips.next_if(|ip| (len == 0 && batch.is_empty()) || len + peer_len + ip.buffer_len() <= NETLINK_PEERS_BUDGET) {
+                peer_len += allowed_ip.buffer_len();
+                batch.push(allowed_ip);
+            }
+            if !batch.is_empty() {
+                peer_attributes.push(WireguardPeerAttribute::AllowedIps(batch));
+            }
+            messages.last_mut().unwrap().push(WireguardPeer(peer_attributes));
+            len += peer_len;
+            if allowed_ips.peek().is_none() {
+                break;
+            }
+            messages.push(Vec::new());
+            len = 0;
+            peer_attributes = vec![WireguardPeerAttribute::PublicKey(peer.public_key.0)];
+        }
+    }
+
+    messages.into_iter().enumerate().map(|(i, peers)| {
+        let mut attributes = vec![WireguardAttribute::IfName(iface.to_string())];
+        if i == 0 {
+            if let Some(private_key) = update.private_key {
+                attributes.push(WireguardAttribute::PrivateKey(private_key.0));
+            }
+            if let Some(listen_port) = update.listen_port {
+                attributes.push(WireguardAttribute::ListenPort(listen_port));
+            }
+            if let Some(fwmark) = update.fwmark {
+                attributes.push(WireguardAttribute::Fwmark(fwmark));
+            }
+            if update.replace_peers {
+                attributes.push(WireguardAttribute::Flags(WireguardDeviceFlags::ReplacePeers));
+            }
+        }
+        if !peers.is_empty() {
+            attributes.push(WireguardAttribute::Peers(peers));
+        }
+        attributes
+    }).collect()
+}
+
+// wg tool
+
+fn tool_conf(wg: &str, command: &str, iface: &str, config: &Config) -> WgControlResult<()> {
+    let wg_conf = get_peer_wg_config(&config.network, &config.network.this_peer, true)?;
+    let mut temp_file = NamedTempFile::new()?;
+    writeln!(temp_file, "{}", wg_conf)?;
+    shell_cmd(&[wg, command, iface, &temp_file.path().to_string_lossy()])?;
+    Ok(())
+}
+
+fn tool_get(wg: &str, iface: &str) -> WgControlResult<WgDevice> {
+    let output = shell_cmd(&[wg, "show", iface, "dump"])?;
+    if !output.status.success() {
+        return Err(WgControlError::ToolOutput(String::from_utf8_lossy(&output.stderr).trim().to_string()));
+    }
+    let dump = String::from_utf8_lossy(&output.stdout);
+    let mut lines = dump.trim().lines();
+    let mut device = WgDevice::default();
+
+    // private-key public-key listen-port fwmark
+    if let Some(line) = lines.next() {
+        let parts: Vec<&str> = line.split('\t').collect();
+        if parts.len() < 4 {
+            return Err(WgControlError::ToolOutput(line.to_string()));
+        }
+        device.private_key = WireGuardKey::from_base64(parts[0]).ok();
+        device.listen_port = parts[2].parse().unwrap_or(0);
+        device.fwmark = parts[3].parse().unwrap_or(0);
+    }
+
+    // public-key preshared-key endpoint allowed-ips latest-handshake transfer-rx transfer-tx persistent-keepalive
+    for line in lines {
+        let parts: Vec<&str> = line.split('\t').collect();
+        if parts.len() < 8 {
+            continue;
+        }
+        let Ok(public_key) = WireGuardKey::from_base64(parts[0]) else { continue };
+        device.peers.push(WgPeer {
+            public_key,
+            preshared_key: WireGuardKey::from_base64(parts[1]).ok(),
+            endpoint: parts[2].parse().ok(),
+            persistent_keepalive: parts[7].parse().unwrap_or(0),
+            allowed_ips: parts[3].split(',').filter_map(|ip| ip.parse().ok()).collect(),
+            last_handshake: parts[4].parse().unwrap_or(0),
+            rx_bytes: parts[5].parse().unwrap_or(0),
+            tx_bytes: parts[6].parse().unwrap_or(0),
+        });
+    }
+    Ok(device)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(byte: u8) -> WireGuardKey {
+        WireGuardKey([byte; 32])
+    }
+
+    fn net(value: &str) -> IpNet {
+        value.parse().unwrap()
+    }
+
+    fn device(peer: WgPeer) -> WgDevice {
+        WgDevice { private_key: Some(key(1)), listen_port: 51820, peers: vec![peer], ..Default::default() }
+    }
+
+    fn running_peer() -> WgPeer {
+        WgPeer {
+            public_key: key(2),
+            preshared_key: Some(key(3)),
+            endpoint: Some("203.0.113.5:51820".parse().unwrap()),
+            persistent_keepalive: 25,
+            allowed_ips: vec![net("10.0.34.2/32"), net("192.168.1.0/24")],
+            ..Default::default()
+        }
+    }
+
+    fn desired(peer: &WgPeer) -> WgDeviceUpdate {
+        WgDeviceUpdate {
+            private_key: Some(key(1)),
+            listen_port: Some(51820),
+            replace_peers: true,
+            peers: vec![WgPeerUpdate {
+                public_key: peer.public_key,
+                preshared_key: peer.preshared_key,
+                endpoint: peer.endpoint,
+                persistent_keepalive: Some(peer.persistent_keepalive),
+                replace_allowed_ips: true,
+                allowed_ips: peer.allowed_ips.clone(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn sync_peer(current: WgPeer, change: impl FnOnce(&mut WgPeer)) -> Option<WgPeerUpdate> {
+        let mut peer = current.clone();
+        change(&mut peer);
+        let update = sync_update(&device(current), desired(&peer));
+        assert!(update.private_key.is_none() && update.listen_port.is_none() && !update.replace_peers);
+        assert!(update.peers.len() <= 1);
+        update.peers.into_iter().next()
+    }
+
+    #[test]
+    fn sync_update_unchanged() {
+        assert!(sync_peer(running_peer(), |_| {}).is_none());
+        assert!(sync_update(&device(running_peer()), desired(&running_peer())).is_empty());
+    }
+
+    #[test]
+    fn sync_update_preshared_key() {
+        let added = sync_peer(WgPeer { preshared_key: None, ..running_peer() }, |p| p.preshared_key = Some(key(3))).unwrap();
+        assert_eq!(added.preshared_key, Some(key(3)));
+        assert!(added.endpoint.is_none() && added.persistent_keepalive.is_none() && !added.replace_allowed_ips);
+
+        let changed = sync_peer(running_peer(), |p| p.preshared_key = Some(key(4))).unwrap();
+        assert_eq!(changed.preshared_key, Some(key(4)));
+
+        let removed = sync_peer(running_peer(), |p| p.preshared_key = None).unwrap();
+        assert_eq!(removed.preshared_key, Some(WireGuardKey::default()));
+        assert!(!removed.remove);
+    }
+
+    #[test]
+    fn sync_update_endpoint_and_keepalive() {
+        let endpoint = sync_peer(running_peer(), |p| p.endpoint = Some("198.51.100.7:51821".parse().unwrap())).unwrap();
+        assert_eq!(endpoint.endpoint, Some("198.51.100.7:51821".parse().unwrap()));
+        assert!(endpoint.preshared_key.is_none() && endpoint.persistent_keepalive.is_none());
+
+        let keepalive = sync_peer(running_peer(), |p| p.persistent_keepalive = 0).unwrap();
+        assert_eq!(keepalive.persistent_keepalive, Some(0));
+        assert!(keepalive.endpoint.is_none() && !keepalive.replace_allowed_ips);
+    }
+
+    #[test]
+    fn sync_update_allowed_ips() {
+        assert!(sync_peer(running_peer(), |p| p.allowed_ips.reverse()).is_none());
+
+        let changed = sync_peer(running_peer(), |p| p.allowed_ips.push(net("fd00::/64"))).unwrap();
+        assert!(changed.replace_allowed_ips);
+        assert_eq!(changed.allowed_ips, vec![net("10.0.34.2/32"), net("192.168.1.0/24"), net("fd00::/64")]);
+    }
+
+    #[test]
+    fn sync_update_peers_added_and_removed() {
+        let new_peer = WgPeer { public_key: key(5), ..running_peer() };
+        let update = sync_update(&device(running_peer()), desired(&new_peer));
+        assert_eq!(update.peers.len(), 2);
+        assert!(update.peers[0].remove && update.peers[0].public_key == key(2));
+        assert!(!update.peers[1].remove && update.peers[1].public_key == key(5));
+        assert!(update.peers[1].replace_allowed_ips);
+    }
+
+    #[test]
+    fn uapi_device_reply() {
+        let reply = format!(
+            "private_key={}\nlisten_port=51820\nfwmark=0\n\
+            public_key={}\npreshared_key={}\nendpoint=[2001:db8::1]:51820\n\
+            last_handshake_time_sec=1700000000\nlast_handshake_time_nsec=0\ntx_bytes=42\nrx_bytes=1024\n\
+            persistent_keepalive_interval=25\nallowed_ip=10.0.34.2/32\nallowed_ip=fd00::2/128\n\
+            public_key={}\npreshared_key={}\npersistent_keepalive_interval=0\n\
+            errno=0\n\n",
+            "01".repeat(32), "02".repeat(32), "03".repeat(32), "0A".repeat(32), "00".repeat(32),
+        );
+        let device = uapi_device(reply.as_bytes()).unwrap();
+        assert_eq!(device.private_key, Some(key(1)));
+        assert_eq!(device.listen_port, 51820);
+        assert_eq!(device.peers.len(), 2);
+
+        let peer = &device.peers[0];
+        assert_eq!(peer.public_key, key(2));
+        assert_eq!(peer.preshared_key, Some(key(3)));
+        assert_eq!(peer.endpoint, Some("[2001:db8::1]:51820".parse().unwrap()));
+        assert_eq!(peer.persistent_keepalive, 25);
+        assert_eq!(peer.allowed_ips, vec![net("10.0.34.2/32"), net("fd00::2/128")]);
+        assert_eq!((peer.last_handshake, peer.rx_bytes, peer.tx_bytes), (1700000000, 1024, 42));
+
+        let peer = &device.peers[1];
+        assert_eq!(peer.public_key, key(10));
+        assert_eq!(peer.preshared_key, None);
+        assert_eq!(peer.endpoint, None);
+        assert!(peer.allowed_ips.is_empty());
+    }
+
+    #[test]
+    fn uapi_device_errno() {
+        assert!(matches!(uapi_device("errno=19\n\n".as_bytes()), Err(WgControlError::UapiErrno(19))));
+        assert!(matches!(uapi_device("listen_port\n\n".as_bytes()), Err(WgControlError::UapiReply(_))));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn netlink_messages_split() {
+        use netlink_packet_core::Emitable;
+        use netlink_packet_wireguard::{WireguardAttribute, WireguardDeviceFlags, WireguardPeerAttribute};
+
+        let mut update = WgDeviceUpdate { private_key: Some(key(1)), replace_peers: true, ..Default::default() };
+        for i in 0..500u32 {
+            let [_, _, b, c] = i.to_be_bytes();
+            update.peers.push(WgPeerUpdate {
+                public_key: WireGuardKey([[0, 0, b, c], [1; 4], [1; 4], [1; 4], [1; 4], [1; 4], [1; 4], [1; 4]].concat().try_into().unwrap()),
+                preshared_key: Some(key(3)),
+                persistent_keepalive: Some(25),
+                replace_allowed_ips: true,
+                allowed_ips: vec![net(&format!("10.{}.{}.0/24", b, c)), net(&format!("fd00::{:x}/128", i))],
+                ..Default::default()
+            });
+        }
+        // a peer whose allowed IPs alone span several messages
+        update.peers.push(WgPeerUpdate {
+            public_key: key(9),
+            replace_allowed_ips: true,
+            allowed_ips: (0..2000u32).map(|i| net(&format!("172.16.{}.{}/32", i / 256, i % 256))).collect(),
+            ..Default::default()
+        });
+
+        let messages = netlink_messages("wg0", &update);
+        assert!(messages.len() > 2);
+        let mut allowed_ips = std::collections::HashMap::new();
+        for (i, attributes) in messages.iter().enumerate() {
+            assert_eq!(attributes[0], WireguardAttribute::IfName("wg0".into()));
+            let replaces = attributes.contains(&WireguardAttribute::Flags(WireguardDeviceFlags::ReplacePeers));
+            assert_eq!(replaces, i == 0);
+            assert_eq!(attributes.iter().any(|a| matches!(a, WireguardAttribute::PrivateKey(_))), i == 0);
+
+            let Some(WireguardAttribute::Peers(peers)) = attributes.last() else { panic!("message {} has no peers", i) };
+            assert!(WireguardAttribute::Peers(peers.clone()).buffer_len() <= NETLINK_PEERS_BUDGET + 4);
+            for peer in peers {
+                let Some(WireguardPeerAttribute::PublicKey(public_key)) = peer.first() else { panic!("peer without key") };
+                // only the first part of a peer replaces its allowed IPs
+                let has_flags = peer.iter().any(|a| matches!(a, WireguardPeerAttribute::Flags(_)));
+                assert_eq!(has_flags, !allowed_ips.contains_key(public_key));
+                let count = allowed_ips.entry(*public_key).or_insert(0);
+                for attribute in peer.iter() {
+                    if let WireguardPeerAttribute::AllowedIps(ips) = attribute {
+                        *count += ips.len();
+                    }
+                }
+            }
+        }
+        assert_eq!(allowed_ips.len(), update.peers.len());
+        assert!(update.peers.iter().all(|peer| allowed_ips[&peer.public_key.0] == peer.allowed_ips.len()));
+    }
+}
@@ -1,11 +1,10 @@
 use std::collections::HashMap;
-use std::fs;
-use std::io::Write;
-use tempfile::NamedTempFile;
 use thiserror::Error;
 use wg_quickrs_lib::types::config::Config;
+use wg_quickrs_lib::helpers::get_peer_addresses;
 use wg_quickrs_lib::types::network::{Peer};
 use crate::helpers::{shell_cmd, ShellError};
+use crate::wireguard::wg_control::{WgControl, WgControlError};
 #[cfg(target_os = "macos")]
 use crate::wireguard::wg_quick_darwin as wg_quick_platform;
 #[cfg(target_os = "linux")]
@@ -30,6 +29,11 @@ pub enum TunnelError {
     WireGuardLibError(#[from] wg_quickrs_lib::types::misc::WireGuardLibError),
     #[error("{0}")]
     ShellError(#[from] ShellError),
+    #[error("{0}")]
+    WgControlError(#[from] WgControlError),
+    #[cfg(target_os = "linux")]
+    #[error("{0}")]
+    NetlinkError(#[from] crate::wireguard::netlink_linux::NetlinkError),
     #[cfg(target_os = "macos")]
     #[error("unable to find default gateway")]
     DefaultGatewayNotFound(),
@@ -104,6 +108,12 @@ impl TunnelManager {
         config.network.name.clone()
     }
 
+    fn wg_control(&self) -> WgControl {
+        let config = self.config.as_ref().unwrap();
+        let iface = self.real_interface.clone().unwrap_or_else(|| self.interface_name());
+        WgControl::new(config, &iface)
+    }
+
     fn this_peer(&self) -> TunnelResult<Peer> {
         let config = self.config.as_ref().unwrap();
 
@@ -117,6 +127,8 @@ impl TunnelManager {
         let config = self.config
             .clone()
             .ok_or_else(|| TunnelError::ConfigNotInitialized())?;
+        #[cfg(target_os = "linux")]
+        wg_quick_platform::set_network_backend(config.agent.vpn.network_backend);
 
         if self.interface_exists()? {
             return Err(TunnelError::InterfaceExists(self.interface_name()));
@@ -131,12 +143,12 @@ impl TunnelManager {
         self.add_addresses()?;
         self.set_mtu_and_up()?;
         self.add_routes()?;
-        let _wg = config.agent.vpn.wg.to_str().unwrap();
+        let _wg = self.wg_control();
         #[cfg(target_os = "macos")]
         {
             log::debug!("[#] Setting endpoint direct route to WireGuard interface: {}", self.interface_name());
             let iface = self.real_interface.as_ref().unwrap();
-            wg_quick_platform::set_endpoint_direct_route(_wg, iface, &mut self.endpoint_router)?;
+            wg_quick_platform::set_endpoint_direct_route(&_wg, iface, &mut self.endpoint_router)?;
         }
         self.set_dns()?;
         #[cfg(target_os = "macos")]
@@ -144,7 +156,7 @@ impl TunnelManager {
             let iface = self.real_interface.as_ref().unwrap();
             let this_peer = &self.this_peer()?;
 
-            wg_quick_platform::start_monitor_daemon(_wg, iface, &interface, &this_peer.dns, &this_peer.mtu, &self.endpoint_router, &self.dns_manager)?;
+            wg_quick_platform::start_monitor_daemon(&_wg, iface, &interface, &this_peer.dns, &this_peer.mtu, &self.endpoint_router, &self.dns_manager)?;
         }
         self.execute_hooks(HookType::PostUp)?;
 
@@ -157,9 +169,11 @@ impl TunnelManager {
     }
 
     pub fn stop_tunnel(&mut self) -> TunnelResult<()> {
-        let _ = self.config
+        let _config = self.config
             .clone()
             .ok_or_else(|| TunnelError::ConfigNotInitialized())?;
+        #[cfg(target_os = "linux")]
+        wg_quick_platform::set_network_backend(_config.agent.vpn.network_backend);
 
         if !self.interface_exists()? {
             log::debug!("Interface already deleted, skipping cleanup");
@@ -217,11 +231,10 @@ impl TunnelManager {
         let interface = self.interface_name();
         log::debug!("[#] Deleting WireGuard interface: {}", &interface);
 
-        let config = self.config.as_ref().unwrap();
-        let wg = config.agent.vpn.wg.to_str().unwrap();
+        let wg = self.wg_control();
         let mut dns_manager = self.dns_manager.clone();
         let mut endpoint_router = self.endpoint_router.clone();
-        wg_quick_platform::del_interface(wg, iface, &interface, &mut dns_manager, &mut endpoint_router)?;
+        wg_quick_platform::del_interface(&wg, iface, &interface, &mut dns_manager, &mut endpoint_router)?;
         self.dns_manager = dns_manager.clone();
         self.endpoint_router = endpoint_router.clone();
 
@@ -233,26 +246,20 @@ impl TunnelManager {
         let iface = self.real_interface.as_ref().unwrap();
 
         let this_peer = &self.this_peer()?;
-        let addresses = vec![this_peer.address];
-
         let config = self.config.as_ref().unwrap();
-        let subnet_slash = config.network.subnet.prefix_len();
 
-        for addr in addresses {
-            let addr_w_subnet = format!("{}/{}", addr, subnet_slash);
-            let is_ipv6 = addr_w_subnet.contains(':');
-            wg_quick_platform::add_address(iface, &addr_w_subnet, is_ipv6)?;
+        for addr_w_subnet in get_peer_addresses(&config.network, this_peer) {
+            wg_quick_platform::add_address(iface, &addr_w_subnet.to_string(), addr_w_subnet.addr().is_ipv6())?;
         }
         Ok(())
     }
 
     fn set_mtu_and_up(&self) -> TunnelResult<()> {
         log::debug!("[#] Setting MTU and bringing up WireGuard interface: {}", self.interface_name());
-        let config = self.config.as_ref().unwrap();
-        let wg = config.agent.vpn.wg.to_str().unwrap();
+        let wg = self.wg_control();
         let iface = self.real_interface.as_ref().unwrap();
 
-        wg_quick_platform::set_mtu_and_up(wg, iface, &self.this_peer()?.mtu)?;
+        wg_quick_platform::set_mtu_and_up(&wg, iface, &self.this_peer()?.mtu)?;
 
         Ok(())
     }
@@ -279,14 +286,13 @@ impl TunnelManager {
 
     fn add_routes(&mut self) -> TunnelResult<()> {
         log::debug!("[#] Adding routes to WireGuard interface: {}", self.interface_name());
-        let config = self.config.as_ref().unwrap();
-        let wg = config.agent.vpn.wg.to_str().unwrap();
+        let wg = self.wg_control();
         let iface = self.real_interface.as_ref().unwrap();
-        let allowed_ips = get_allowed_ips(wg, iface)?;
+        let allowed_ips = get_allowed_ips(&wg);
         let config = self.config.as_ref().unwrap();
 
         for cidr in allowed_ips {
-            wg_quick_platform::add_route(wg, iface, &config.network.name, &cidr, &mut self.endpoint_router)?;
+            wg_quick_platform::add_route(&wg, iface, &config.network.name, &cidr, &mut self.endpoint_router)?;
         }
 
         Ok(())
@@ -304,36 +310,13 @@ impl TunnelManager {
     fn set_config(&self) -> TunnelResult<()> {
         log::debug!("[#] Setting WireGuard interface configuration: {}", self.interface_name());
         let config = self.config.as_ref().unwrap();
-        let wg = config.agent.vpn.wg.to_str().unwrap();
-        let iface = self.real_interface.as_ref().unwrap();
-
-        let wg_config = wg_quickrs_lib::helpers::get_peer_wg_config(&config.network, &config.network.this_peer, true)?;
-
-        let mut temp_file = NamedTempFile::new()?;
-        writeln!(temp_file, "{}", wg_config)?;
-        shell_cmd(&[wg, "setconf", iface, &temp_file.path().to_string_lossy()])?;
-        let _ = fs::remove_file(&temp_file);
+        self.wg_control().set_config(config)?;
 
         Ok(())
     }
 
     fn is_wireguard_interface(&self) -> TunnelResult<bool> {
-        let config = self.config.as_ref().unwrap();
-        let wg = config.agent.vpn.wg.to_str().unwrap();
-        let output = shell_cmd(&[wg, "show", "interfaces"])?;
-
-        if !output.status.success() {
-            return Ok(false);
-        }
-
-        let output_str = String::from_utf8_lossy(&output.stdout);
-        let interfaces: Vec<&str> = output_str.split_whitespace().collect();
-
-        let interface_name = self.interface_name();
-        let real_iface = self.real_interface.as_deref();
-
-        Ok(interfaces.contains(&&*interface_name) ||
-            (real_iface.is_some() && interfaces.contains(&real_iface.unwrap())))
+        Ok(self.wg_control().get_device().is_ok())
     }
 
     fn execute_hooks(&self, hook_type: HookType) -> TunnelResult<()> {
@@ -404,64 +387,32 @@ pub(crate) enum HookType {
     PostDown,
 }
 
-fn extract_ip_from_endpoint(endpoint: &str) -> Option<String> {
-    if endpoint.starts_with('[')
-        && let Some(end) = endpoint.find(']') {
-            return Some(endpoint[1..end].to_string());
-        }
-
-    if let Some(colon_pos) = endpoint.rfind(':') {
-        return Some(endpoint[..colon_pos].to_string());
-    }
-
-    None
-}
-
-fn get_allowed_ips(wg: &str, iface: &str) -> TunnelResult<Vec<String>> {
-    let output = match shell_cmd(&[wg, "show", iface, "allowed-ips"]) {
-        Ok(output) => output,
+fn get_allowed_ips(wg: &WgControl) -> Vec<String> {
+    let device = match wg.get_device() {
+        Ok(device) => device,
         Err(e) => {
             log::warn!("Failed to get allowed IPs: {}, defaulting to an empty list of allowed IPs", e);
-            return Ok(Vec::new());
+            return Vec::new();
         }
     };
 
-    // Parse and collect valid CIDR entries
-    let mut cidrs: Vec<String> = String::from_utf8_lossy(&output.stdout)
-        .split_whitespace()
-        .filter(|s| wg_quickrs_lib::validation::network::parse_and_validate_ipv4_subnet(s).is_ok())
-        .map(String::from)
-        .collect();
+    let mut cidrs: Vec<ipnet::IpNet> = device.peers.into_iter().flat_map(|peer| peer.allowed_ips).collect();
 
     // Sort by prefix length (descending)
-    cidrs.sort_by(|a, b| {
-        let prefix_a = a.split('/').nth(1).and_then(|p| p.parse::<u8>().ok()).unwrap_or(0);
-        let prefix_b = b.split('/').nth(1).and_then(|p| p.parse::<u8>().ok()).unwrap_or(0);
-        prefix_b.cmp(&prefix_a)
-    });
+    cidrs.sort_by_key(|cidr| std::cmp::Reverse(cidr.prefix_len()));
 
-    Ok(cidrs)
+    cidrs.iter().map(|cidr| cidr.to_string()).collect()
 }
 
-pub fn get_endpoints(wg: &str, iface: &str) -> Vec<String> {
-    let output = match shell_cmd(&[wg, "show", iface, "endpoints"]) {
-        Ok(output) => output,
+pub fn get_endpoints(wg: &WgControl) -> Vec<String> {
+    match wg.get_device() {
+        Ok(device) => device.peers.iter()
+            .filter_map(|peer| peer.endpoint)
+            .map(|endpoint| endpoint.ip().to_string())
+            .collect(),
         Err(e) => {
             log::warn!("Failed to get endpoints: {}, defaulting to an empty list of endpoints", e);
-            return Vec::new();
+            Vec::new()
         }
-    };
-
-    let output_str = String::from_utf8_lossy(&output.stdout);
-    let mut endpoints = Vec::new();
-
-    for line in output_str.lines() {
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        if parts.len() > 1
-            && let Some(ip) = extract_ip_from_endpoint(parts[1]) {
-                endpoints.push(ip.clone());
-            }
     }
-
-    endpoints
 }
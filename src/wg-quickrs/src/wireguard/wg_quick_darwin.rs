@@ -1,7 +1,7 @@
 #![cfg(target_os = "macos")]
 use std::collections::HashMap;
 use std::fs;
-use std::net::Ipv4Addr;
+use std::net::IpAddr;
 use std::os::unix::fs::FileTypeExt;
 use std::os::unix::process::CommandExt;
 use std::process::Command;
@@ -10,6 +10,7 @@ use log::{log_enabled, Level};
 use regex::Regex;
 use wg_quickrs_lib::types::network::{Dns, Mtu};
 use crate::helpers::shell_cmd;
+use crate::wireguard::wg_control::WgControl;
 use crate::wireguard::wg_quick;
 use crate::wireguard::wg_quick::{DnsManager, EndpointRouter, TunnelError, TunnelResult};
 
@@ -100,7 +101,7 @@ pub fn add_interface(interface: &str, userspace: bool, userspace_binary: &str) -
     Ok(iface)
 }
 
-pub fn del_interface(_wg: &str, iface: &str, interface: &str, _dns_manager: &mut DnsManager, _endpoint_router: &mut EndpointRouter) -> TunnelResult<()> {
+pub fn del_interface(_wg: &WgControl, iface: &str, interface: &str, _dns_manager: &mut DnsManager, _endpoint_router: &mut EndpointRouter) -> TunnelResult<()> {
     let sock_file = format!("/var/run/wireguard/{}.sock", iface);
     let _ = fs::remove_file(sock_file);
 
@@ -121,7 +122,7 @@ pub fn add_address(iface: &str, addr: &str, is_ipv6: bool) -> TunnelResult<()> {
     Ok(())
 }
 
-pub fn set_mtu_and_up(_wg: &str, iface: &str, mtu: &Mtu) -> TunnelResult<()> {
+pub fn set_mtu_and_up(_wg: &WgControl, iface: &str, mtu: &Mtu) -> TunnelResult<()> {
     set_mtu(iface, mtu)?;
     shell_cmd(&["ifconfig", iface, "up"])?;
 
@@ -233,7 +234,7 @@ fn get_search_domains(service: &str) -> TunnelResult<String> {
     })
 }
 
-pub fn set_dns(dns_servers: &[Ipv4Addr], _interface: &str, dns_manager: &mut DnsManager) -> TunnelResult<()> {
+pub fn set_dns(dns_servers: &[IpAddr], _interface: &str, dns_manager: &mut DnsManager) -> TunnelResult<()> {
     collect_services(dns_manager)?;
 
     for service in dns_manager.service_dns.keys() {
@@ -269,7 +270,7 @@ pub fn del_dns(_interface: &str, dns_manager: &mut DnsManager) -> TunnelResult<(
     Ok(())
 }
 
-pub fn add_route(_wg: &str, iface: &str, _interface_name: &str, cidr: &str, endpoint_router: &mut EndpointRouter) -> TunnelResult<()> {
+pub fn add_route(_wg: &WgControl, iface: &str, _interface_name: &str, cidr: &str, endpoint_router: &mut EndpointRouter) -> TunnelResult<()> {
     let is_default = cidr.ends_with("/0");
     let is_ipv6 = cidr.contains(':');
 
@@ -302,14 +303,14 @@ pub fn add_route(_wg: &str, iface: &str, _interface_name: &str, cidr: &str, endp
     Ok(())
 }
 
-pub fn set_endpoint_direct_route(wg: &str, iface: &str, endpoint_router: &mut EndpointRouter) -> TunnelResult<()> {
+pub fn set_endpoint_direct_route(wg: &WgControl, iface: &str, endpoint_router: &mut EndpointRouter) -> TunnelResult<()> {
     let mut old_endpoints = endpoint_router.endpoints.clone();
     let old_gateway4 = endpoint_router.gateway4.clone();
     let old_gateway6 = endpoint_router.gateway6.clone();
 
     endpoint_router.gateway4 = get_default_gateway(false).ok();
     endpoint_router.gateway6 = get_default_gateway(true).ok();
-    endpoint_router.endpoints = wg_quick::get_endpoints(wg, iface);
+    endpoint_router.endpoints = wg_quick::get_endpoints(wg);
 
     // Check if gateways changed
     let remove_all_old = old_gateway4 != endpoint_router.gateway4 || old_gateway6 != endpoint_router.gateway6;
@@ -385,8 +386,8 @@ fn get_default_gateway(ipv6: bool) -> TunnelResult<String> {
     Err(TunnelError::DefaultGatewayNotFound())
 }
 
-pub fn start_monitor_daemon(wg: &str, iface: &str, interface_name: &str, dns: &Dns, mtu: &Mtu, endpoint_router: &EndpointRouter, dns_manager: &DnsManager) -> TunnelResult<()> {
-    let wg_clone = wg.to_string();
+pub fn start_monitor_daemon(wg: &WgControl, iface: &str, interface_name: &str, dns: &Dns, mtu: &Mtu, endpoint_router: &EndpointRouter, dns_manager: &DnsManager) -> TunnelResult<()> {
+    let wg_clone = wg.clone();
     let iface_clone = iface.to_string();
     let interface_name_clone = interface_name.to_string();
     let dns_clone = dns.clone();
@@ -421,7 +422,7 @@ pub fn start_monitor_daemon(wg: &str, iface: &str, interface_name: &str, dns: &D
 }
 
 fn monitor_daemon_worker(
-    wg: String,
+    wg: WgControl,
     real_iface: String,
     _interface: String,
     dns: Dns,
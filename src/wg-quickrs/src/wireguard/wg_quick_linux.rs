@@ -4,17 +4,54 @@ use std::process::Command;
 use std::fs;
 use std::path::Path;
 use std::env;
-use std::net::Ipv4Addr;
+use std::net::IpAddr;
 use std::os::unix::prelude::CommandExt;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
+use ipnet::IpNet;
 use log::{log_enabled, Level};
+use netlink_packet_route::AddressFamily;
 use regex::Regex;
+use wg_quickrs_lib::types::config::NetworkBackend;
 use wg_quickrs_lib::types::network::Mtu;
 use crate::helpers::shell_cmd;
+use crate::wireguard::netlink_linux::{rule_suppresses_default, rule_table, RtNetlink, RT_TABLE_MAIN};
+use crate::wireguard::wg_control::WgControl;
 use crate::wireguard::wg_quick;
 use crate::wireguard::wg_quick::{DnsManager, TunnelError, TunnelResult};
 
+static USE_NETLINK: AtomicBool = AtomicBool::new(true);
+
+pub fn set_network_backend(backend: NetworkBackend) {
+    USE_NETLINK.store(backend == NetworkBackend::Netlink, Ordering::Relaxed);
+}
+
+/// Opens an rtnetlink connection when the netlink backend is selected.
+/// Returns None for the shell backend, or if the socket can't be opened (then the shell backend is used from now on).
+fn netlink() -> Option<RtNetlink> {
+    if !USE_NETLINK.load(Ordering::Relaxed) {
+        return None;
+    }
+    match RtNetlink::open() {
+        Ok(nl) => Some(nl),
+        Err(e) => {
+            log::warn!("[!] {}, falling back to the shell network backend", e);
+            USE_NETLINK.store(false, Ordering::Relaxed);
+            None
+        }
+    }
+}
+
+fn parse_cidr(cidr: &str) -> TunnelResult<IpNet> {
+    cidr.parse::<IpNet>()
+        .map_err(|e| TunnelError::InvalidConfig(format!("Invalid CIDR {}: {}", cidr, e)))
+}
+
 pub fn interface_exists(interface: &str) -> TunnelResult<Option<String>> {
+    if let Some(mut nl) = netlink() {
+        return Ok(nl.link_index(interface)?.map(|_| interface.to_string()));
+    }
+
     let output = shell_cmd(&["ip", "link", "show", "dev", interface]);
 
     if output.is_ok() {
@@ -53,6 +90,12 @@ pub fn add_interface(interface: &str, userspace: bool, userspace_binary: &str) -
             log::debug!("[+] {} exit", userspace_binary_cloned);
         });
         std::thread::sleep(Duration::from_millis(500)); // TODO: replace with a better solution
+    } else if let Some(mut nl) = netlink() {
+        log::debug!("[+] netlink: ip link add {} type wireguard", interface);
+        if let Err(e) = nl.add_wireguard_link(interface) {
+            log::error!("[!] Missing WireGuard kernel module. Please install and/or load the WireGuard kernel module and try again.");
+            return Err(e.into());
+        }
     } else {
         let result = shell_cmd(&["ip", "link", "add", interface, "type", "wireguard"]);
 
@@ -68,25 +111,80 @@ pub fn add_interface(interface: &str, userspace: bool, userspace_binary: &str) -
 }
 
 pub fn add_address(iface: &str, addr: &str, is_ipv6: bool) -> TunnelResult<()> {
+    if let Some(mut nl) = netlink() {
+        log::debug!("[+] netlink: ip address add {} dev {}", addr, iface);
+        nl.add_address(iface, &parse_cidr(addr)?)?;
+        return Ok(());
+    }
+
     let proto = if is_ipv6 { "-6" } else { "-4" };
     shell_cmd(&["ip", proto, "address", "add", addr, "dev", iface])?;
     Ok(())
 }
 
-pub fn set_mtu_and_up(wg: &str, iface: &str, mtu: &Mtu) -> TunnelResult<()> {
+pub fn set_mtu_and_up(wg: &WgControl, iface: &str, mtu: &Mtu) -> TunnelResult<()> {
     let mtu_val = if mtu.enabled {
-        mtu.value.to_string()
+        mtu.value
     } else {
-        calculate_mtu(wg, iface).unwrap_or(1420).to_string()
+        calculate_mtu(wg).unwrap_or(1420)
     };
 
-    shell_cmd(&["ip", "link", "set", "mtu", &mtu_val, "up", "dev", iface])?;
+    if let Some(mut nl) = netlink() {
+        log::debug!("[+] netlink: ip link set mtu {} up dev {}", mtu_val, iface);
+        nl.set_link_mtu_up(iface, mtu_val as u32)?;
+        return Ok(());
+    }
+    shell_cmd(&["ip", "link", "set", "mtu", &mtu_val.to_string(), "up", "dev", iface])?;
 
     Ok(())
 }
 
-fn calculate_mtu(wg: &str, iface: &str) -> TunnelResult<u16> {
-    let endpoints = wg_quick::get_endpoints(wg, iface);
+fn calculate_mtu(wg: &WgControl) -> TunnelResult<u16> {
+    let mut min_mtu = match netlink() {
+        Some(mut nl) => min_endpoint_mtu_netlink(&mut nl, wg)?,
+        None => min_endpoint_mtu(wg)?,
+    };
+
+    if !(min_mtu > 80 && min_mtu < u16::MAX) {
+        min_mtu = 1500;
+    }
+
+    Ok(min_mtu.saturating_sub(80))
+}
+
+fn min_endpoint_mtu_netlink(nl: &mut RtNetlink, wg: &WgControl) -> TunnelResult<u16> {
+    let mut min_mtu = u16::MAX;
+
+    for endpoint in wg_quick::get_endpoints(wg) {
+        let Ok(addr) = endpoint.parse::<IpAddr>() else { continue };
+        let route = nl.route_get(addr)?;
+        if let Some(mtu) = route_path_mtu(nl, route)? {
+            min_mtu = min_mtu.min(mtu);
+        }
+    }
+
+    if min_mtu == u16::MAX {
+        let route = nl.default_route()?;
+        if let Some(mtu) = route_path_mtu(nl, route)? {
+            min_mtu = min_mtu.min(mtu);
+        }
+    }
+
+    Ok(min_mtu)
+}
+
+// Same as extract_mtu: the route's own MTU, otherwise the MTU of its outgoing link
+fn route_path_mtu(nl: &mut RtNetlink, route: Option<(Option<u32>, Option<u32>)>) -> TunnelResult<Option<u16>> {
+    let mtu = match route {
+        Some((_, Some(mtu))) => Some(mtu),
+        Some((Some(oif), None)) => nl.link_mtu(oif)?,
+        _ => None,
+    };
+    Ok(mtu.map(|m| u16::try_from(m).unwrap_or(u16::MAX)))
+}
+
+fn min_endpoint_mtu(wg: &WgControl) -> TunnelResult<u16> {
+    let endpoints = wg_quick::get_endpoints(wg);
     let mut min_mtu = u16::MAX;
 
     // Regex patterns
@@ -116,11 +214,7 @@ fn calculate_mtu(wg: &str, iface: &str) -> TunnelResult<u16> {
         }
     }
 
-    if !(min_mtu > 80 && min_mtu < u16::MAX) {
-        min_mtu = 1500;
-    }
-
-    Ok(min_mtu.saturating_sub(80))
+    Ok(min_mtu)
 }
 
 fn extract_mtu(output: &str, mtu_regex: &Regex, dev_regex: &Regex) -> Option<u16> {
@@ -190,7 +284,7 @@ fn resolvconf_iface_prefix() -> String {
     String::new()
 }
 
-pub fn set_dns(dns_servers: &Vec<Ipv4Addr>, interface: &str, dns_manager: &mut DnsManager) -> TunnelResult<()> {
+pub fn set_dns(dns_servers: &[IpAddr], interface: &str, dns_manager: &mut DnsManager) -> TunnelResult<()> {
     dns_manager.have_set_dns = false;
     if dns_servers.is_empty() {
         return Ok(());
@@ -217,7 +311,7 @@ pub fn set_dns(dns_servers: &Vec<Ipv4Addr>, interface: &str, dns_manager: &mut D
     Ok(())
 }
 
-pub fn add_route(wg: &str, iface: &str, interface_name: &str, cidr: &str, endpoint_router: &mut wg_quick::EndpointRouter) -> TunnelResult<()> {
+pub fn add_route(wg: &WgControl, iface: &str, interface_name: &str, cidr: &str, endpoint_router: &mut wg_quick::EndpointRouter) -> TunnelResult<()> {
     endpoint_router.have_set_firewall = false;
     let is_default = cidr.ends_with("/0");
     
@@ -225,6 +319,15 @@ pub fn add_route(wg: &str, iface: &str, interface_name: &str, cidr: &str, endpoi
         add_default_route(wg, interface_name, cidr)?;
         endpoint_router.have_set_firewall = true;
     } else {
+        if let Some(mut nl) = netlink() {
+            let net = parse_cidr(cidr)?;
+            if !nl.route_exists(iface, &net)? {
+                log::debug!("[+] netlink: ip route add {} dev {}", cidr, iface);
+                nl.add_route(iface, &net, RT_TABLE_MAIN)?;
+            }
+            return Ok(());
+        }
+
         let is_ipv6 = cidr.contains(':');
         let proto = if is_ipv6 { "-6" } else { "-4" };
 
@@ -237,27 +340,36 @@ pub fn add_route(wg: &str, iface: &str, interface_name: &str, cidr: &str, endpoi
     Ok(())
 }
 
-fn get_fwmark(wg: &str, interface: &str) -> TunnelResult<u16> {
-    let output = shell_cmd(&[wg, "show", interface, "fwmark"])?;
-    let fwmark_str = String::from_utf8_lossy(&output.stdout).trim().to_string();
+fn get_fwmark(wg: &WgControl) -> TunnelResult<u16> {
+    let fwmark = wg.get_device()?.fwmark;
 
-    if fwmark_str.is_empty() || fwmark_str == "off" {
+    if fwmark == 0 {
         return Err(TunnelError::InvalidConfig("No fwmark set".into()));
     }
 
-    fwmark_str.parse().map_err(|e| {
+    u16::try_from(fwmark).map_err(|e| {
         TunnelError::InvalidConfig(format!("Invalid fwmark value: {}", e))
     })
 }
 
 fn find_unused_table() -> TunnelResult<u16> {
     let mut table = 51820u16;
+    let mut nl = netlink();
 
     loop {
-        let ipv4_check = shell_cmd(&["ip", "-4", "route", "show", "table", &table.to_string()])?;
-        let ipv6_check = shell_cmd(&["ip", "-6", "route", "show", "table", &table.to_string()])?;
+        let unused = match nl.as_mut() {
+            Some(nl) => {
+                !nl.table_has_routes(AddressFamily::Inet, table as u32)?
+                    && !nl.table_has_routes(AddressFamily::Inet6, table as u32)?
+            }
+            None => {
+                let ipv4_check = shell_cmd(&["ip", "-4", "route", "show", "table", &table.to_string()])?;
+                let ipv6_check = shell_cmd(&["ip", "-6", "route", "show", "table", &table.to_string()])?;
+                ipv4_check.stdout.is_empty() && ipv6_check.stdout.is_empty()
+            }
+        };
 
-        if ipv4_check.stdout.is_empty() && ipv6_check.stdout.is_empty() {
+        if unused {
             return Ok(table);
         }
 
@@ -271,16 +383,17 @@ fn find_unused_table() -> TunnelResult<u16> {
     }
 }
 
-pub fn add_default_route(wg: &str, interface: &str, cidr: &str) -> TunnelResult<()> {
+pub fn add_default_route(wg: &WgControl, interface: &str, cidr: &str) -> TunnelResult<()> {
     // Get or create fwmark/table
-    let table = &match get_fwmark(wg, interface) {
+    let table_id = match get_fwmark(wg) {
         Ok(mark) => mark,
         Err(_) => {
             let table = find_unused_table()?;
-            shell_cmd(&[wg, "set", interface, "fwmark", &table.to_string()])?;
+            wg.set_fwmark(table as u32)?;
             table
         }
-    }.to_string();
+    };
+    let table = &table_id.to_string();
 
     // Detect IPv4 vs IPv6
     let is_ipv6 = cidr.contains(':');
@@ -288,10 +401,22 @@ pub fn add_default_route(wg: &str, interface: &str, cidr: &str) -> TunnelResult<
     let iptables = if is_ipv6 { "ip6tables" } else { "iptables" };
     let pf = if is_ipv6 { "ip6" } else { "ip" };
 
+    let family = if is_ipv6 { AddressFamily::Inet6 } else { AddressFamily::Inet };
+    let mut nl = netlink();
+
     // Add routing rules
-    shell_cmd(&["ip", proto, "rule", "add", "not", "fwmark", table, "table", table])?;
-    shell_cmd(&["ip", proto, "rule", "add", "table", "main", "suppress_prefixlength", "0"])?;
-    shell_cmd(&["ip", proto, "route", "add", cidr, "dev", interface, "table", table])?;
+    if let Some(nl) = nl.as_mut() {
+        log::debug!("[+] netlink: ip {} rule add not fwmark {} table {}", proto, table, table);
+        nl.add_rule_not_fwmark(family, table_id as u32)?;
+        log::debug!("[+] netlink: ip {} rule add table main suppress_prefixlength 0", proto);
+        nl.add_rule_suppress_main(family)?;
+        log::debug!("[+] netlink: ip {} route add {} dev {} table {}", proto, cidr, interface, table);
+        nl.add_route(interface, &parse_cidr(cidr)?, table_id as u32)?;
+    } else {
+        shell_cmd(&["ip", proto, "rule", "add", "not", "fwmark", table, "table", table])?;
+        shell_cmd(&["ip", proto, "rule", "add", "table", "main", "suppress_prefixlength", "0"])?;
+        shell_cmd(&["ip", proto, "route", "add", cidr, "dev", interface, "table", table])?;
+    }
 
     // Build firewall rules
     let marker = format!("-m comment --comment \"wg-quickrs rule for {}\"", interface);
@@ -316,28 +441,33 @@ pub fn add_default_route(wg: &str, interface: &str, cidr: &str) -> TunnelResult<
     ));
 
     // Get interface addresses and add anti-spoofing rules
-    let addr_output = shell_cmd(&["ip", "-o", proto, "addr", "show", "dev", interface])?;
-
-    let addr_str = String::from_utf8_lossy(&addr_output.stdout);
-    let addr_regex = Regex::new(r".*inet6?\\ ([0-9a-f:.]+)/[0-9]+.*")
-        .map_err(|e| TunnelError::InvalidConfig(format!("Regex error: {}", e)))?;
-
-    for line in addr_str.lines() {
-        if let Some(caps) = addr_regex.captures(line) {
-            let ip_addr = &caps[1];
+    let ip_addrs: Vec<String> = match nl.as_mut() {
+        Some(nl) => nl.addresses(interface, family)?.iter().map(|ip| ip.to_string()).collect(),
+        None => {
+            let addr_output = shell_cmd(&["ip", "-o", proto, "addr", "show", "dev", interface])?;
+
+            let addr_str = String::from_utf8_lossy(&addr_output.stdout);
+            let addr_regex = Regex::new(r".*inet6?\\ ([0-9a-f:.]+)/[0-9]+.*")
+                .map_err(|e| TunnelError::InvalidConfig(format!("Regex error: {}", e)))?;
+
+            addr_str.lines()
+                .filter_map(|line| addr_regex.captures(line).map(|caps| caps[1].to_string()))
+                .collect()
+        }
+    };
 
-            // iptables rule
-            restore.push_str(&format!(
-                "-I PREROUTING ! -i {} -d {} -m addrtype ! --src-type LOCAL -j DROP {}\n",
-                interface, ip_addr, marker
-            ));
+    for ip_addr in &ip_addrs {
+        // iptables rule
+        restore.push_str(&format!(
+            "-I PREROUTING ! -i {} -d {} -m addrtype ! --src-type LOCAL -j DROP {}\n",
+            interface, ip_addr, marker
+        ));
 
-            // nftables rule
-            nftcmd.push_str(&format!(
-                "add rule {} {} preraw iifname != \"{}\" {} daddr {} fib saddr type != local drop\n",
-                pf, nftable, interface, pf, ip_addr
-            ));
-        }
+        // nftables rule
+        nftcmd.push_str(&format!(
+            "add rule {} {} preraw iifname != \"{}\" {} daddr {} fib saddr type != local drop\n",
+            pf, nftable, interface, pf, ip_addr
+        ));
     }
 
     // Connection marking rules
@@ -361,7 +491,12 @@ pub fn add_default_route(wg: &str, interface: &str, cidr: &str) -> TunnelResult<
 
     // Enable source validation for IPv4
     if !is_ipv6 {
-        let _ = shell_cmd(&["sysctl", "-q", "net.ipv4.conf.all.src_valid_mark=1"]);
+        if nl.is_some() {
+            log::debug!("[+] netlink: sysctl -q net.ipv4.conf.all.src_valid_mark=1");
+            let _ = fs::write("/proc/sys/net/ipv4/conf/all/src_valid_mark", "1");
+        } else {
+            let _ = shell_cmd(&["sysctl", "-q", "net.ipv4.conf.all.src_valid_mark=1"]);
+        }
     }
 
     // Apply firewall rules - prefer nftables if available
@@ -500,6 +635,16 @@ fn remove_iptables(interface: &str) -> TunnelResult<()> {
 }
 
 fn remove_routing_rules(table: u16) -> TunnelResult<()> {
+    if let Some(mut nl) = netlink() {
+        for family in [AddressFamily::Inet, AddressFamily::Inet6] {
+            log::debug!("[+] netlink: ip rule delete table {}", table);
+            nl.del_rules(family, |rule| rule_table(rule) == table as u32)?;
+            log::debug!("[+] netlink: ip rule delete table main suppress_prefixlength 0");
+            nl.del_rules(family, |rule| rule_table(rule) == RT_TABLE_MAIN && rule_suppresses_default(rule))?;
+        }
+        return Ok(());
+    }
+
     for proto in ["-4", "-6"] {
         // Remove IPv4/IPv6 rules
         remove_rules_matching(proto, &format!("lookup {}", table), &[
@@ -529,7 +674,7 @@ fn remove_rules_matching(proto: &str, pattern: &str, delete_cmd: &[&str]) -> Tun
     Ok(())
 }
 
-pub fn del_interface(wg: &str, _iface: &str, interface: &str, dns_manager: &mut DnsManager, endpoint_router: &mut wg_quick::EndpointRouter) -> TunnelResult<()> {
+pub fn del_interface(wg: &WgControl, _iface: &str, interface: &str, dns_manager: &mut DnsManager, endpoint_router: &mut wg_quick::EndpointRouter) -> TunnelResult<()> {
     // Unset DNS if it was configured
     if dns_manager.have_set_dns {
         del_dns(interface, dns_manager)?;
@@ -539,7 +684,7 @@ pub fn del_interface(wg: &str, _iface: &str, interface: &str, dns_manager: &mut
     if endpoint_router.have_set_firewall {
         remove_nftables(interface)?;
         remove_iptables(interface)?;
-        if let Ok(table) = get_fwmark(wg, interface) {
+        if let Ok(table) = get_fwmark(wg) {
             remove_routing_rules(table)?;
         } else {
             log::warn!("Failed to get fwmark for interface {}. Skipping routing rules cleanup.", interface);
@@ -548,7 +693,12 @@ pub fn del_interface(wg: &str, _iface: &str, interface: &str, dns_manager: &mut
     }
 
     // Delete the interface
-    shell_cmd(&["ip", "link", "delete", "dev", interface])?;
+    if let Some(mut nl) = netlink() {
+        log::debug!("[+] netlink: ip link delete dev {}", interface);
+        nl.del_link(interface)?;
+    } else {
+        shell_cmd(&["ip", "link", "delete", "dev", interface])?;
+    }
     Ok(())
 }
 
@@ -0,0 +1,137 @@
+import base64
+import hashlib
+import hmac
+import stat
+import subprocess
+import requests
+
+from tests.pytest.conftest import setup_wg_quickrs_agent
+from tests.pytest.helpers import get_paths, get_token, get_wg_quickrs_command, get_test_peer_data
+
+THIS_PEER_ID = "0ed989c6-6dba-4e3c-8034-08adf4262d9e"
+
+
+def auth(token):
+    return { "Authorization": f"Bearer {token}" }
+
+
+def run_config_command(*args):
+    result = subprocess.run(get_wg_quickrs_command() + ["config"] + list(args), capture_output=True, text=True)
+    assert result.returncode == 0, result.stderr
+    return result.stdout.strip().splitlines()
+
+
+def add_api_key(name, role):
+    return run_config_command("add", "api-key", name, "--role", role)[-1]
+
+
+def test_api_key_role(setup_wg_quickrs_agent):
+    """Test an API key acts with its role without signing in and records when it was used."""
+    base_url = setup_wg_quickrs_agent("test_pwd_single_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    verify = wg_quickrs_config_folder / "certs/root/rootCA.crt"
+    api_key = add_api_key("ci-deploy", "operator")
+    assert api_key.startswith("wgq_")
+    assert run_config_command("list", "api-keys")[-1].endswith("last used: never")
+
+    response = requests.get(f"{base_url}/api/network/summary?only_digest=false", headers=auth(api_key), verify=verify)
+    assert response.status_code == 200
+
+    reservation = requests.post(f"{base_url}/api/network/reserve/address", headers=auth(api_key), verify=verify).json()
+    peer_data = get_test_peer_data()
+    peer_data["address"] = reservation["address"]
+    response = requests.patch(f"{base_url}/api/network/config",
+                              json={ "added_peers": { reservation["peer_id"]: peer_data } },
+                              headers=auth(api_key),
+                              verify=verify)
+    assert response.status_code == 200, response.text
+    history = requests.get(f"{base_url}/api/config/history", headers=auth(api_key), verify=verify).json()
+    assert history[-1]["author"] == "api:api-key:ci-deploy"
+
+    response = requests.post(f"{base_url}/api/config/history/1/rollback", headers=auth(api_key), verify=verify)
+    assert response.status_code == 403
+
+    line = run_config_command("list", "api-keys")[-1]
+    assert line.startswith("ci-deploy (operator) created: ")
+    assert not line.endswith("last used: never")
+
+
+def test_api_key_revoked(setup_wg_quickrs_agent):
+    """Test an API key stops working once it is removed."""
+    base_url = setup_wg_quickrs_agent("test_pwd_single_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    verify = wg_quickrs_config_folder / "certs/root/rootCA.crt"
+    api_key = add_api_key("terraform", "viewer")
+
+    response = requests.get(f"{base_url}/api/version", headers=auth(api_key), verify=verify)
+    assert response.status_code == 200
+    run_config_command("remove", "api-key", "terraform")
+    response = requests.get(f"{base_url}/api/version", headers=auth(api_key), verify=verify)
+    assert response.status_code == 401
+
+
+def test_api_key_invalid(setup_wg_quickrs_agent):
+    """Test unknown or malformed API keys are refused."""
+    base_url = setup_wg_quickrs_agent("test_pwd_single_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    api_key = add_api_key("ci-deploy", "admin")
+
+    for invalid_key in [api_key.split(".")[0] + ".wrong", "wgq_unknown.secret", "wgq_no-separator"]:
+        response = requests.get(f"{base_url}/api/version",
+                                headers=auth(invalid_key),
+                                verify=wg_quickrs_config_folder / "certs/root/rootCA.crt")
+        assert response.status_code == 401
+
+
+def test_api_key_lockout(setup_wg_quickrs_agent):
+    """Test repeated wrong API keys lock the client out like failed logins."""
+    base_url = setup_wg_quickrs_agent("test_pwd_single_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    verify = wg_quickrs_config_folder / "certs/root/rootCA.crt"
+    api_key = add_api_key("ci-deploy", "viewer")
+
+    for _ in range(6):
+        response = requests.get(f"{base_url}/api/version", headers=auth(api_key + "wrong"), verify=verify)
+        assert response.status_code == 401
+    response = requests.get(f"{base_url}/api/version", headers=auth(api_key), verify=verify)
+    assert response.status_code == 429
+    assert int(response.headers["Retry-After"]) >= 1
+
+
+def test_persistent_jwt_key(setup_wg_quickrs_agent):
+    """Test JWTs are signed with the key kept in the config folder once it is enabled."""
+    base_url = setup_wg_quickrs_agent("test_pwd_single_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    jwt_key_file = wg_quickrs_config_folder / "jwt.key"
+    get_token(base_url)
+    assert not jwt_key_file.exists()
+
+    run_config_command("enable", "agent", "web", "persistent-jwt-key")
+    token = get_token(base_url)
+    assert stat.S_IMODE(jwt_key_file.stat().st_mode) == 0o600
+
+    signing_input, signature = token.rsplit(".", 1)
+    expected = hmac.new(jwt_key_file.read_bytes(), signing_input.encode(), hashlib.sha256).digest()
+    assert signature == base64.urlsafe_b64encode(expected).rstrip(b"=").decode()
+
+    response = requests.get(f"{base_url}/api/version",
+                            headers=auth(token),
+                            verify=wg_quickrs_config_folder / "certs/root/rootCA.crt")
+    assert response.status_code == 200
+
+
+def test_api_key_revoked_rollback(setup_wg_quickrs_agent):
+    """Test rolling back to a revision from before an API key was removed does not bring the key back."""
+    base_url = setup_wg_quickrs_agent("test_pwd_single_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    verify = wg_quickrs_config_folder / "certs/root/rootCA.crt"
+    api_key = add_api_key("terraform", "admin")
+    admin = auth(get_token(base_url))
+    revision_with_key = requests.get(f"{base_url}/api/config/history", headers=admin, verify=verify).json()[-1]["revision"]
+    run_config_command("remove", "api-key", "terraform")
+
+    response = requests.post(f"{base_url}/api/config/history/{revision_with_key}/rollback", headers=admin, verify=verify)
+    assert response.status_code == 200, response.text
+    response = requests.get(f"{base_url}/api/version", headers=auth(api_key), verify=verify)
+    assert response.status_code == 401
+    assert run_config_command("list", "api-keys")[-1] == "No API keys found."
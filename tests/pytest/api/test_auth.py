@@ -1,7 +1,9 @@
+import subprocess
+import time
 import pytest
 
 from tests.pytest.conftest import setup_wg_quickrs_agent
-from tests.pytest.helpers import get_paths, get_token
+from tests.pytest.helpers import get_paths, get_token, get_wg_quickrs_command
 import requests
 
 
@@ -22,6 +24,32 @@ def test_api_token(setup_wg_quickrs_agent):
     _ = get_token(base_url)
 
 
+def test_api_token_lockout(setup_wg_quickrs_agent):
+    """Test POST /api/token locks out a client after repeated failed logins."""
+    base_url = setup_wg_quickrs_agent("test_pwd_single_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    verify = wg_quickrs_config_folder / "certs/root/rootCA.crt"
+
+    def post_token(client_id, password):
+        return requests.post(f"{base_url}/api/token", json={ "client_id": client_id, "password": password }, verify=verify)
+
+    # the first few failures are free, the next one locks the client out
+    for _ in range(6):
+        assert post_token("pytest", "...").status_code == 401
+
+    # even the correct password is refused until the lockout ends, also for other accounts of the same IP
+    for client_id in ["pytest", "other"]:
+        response = post_token(client_id, "test")
+        assert response.status_code == 429
+        assert int(response.headers["Retry-After"]) >= 1
+
+    time.sleep(int(response.headers["Retry-After"]))
+    assert post_token("pytest", "test").status_code == 200
+    # a successful login clears the failures
+    assert post_token("pytest", "...").status_code == 401
+    assert post_token("pytest", "test").status_code == 200
+
+
 @pytest.mark.parametrize(
     "path",
     [
@@ -159,3 +187,113 @@ def test_api_auth_endpoint_without_password(setup_wg_quickrs_agent):
                             verify=wg_quickrs_config_folder / "certs/root/rootCA.crt")
     assert response.status_code == 200
 
+
+
+def add_user(name, role):
+    """Add a user through the running agent and sign in as it."""
+    result = subprocess.run(
+        get_wg_quickrs_command() + ["config", "add", "user", name, "--role", role, "--password", f"{name}-pwd"],
+        capture_output=True,
+        text=True
+    )
+    assert result.returncode == 0, result.stderr
+
+
+def get_user_token(base_url, name):
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    response = requests.post(f"{base_url}/api/token",
+                             json={ "client_id": "pytest", "username": name, "password": f"{name}-pwd" },
+                             verify=wg_quickrs_config_folder / "certs/root/rootCA.crt")
+    assert response.status_code == 200
+    return response.text
+
+
+def test_api_token_user(setup_wg_quickrs_agent):
+    """Test POST /api/token for a user checks the user's password, not the shared one."""
+    base_url = setup_wg_quickrs_agent("test_pwd_single_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    add_user("alice", "viewer")
+
+    for body in [
+        { "client_id": "pytest", "username": "alice", "password": "test" },  # shared password
+        { "client_id": "pytest", "username": "nobody", "password": "alice-pwd" },  # unknown user
+    ]:
+        response = requests.post(f"{base_url}/api/token", json=body,
+                                 verify=wg_quickrs_config_folder / "certs/root/rootCA.crt")
+        assert response.status_code == 401
+
+    _ = get_user_token(base_url, "alice")
+
+
+def test_api_token_unknown_account_timing(setup_wg_quickrs_agent):
+    """Test logins naming an unknown user or peer token take as long as wrong passwords, so they don't tell which exist."""
+    base_url = setup_wg_quickrs_agent("test_pwd_single_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    add_user("alice", "viewer")
+
+    def login_time(body):
+        start = time.monotonic()
+        response = requests.post(f"{base_url}/api/token", json={ "client_id": "pytest", **body },
+                                 verify=wg_quickrs_config_folder / "certs/root/rootCA.crt")
+        assert response.status_code == 401
+        return time.monotonic() - start
+
+    # the first login pays for setting things up
+    get_user_token(base_url, "alice")
+    # below the 5 failures a client IP may have before it is locked out
+    wrong_password = login_time({ "username": "alice", "password": "wrong" })
+    unknown_user = login_time({ "username": "nobody", "password": "wrong" })
+    unknown_peer_token = login_time({ "peer_token": "unknown.wrong" })
+    assert unknown_user > wrong_password / 2
+    assert unknown_peer_token > wrong_password / 2
+
+
+@pytest.mark.parametrize(
+    "method,path,body,allowed_role",
+    [
+        ("get", "version", None, "viewer"),
+        ("get", "network/summary?only_digest=false", None, "viewer"),
+        ("post", "network/reserve/address", None, "operator"),
+        ("get", "config/history", None, "operator"),
+        ("patch", "network/config", { "changed_fields": { "defaults": { "peer": { "mtu": { "enabled": True, "value": 1400 } } } } }, "admin"),
+        ("post", "network/secrets/reveal", None, "admin"),
+        ("post", "wireguard/status", { "status": "up" }, "admin"),
+    ])
+def test_api_roles(setup_wg_quickrs_agent, method, path, body, allowed_role):
+    """Test each role is allowed what its role and the roles before it are, and refused the rest."""
+    base_url = setup_wg_quickrs_agent("test_pwd_single_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    roles = ["viewer", "operator", "admin"]
+    for role in roles:
+        add_user(role, role)
+
+    for role in roles:
+        json_body = { "password": f"{role}-pwd" } if path == "network/secrets/reveal" else body
+        response = requests.request(method, f"{base_url}/api/{path}",
+                                    json=json_body,
+                                    headers={ "Authorization": f"Bearer {get_user_token(base_url, role)}" },
+                                    verify=wg_quickrs_config_folder / "certs/root/rootCA.crt")
+        if roles.index(role) < roles.index(allowed_role):
+            assert response.status_code == 403, role
+            assert response.text.endswith(" role"), role
+        else:
+            # the endpoint may still refuse the request itself, e.g. the tunnel while the VPN is disabled
+            assert response.status_code != 401, role
+            assert not response.text.startswith("Requires the"), role
+
+
+def test_api_removed_user_token(setup_wg_quickrs_agent):
+    """Test a user's token stops working once the user is removed."""
+    base_url = setup_wg_quickrs_agent("test_pwd_single_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    add_user("alice", "operator")
+    token = get_user_token(base_url, "alice")
+
+    result = subprocess.run(get_wg_quickrs_command() + ["config", "remove", "user", "alice"],
+                            capture_output=True, text=True)
+    assert result.returncode == 0, result.stderr
+
+    response = requests.get(f"{base_url}/api/version",
+                            headers={ "Authorization": f"Bearer {token}" },
+                            verify=wg_quickrs_config_folder / "certs/root/rootCA.crt")
+    assert response.status_code == 401
@@ -0,0 +1,26 @@
+from tests.pytest.conftest import setup_wg_quickrs_agent
+from tests.pytest.helpers import get_paths
+import requests
+
+OTHER_PEER_ID = "6e9a8440-f884-4b54-bfe7-b982f15e40fd"
+
+
+def test_external_edit_is_not_overwritten(setup_wg_quickrs_agent):
+    """Test the agent refuses to write over a conf.yml that was changed by another process."""
+    base_url = setup_wg_quickrs_agent("no_auth_multi_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+
+    digest = requests.get(f"{base_url}/api/network/summary?only_digest=true").json()["digest"]
+    # e.g. edited by hand while the agent runs
+    edited = wg_quickrs_config_file.read_text().replace("name: other-peer1", "name: edited-by-hand")
+    wg_quickrs_config_file.write_text(edited)
+
+    change_sum = {"changed_fields": {"peers": {OTHER_PEER_ID: {"name": "renamed"}}}}
+    response = requests.patch(f"{base_url}/api/network/config", json=change_sum)
+    assert response.status_code == 409
+    assert wg_quickrs_config_file.read_text() == edited
+
+    # the agent keeps serving the config it had before the failed write
+    response = requests.get(f"{base_url}/api/network/summary?only_digest=true")
+    assert response.json()["digest"] == digest
+    assert requests.post(f"{base_url}/api/network/reserve/address").status_code == 409
@@ -0,0 +1,91 @@
+from tests.pytest.conftest import setup_wg_quickrs_agent
+from tests.pytest.helpers import get_paths, get_token
+import requests
+import json
+
+OTHER_PEER_ID = "6e9a8440-f884-4b54-bfe7-b982f15e40fd"
+
+
+def test_config_history_auth(setup_wg_quickrs_agent):
+    """Test the history requires a token and records the client id as the author."""
+    base_url = setup_wg_quickrs_agent("test_pwd_single_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    verify = wg_quickrs_config_folder / "certs/root/rootCA.crt"
+
+    response = requests.get(f"{base_url}/api/config/history", verify=verify)
+    assert response.status_code == 401
+    response = requests.post(f"{base_url}/api/config/history/1/rollback", verify=verify)
+    assert response.status_code == 401
+
+    headers = {"Authorization": f"Bearer {get_token(base_url)}"}
+    response = requests.post(f"{base_url}/api/network/reserve/address", headers=headers, verify=verify)
+    assert response.status_code == 200
+
+    revisions = requests.get(f"{base_url}/api/config/history", headers=headers, verify=verify).json()
+    assert revisions[-1]["author"] == "api:pytest"
+    assert "+network.reservations." in revisions[-1]["summary"]
+
+
+def test_config_history_show_and_diff(setup_wg_quickrs_agent):
+    """Test revisions are served with their secrets redacted."""
+    base_url = setup_wg_quickrs_agent("no_auth_multi_peer")
+
+    change_sum = {"changed_fields": {"peers": {OTHER_PEER_ID: {"name": "renamed"}}}}
+    response = requests.patch(f"{base_url}/api/network/config", json=change_sum)
+    assert response.status_code == 200
+
+    revisions = requests.get(f"{base_url}/api/config/history").json()
+    assert [revision["revision"] for revision in revisions] == [1, 2]
+    assert revisions[1]["author"] == "api"
+    assert f"~network.peers.{OTHER_PEER_ID}.name" in revisions[1]["summary"]
+
+    response = requests.get(f"{base_url}/api/config/history/2")
+    assert response.status_code == 200
+    data = response.json()
+    assert data["revision"]["revision"] == 2
+    assert data["config"]["network"]["peers"][OTHER_PEER_ID]["name"] == "renamed"
+    assert "private_key" not in data["config"]["network"]["peers"][OTHER_PEER_ID]
+    assert "hash" not in data["config"]["agent"]["web"]["password"]
+
+    response = requests.get(f"{base_url}/api/config/history/2/diff")
+    assert response.status_code == 200
+    data = response.json()
+    assert data["from"] == 1
+    assert data["to"] == 2
+    assert "-      name: other-peer1" in data["diff"]
+    assert "+      name: renamed" in data["diff"]
+    assert '"private_key":' not in json.dumps(data)
+
+    assert requests.get(f"{base_url}/api/config/history/99").status_code == 404
+    assert requests.get(f"{base_url}/api/config/history/2/diff?against=99").status_code == 404
+
+
+def test_config_history_rollback(setup_wg_quickrs_agent):
+    """Test a rollback restores the network and invalidates older digests."""
+    base_url = setup_wg_quickrs_agent("no_auth_multi_peer")
+
+    change_sum = {"changed_fields": {"peers": {OTHER_PEER_ID: {"name": "renamed"}}}}
+    response = requests.patch(f"{base_url}/api/network/config", json=change_sum)
+    assert response.status_code == 200
+    patched_digest = response.headers["ETag"].strip('"')
+
+    response = requests.post(f"{base_url}/api/config/history/1/rollback")
+    assert response.status_code == 200
+    data = response.json()
+    assert data["revision"] == 3
+    assert response.headers["ETag"].strip('"') == data["digest"]
+
+    summary = requests.get(f"{base_url}/api/network/summary?only_digest=false").json()
+    assert summary["digest"] == data["digest"]
+    assert summary["network"]["peers"][OTHER_PEER_ID]["name"] == "other-peer1"
+
+    revisions = requests.get(f"{base_url}/api/config/history").json()
+    assert revisions[-1]["rollback_of"] == 1
+
+    # patches based on the network before the rollback can't be merged
+    change_sum = {"changed_fields": {"peers": {OTHER_PEER_ID: {"name": "stale"}}}}
+    response = requests.patch(f"{base_url}/api/network/config", json=change_sum,
+                              headers={"If-Match": f'"{patched_digest}"'})
+    assert response.status_code == 409
+
+    assert requests.post(f"{base_url}/api/config/history/99/rollback").status_code == 404
@@ -0,0 +1,69 @@
+from tests.pytest.conftest import setup_wg_quickrs_agent
+from tests.pytest.helpers import get_paths, get_token
+import requests
+
+THIS_PEER_ID = "0ed989c6-6dba-4e3c-8034-08adf4262d9e"
+
+
+def test_metrics_disabled(setup_wg_quickrs_agent):
+    """Test GET /metrics is not served unless enabled."""
+    base_url = setup_wg_quickrs_agent("test_pwd_single_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+
+    response = requests.get(f"{base_url}/metrics",
+                            headers={"Authorization": "Bearer test"},
+                            verify=wg_quickrs_config_folder / "certs/root/rootCA.crt")
+    assert response.status_code == 404
+
+
+def test_metrics_token(setup_wg_quickrs_agent):
+    """Test GET /metrics requires its own bearer token."""
+    base_url = setup_wg_quickrs_agent("test_pwd_single_peer_w_metrics")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    verify = wg_quickrs_config_folder / "certs/root/rootCA.crt"
+
+    response = requests.get(f"{base_url}/metrics", verify=verify)
+    assert response.status_code == 401
+
+    response = requests.get(f"{base_url}/metrics", headers={"Authorization": "Bearer ..."}, verify=verify)
+    assert response.status_code == 401
+
+    # a web UI token is not a metrics token
+    token = get_token(base_url)
+    response = requests.get(f"{base_url}/metrics", headers={"Authorization": f"Bearer {token}"}, verify=verify)
+    assert response.status_code == 401
+
+    response = requests.get(f"{base_url}/metrics", headers={"Authorization": "Bearer test"}, verify=verify)
+    assert response.status_code == 200
+    assert response.headers["Content-Type"].startswith("text/plain; version=0.0.4")
+
+
+def test_metrics_token_lockout(setup_wg_quickrs_agent):
+    """Test repeated wrong metrics tokens lock the client out like failed logins."""
+    base_url = setup_wg_quickrs_agent("test_pwd_single_peer_w_metrics")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    verify = wg_quickrs_config_folder / "certs/root/rootCA.crt"
+
+    for _ in range(6):
+        response = requests.get(f"{base_url}/metrics", headers={"Authorization": "Bearer wrong"}, verify=verify)
+        assert response.status_code == 401
+    response = requests.get(f"{base_url}/metrics", headers={"Authorization": "Bearer test"}, verify=verify)
+    assert response.status_code == 429
+    assert int(response.headers["Retry-After"]) >= 1
+
+
+def test_metrics_content(setup_wg_quickrs_agent):
+    """Test GET /metrics exposes the tunnel status, reservations and peers."""
+    base_url = setup_wg_quickrs_agent("test_pwd_single_peer_w_metrics")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+
+    response = requests.get(f"{base_url}/metrics",
+                            headers={"Authorization": "Bearer test"},
+                            verify=wg_quickrs_config_folder / "certs/root/rootCA.crt")
+    assert response.status_code == 200
+    lines = response.text.splitlines()
+    assert "# TYPE wg_quickrs_wireguard_status gauge" in lines
+    assert "# TYPE wg_quickrs_connection_transfer_a_to_b_bytes_total counter" in lines
+    assert 'wg_quickrs_wireguard_status{status="up"} 0' in lines
+    assert "wg_quickrs_reservations 0" in lines
+    assert f'wg_quickrs_peer_enabled{{peer_id="{THIS_PEER_ID}",peer_name="wg-quickrs-host"}} 1' in lines
@@ -57,7 +57,10 @@ def test_add_bad_connection(setup_wg_quickrs_agent):
     }
     setup_response = requests.patch(f"{base_url}/api/network/config", json=fake_setup_change_sum)
     assert setup_response.status_code == 400
-    assert "'peer_id' does not exist" in setup_response.content.decode("utf-8")
+    assert setup_response.json()["errors"] == [
+        {"path": f"added_connections.{fake_connection_id}", "error": f"peer '{peer1_id}' does not exist"},
+        {"path": f"added_connections.{fake_connection_id}", "error": f"peer '{peer2_id}' does not exist"},
+    ]
 
 
 def test_invalid_json(setup_wg_quickrs_agent):
@@ -354,3 +357,217 @@ def test_modify_peer_scripts_security(setup_wg_quickrs_agent, peer_id_type, scri
         for script_type, script_value in script_changes.items():
             assert new_conf["network"]["peers"][peer_id]["scripts"][script_type] == script_value
 
+
+
+def get_digest(base_url):
+    response = requests.get(f"{base_url}/api/network/summary?only_digest=true")
+    assert response.status_code == 200
+    assert response.headers["ETag"] == f'"{response.json()["digest"]}"'
+    return response.json()["digest"]
+
+
+def test_patch_with_current_digest(setup_wg_quickrs_agent):
+    """Test a change based on the current digest is applied and returns the new digest."""
+    base_url = setup_wg_quickrs_agent("no_auth_multi_peer")
+    this_peer_id = get_this_peer_id(base_url)
+    digest = get_digest(base_url)
+
+    change_sum = {"changed_fields": {"peers": {this_peer_id: {"name": "renamed"}}}}
+    response = requests.patch(f"{base_url}/api/network/config", json=change_sum, headers={"If-Match": f'"{digest}"'})
+    assert response.status_code == 200
+    assert response.headers["ETag"] == f'"{get_digest(base_url)}"'
+
+    # the digest can also be sent in the body
+    change_sum = {"digest": get_digest(base_url), "changed_fields": {"peers": {this_peer_id: {"name": "renamed-again"}}}}
+    response = requests.patch(f"{base_url}/api/network/config", json=change_sum)
+    assert response.status_code == 200
+
+
+def test_patch_with_stale_digest(setup_wg_quickrs_agent):
+    """Test a change based on a stale digest is merged unless it touches what changed since."""
+    base_url = setup_wg_quickrs_agent("no_auth_multi_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    this_peer_id = get_this_peer_id(base_url)
+    stale_digest = get_digest(base_url)
+    headers = {"If-Match": f'"{stale_digest}"'}
+
+    response = requests.patch(f"{base_url}/api/network/config",
+                              json={"changed_fields": {"peers": {this_peer_id: {"name": "first"}}}},
+                              headers=headers)
+    assert response.status_code == 200
+    response = requests.post(f"{base_url}/api/network/reserve/address")
+    assert response.status_code == 200
+
+    # another field of the same peer
+    response = requests.patch(f"{base_url}/api/network/config",
+                              json={"changed_fields": {"peers": {this_peer_id: {"kind": "server"}}}},
+                              headers=headers)
+    assert response.status_code == 200
+
+    # the same field
+    response = requests.patch(f"{base_url}/api/network/config",
+                              json={"changed_fields": {"peers": {this_peer_id: {"name": "second"}}}},
+                              headers=headers)
+    assert response.status_code == 409
+    assert response.json()["digest"] == get_digest(base_url)
+    assert response.json()["conflicting_fields"] == [f"peers/{this_peer_id}/name"]
+
+    # removing the peer touches all of its fields
+    other_peer_id = "6e9a8440-f884-4b54-bfe7-b982f15e40fd"
+    response = requests.patch(f"{base_url}/api/network/config",
+                              json={"changed_fields": {"peers": {other_peer_id: {"name": "renamed"}}}},
+                              headers={"If-Match": f'"{get_digest(base_url)}"'})
+    assert response.status_code == 200
+    response = requests.patch(f"{base_url}/api/network/config",
+                              json={"removed_peers": [other_peer_id]},
+                              headers=headers)
+    assert response.status_code == 409
+    assert response.json()["conflicting_fields"] == [f"peers/{other_peer_id}"]
+
+    with open(wg_quickrs_config_file) as stream:
+        conf = yaml.load(stream)
+    assert conf["network"]["peers"][this_peer_id]["name"] == "first"
+    assert conf["network"]["peers"][this_peer_id]["kind"] == "server"
+    assert other_peer_id in conf["network"]["peers"]
+
+
+def test_patch_with_unknown_digest(setup_wg_quickrs_agent):
+    """Test a change based on an unknown digest is rejected."""
+    base_url = setup_wg_quickrs_agent("no_auth_multi_peer")
+    this_peer_id = get_this_peer_id(base_url)
+    change_sum = {"changed_fields": {"peers": {this_peer_id: {"name": "renamed"}}}}
+
+    response = requests.patch(f"{base_url}/api/network/config", json=change_sum, headers={"If-Match": '"unknown"'})
+    assert response.status_code == 409
+    assert response.json()["digest"] == get_digest(base_url)
+    assert response.json()["conflicting_fields"] == []
+
+    response = requests.patch(f"{base_url}/api/network/config", json=change_sum, headers={"If-Match": '"a", "b"'})
+    assert response.status_code == 400
+
+    response = requests.patch(f"{base_url}/api/network/config", json={**change_sum, "digest": "other"},
+                              headers={"If-Match": f'"{get_digest(base_url)}"'})
+    assert response.status_code == 400
+
+    response = requests.patch(f"{base_url}/api/network/config", json=change_sum, headers={"If-Match": "*"})
+    assert response.status_code == 200
+
+
+def test_patch_is_all_or_nothing(setup_wg_quickrs_agent):
+    """Test a patch with an invalid part changes nothing and lists every violation."""
+    base_url = setup_wg_quickrs_agent("no_auth_multi_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    this_peer_id = get_this_peer_id(base_url)
+    other_peer_id = "6e9a8440-f884-4b54-bfe7-b982f15e40fd"
+
+    change_sum = {
+        "changed_fields": {
+            "peers": {
+                other_peer_id: {"name": "partially-applied", "mtu": {"enabled": True, "value": 10001}},
+                this_peer_id: {"name": "", "scripts": {"pre_up": []}},
+            }
+        },
+        "removed_peers": [this_peer_id],
+    }
+    response = requests.patch(f"{base_url}/api/network/config", json=change_sum)
+    assert response.status_code == 400
+    paths = [error["path"] for error in response.json()["errors"]]
+    assert paths == [
+        f"changed_fields.peers.{this_peer_id}.name",
+        f"changed_fields.peers.{this_peer_id}.scripts",
+        f"changed_fields.peers.{other_peer_id}.mtu",
+        f"removed_peers.{this_peer_id}",
+    ]
+
+    # a later valid patch must not persist any part of the rejected one
+    response = requests.patch(f"{base_url}/api/network/config",
+                              json={"changed_fields": {"peers": {this_peer_id: {"kind": "server"}}}})
+    assert response.status_code == 200
+    with open(wg_quickrs_config_file) as stream:
+        conf = yaml.load(stream)
+    assert conf["network"]["peers"][other_peer_id]["name"] != "partially-applied"
+    assert conf["network"]["peers"][other_peer_id]["mtu"]["value"] != 10001
+
+
+def test_patch_swap_peer_addresses(setup_wg_quickrs_agent):
+    """Test addresses are checked against the network as it would be stored."""
+    base_url = setup_wg_quickrs_agent("no_auth_multi_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    this_peer_id = get_this_peer_id(base_url)
+    other_peer_id = "6e9a8440-f884-4b54-bfe7-b982f15e40fd"
+    with open(wg_quickrs_config_file) as stream:
+        old_conf = yaml.load(stream)
+    this_address = str(old_conf["network"]["peers"][this_peer_id]["address"])
+    other_address = str(old_conf["network"]["peers"][other_peer_id]["address"])
+
+    change_sum = {
+        "changed_fields": {
+            "peers": {
+                this_peer_id: {"address": other_address},
+                other_peer_id: {"address": this_address},
+            }
+        }
+    }
+    response = requests.patch(f"{base_url}/api/network/config", json=change_sum)
+    assert response.status_code == 200
+
+    with open(wg_quickrs_config_file) as stream:
+        new_conf = yaml.load(stream)
+    assert str(new_conf["network"]["peers"][this_peer_id]["address"]) == other_address
+    assert str(new_conf["network"]["peers"][other_peer_id]["address"]) == this_address
+
+
+def test_patch_cross_entity_violations(setup_wg_quickrs_agent):
+    """Test added peers can't share an address and connections can't reference removed peers."""
+    base_url = setup_wg_quickrs_agent("no_auth_multi_peer")
+    other_peer_id = "6e9a8440-f884-4b54-bfe7-b982f15e40fd"
+    peer1_id = "a1c11ade-dd1a-4f5a-a6f9-3b6c6d10f416"
+    peer2_id = "b1c11ade-dd1a-4f5a-a6f9-3b6c6d10f416"
+    peer_data = get_test_peer_data()
+    peer_data["address"] = "10.0.34.50"
+
+    change_sum = {
+        "added_peers": {peer1_id: peer_data, peer2_id: peer_data},
+        "removed_peers": [other_peer_id],
+        "added_connections": {f"{other_peer_id}*{peer1_id}": get_test_connection_data()},
+    }
+    response = requests.patch(f"{base_url}/api/network/config", json=change_sum)
+    assert response.status_code == 400
+    errors = {error["path"]: error["error"] for error in response.json()["errors"]}
+    assert "does not exist" in errors[f"added_connections.{other_peer_id}*{peer1_id}"]
+    assert f"taken by {peer2_id}" in errors[f"added_peers.{peer1_id}.address"]
+    assert f"taken by {peer1_id}" in errors[f"added_peers.{peer2_id}.address"]
+
+    summary = requests.get(f"{base_url}/api/network/summary?only_digest=false").json()
+    assert other_peer_id in summary["network"]["peers"]
+    assert peer1_id not in summary["network"]["peers"]
+
+
+def test_patch_dry_run(setup_wg_quickrs_agent):
+    """Test a dry run previews the change without applying it."""
+    base_url = setup_wg_quickrs_agent("no_auth_multi_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    other_peer_id = "6e9a8440-f884-4b54-bfe7-b982f15e40fd"
+    with open(wg_quickrs_config_file) as stream:
+        old_text = stream.read()
+    old_digest = requests.get(f"{base_url}/api/network/summary?only_digest=true").json()["digest"]
+
+    change_sum = {"changed_fields": {"peers": {other_peer_id: {"address": "10.0.34.50"}}}}
+    response = requests.patch(f"{base_url}/api/network/config?dry_run=true", json=change_sum)
+    assert response.status_code == 200
+    preview = response.json()
+    assert str(preview["network"]["peers"][other_peer_id]["address"]) == "10.0.34.50"
+    assert preview["digest"] != old_digest
+    assert "10.0.34.50" in preview["peer_conf_diffs"][other_peer_id]
+    assert "syncconf" in preview
+    assert '"private_key":' not in response.text
+
+    with open(wg_quickrs_config_file) as stream:
+        assert stream.read() == old_text
+    assert requests.get(f"{base_url}/api/network/summary?only_digest=true").json()["digest"] == old_digest
+
+    # a dry run reports violations like a real patch
+    change_sum = {"changed_fields": {"peers": {other_peer_id: {"address": "10.0.50.1"}}}}
+    response = requests.patch(f"{base_url}/api/network/config?dry_run=true", json=change_sum)
+    assert response.status_code == 400
+    assert response.json()["errors"][0]["path"] == f"changed_fields.peers.{other_peer_id}.address"
@@ -0,0 +1,58 @@
+from tests.pytest.conftest import setup_wg_quickrs_agent
+import requests
+import pytest
+
+CONNECTION_ID = "6e9a8440-f884-4b54-bfe7-b982f15e40fd*0ed989c6-6dba-4e3c-8034-08adf4262d9e"
+UNKNOWN_CONNECTION_ID = "6e9a8440-f884-4b54-bfe7-b982f15e40fd*9541bbb0-a3c0-4b83-8637-000000000000"
+
+
+def test_get_connection_telemetry_disabled(setup_wg_quickrs_agent):
+    """Test GET /api/network/connections/{id}/telemetry is rejected while history is disabled."""
+    base_url = setup_wg_quickrs_agent("no_auth_multi_peer")
+    response = requests.get(f"{base_url}/api/network/connections/{CONNECTION_ID}/telemetry")
+    assert response.status_code == 403
+
+
+def test_get_connection_telemetry_empty(setup_wg_quickrs_agent):
+    """Test GET /api/network/connections/{id}/telemetry returns an empty history before any sample."""
+    base_url = setup_wg_quickrs_agent("no_auth_multi_peer_w_telemetry_history")
+    response = requests.get(f"{base_url}/api/network/connections/{CONNECTION_ID}/telemetry")
+    assert response.status_code == 200
+    data = response.json()
+    assert data["connection_id"] == CONNECTION_ID
+    assert data["resolution"] == "raw"
+    assert data["to"] - data["from"] == 24 * 60 * 60 * 1000
+    assert data["transfer_a_to_b"] == 0
+    assert data["transfer_b_to_a"] == 0
+    assert data["points"] == []
+
+
+@pytest.mark.parametrize("resolution", ["raw", "hourly", "daily"])
+def test_get_connection_telemetry_resolution(setup_wg_quickrs_agent, resolution):
+    """Test GET /api/network/connections/{id}/telemetry honors the requested resolution."""
+    base_url = setup_wg_quickrs_agent("no_auth_multi_peer_w_telemetry_history")
+    response = requests.get(
+        f"{base_url}/api/network/connections/{CONNECTION_ID}/telemetry",
+        params={"from": 0, "to": 1_000_000, "resolution": resolution}
+    )
+    assert response.status_code == 200
+    data = response.json()
+    assert data["resolution"] == resolution
+    assert data["from"] == 0
+    assert data["to"] == 1_000_000
+
+
+@pytest.mark.parametrize(
+    "connection_id,params,expected_status",
+    [
+        ("not-a-connection", {}, 400),
+        (UNKNOWN_CONNECTION_ID, {}, 404),
+        (CONNECTION_ID, {"resolution": "weekly"}, 400),
+        (CONNECTION_ID, {"from": 2000, "to": 1000}, 400),
+    ],
+)
+def test_get_connection_telemetry_bad_request(setup_wg_quickrs_agent, connection_id, params, expected_status):
+    """Test GET /api/network/connections/{id}/telemetry rejects bad ids and queries."""
+    base_url = setup_wg_quickrs_agent("no_auth_multi_peer_w_telemetry_history")
+    response = requests.get(f"{base_url}/api/network/connections/{connection_id}/telemetry", params=params)
+    assert response.status_code == expected_status
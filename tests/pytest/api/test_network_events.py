@@ -0,0 +1,103 @@
+from tests.pytest.conftest import setup_wg_quickrs_agent
+from tests.pytest.helpers import get_test_peer_data, get_paths, get_wg_quickrs_command
+import requests
+import json
+import subprocess
+
+
+def read_events(response):
+    """Yield (event, data) pairs of a server-sent event stream."""
+    event, data = None, ""
+    for line in response.iter_lines(decode_unicode=True):
+        if line.startswith("event: "):
+            event = line[len("event: "):]
+        elif line.startswith("data: "):
+            data += line[len("data: "):]
+        elif line == "" and event is not None:
+            yield event, json.loads(data)
+            event, data = None, ""
+
+
+def next_event(events, name):
+    for event, data in events:
+        if event == name:
+            return data
+    return None
+
+
+def test_network_events_auth(setup_wg_quickrs_agent):
+    """Test GET /api/network/events requires a token when password auth is enabled."""
+    base_url = setup_wg_quickrs_agent("test_pwd_single_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+
+    response = requests.get(f"{base_url}/api/network/events",
+                            verify=wg_quickrs_config_folder / "certs/root/rootCA.crt")
+    assert response.status_code == 401
+
+
+def test_network_events_revoked(setup_wg_quickrs_agent):
+    """Test the stream ends once the credentials it was opened with are revoked."""
+    base_url = setup_wg_quickrs_agent("test_pwd_single_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    config_command = get_wg_quickrs_command() + ["config"]
+    api_key = subprocess.run(config_command + ["add", "api-key", "dashboard", "--role", "viewer"],
+                             capture_output=True, text=True, check=True).stdout.strip().splitlines()[-1]
+
+    with requests.get(f"{base_url}/api/network/events", headers={"Authorization": f"Bearer {api_key}"}, stream=True,
+                      timeout=10, verify=wg_quickrs_config_folder / "certs/root/rootCA.crt") as response:
+        assert response.status_code == 200
+        events = read_events(response)
+        assert next_event(events, "digest") is not None
+
+        subprocess.run(config_command + ["remove", "api-key", "dashboard"], capture_output=True, check=True)
+        # the stream ends instead of running into the read timeout
+        assert next_event(events, "change") is None
+
+
+def test_network_events_digest(setup_wg_quickrs_agent):
+    """Test the stream starts with a digest matching the summary."""
+    base_url = setup_wg_quickrs_agent("no_auth_single_peer")
+
+    summary = requests.get(f"{base_url}/api/network/summary?only_digest=true").json()
+
+    with requests.get(f"{base_url}/api/network/events", stream=True, timeout=10) as response:
+        assert response.status_code == 200
+        assert response.headers["Content-Type"].startswith("text/event-stream")
+
+        data = next_event(read_events(response), "digest")
+        assert data["digest"] == summary["digest"]
+        assert "status" in data
+        assert "timestamp" in data
+        assert "network" not in data
+
+
+def test_network_events_change(setup_wg_quickrs_agent):
+    """Test changes are pushed with their secrets redacted."""
+    base_url = setup_wg_quickrs_agent("no_auth_single_peer")
+
+    with requests.get(f"{base_url}/api/network/events", stream=True, timeout=10) as response:
+        assert response.status_code == 200
+        events = read_events(response)
+        next_event(events, "digest")
+
+        # a reservation changes the network without a change sum
+        reservation = requests.post(f"{base_url}/api/network/reserve/address")
+        assert reservation.status_code == 200
+        data = next_event(events, "change")
+        assert data["change_sum"] is None
+
+        peer_data = get_test_peer_data()
+        peer_data["address"] = reservation.json()["address"]
+        peer_id = reservation.json()["peer_id"]
+        response_patch = requests.patch(f"{base_url}/api/network/config",
+                                        json={"added_peers": {peer_id: peer_data}})
+        assert response_patch.status_code == 200
+
+        data = next_event(events, "change")
+        added_peer = data["change_sum"]["added_peers"][peer_id]
+        assert "private_key" not in added_peer
+        assert added_peer["private_key_fingerprint"].startswith("SHA256:")
+        assert peer_data["private_key"] not in json.dumps(data)
+
+        summary = requests.get(f"{base_url}/api/network/summary?only_digest=true").json()
+        assert data["digest"] == summary["digest"]
@@ -0,0 +1,65 @@
+from tests.pytest.conftest import setup_wg_quickrs_agent
+import requests
+import pytest
+
+THIS_PEER_ID = "0ed989c6-6dba-4e3c-8034-08adf4262d9e"
+
+
+def test_get_peer_conf_default(setup_wg_quickrs_agent):
+    """Test GET /api/network/peers/{id}/conf returns the wg-quick config by default."""
+    base_url = setup_wg_quickrs_agent("no_auth_single_peer")
+    response = requests.get(f"{base_url}/api/network/peers/{THIS_PEER_ID}/conf")
+    assert response.status_code == 200
+    assert response.headers["Content-Type"].startswith("text/plain")
+    assert response.headers["Content-Disposition"] == 'attachment; filename="wg-quickrs-home-wg-quickrs-host.conf"'
+    assert "[Interface]" in response.text
+    assert "PrivateKey" in response.text
+    assert "Address" in response.text
+
+
+def test_get_peer_conf_stripped(setup_wg_quickrs_agent):
+    """Test GET /api/network/peers/{id}/conf?stripped=true omits the wg-quick only fields."""
+    base_url = setup_wg_quickrs_agent("no_auth_single_peer")
+    response = requests.get(f"{base_url}/api/network/peers/{THIS_PEER_ID}/conf?stripped=true")
+    assert response.status_code == 200
+    assert "[Interface]" in response.text
+    assert "Address" not in response.text
+    assert "DNS" not in response.text
+
+
+def test_get_peer_conf_singular_path(setup_wg_quickrs_agent):
+    """Test GET /api/network/peer/{id}/conf is served as well."""
+    base_url = setup_wg_quickrs_agent("no_auth_single_peer")
+    response = requests.get(f"{base_url}/api/network/peer/{THIS_PEER_ID}/conf?format=qr")
+    assert response.status_code == 200
+    assert "Content-Disposition" not in response.headers
+
+
+@pytest.mark.parametrize("conf_format,content_type", [
+    ("qr", "text/plain"),
+    ("png", "image/png"),
+    ("svg", "image/svg+xml"),
+])
+def test_get_peer_conf_qr_formats(setup_wg_quickrs_agent, conf_format, content_type):
+    """Test GET /api/network/peers/{id}/conf?format=... renders a QR code."""
+    base_url = setup_wg_quickrs_agent("no_auth_single_peer")
+    response = requests.get(f"{base_url}/api/network/peers/{THIS_PEER_ID}/conf?format={conf_format}")
+    assert response.status_code == 200
+    assert response.headers["Content-Type"].startswith(content_type)
+    assert b"[Interface]" not in response.content
+    if conf_format == "png":
+        assert response.content.startswith(b"\x89PNG\r\n\x1a\n")
+
+
+def test_get_peer_conf_invalid_requests(setup_wg_quickrs_agent):
+    """Test GET /api/network/peers/{id}/conf with invalid ids and formats."""
+    base_url = setup_wg_quickrs_agent("no_auth_single_peer")
+
+    response = requests.get(f"{base_url}/api/network/peers/not-a-uuid/conf")
+    assert response.status_code == 400
+
+    response = requests.get(f"{base_url}/api/network/peers/00000000-0000-0000-0000-000000000000/conf")
+    assert response.status_code == 404
+
+    response = requests.get(f"{base_url}/api/network/peers/{THIS_PEER_ID}/conf?format=jpeg")
+    assert response.status_code == 400
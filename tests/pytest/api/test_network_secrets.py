@@ -0,0 +1,49 @@
+from tests.pytest.conftest import setup_wg_quickrs_agent
+from tests.pytest.helpers import get_paths, get_token
+import requests
+from ruamel.yaml import YAML
+yaml = YAML()
+yaml.preserve_quotes = True
+
+
+def test_reveal_secrets_no_auth(setup_wg_quickrs_agent):
+    """Test POST /api/network/secrets/reveal returns the secrets of the network."""
+    base_url = setup_wg_quickrs_agent("no_auth_multi_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    with open(wg_quickrs_config_file) as stream:
+        conf = yaml.load(stream)
+
+    response = requests.post(f"{base_url}/api/network/secrets/reveal")
+    assert response.status_code == 200
+
+    secrets = response.json()
+    for peer_id, peer in conf["network"]["peers"].items():
+        assert secrets["peers"][peer_id]["private_key"] == peer["private_key"]
+    for connection_id, connection in conf["network"]["connections"].items():
+        assert secrets["connections"][connection_id]["pre_shared_key"] == connection["pre_shared_key"]
+
+
+def test_reveal_secrets_requires_password(setup_wg_quickrs_agent):
+    """Test POST /api/network/secrets/reveal requires the password to be confirmed in addition to the token."""
+    base_url = setup_wg_quickrs_agent("test_pwd_single_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    verify = wg_quickrs_config_folder / "certs/root/rootCA.crt"
+
+    # no token
+    response = requests.post(f"{base_url}/api/network/secrets/reveal", json={"password": "test"}, verify=verify)
+    assert response.status_code == 401
+
+    headers = {"Authorization": f"Bearer {get_token(base_url)}"}
+
+    # token without password
+    response = requests.post(f"{base_url}/api/network/secrets/reveal", headers=headers, verify=verify)
+    assert response.status_code == 400
+
+    # token with wrong password
+    response = requests.post(f"{base_url}/api/network/secrets/reveal", json={"password": "..."}, headers=headers, verify=verify)
+    assert response.status_code == 401
+
+    # token with password
+    response = requests.post(f"{base_url}/api/network/secrets/reveal", json={"password": "test"}, headers=headers, verify=verify)
+    assert response.status_code == 200
+    assert "private_key" in next(iter(response.json()["peers"].values()))
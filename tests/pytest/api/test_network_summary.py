@@ -77,3 +77,19 @@ def test_get_summary_with_multi_peer(setup_wg_quickrs_agent):
     data = response.json()
     assert len(data["network"]["peers"]) > 1
     assert len(data["network"]["connections"]) >= 1
+
+
+def test_get_summary_redacts_secrets(setup_wg_quickrs_agent):
+    """Test GET /api/network/summary replaces private and pre-shared keys with public keys and fingerprints."""
+    base_url = setup_wg_quickrs_agent("no_auth_multi_peer")
+    response = requests.get(f"{base_url}/api/network/summary?only_digest=false")
+    assert response.status_code == 200
+
+    network = response.json()["network"]
+    for peer in network["peers"].values():
+        assert "private_key" not in peer
+        assert "public_key" in peer
+        assert peer["private_key_fingerprint"].startswith("SHA256:")
+    for connection in network["connections"].values():
+        assert "pre_shared_key" not in connection
+        assert connection["pre_shared_key_fingerprint"].startswith("SHA256:")
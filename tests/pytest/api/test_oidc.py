@@ -0,0 +1,268 @@
+import base64
+import hashlib
+import json
+import secrets
+import subprocess
+import threading
+import time
+from http.server import BaseHTTPRequestHandler, ThreadingHTTPServer
+from urllib.parse import parse_qs, urlencode, urlparse
+import pytest
+import requests
+
+from tests.pytest.conftest import setup_wg_quickrs_agent
+from tests.pytest.helpers import get_paths, get_wg_quickrs_command
+
+CLIENT_ID = "wg-quickrs"
+CLIENT_SECRET = "mock-client-secret"
+
+
+def b64url(data):
+    return base64.urlsafe_b64encode(data).rstrip(b"=").decode()
+
+
+def b64url_int(value):
+    return b64url(value.to_bytes((value.bit_length() + 7) // 8, "big"))
+
+
+def is_probable_prime(n):
+    if n % 2 == 0:
+        return False
+    d, r = n - 1, 0
+    while d % 2 == 0:
+        d, r = d // 2, r + 1
+    for _ in range(32):
+        x = pow(secrets.randbelow(n - 3) + 2, d, n)
+        if x in (1, n - 1):
+            continue
+        for _ in range(r - 1):
+            x = pow(x, 2, n)
+            if x == n - 1:
+                break
+        else:
+            return False
+    return True
+
+
+def random_prime(bits):
+    while True:
+        # the two top bits set make the product of two primes exactly twice as long
+        candidate = secrets.randbits(bits) | (3 << (bits - 2)) | 1
+        if is_probable_prime(candidate):
+            return candidate
+
+
+class MockProvider:
+    """A minimal OpenID Connect provider signing ID tokens with a fresh RS256 key."""
+
+    def __init__(self):
+        self.e = 65537
+        while True:
+            p, q = random_prime(1024), random_prime(1024)
+            phi = (p - 1) * (q - 1)
+            if p != q and phi % self.e != 0:
+                break
+        self.n = p * q
+        self.d = pow(self.e, -1, phi)
+        self.claims = {}
+        self.logins = {}
+        self.server = ThreadingHTTPServer(("127.0.0.1", 0), self.handler())
+        self.issuer = f"http://127.0.0.1:{self.server.server_port}"
+        threading.Thread(target=self.server.serve_forever, daemon=True).start()
+
+    def sign(self, claims):
+        signing_input = f"{b64url(json.dumps({'alg': 'RS256', 'typ': 'JWT', 'kid': 'mock'}).encode())}.{b64url(json.dumps(claims).encode())}"
+        # PKCS#1 v1.5 padding of the SHA-256 DigestInfo
+        digest_info = bytes.fromhex("3031300d060960864801650304020105000420") + hashlib.sha256(signing_input.encode()).digest()
+        length = (self.n.bit_length() + 7) // 8
+        padded = b"\x00\x01" + b"\xff" * (length - len(digest_info) - 3) + b"\x00" + digest_info
+        signature = pow(int.from_bytes(padded, "big"), self.d, self.n).to_bytes(length, "big")
+        return f"{signing_input}.{b64url(signature)}"
+
+    def handler(self):
+        provider = self
+
+        class Handler(BaseHTTPRequestHandler):
+            def log_message(self, *args):
+                pass
+
+            def send_json(self, status, body):
+                self.send_response(status)
+                self.send_header("Content-Type", "application/json")
+                self.end_headers()
+                self.wfile.write(json.dumps(body).encode())
+
+            def do_GET(self):
+                url = urlparse(self.path)
+                query = {key: values[0] for key, values in parse_qs(url.query).items()}
+                if url.path == "/.well-known/openid-configuration":
+                    self.send_json(200, {
+                        "issuer": provider.issuer,
+                        "authorization_endpoint": f"{provider.issuer}/authorize",
+                        "token_endpoint": f"{provider.issuer}/token",
+                        "jwks_uri": f"{provider.issuer}/jwks",
+                    })
+                elif url.path == "/jwks":
+                    self.send_json(200, { "keys": [{
+                        "kty": "RSA", "kid": "mock", "use": "sig", "alg": "RS256",
+                        "n": b64url_int(provider.n), "e": b64url_int(provider.e),
+                    }] })
+                elif url.path == "/authorize":
+                    code = secrets.token_urlsafe(16)
+                    provider.logins[code] = query
+                    self.send_response(302)
+                    self.send_header("Location", f"{query['redirect_uri']}?{urlencode({ 'code': code, 'state': query['state'] })}")
+                    self.end_headers()
+                else:
+                    self.send_json(404, {})
+
+            def do_POST(self):
+                form = {key: values[0] for key, values in parse_qs(self.rfile.read(int(self.headers["Content-Length"])).decode()).items()}
+                login = provider.logins.pop(form.get("code"), None)
+                expected_auth = "Basic " + base64.b64encode(f"{CLIENT_ID}:{CLIENT_SECRET}".encode()).decode()
+                if (login is None
+                        or self.headers.get("Authorization") != expected_auth
+                        or b64url(hashlib.sha256(form["code_verifier"].encode()).digest()) != login["code_challenge"]):
+                    self.send_json(400, { "error": "invalid_grant" })
+                    return
+                self.send_json(200, { "id_token": provider.sign({
+                    "iss": provider.issuer,
+                    "aud": CLIENT_ID,
+                    "sub": "f3b1c2d4",
+                    "iat": int(time.time()),
+                    "exp": int(time.time()) + 300,
+                    "nonce": login["nonce"],
+                    **provider.claims,
+                }) })
+
+        return Handler
+
+
+@pytest.fixture(scope="module")
+def mock_provider():
+    provider = MockProvider()
+    yield provider
+    provider.server.shutdown()
+
+
+def run_config_command(*args):
+    result = subprocess.run(get_wg_quickrs_command() + ["config"] + list(args), capture_output=True, text=True)
+    assert result.returncode == 0, result.stderr
+    return result.stdout.strip().splitlines()
+
+
+def setup_oidc(base_url, provider):
+    run_config_command("set", "agent", "web", "oidc", "issuer", provider.issuer)
+    run_config_command("set", "agent", "web", "oidc", "client-id", CLIENT_ID)
+    run_config_command("set", "agent", "web", "oidc", "client-secret", "--client-secret", CLIENT_SECRET)
+    run_config_command("set", "agent", "web", "oidc", "redirect-url", f"{base_url}/api/oidc/callback")
+    run_config_command("add", "oidc-role-mapping", "vpn-admins", "--role", "admin")
+    run_config_command("add", "oidc-role-mapping", "staff", "--role", "viewer")
+    run_config_command("enable", "agent", "web", "oidc")
+
+
+def start_login(browser, base_url, verify):
+    """Starts a login in the browser and signs in at the provider, returns the callback URL the provider redirects to."""
+    response = browser.get(f"{base_url}/api/oidc/login", allow_redirects=False, verify=verify)
+    assert response.status_code == 302, response.text
+    response = requests.get(response.headers["Location"], allow_redirects=False)
+    assert response.status_code == 302
+    return response.headers["Location"]
+
+
+def sign_in(base_url, verify):
+    """Follows the redirects of the login, returns the agent's answer to the callback."""
+    browser = requests.Session()
+    return browser.get(start_login(browser, base_url, verify), allow_redirects=False, verify=verify)
+
+
+def test_oidc_login(setup_wg_quickrs_agent, mock_provider):
+    """Test signing in with the provider issues a token with the highest mapped role."""
+    base_url = setup_wg_quickrs_agent("test_pwd_single_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    verify = wg_quickrs_config_folder / "certs/root/rootCA.crt"
+    assert requests.get(f"{base_url}/api/oidc", verify=verify).json() == { "enabled": False }
+    assert requests.get(f"{base_url}/api/oidc/login", allow_redirects=False, verify=verify).status_code == 404
+
+    setup_oidc(base_url, mock_provider)
+    assert requests.get(f"{base_url}/api/oidc", verify=verify).json() == { "enabled": True }
+    mock_provider.claims = { "preferred_username": "alice", "groups": ["staff", "vpn-admins"] }
+    response = sign_in(base_url, verify)
+    assert response.status_code == 302, response.text
+    assert response.headers["Location"].startswith("/#token=")
+    token = response.headers["Location"].removeprefix("/#token=")
+
+    response = requests.post(f"{base_url}/api/network/reserve/address", headers={ "Authorization": f"Bearer {token}" }, verify=verify)
+    assert response.status_code == 200, response.text
+    history = requests.get(f"{base_url}/api/config/history", headers={ "Authorization": f"Bearer {token}" }, verify=verify).json()
+    assert history[-1]["author"] == "api:oidc:alice"
+
+    mock_provider.claims = { "preferred_username": "bob", "groups": "staff" }
+    viewer_token = sign_in(base_url, verify).headers["Location"].removeprefix("/#token=")
+    response = requests.post(f"{base_url}/api/network/reserve/address", headers={ "Authorization": f"Bearer {viewer_token}" }, verify=verify)
+    assert response.status_code == 403
+
+    # disabling OIDC login revokes its tokens
+    run_config_command("disable", "agent", "web", "oidc")
+    response = requests.get(f"{base_url}/api/version", headers={ "Authorization": f"Bearer {token}" }, verify=verify)
+    assert response.status_code == 401
+
+
+def test_oidc_login_unmapped_role(setup_wg_quickrs_agent, mock_provider):
+    """Test users none of whose role claim values are mapped are refused."""
+    base_url = setup_wg_quickrs_agent("test_pwd_single_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    verify = wg_quickrs_config_folder / "certs/root/rootCA.crt"
+    setup_oidc(base_url, mock_provider)
+
+    mock_provider.claims = { "preferred_username": "mallory", "groups": ["contractors"] }
+    assert sign_in(base_url, verify).status_code == 403
+    mock_provider.claims = { "preferred_username": "mallory" }
+    assert sign_in(base_url, verify).status_code == 403
+
+
+def test_oidc_callback_unknown_login(setup_wg_quickrs_agent, mock_provider):
+    """Test callbacks of logins the agent did not start, or finished already, are refused."""
+    base_url = setup_wg_quickrs_agent("test_pwd_single_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    verify = wg_quickrs_config_folder / "certs/root/rootCA.crt"
+    setup_oidc(base_url, mock_provider)
+
+    response = requests.get(f"{base_url}/api/oidc/callback?code=forged&state=forged", allow_redirects=False, verify=verify)
+    assert response.status_code == 400
+
+    mock_provider.claims = { "preferred_username": "alice", "groups": "staff" }
+    browser = requests.Session()
+    callback_url = start_login(browser, base_url, verify)
+    assert browser.get(callback_url, allow_redirects=False, verify=verify).status_code == 302
+    assert browser.get(callback_url, allow_redirects=False, verify=verify).status_code == 400
+
+
+def test_oidc_callback_other_browser(setup_wg_quickrs_agent, mock_provider):
+    """Test the callback of a login started in another browser does not sign this one in."""
+    base_url = setup_wg_quickrs_agent("test_pwd_single_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    verify = wg_quickrs_config_folder / "certs/root/rootCA.crt"
+    setup_oidc(base_url, mock_provider)
+
+    mock_provider.claims = { "preferred_username": "mallory", "groups": "vpn-admins" }
+    attacker, victim = requests.Session(), requests.Session()
+    callback_url = start_login(attacker, base_url, verify)
+    assert victim.get(callback_url, allow_redirects=False, verify=verify).status_code == 400
+    # the login is still there for the browser that started it
+    assert attacker.get(callback_url, allow_redirects=False, verify=verify).status_code == 302
+
+
+def test_oidc_login_throttled(setup_wg_quickrs_agent, mock_provider):
+    """Test a client starting logins without finishing them is turned away."""
+    base_url = setup_wg_quickrs_agent("test_pwd_single_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    verify = wg_quickrs_config_folder / "certs/root/rootCA.crt"
+    setup_oidc(base_url, mock_provider)
+
+    for _ in range(6):
+        response = requests.get(f"{base_url}/api/oidc/login", allow_redirects=False, verify=verify)
+        assert response.status_code == 302
+    response = requests.get(f"{base_url}/api/oidc/login", allow_redirects=False, verify=verify)
+    assert response.status_code == 429
+    assert int(response.headers["Retry-After"]) >= 1
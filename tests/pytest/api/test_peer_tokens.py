@@ -0,0 +1,143 @@
+import subprocess
+import requests
+
+from tests.pytest.conftest import setup_wg_quickrs_agent
+from tests.pytest.helpers import get_paths, get_token, get_wg_quickrs_command, get_test_peer_data, get_test_connection_data
+
+THIS_PEER_ID = "0ed989c6-6dba-4e3c-8034-08adf4262d9e"
+
+
+def auth(token):
+    return { "Authorization": f"Bearer {token}" }
+
+
+def add_peer(base_url):
+    """Add a peer connected to this peer with the shared password, returns its id."""
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    verify = wg_quickrs_config_folder / "certs/root/rootCA.crt"
+    token = get_token(base_url)
+
+    reservation = requests.post(f"{base_url}/api/network/reserve/address", headers=auth(token), verify=verify).json()
+    peer_id = reservation["peer_id"]
+    peer_data = get_test_peer_data()
+    peer_data["address"] = reservation["address"]
+    response = requests.patch(f"{base_url}/api/network/config",
+                              json={
+                                  "added_peers": { peer_id: peer_data },
+                                  "added_connections": { f"{THIS_PEER_ID}*{peer_id}": get_test_connection_data() },
+                              },
+                              headers=auth(token),
+                              verify=verify)
+    assert response.status_code == 200, response.text
+    return peer_id
+
+
+def add_peer_token(peer_id, *args):
+    result = subprocess.run(get_wg_quickrs_command() + ["config", "add", "peer-token", peer_id] + list(args),
+                            capture_output=True, text=True)
+    assert result.returncode == 0, result.stderr
+    return result.stdout.strip().splitlines()[-1]
+
+
+def get_peer_token_jwt(base_url, peer_token):
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    response = requests.post(f"{base_url}/api/token",
+                             json={ "client_id": "pytest", "peer_token": peer_token },
+                             verify=wg_quickrs_config_folder / "certs/root/rootCA.crt")
+    assert response.status_code == 200
+    return response.text
+
+
+def test_peer_token_scope(setup_wg_quickrs_agent):
+    """Test a peer token only reads its own peer's config, connections and telemetry."""
+    base_url = setup_wg_quickrs_agent("test_pwd_single_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    verify = wg_quickrs_config_folder / "certs/root/rootCA.crt"
+    peer_id = add_peer(base_url)
+    token = get_peer_token_jwt(base_url, add_peer_token(peer_id))
+
+    response = requests.get(f"{base_url}/api/network/peers/{peer_id}/conf", headers=auth(token), verify=verify)
+    assert response.status_code == 200
+    assert "[Interface]" in response.text
+
+    response = requests.get(f"{base_url}/api/network/peers/{peer_id}/connections", headers=auth(token), verify=verify)
+    assert response.status_code == 200
+    assert list(response.json()) == [f"{THIS_PEER_ID}*{peer_id}"]
+    assert response.json()[f"{THIS_PEER_ID}*{peer_id}"]["peer_id"] == THIS_PEER_ID
+
+    # telemetry history is disabled in this config, which is checked after the token's scope
+    response = requests.get(f"{base_url}/api/network/connections/{THIS_PEER_ID}*{peer_id}/telemetry", headers=auth(token), verify=verify)
+    assert not response.text.startswith("Peer tokens")
+
+    for method, path in [
+        ("get", f"network/peers/{THIS_PEER_ID}/conf"),
+        ("get", "network/summary?only_digest=false"),
+        ("get", "version"),
+        ("get", "config/history"),
+        ("patch", "network/config"),
+        ("post", "network/reserve/address"),
+    ]:
+        response = requests.request(method, f"{base_url}/api/{path}", headers=auth(token), verify=verify)
+        assert response.status_code == 403, path
+
+    # key rotation has to be allowed when the token is added
+    response = requests.post(f"{base_url}/api/network/peers/{peer_id}/private-key/rotate", headers=auth(token), verify=verify)
+    assert response.status_code == 403
+
+
+def test_peer_token_key_rotation(setup_wg_quickrs_agent):
+    """Test a peer token allowing key rotation replaces the peer's private key."""
+    base_url = setup_wg_quickrs_agent("test_pwd_single_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    verify = wg_quickrs_config_folder / "certs/root/rootCA.crt"
+    peer_id = add_peer(base_url)
+    token = get_peer_token_jwt(base_url, add_peer_token(peer_id, "--key-rotation"))
+
+    old_conf = requests.get(f"{base_url}/api/network/peers/{peer_id}/conf", headers=auth(token), verify=verify).text
+    response = requests.post(f"{base_url}/api/network/peers/{peer_id}/private-key/rotate", headers=auth(token), verify=verify)
+    assert response.status_code == 200
+    assert "[Interface]" in response.text
+    assert response.text != old_conf
+    assert response.text == requests.get(f"{base_url}/api/network/peers/{peer_id}/conf", headers=auth(token), verify=verify).text
+
+    history = requests.get(f"{base_url}/api/config/history", headers=auth(get_token(base_url)), verify=verify).json()
+    assert history[-1]["author"].startswith("api:peer-token:")
+
+
+def test_peer_token_revoked(setup_wg_quickrs_agent):
+    """Test JWTs of a peer token stop working when the token or its peer is removed."""
+    base_url = setup_wg_quickrs_agent("test_pwd_single_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    verify = wg_quickrs_config_folder / "certs/root/rootCA.crt"
+    peer_id = add_peer(base_url)
+
+    peer_token = add_peer_token(peer_id)
+    token = get_peer_token_jwt(base_url, peer_token)
+    result = subprocess.run(get_wg_quickrs_command() + ["config", "remove", "peer-token", peer_token.split(".")[0]],
+                            capture_output=True, text=True)
+    assert result.returncode == 0, result.stderr
+    response = requests.get(f"{base_url}/api/network/peers/{peer_id}/conf", headers=auth(token), verify=verify)
+    assert response.status_code == 401
+
+    token = get_peer_token_jwt(base_url, add_peer_token(peer_id))
+    response = requests.patch(f"{base_url}/api/network/config",
+                              json={ "removed_peers": [peer_id] },
+                              headers=auth(get_token(base_url)),
+                              verify=verify)
+    assert response.status_code == 200
+    response = requests.get(f"{base_url}/api/network/peers/{peer_id}/conf", headers=auth(token), verify=verify)
+    assert response.status_code == 401
+
+
+def test_peer_token_invalid(setup_wg_quickrs_agent):
+    """Test POST /api/token refuses unknown or malformed peer tokens."""
+    base_url = setup_wg_quickrs_agent("test_pwd_single_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    peer_id = add_peer(base_url)
+    peer_token = add_peer_token(peer_id)
+
+    for invalid_token in [peer_token.split(".")[0] + ".wrong", "unknown.secret", "no-separator"]:
+        response = requests.post(f"{base_url}/api/token",
+                                 json={ "client_id": "pytest", "peer_token": invalid_token },
+                                 verify=wg_quickrs_config_folder / "certs/root/rootCA.crt")
+        assert response.status_code == 401
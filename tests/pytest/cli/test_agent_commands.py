@@ -1,3 +1,4 @@
+import hashlib
 import os
 import shutil
 
@@ -109,6 +110,62 @@ def test_agent_toggle_simple(setup_wg_quickrs_folder):
         run_and_check_success([action] + target, path, value, success)
 
 
+def test_agent_toggle_metrics(setup_wg_quickrs_folder):
+    setup_wg_quickrs_folder("no_auth_single_peer")
+    for action, target, path, value, success in [
+        ("enable", ["agent", "web", "metrics"], ('agent', 'web', 'metrics', 'enabled'), True, True),
+        ("disable", ["agent", "web", "metrics"], ('agent', 'web', 'metrics', 'enabled'), False, True),
+        ("enable", ["agent", "web", "metrics-token"], ('agent', 'web', 'metrics', 'token', 'enabled'), True, False),
+    ]:
+        run_and_check_success([action] + target, path, value, success)
+
+    # the token is printed once, only its hash ends up in the config
+    result = subprocess.run(
+        get_wg_quickrs_command() + ['config', 'reset', 'agent', 'web', 'metrics-token'],
+        capture_output=True,
+        text=True
+    )
+    assert result.returncode == 0
+    token = result.stdout.strip().splitlines()[-1]
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    with open(wg_quickrs_config_file) as stream:
+        conf = yaml.load(stream)
+    assert conf['agent']['web']['metrics']['token']['hash'] == "sha256:" + hashlib.sha256(token.encode()).hexdigest()
+
+    for action, target, path, value, success in [
+        ("enable", ["agent", "web", "metrics-token"], ('agent', 'web', 'metrics', 'token', 'enabled'), True, True),
+    ]:
+        run_and_check_success([action] + target, path, value, success)
+
+
+def test_agent_toggle_persistent_jwt_key(setup_wg_quickrs_folder):
+    setup_wg_quickrs_folder("no_auth_single_peer")
+    for action, target, path, value, success in [
+        ("enable", ["agent", "web", "persistent-jwt-key"], ('agent', 'web', 'persistent_jwt_key', 'enabled'), True, True),
+        ("disable", ["agent", "web", "persistent-jwt-key"], ('agent', 'web', 'persistent_jwt_key', 'enabled'), False, True),
+    ]:
+        run_and_check_success([action] + target, path, value, success)
+
+
+def test_agent_toggle_oidc(setup_wg_quickrs_folder):
+    setup_wg_quickrs_folder("test_pwd_single_peer")
+    # the provider and a role mapping have to be set before OIDC login can be enabled
+    run_and_check_success(["enable", "agent", "web", "oidc"], ('agent', 'web', 'oidc', 'enabled'), True, False)
+    for cmd in [
+        ["set", "agent", "web", "oidc", "issuer", "https://sso.example.com/realms/main"],
+        ["set", "agent", "web", "oidc", "client-id", "wg-quickrs"],
+        ["set", "agent", "web", "oidc", "redirect-url", "https://vpn.example.com/api/oidc/callback"],
+        ["add", "oidc-role-mapping", "staff", "--role", "viewer"],
+    ]:
+        result = subprocess.run(get_wg_quickrs_command() + ['config'] + cmd, capture_output=True, text=True)
+        assert result.returncode == 0, result.stderr
+    for action, target, path, value, success in [
+        ("enable", ["agent", "web", "oidc"], ('agent', 'web', 'oidc', 'enabled'), True, True),
+        ("disable", ["agent", "web", "oidc"], ('agent', 'web', 'oidc', 'enabled'), False, True),
+    ]:
+        run_and_check_success([action] + target, path, value, success)
+
+
 def test_agent_toggle_w_pwd(setup_wg_quickrs_folder):
     setup_wg_quickrs_folder("test_pwd_single_peer")
     for action, target, path, value, success in [
@@ -0,0 +1,131 @@
+import signal
+import socket
+import stat
+import subprocess
+import requests
+from tests.pytest.conftest import setup_wg_quickrs_folder
+from tests.pytest.helpers import get_wg_quickrs_command, get_paths, wait_for_tcp_port
+
+OTHER_PEER_ID = "6e9a8440-f884-4b54-bfe7-b982f15e40fd"
+BASE_URL = "http://127.0.0.1:9080"
+
+
+def start_agent():
+    agent = subprocess.Popen(get_wg_quickrs_command() + ["agent", "run"])
+    assert wait_for_tcp_port(("127.0.0.1", 9080), timeout=10)
+    return agent
+
+
+def run(*args):
+    return subprocess.run(get_wg_quickrs_command() + list(args), capture_output=True, text=True)
+
+
+def get_peer_name():
+    return requests.get(f"{BASE_URL}/api/network/summary?only_digest=false").json()["network"]["peers"][OTHER_PEER_ID]["name"]
+
+
+def test_control_socket_lifecycle(setup_wg_quickrs_folder):
+    """Test the control socket is only accessible by its owner and removed when the agent stops."""
+    setup_wg_quickrs_folder("no_auth_multi_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    agent = start_agent()
+    socket_path = wg_quickrs_config_folder / "agent.sock"
+    try:
+        mode = socket_path.stat().st_mode
+        assert stat.S_ISSOCK(mode)
+        assert stat.S_IMODE(mode) == 0o600
+        # the directory the socket is bound in before it is restricted is gone
+        assert not list(wg_quickrs_config_folder.glob(".agent-sock-*"))
+    finally:
+        agent.terminate()
+        agent.wait(timeout=10)
+    assert not socket_path.exists()
+
+
+def test_second_agent_refused(setup_wg_quickrs_folder):
+    """Test a second agent on the same config folder does not take the control socket over."""
+    setup_wg_quickrs_folder("no_auth_multi_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    agent = start_agent()
+    try:
+        result = run("agent", "run")
+        assert result.returncode != 0
+        assert "another agent is already running" in result.stderr + result.stdout
+        assert (wg_quickrs_config_folder / "agent.sock").exists()
+
+        result = run("config", "set", "network", "peer", OTHER_PEER_ID, "name", "renamed-by-cli")
+        assert "applied the change on the running agent" in result.stderr + result.stdout
+        assert get_peer_name() == "renamed-by-cli"
+    finally:
+        agent.terminate()
+        agent.wait(timeout=10)
+
+
+def test_stale_control_socket_replaced(setup_wg_quickrs_folder):
+    """Test a socket left behind by an agent that did not exit cleanly is replaced."""
+    setup_wg_quickrs_folder("no_auth_multi_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    stale = socket.socket(socket.AF_UNIX)
+    stale.bind(str(wg_quickrs_config_folder / "agent.sock"))
+    stale.close()
+
+    agent = start_agent()
+    try:
+        result = run("config", "set", "network", "peer", OTHER_PEER_ID, "name", "renamed-by-cli")
+        assert "applied the change on the running agent" in result.stderr + result.stdout
+    finally:
+        agent.terminate()
+        agent.wait(timeout=10)
+
+
+def test_config_applied_by_running_agent(setup_wg_quickrs_folder):
+    """Test network changes from the CLI take effect on the running agent right away."""
+    setup_wg_quickrs_folder("no_auth_multi_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    agent = start_agent()
+    try:
+        result = run("config", "set", "network", "peer", OTHER_PEER_ID, "name", "renamed-by-cli")
+        assert result.returncode == 0
+        assert "applied the change on the running agent" in result.stderr + result.stdout
+        assert get_peer_name() == "renamed-by-cli"
+        assert "renamed-by-cli" in wg_quickrs_config_file.read_text()
+
+        # the agent wrote conf.yml itself, so it keeps taking changes over the API
+        change_sum = {"changed_fields": {"peers": {OTHER_PEER_ID: {"name": "renamed-by-api"}}}}
+        assert requests.patch(f"{BASE_URL}/api/network/config", json=change_sum).status_code == 200
+
+        revisions = requests.get(f"{BASE_URL}/api/config/history").json()
+        assert revisions[-2]["author"].startswith("cli:")
+        assert revisions[-1]["author"] == "api"
+    finally:
+        agent.terminate()
+        agent.wait(timeout=10)
+
+
+def test_config_agent_settings_reload_running_agent(setup_wg_quickrs_folder):
+    """Test changes a ChangeSum can't express are written and loaded by the running agent."""
+    setup_wg_quickrs_folder("no_auth_multi_peer")
+    agent = start_agent()
+    try:
+        result = run("config", "set", "agent", "web", "http", "port", "9081")
+        assert result.returncode == 0
+        assert "reloaded the running agent" in result.stderr + result.stdout
+        assert wait_for_tcp_port(("127.0.0.1", 9081), timeout=10)
+        assert requests.get("http://127.0.0.1:9081/api/version").status_code == 200
+    finally:
+        agent.terminate()
+        agent.wait(timeout=10)
+
+
+def test_config_without_running_agent(setup_wg_quickrs_folder):
+    """Test the CLI writes conf.yml itself when the agent left a stale socket behind."""
+    setup_wg_quickrs_folder("no_auth_multi_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    agent = start_agent()
+    agent.send_signal(signal.SIGKILL)
+    agent.wait(timeout=10)
+    assert (wg_quickrs_config_folder / "agent.sock").exists()
+
+    result = run("config", "set", "network", "peer", OTHER_PEER_ID, "name", "renamed-by-cli")
+    assert result.returncode == 0
+    assert "renamed-by-cli" in wg_quickrs_config_file.read_text()
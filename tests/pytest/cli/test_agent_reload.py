@@ -0,0 +1,133 @@
+import signal
+import subprocess
+import time
+import requests
+from tests.pytest.conftest import setup_wg_quickrs_folder
+from tests.pytest.helpers import get_wg_quickrs_command, get_paths, wait_for_tcp_port
+
+OTHER_PEER_ID = "6e9a8440-f884-4b54-bfe7-b982f15e40fd"
+BASE_URL = "http://127.0.0.1:9080"
+
+
+def start_agent(*args, **kwargs):
+    agent = subprocess.Popen(get_wg_quickrs_command() + ["agent", "run"] + list(args), **kwargs)
+    assert wait_for_tcp_port(("127.0.0.1", 9080), timeout=10)
+    return agent
+
+
+def stop_agent(agent):
+    agent.terminate()
+    agent.wait(timeout=10)
+
+
+def edit_config_file(old, new):
+    """Edit conf.yml by hand, the CLI would hand its changes to the running agent instead."""
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    wg_quickrs_config_file.write_text(wg_quickrs_config_file.read_text().replace(old, new))
+
+
+def get_peer_name(base_url=BASE_URL):
+    return requests.get(f"{base_url}/api/network/summary?only_digest=false").json()["network"]["peers"][OTHER_PEER_ID]["name"]
+
+
+def wait_for_peer_name(name, timeout=5.0):
+    start = time.time()
+    while time.time() - start < timeout:
+        if get_peer_name() == name:
+            return True
+        time.sleep(0.1)
+    return False
+
+
+def test_agent_reload_on_sighup(setup_wg_quickrs_folder):
+    """Test SIGHUP loads changes made to conf.yml while the agent runs."""
+    setup_wg_quickrs_folder("no_auth_multi_peer")
+    agent = start_agent()
+    try:
+        digest = requests.get(f"{BASE_URL}/api/network/summary?only_digest=true").json()["digest"]
+        edit_config_file("name: other-peer1", "name: renamed-by-hand")
+        assert get_peer_name() == "other-peer1"
+
+        agent.send_signal(signal.SIGHUP)
+        assert wait_for_peer_name("renamed-by-hand")
+        assert agent.poll() is None
+
+        # the reloaded network is not a known successor of the old digest
+        change_sum = {"changed_fields": {"peers": {OTHER_PEER_ID: {"name": "renamed-by-api"}}}}
+        response = requests.patch(f"{BASE_URL}/api/network/config", json=change_sum, headers={"If-Match": f'"{digest}"'})
+        assert response.status_code == 409
+        # and the agent writes conf.yml again
+        response = requests.patch(f"{BASE_URL}/api/network/config", json=change_sum)
+        assert response.status_code == 200
+        assert get_peer_name() == "renamed-by-api"
+    finally:
+        stop_agent(agent)
+
+
+def test_agent_reload_restarts_web_server(setup_wg_quickrs_folder):
+    """Test the web server is restarted on the new port when its settings change."""
+    setup_wg_quickrs_folder("no_auth_multi_peer")
+    agent = start_agent()
+    try:
+        edit_config_file("port: 9080", "port: 9081")
+
+        agent.send_signal(signal.SIGHUP)
+        assert wait_for_tcp_port(("127.0.0.1", 9081), timeout=10)
+        assert requests.get("http://127.0.0.1:9081/api/version").status_code == 200
+        assert not wait_for_tcp_port(("127.0.0.1", 9080), timeout=1)
+        assert agent.poll() is None
+    finally:
+        stop_agent(agent)
+
+
+def test_agent_reload_keeps_tunnel_for_telemetry(setup_wg_quickrs_folder):
+    """Test telemetry settings are applied to the running tunnel instead of restarting it."""
+    setup_wg_quickrs_folder("no_auth_multi_peer")
+    agent = start_agent(stdout=subprocess.PIPE, stderr=subprocess.STDOUT, text=True)
+    try:
+        for args in [["set", "agent", "vpn", "telemetry", "interval", "500"],
+                     ["set", "agent", "vpn", "telemetry", "mode", "always"],
+                     ["enable", "agent", "vpn", "telemetry-history"]]:
+            assert subprocess.run(get_wg_quickrs_command() + ["config"] + args).returncode == 0
+        # the VPN port is only applied when the tunnel comes up
+        assert subprocess.run(get_wg_quickrs_command() + ["config", "set", "agent", "vpn", "port", "51900"]).returncode == 0
+    finally:
+        stop_agent(agent)
+    assert agent.stdout.read().count("VPN settings changed") == 1
+
+
+def test_agent_reload_keeps_running_config_when_invalid(setup_wg_quickrs_folder):
+    """Test an invalid conf.yml is not loaded and the agent keeps running."""
+    setup_wg_quickrs_folder("no_auth_multi_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    agent = start_agent()
+    try:
+        with open(wg_quickrs_config_file, "w") as f:
+            f.write("invalid: yaml: content: [")
+
+        agent.send_signal(signal.SIGHUP)
+        time.sleep(1)
+        assert agent.poll() is None
+        assert get_peer_name() == "other-peer1"
+    finally:
+        stop_agent(agent)
+
+
+def test_agent_watch_config(setup_wg_quickrs_folder):
+    """Test --watch-config loads changes without a signal."""
+    setup_wg_quickrs_folder("no_auth_multi_peer")
+    agent = start_agent("--watch-config")
+    try:
+        edit_config_file("name: other-peer1", "name: renamed-by-hand")
+        assert wait_for_peer_name("renamed-by-hand")
+
+        # writes of the agent itself are not mistaken for external changes
+        change_sum = {"changed_fields": {"peers": {OTHER_PEER_ID: {"name": "renamed-by-api"}}}}
+        assert requests.patch(f"{BASE_URL}/api/network/config", json=change_sum).status_code == 200
+        time.sleep(2)
+        assert get_peer_name() == "renamed-by-api"
+        assert requests.patch(f"{BASE_URL}/api/network/config", json={
+            "changed_fields": {"peers": {OTHER_PEER_ID: {"name": "renamed-again"}}}
+        }).status_code == 200
+    finally:
+        stop_agent(agent)
@@ -1,7 +1,10 @@
 from tests.pytest.conftest import setup_wg_quickrs_folder
 from tests.pytest.helpers import get_wg_quickrs_command, get_paths
+import hashlib
 import subprocess
 import pytest
+from ruamel.yaml import YAML
+yaml = YAML()
 
 
 def add_peer_no_prompt(opts):
@@ -247,3 +250,168 @@ def test_add_connection_missing_required_with_no_prompt(setup_wg_quickrs_folder)
 
     assert result.returncode != 0
     assert "ERROR" in result.stdout or "ERROR" in result.stderr
+
+
+def add_user(*args, password="secret"):
+    """Run config add user, typing the password at the prompt."""
+    return subprocess.run(
+        get_wg_quickrs_command() + ["config", "add", "user"] + list(args),
+        input=f"{password}\n",
+        capture_output=True,
+        text=True
+    )
+
+
+def test_add_user(setup_wg_quickrs_folder):
+    """Test adding a user keeps its role and only the hash of its password."""
+    setup_wg_quickrs_folder("test_pwd_single_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+
+    result = add_user("alice", "--role", "operator")
+    assert result.returncode == 0, result.stderr
+
+    with open(wg_quickrs_config_file) as stream:
+        config = yaml.load(stream)
+    user = config["agent"]["web"]["users"]["alice"]
+    assert user["role"] == "operator"
+    assert user["hash"].startswith("$argon2")
+    assert "secret" not in user["hash"]
+
+
+@pytest.mark.parametrize(
+    "name,role",
+    [
+        ("alice", "root"),  # unknown role
+        ("bob smith", "viewer"),  # space in name
+        ("", "viewer"),  # empty name
+    ],
+)
+def test_add_user_invalid(setup_wg_quickrs_folder, name, role):
+    """Test adding a user with an invalid name or role fails."""
+    setup_wg_quickrs_folder("test_pwd_single_peer")
+
+    result = add_user(name, "--role", role)
+    assert result.returncode != 0
+
+
+def test_add_user_duplicate(setup_wg_quickrs_folder):
+    """Test adding a user twice fails."""
+    setup_wg_quickrs_folder("test_pwd_single_peer")
+
+    assert add_user("alice", "--role", "viewer").returncode == 0
+    result = add_user("alice", "--role", "admin")
+    assert result.returncode != 0
+    assert "already exists" in result.stdout + result.stderr
+
+
+def test_add_peer_token(setup_wg_quickrs_folder):
+    """Test a peer token is printed once and only its hash is kept."""
+    setup_wg_quickrs_folder("no_auth_multi_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    peer_id = "6e9a8440-f884-4b54-bfe7-b982f15e40fd"
+
+    result = subprocess.run(
+        get_wg_quickrs_command() + ["config", "add", "peer-token", peer_id, "--key-rotation"],
+        capture_output=True,
+        text=True
+    )
+    assert result.returncode == 0, result.stderr
+    token_id, secret = result.stdout.strip().splitlines()[-1].split(".")
+
+    with open(wg_quickrs_config_file) as stream:
+        config = yaml.load(stream)
+    peer_token = config["agent"]["web"]["peer_tokens"][token_id]
+    assert peer_token["peer_id"] == peer_id
+    assert peer_token["key_rotation"] is True
+    assert peer_token["hash"].startswith("$argon2")
+    assert secret not in open(wg_quickrs_config_file).read()
+
+
+@pytest.mark.parametrize(
+    "peer_id",
+    [
+        "0ed989c6-6dba-4e3c-8034-08adf4262d9e",  # this peer
+        "00000000-0000-4000-8000-000000000000",  # nonexistent peer
+    ],
+)
+def test_add_peer_token_invalid_peer(setup_wg_quickrs_folder, peer_id):
+    """Test peer tokens can't be added for this peer or a peer that doesn't exist."""
+    setup_wg_quickrs_folder("no_auth_multi_peer")
+
+    result = subprocess.run(
+        get_wg_quickrs_command() + ["config", "add", "peer-token", peer_id],
+        capture_output=True,
+        text=True
+    )
+    assert result.returncode != 0
+
+
+def test_add_api_key(setup_wg_quickrs_folder):
+    """Test adding an API key prints it once and only keeps its hash."""
+    setup_wg_quickrs_folder("no_auth_single_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+
+    result = subprocess.run(
+        get_wg_quickrs_command() + ["config", "add", "api-key", "ci-deploy", "--role", "viewer"],
+        capture_output=True,
+        text=True
+    )
+    assert result.returncode == 0, result.stderr
+    key_id, secret = result.stdout.strip().splitlines()[-1].removeprefix("wgq_").split(".")
+
+    with open(wg_quickrs_config_file) as stream:
+        config = yaml.load(stream)
+    api_key = config["agent"]["web"]["api_keys"]["ci-deploy"]
+    assert api_key["id"] == key_id
+    assert api_key["role"] == "viewer"
+    assert api_key["hash"] == "sha256:" + hashlib.sha256(secret.encode()).hexdigest()
+    assert secret not in open(wg_quickrs_config_file).read()
+
+
+@pytest.mark.parametrize(
+    "name,role",
+    [
+        ("ci-deploy", "root"),  # unknown role
+        ("terraform/prod", "viewer"),  # slash in name
+        ("existing", "viewer"),  # duplicate name
+    ],
+)
+def test_add_api_key_invalid(setup_wg_quickrs_folder, name, role):
+    """Test adding an API key with an invalid or taken name or an invalid role fails."""
+    setup_wg_quickrs_folder("no_auth_single_peer")
+
+    result = subprocess.run(
+        get_wg_quickrs_command() + ["config", "add", "api-key", "existing", "--role", "admin"],
+        capture_output=True,
+        text=True
+    )
+    assert result.returncode == 0, result.stderr
+    result = subprocess.run(
+        get_wg_quickrs_command() + ["config", "add", "api-key", name, "--role", role],
+        capture_output=True,
+        text=True
+    )
+    assert result.returncode != 0
+
+
+def test_add_oidc_role_mapping(setup_wg_quickrs_folder):
+    """Test mapping OpenID Connect role claim values to roles."""
+    setup_wg_quickrs_folder("no_auth_single_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+
+    for value, role, expected_success in [
+        ("vpn-admins", "admin", True),
+        ("staff", "viewer", True),
+        ("staff", "operator", True),  # remapped
+        ("contractors", "root", False),  # unknown role
+    ]:
+        result = subprocess.run(
+            get_wg_quickrs_command() + ["config", "add", "oidc-role-mapping", value, "--role", role],
+            capture_output=True,
+            text=True
+        )
+        assert (result.returncode == 0) == expected_success
+
+    with open(wg_quickrs_config_file) as stream:
+        config = yaml.load(stream)
+    assert dict(config["agent"]["web"]["oidc"]["role_mapping"]) == {"vpn-admins": "admin", "staff": "operator"}
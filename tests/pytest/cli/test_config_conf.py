@@ -1,3 +1,4 @@
+import pytest
 import subprocess
 from tests.pytest.conftest import setup_wg_quickrs_folder
 from tests.pytest.helpers import get_wg_quickrs_command, get_paths
@@ -149,3 +150,74 @@ def test_config_conf_with_amnezia_enabled(setup_wg_quickrs_folder):
     assert "H2" in result.stdout
     assert "H3" in result.stdout
     assert "H4" in result.stdout
+
+
+@pytest.mark.parametrize("conf_format", ["qr", "svg"])
+def test_config_conf_qr_text_formats(setup_wg_quickrs_folder, conf_format):
+    """Test that config conf --format qr/svg renders a QR code to stdout."""
+    setup_wg_quickrs_folder("no_auth_multi_peer")
+
+    peer_id = "0ed989c6-6dba-4e3c-8034-08adf4262d9e"
+
+    result = subprocess.run(
+        get_wg_quickrs_command() + ["config", "conf", peer_id, "--format", conf_format],
+        capture_output=True,
+        text=True
+    )
+    print(result.stderr)
+
+    assert result.returncode == 0
+    # The config itself should not be printed in plain text
+    assert "[Interface]" not in result.stdout
+    if conf_format == "qr":
+        assert "▀" in result.stdout or "▄" in result.stdout or "█" in result.stdout
+    else:
+        assert result.stdout.startswith("<?xml")
+        assert "<svg" in result.stdout
+
+
+def test_config_conf_png_to_file(setup_wg_quickrs_folder):
+    """Test that config conf --format png -o writes a PNG image."""
+    setup_wg_quickrs_folder("no_auth_multi_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+
+    peer_id = "0ed989c6-6dba-4e3c-8034-08adf4262d9e"
+    output_file = wg_quickrs_config_folder / "test.png"
+
+    result = subprocess.run(
+        get_wg_quickrs_command() + ["config", "conf", peer_id, "--format", "png", "-o", str(output_file)],
+        capture_output=True,
+        text=True
+    )
+    print(result.stdout)
+    print(result.stderr)
+
+    assert result.returncode == 0
+    with open(output_file, 'rb') as f:
+        assert f.read(8) == b"\x89PNG\r\n\x1a\n"
+
+
+def test_config_conf_public_key_peer(setup_wg_quickrs_folder):
+    """Test that configs of peers without a private key have a placeholder, and other peers use the public key."""
+    setup_wg_quickrs_folder("no_auth_multi_peer")
+    public_key = "uo2hR4Jw2lv/0+db23XgnE32jN5woDKWUHACft7W/Eo="
+    subprocess.run(
+        get_wg_quickrs_command() + ["config", "set", "network", "peer", "6e9a8440-f884-4b54-bfe7-b982f15e40fd", "public-key", public_key],
+        check=True
+    )
+
+    result = subprocess.run(
+        get_wg_quickrs_command() + ["config", "conf", "6e9a8440-f884-4b54-bfe7-b982f15e40fd"],
+        capture_output=True,
+        text=True
+    )
+    assert result.returncode == 0
+    assert "PrivateKey = <PRIVATE_KEY>" in result.stdout
+
+    result = subprocess.run(
+        get_wg_quickrs_command() + ["config", "conf", "0ed989c6-6dba-4e3c-8034-08adf4262d9e"],
+        capture_output=True,
+        text=True
+    )
+    assert result.returncode == 0
+    assert f"PublicKey = {public_key}" in result.stdout
@@ -0,0 +1,98 @@
+import subprocess
+from tests.pytest.helpers import get_wg_quickrs_command, get_paths
+from tests.pytest.conftest import setup_wg_quickrs_folder
+
+OTHER_PEER_ID = "6e9a8440-f884-4b54-bfe7-b982f15e40fd"
+
+
+def run(*args):
+    return subprocess.run(get_wg_quickrs_command() + list(args), capture_output=True, text=True)
+
+
+def test_config_history_empty(setup_wg_quickrs_folder):
+    """Test a config that was never written has no history."""
+    setup_wg_quickrs_folder("no_auth_multi_peer")
+
+    result = run("config", "history", "list")
+    assert result.returncode == 0
+    assert "No revisions found." in result.stdout
+
+    result = run("config", "history", "show", "1")
+    assert result.returncode != 0
+
+
+def test_config_history_records_writes(setup_wg_quickrs_folder):
+    """Test every write keeps a revision with its author and a summary."""
+    setup_wg_quickrs_folder("no_auth_multi_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+
+    assert run("config", "set", "network", "peer", OTHER_PEER_ID, "address", "10.0.34.50").returncode == 0
+
+    result = run("config", "history", "list")
+    assert result.returncode == 0
+    lines = result.stdout.strip().split('\n')
+    # the config before the first write is kept as well
+    assert len(lines) == 2
+    assert lines[0].startswith("1 ")
+    assert lines[1].startswith("2 ")
+    assert "cli:" in lines[1]
+    assert f"~network.peers.{OTHER_PEER_ID}.address" in lines[1]
+
+    result = run("config", "history", "show", "2")
+    assert result.returncode == 0
+    with open(wg_quickrs_config_file) as stream:
+        assert result.stdout == stream.read()
+
+    result = run("config", "history", "diff", "2")
+    assert result.returncode == 0
+    assert "--- a/1.yml" in result.stdout
+    assert "+++ b/2.yml" in result.stdout
+    assert "-      address: 10.0.34.2" in result.stdout
+    assert "+      address: 10.0.34.50" in result.stdout
+
+
+def test_config_history_rollback(setup_wg_quickrs_folder):
+    """Test a rollback restores a revision as a new revision."""
+    setup_wg_quickrs_folder("no_auth_multi_peer")
+
+    assert run("config", "set", "network", "peer", OTHER_PEER_ID, "name", "renamed").returncode == 0
+    assert run("config", "set", "network", "peer", OTHER_PEER_ID, "address", "10.0.34.50").returncode == 0
+
+    result = run("config", "history", "rollback", "1")
+    assert result.returncode == 0
+    assert run("config", "get", "network", "peers", OTHER_PEER_ID, "name").stdout.strip() == "other-peer1"
+    assert run("config", "get", "network", "peers", OTHER_PEER_ID, "address").stdout.strip() == "10.0.34.2"
+
+    lines = run("config", "history", "list").stdout.strip().split('\n')
+    assert len(lines) == 4
+    assert lines[3].endswith("(rollback of 1)")
+
+    # the rollback itself can be rolled back
+    assert run("config", "history", "rollback", "3").returncode == 0
+    assert run("config", "get", "network", "peers", OTHER_PEER_ID, "address").stdout.strip() == "10.0.34.50"
+
+    assert run("config", "history", "rollback", "99").returncode != 0
+
+
+def test_config_history_retention(setup_wg_quickrs_folder):
+    """Test only the configured number of revisions is kept."""
+    setup_wg_quickrs_folder("no_auth_multi_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+
+    for retention in ["0", "10001", "all"]:
+        result = run("config", "set", "agent", "config-history", "retention", retention)
+        assert result.returncode != 0
+        assert "config history retention is invalid" in result.stderr + result.stdout
+    assert run("config", "set", "agent", "config-history", "retention", "2").returncode == 0
+    assert run("config", "get", "agent", "config-history", "retention").stdout.strip() == "2"
+    for name in ["a", "b", "c"]:
+        assert run("config", "set", "network", "peer", OTHER_PEER_ID, "name", name).returncode == 0
+
+    lines = run("config", "history", "list").stdout.strip().split('\n')
+    assert [line.split(' ')[0] for line in lines] == ["4", "5"]
+    assert sorted(path.name for path in (wg_quickrs_config_folder / "history").glob("*.yml")) == ["4.yml", "5.yml"]
+
+    # the oldest kept revision is diffed against nothing
+    result = run("config", "history", "diff", "4")
+    assert result.returncode == 0
+    assert "--- /dev/null" in result.stdout
@@ -0,0 +1,153 @@
+from tests.pytest.conftest import setup_wg_quickrs_folder
+from tests.pytest.helpers import get_wg_quickrs_command, get_paths
+import subprocess
+from ruamel.yaml import YAML
+yaml = YAML()
+yaml.preserve_quotes = True
+
+LAPTOP_CONF = """\
+[Interface]
+PrivateKey = wODITqX4oJtjT1N4Mx17K2dRaogd9i/ZBhgNVsVoDlg=
+Address = 10.0.34.50/24
+DNS = 1.1.1.1
+PostUp = echo up
+
+[Peer]
+PublicKey = uo2hR4Jw2lv/0+db23XgnE32jN5woDKWUHACft7W/Eo=
+PresharedKey = iF9xlxiI3W/p9LSZ5QhT/4Rk6IHi8v5NzA/UTUdPOVI=
+AllowedIPs = 10.0.34.51/32
+
+[Peer]
+PublicKey = wzTKDk4Ws8z58okzyLrJTnN5tkAJmBBOwTIq52RzgWQ=
+AllowedIPs = 10.0.34.99/32
+"""
+
+PHONE_CONF = """\
+[Interface]
+PrivateKey = QOp247ORpfEimcZFE4DxS5+LTJ5s5mUfpiqTnaEtC0Q=
+Address = 10.0.34.51/32
+MTU = 1380
+
+[Peer]
+PublicKey = QMUUFvCRL9SVEBmnM1or3lC7VZI/pLgnN9jrPIJngzk=
+PresharedKey = iF9xlxiI3W/p9LSZ5QhT/4Rk6IHi8v5NzA/UTUdPOVI=
+AllowedIPs = 10.0.34.0/24
+Endpoint = 203.0.113.5:51820
+PersistentKeepalive = 25
+"""
+
+
+def import_wg_quick(*files):
+    result = subprocess.run(
+        get_wg_quickrs_command() + ["config", "import", "wg-quick"] + [str(f) for f in files],
+        capture_output=True,
+        text=True
+    )
+    print(result.stdout)
+    print(result.stderr)
+    return result
+
+
+def test_config_import_wg_quick(setup_wg_quickrs_folder):
+    """Test that config import wg-quick creates peers and connections from wg-quick files."""
+    setup_wg_quickrs_folder("no_auth_single_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    (wg_quickrs_config_folder / "laptop.conf").write_text(LAPTOP_CONF)
+    (wg_quickrs_config_folder / "phone.conf").write_text(PHONE_CONF)
+
+    result = import_wg_quick(wg_quickrs_config_folder / "laptop.conf", wg_quickrs_config_folder / "phone.conf")
+    assert result.returncode == 0
+
+    with open(wg_quickrs_config_file) as stream:
+        conf = yaml.load(stream)
+    peers = {peer["name"]: (peer_id, peer) for peer_id, peer in conf["network"]["peers"].items()}
+    laptop_id, laptop = peers["laptop"]
+    phone_id, phone = peers["phone"]
+
+    assert laptop["address"] == "10.0.34.50"
+    assert laptop["dns"] == {"enabled": True, "addresses": ["1.1.1.1"]}
+    assert laptop["scripts"]["post_up"] == [{"enabled": True, "script": "echo up;"}]
+    assert laptop["endpoint"]["enabled"]
+    assert laptop["endpoint"]["address"]["ipv4"] == "203.0.113.5"
+    assert phone["address"] == "10.0.34.51"
+    assert phone["host_address"]
+    assert phone["mtu"] == {"enabled": True, "value": 1380}
+
+    # the peer with an unknown private key is imported with its public key only
+    unknown_id, unknown = next((peer_id, peer) for peer_id, peer in conf["network"]["peers"].items()
+                               if peer.get("public_key") == "wzTKDk4Ws8z58okzyLrJTnN5tkAJmBBOwTIq52RzgWQ=")
+    assert "private_key" not in unknown
+    assert unknown["address"] == "10.0.34.99"
+    assert f"{max(laptop_id, unknown_id)}*{min(laptop_id, unknown_id)}" in conf["network"]["connections"]
+
+    connection_id = f"{max(laptop_id, phone_id)}*{min(laptop_id, phone_id)}"
+    connection = conf["network"]["connections"][connection_id]
+    assert connection["pre_shared_key"] == "iF9xlxiI3W/p9LSZ5QhT/4Rk6IHi8v5NzA/UTUdPOVI="
+    assert connection["persistent_keepalive"] == {"enabled": True, "period": 25}
+    laptop_to_phone, phone_to_laptop = ("allowed_ips_a_to_b", "allowed_ips_b_to_a") if laptop_id > phone_id else ("allowed_ips_b_to_a", "allowed_ips_a_to_b")
+    assert connection[laptop_to_phone] == ["10.0.34.51/32"]
+    assert connection[phone_to_laptop] == ["10.0.34.0/24"]
+
+
+def test_config_import_wg_quick_address_taken(setup_wg_quickrs_folder):
+    """Test that config import wg-quick fails without changes when an address is already taken."""
+    setup_wg_quickrs_folder("no_auth_single_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    (wg_quickrs_config_folder / "laptop.conf").write_text(LAPTOP_CONF.replace("10.0.34.50/24", "10.0.34.1/24"))
+    with open(wg_quickrs_config_file) as stream:
+        old_conf = stream.read()
+
+    result = import_wg_quick(wg_quickrs_config_folder / "laptop.conf")
+    assert result.returncode != 0
+    assert "laptop.conf" in result.stdout
+
+    with open(wg_quickrs_config_file) as stream:
+        assert stream.read() == old_conf
+
+
+def test_config_import_wg_quick_listen_port(setup_wg_quickrs_folder):
+    """Test that the ListenPort of an imported interface becomes the port of its endpoint."""
+    setup_wg_quickrs_folder("no_auth_single_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    (wg_quickrs_config_folder / "laptop.conf").write_text(LAPTOP_CONF.replace("DNS = 1.1.1.1", "ListenPort = 51900"))
+    (wg_quickrs_config_folder / "phone.conf").write_text(PHONE_CONF)
+
+    result = import_wg_quick(wg_quickrs_config_folder / "laptop.conf", wg_quickrs_config_folder / "phone.conf")
+    assert result.returncode == 0
+
+    with open(wg_quickrs_config_file) as stream:
+        conf = yaml.load(stream)
+    laptop = next(peer for peer in conf["network"]["peers"].values() if peer["name"] == "laptop")
+    assert laptop["endpoint"]["address"] == {"ipv4": "203.0.113.5", "port": 51900}
+
+
+def test_config_import_wg_quick_listen_port_unknown_endpoint(setup_wg_quickrs_folder):
+    """Test that a ListenPort without a known endpoint is reported instead of silently dropped."""
+    setup_wg_quickrs_folder("no_auth_single_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    (wg_quickrs_config_folder / "laptop.conf").write_text(LAPTOP_CONF.replace("DNS = 1.1.1.1", "ListenPort = 51900"))
+
+    result = import_wg_quick(wg_quickrs_config_folder / "laptop.conf")
+    assert result.returncode == 0
+    assert "ignoring ListenPort = 51900" in result.stdout
+
+
+def test_config_import_wg_quick_this_peer_listen_port(setup_wg_quickrs_folder):
+    """Test that importing the agent's own interface takes its ListenPort as the VPN port."""
+    setup_wg_quickrs_folder("no_auth_single_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    (wg_quickrs_config_folder / "hub.conf").write_text("""\
+[Interface]
+PrivateKey = yGFmuVZeV1V/Jy0rAx7bOF4Gx4Mt/17VMazM5grvqE4=
+Address = 10.0.34.1/24
+ListenPort = 51000
+""")
+
+    result = import_wg_quick(wg_quickrs_config_folder / "hub.conf")
+    assert result.returncode == 0
+
+    with open(wg_quickrs_config_file) as stream:
+        conf = yaml.load(stream)
+    assert conf["agent"]["vpn"]["port"] == 51000
+    this_peer = conf["network"]["peers"][conf["network"]["this_peer"]]
+    assert this_peer["endpoint"]["address"]["port"] == 51000
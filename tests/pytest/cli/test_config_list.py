@@ -115,4 +115,59 @@ def test_config_list_reservations_empty(setup_wg_quickrs_folder):
     assert result.returncode == 0
     assert "No reservations found" in result.stdout
 
+
+def test_config_list_users(setup_wg_quickrs_folder):
+    """Test listing users with their roles."""
+    setup_wg_quickrs_folder("test_pwd_single_peer")
+
+    result = subprocess.run(
+        get_wg_quickrs_command() + ["config", "list", "users"],
+        capture_output=True,
+        text=True
+    )
+    assert result.returncode == 0
+    assert "No users found" in result.stdout
+
+    for name, role in [("bob", "admin"), ("alice", "viewer")]:
+        subprocess.run(
+            get_wg_quickrs_command() + ["config", "add", "user", name, "--role", role, "--password", "secret"],
+            capture_output=True,
+            text=True
+        )
+    result = subprocess.run(
+        get_wg_quickrs_command() + ["config", "list", "users"],
+        capture_output=True,
+        text=True
+    )
+    assert result.returncode == 0
+    assert result.stdout.strip().splitlines() == ["alice (viewer)", "bob (admin)"]
+
+
+def test_config_list_api_keys(setup_wg_quickrs_folder):
+    """Test listing API keys with their roles, keys that were never used say so."""
+    setup_wg_quickrs_folder("no_auth_single_peer")
+
+    result = subprocess.run(
+        get_wg_quickrs_command() + ["config", "list", "api-keys"],
+        capture_output=True,
+        text=True
+    )
+    assert result.returncode == 0
+    assert "No API keys found" in result.stdout
+
+    subprocess.run(
+        get_wg_quickrs_command() + ["config", "add", "api-key", "ci-deploy", "--role", "operator"],
+        capture_output=True,
+        text=True
+    )
+    result = subprocess.run(
+        get_wg_quickrs_command() + ["config", "list", "api-keys"],
+        capture_output=True,
+        text=True
+    )
+    assert result.returncode == 0
+    line = result.stdout.strip().splitlines()[-1]
+    assert line.startswith("ci-deploy (operator) created: ")
+    assert line.endswith("last used: never")
+
 # TODO: add tests for reservations
@@ -0,0 +1,48 @@
+import fcntl
+import os
+import subprocess
+import time
+from tests.pytest.helpers import get_wg_quickrs_command, get_paths
+from tests.pytest.conftest import setup_wg_quickrs_folder
+
+OTHER_PEER_ID = "6e9a8440-f884-4b54-bfe7-b982f15e40fd"
+
+
+def test_config_waits_for_lock(setup_wg_quickrs_folder):
+    """Test a config command waits while another process holds the config folder lock."""
+    setup_wg_quickrs_folder("no_auth_multi_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+
+    with open(wg_quickrs_config_folder / ".lock", "w") as lock_file:
+        fcntl.flock(lock_file, fcntl.LOCK_EX)
+        process = subprocess.Popen(
+            get_wg_quickrs_command() + ["config", "set", "network", "peer", OTHER_PEER_ID, "address", "10.0.34.50"],
+            stdout=subprocess.PIPE, stderr=subprocess.PIPE, text=True,
+        )
+        time.sleep(1)
+        assert process.poll() is None
+        assert "10.0.34.50" not in wg_quickrs_config_file.read_text()
+        fcntl.flock(lock_file, fcntl.LOCK_UN)
+
+    process.communicate(timeout=10)
+    assert process.returncode == 0
+    assert "10.0.34.50" in wg_quickrs_config_file.read_text()
+
+
+def test_config_write_is_atomic(setup_wg_quickrs_folder):
+    """Test conf.yml is replaced as a whole and keeps its permissions."""
+    setup_wg_quickrs_folder("no_auth_multi_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    os.chmod(wg_quickrs_config_file, 0o600)
+    inode = wg_quickrs_config_file.stat().st_ino
+
+    result = subprocess.run(
+        get_wg_quickrs_command() + ["config", "set", "network", "peer", OTHER_PEER_ID, "address", "10.0.34.50"],
+        capture_output=True, text=True,
+    )
+    assert result.returncode == 0
+
+    # written to a temporary file that was renamed over conf.yml
+    assert wg_quickrs_config_file.stat().st_ino != inode
+    assert wg_quickrs_config_file.stat().st_mode & 0o777 == 0o600
+    assert not [f for f in os.listdir(wg_quickrs_config_folder) if f.startswith(".tmp")]
@@ -229,3 +229,100 @@ def test_config_remove_reservation_invalid_ip(setup_wg_quickrs_folder, invalid_i
         text=True
     )
     assert result.returncode != 0
+
+
+def test_config_remove_user(setup_wg_quickrs_folder):
+    """Test removing a user."""
+    setup_wg_quickrs_folder("test_pwd_single_peer")
+
+    subprocess.run(
+        get_wg_quickrs_command() + ["config", "add", "user", "alice", "--role", "viewer", "--password", "secret"],
+        capture_output=True,
+        text=True
+    )
+    result = subprocess.run(
+        get_wg_quickrs_command() + ["config", "remove", "user", "alice"],
+        capture_output=True,
+        text=True
+    )
+    assert result.returncode == 0
+
+    result = subprocess.run(
+        get_wg_quickrs_command() + ["config", "remove", "user", "alice"],
+        capture_output=True,
+        text=True
+    )
+    assert result.returncode != 0
+    assert "not found" in result.stdout.lower() or "not found" in result.stderr.lower()
+
+
+def test_config_remove_peer_revokes_peer_tokens(setup_wg_quickrs_folder):
+    """Test removing a peer also removes its peer tokens."""
+    setup_wg_quickrs_folder("no_auth_multi_peer")
+    peer_id = "6e9a8440-f884-4b54-bfe7-b982f15e40fd"
+
+    result = subprocess.run(
+        get_wg_quickrs_command() + ["config", "add", "peer-token", peer_id],
+        capture_output=True,
+        text=True
+    )
+    token_id = result.stdout.strip().splitlines()[-1].split(".")[0]
+    subprocess.run(get_wg_quickrs_command() + ["config", "remove", "peer", peer_id], capture_output=True, text=True)
+
+    result = subprocess.run(
+        get_wg_quickrs_command() + ["config", "remove", "peer-token", token_id],
+        capture_output=True,
+        text=True
+    )
+    assert result.returncode != 0
+    assert "not found" in result.stdout.lower() or "not found" in result.stderr.lower()
+
+
+def test_config_remove_api_key(setup_wg_quickrs_folder):
+    """Test removing an API key."""
+    setup_wg_quickrs_folder("no_auth_single_peer")
+
+    subprocess.run(
+        get_wg_quickrs_command() + ["config", "add", "api-key", "ci-deploy", "--role", "operator"],
+        capture_output=True,
+        text=True
+    )
+    result = subprocess.run(
+        get_wg_quickrs_command() + ["config", "remove", "api-key", "ci-deploy"],
+        capture_output=True,
+        text=True
+    )
+    assert result.returncode == 0
+
+    result = subprocess.run(
+        get_wg_quickrs_command() + ["config", "remove", "api-key", "ci-deploy"],
+        capture_output=True,
+        text=True
+    )
+    assert result.returncode != 0
+    assert "not found" in result.stdout.lower() or "not found" in result.stderr.lower()
+
+
+def test_config_remove_oidc_role_mapping(setup_wg_quickrs_folder):
+    """Test removing an OpenID Connect role mapping."""
+    setup_wg_quickrs_folder("no_auth_single_peer")
+
+    subprocess.run(
+        get_wg_quickrs_command() + ["config", "add", "oidc-role-mapping", "staff", "--role", "viewer"],
+        capture_output=True,
+        text=True
+    )
+    result = subprocess.run(
+        get_wg_quickrs_command() + ["config", "remove", "oidc-role-mapping", "staff"],
+        capture_output=True,
+        text=True
+    )
+    assert result.returncode == 0
+
+    result = subprocess.run(
+        get_wg_quickrs_command() + ["config", "remove", "oidc-role-mapping", "staff"],
+        capture_output=True,
+        text=True
+    )
+    assert result.returncode != 0
+    assert "not found" in result.stdout.lower() or "not found" in result.stderr.lower()
@@ -1,6 +1,7 @@
 import subprocess
 import pytest
-from tests.pytest.helpers import get_wg_quickrs_command
+from ruamel.yaml import YAML
+from tests.pytest.helpers import get_wg_quickrs_command, get_paths
 from tests.pytest.conftest import setup_wg_quickrs_folder
 
 
@@ -101,6 +102,40 @@ def test_config_reset_peer_private_key(setup_wg_quickrs_folder):
     assert new_key != old_key
 
 
+
+def test_config_set_peer_public_key(setup_wg_quickrs_folder):
+    """Test that setting a peer public key drops its private key, except for this peer."""
+    setup_wg_quickrs_folder("no_auth_multi_peer")
+    public_key = "uo2hR4Jw2lv/0+db23XgnE32jN5woDKWUHACft7W/Eo="
+
+    result = subprocess.run(
+        get_wg_quickrs_command() + ["config", "set", "network", "peer", "6e9a8440-f884-4b54-bfe7-b982f15e40fd", "public-key", public_key],
+        capture_output=True,
+        text=True
+    )
+    assert result.returncode == 0
+    result = subprocess.run(
+        get_wg_quickrs_command() + ["config", "get", "network", "peers", "6e9a8440-f884-4b54-bfe7-b982f15e40fd", "public-key"],
+        capture_output=True,
+        text=True
+    )
+    assert result.stdout.strip() == public_key
+    result = subprocess.run(
+        get_wg_quickrs_command() + ["config", "get", "network", "peers", "6e9a8440-f884-4b54-bfe7-b982f15e40fd", "private-key"],
+        capture_output=True,
+        text=True
+    )
+    assert result.stdout.strip() == "null"
+
+    # the agent needs its own private key
+    result = subprocess.run(
+        get_wg_quickrs_command() + ["config", "set", "network", "peer", "0ed989c6-6dba-4e3c-8034-08adf4262d9e", "public-key", public_key],
+        capture_output=True,
+        text=True
+    )
+    assert result.returncode != 0
+
+
 @pytest.mark.parametrize(
     "action",
     ["enable", "disable"],
@@ -407,3 +442,156 @@ def test_config_agent_vpn_wg_userspace_toggle(setup_wg_quickrs_folder):
         text=True
     )
     assert result.returncode == 0
+
+
+def test_config_agent_vpn_telemetry_history_toggle(setup_wg_quickrs_folder):
+    """Test enabling/disabling agent VPN telemetry history."""
+    setup_wg_quickrs_folder("no_auth_single_peer")
+
+    for action, expected in [("enable", "true"), ("disable", "false")]:
+        result = subprocess.run(
+            get_wg_quickrs_command() + ["config", action, "agent", "vpn", "telemetry-history"],
+            capture_output=True,
+            text=True
+        )
+        assert result.returncode == 0
+
+        result = subprocess.run(
+            get_wg_quickrs_command() + ["config", "get", "agent", "vpn", "telemetry", "history", "enabled"],
+            capture_output=True,
+            text=True
+        )
+        assert result.returncode == 0
+        assert result.stdout.strip() == expected
+
+
+@pytest.mark.parametrize(
+    "backend,expected_success",
+    [
+        ("shell", True),
+        ("netlink", True),
+        ("iproute2", False),
+    ],
+)
+def test_config_set_agent_vpn_network_backend(setup_wg_quickrs_folder, backend, expected_success):
+    """Test setting the Linux network backend of the agent VPN."""
+    setup_wg_quickrs_folder("no_auth_single_peer")
+
+    result = subprocess.run(
+        get_wg_quickrs_command() + ["config", "set", "agent", "vpn", "network-backend", backend],
+        capture_output=True,
+        text=True
+    )
+    assert (result.returncode == 0) == expected_success
+
+    result = subprocess.run(
+        get_wg_quickrs_command() + ["config", "get", "agent", "vpn", "network-backend"],
+        capture_output=True,
+        text=True
+    )
+    assert result.returncode == 0
+    assert result.stdout.strip() == (backend if expected_success else "netlink")
+
+
+@pytest.mark.parametrize(
+    "field,value,expected_success,default",
+    [
+        ("mode", "always", True, "on-demand"),
+        ("mode", "off", True, "on-demand"),
+        ("mode", "sometimes", False, "on-demand"),
+        ("interval", "500", True, "1000"),
+        ("interval", "50", False, "1000"),
+        ("interval", "1s", False, "1000"),
+        ("retention", "60", True, "20"),
+        ("retention", "0", False, "20"),
+        ("retention", "10000", False, "20"),
+    ],
+)
+def test_config_set_agent_vpn_telemetry(setup_wg_quickrs_folder, field, value, expected_success, default):
+    """Test setting how the agent VPN telemetry is collected."""
+    setup_wg_quickrs_folder("no_auth_single_peer")
+
+    result = subprocess.run(
+        get_wg_quickrs_command() + ["config", "set", "agent", "vpn", "telemetry", field, value],
+        capture_output=True,
+        text=True
+    )
+    assert (result.returncode == 0) == expected_success
+    if not expected_success:
+        assert f"telemetry {field}" in result.stderr + result.stdout
+
+    result = subprocess.run(
+        get_wg_quickrs_command() + ["config", "get", "agent", "vpn", "telemetry", field],
+        capture_output=True,
+        text=True
+    )
+    assert result.returncode == 0
+    assert result.stdout.strip() == (value if expected_success else default)
+
+
+@pytest.mark.parametrize(
+    "field,key,value,expected_success",
+    [
+        ("issuer", "issuer", "https://sso.example.com/realms/main", True),
+        ("issuer", "issuer", "sso.example.com", False),
+        ("client-id", "client_id", "wg-quickrs", True),
+        ("client-id", "client_id", "", False),
+        ("redirect-url", "redirect_url", "https://vpn.example.com/api/oidc/callback", True),
+        ("redirect-url", "redirect_url", "ftp://vpn.example.com", False),
+        ("username-claim", "username_claim", "email", True),
+        ("role-claim", "role_claim", "roles", True),
+        ("role-claim", "role_claim", "", False),
+    ],
+)
+def test_config_set_agent_web_oidc(setup_wg_quickrs_folder, field, key, value, expected_success):
+    """Test setting the OpenID Connect login configuration."""
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = setup_wg_quickrs_folder("no_auth_single_peer")
+
+    result = subprocess.run(
+        get_wg_quickrs_command() + ["config", "set", "agent", "web", "oidc", field, value],
+        capture_output=True,
+        text=True
+    )
+    assert (result.returncode == 0) == expected_success
+
+    with open(wg_quickrs_config_file) as stream:
+        conf = YAML().load(stream)
+    assert (conf["agent"]["web"].get("oidc", {}).get(key) == value) == expected_success
+
+
+def test_config_set_agent_web_oidc_client_secret(setup_wg_quickrs_folder):
+    """Test the OpenID Connect client secret is read from stdin when it is not given."""
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = setup_wg_quickrs_folder("no_auth_single_peer")
+
+    result = subprocess.run(
+        get_wg_quickrs_command() + ["config", "set", "agent", "web", "oidc", "client-secret"],
+        input="s3cr3t\n",
+        capture_output=True,
+        text=True
+    )
+    assert result.returncode == 0, result.stderr
+
+    with open(wg_quickrs_config_file) as stream:
+        conf = YAML().load(stream)
+    assert conf["agent"]["web"]["oidc"]["client_secret"] == "s3cr3t"
+
+def test_config_dry_run(setup_wg_quickrs_folder):
+    """Test --dry-run prints the preview without writing conf.yml."""
+    setup_wg_quickrs_folder("no_auth_multi_peer")
+    pytest_folder, wg_quickrs_config_folder, wg_quickrs_config_file = get_paths()
+    with open(wg_quickrs_config_file) as stream:
+        old_text = stream.read()
+
+    result = subprocess.run(
+        get_wg_quickrs_command() + [
+            "config", "set", "network", "peer", "6e9a8440-f884-4b54-bfe7-b982f15e40fd", "address", "10.0.34.50", "--dry-run"
+        ],
+        capture_output=True,
+        text=True
+    )
+
+    assert result.returncode == 0
+    assert "# dry run" in result.stdout
+    assert "+Address = 10.0.34.50/24" in result.stdout
+    with open(wg_quickrs_config_file) as stream:
+        assert stream.read() == old_text